@@ -18,6 +18,9 @@ pub(crate) mod test_vectors;
 mod p128pow5t3;
 pub use p128pow5t3::P128Pow5T3;
 
+mod transcript;
+pub use transcript::{PoseidonRead, PoseidonWrite};
+
 use grain::SboxType;
 
 /// The type used to hold permutation state.
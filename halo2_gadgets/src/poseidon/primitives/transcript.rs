@@ -0,0 +1,346 @@
+//! Transcripts based on the Poseidon sponge, for proofs that are verified
+//! inside another circuit.
+
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use group::ff::PrimeField;
+use group::GroupEncoding;
+use halo2_proofs::arithmetic::{CurveAffine, FieldExt};
+use halo2_proofs::transcript::{
+    Challenge255, EncodedChallenge, Transcript, TranscriptRead, TranscriptReadBuffer,
+    TranscriptWrite, TranscriptWriterBuffer,
+};
+
+use super::{permute, Mds, Spec, State};
+
+/// The initial capacity element of transcript sponges, "Halo2-Transcript"
+/// read as a little-endian integer.
+fn initial_capacity_element<F: FieldExt>() -> F {
+    F::from_u128(u128::from_le_bytes(*b"Halo2-Transcript"))
+}
+
+/// A Poseidon sponge in duplex mode: elements are absorbed until a challenge
+/// is squeezed, after which absorbing continues from the same state.
+#[derive(Debug)]
+struct DuplexSponge<F: FieldExt, S: Spec<F, T, RATE>, const T: usize, const RATE: usize> {
+    state: State<F, T>,
+    pending: Vec<F>,
+    mds_matrix: Mds<F, T>,
+    round_constants: Vec<[F; T]>,
+    _marker: PhantomData<S>,
+}
+
+// Not derived, as that would require `S: Clone`
+impl<F: FieldExt, S: Spec<F, T, RATE>, const T: usize, const RATE: usize> Clone
+    for DuplexSponge<F, S, T, RATE>
+{
+    fn clone(&self) -> Self {
+        DuplexSponge {
+            state: self.state,
+            pending: self.pending.clone(),
+            mds_matrix: self.mds_matrix,
+            round_constants: self.round_constants.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: FieldExt, S: Spec<F, T, RATE>, const T: usize, const RATE: usize>
+    DuplexSponge<F, S, T, RATE>
+{
+    fn new() -> Self {
+        let (round_constants, mds_matrix, _) = S::constants();
+
+        let mut state = [F::zero(); T];
+        state[RATE] = initial_capacity_element();
+
+        DuplexSponge {
+            state,
+            pending: vec![],
+            mds_matrix,
+            round_constants,
+            _marker: PhantomData,
+        }
+    }
+
+    fn absorb(&mut self, value: F) {
+        self.pending.push(value);
+    }
+
+    fn squeeze(&mut self) -> F {
+        // Pad the pending elements with a one and then zeros up to a multiple
+        // of the rate, so that each squeeze runs at least one permutation and
+        // distinct sequences of elements are absorbed differently.
+        self.pending.push(F::one());
+        while self.pending.len() % RATE != 0 {
+            self.pending.push(F::zero());
+        }
+
+        for chunk in self.pending.chunks(RATE) {
+            for (word, value) in self.state.iter_mut().zip(chunk.iter()) {
+                *word += value;
+            }
+            permute::<F, S, T, RATE>(&mut self.state, &self.mds_matrix, &self.round_constants);
+        }
+        self.pending.clear();
+
+        self.state[0]
+    }
+}
+
+/// Absorbs an element of the base field as 128-bit limbs of its
+/// little-endian representation, which fit in the scalar field.
+fn absorb_base<C: CurveAffine, S: Spec<C::Scalar, T, RATE>, const T: usize, const RATE: usize>(
+    sponge: &mut DuplexSponge<C::Scalar, S, T, RATE>,
+    value: C::Base,
+) {
+    for limb in value.to_repr().as_ref().chunks(16) {
+        let mut bytes = [0u8; 16];
+        bytes[..limb.len()].copy_from_slice(limb);
+        sponge.absorb(C::Scalar::from_u128(u128::from_le_bytes(bytes)));
+    }
+}
+
+fn absorb_point<C: CurveAffine, S: Spec<C::Scalar, T, RATE>, const T: usize, const RATE: usize>(
+    sponge: &mut DuplexSponge<C::Scalar, S, T, RATE>,
+    point: C,
+) -> io::Result<()> {
+    let coords = point.coordinates();
+    if bool::from(coords.is_none()) {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "cannot write points at infinity to the transcript",
+        ));
+    }
+    let coords = coords.unwrap();
+    absorb_base::<C, S, T, RATE>(sponge, *coords.x());
+    absorb_base::<C, S, T, RATE>(sponge, *coords.y());
+
+    Ok(())
+}
+
+fn squeeze_challenge<
+    C: CurveAffine,
+    S: Spec<C::Scalar, T, RATE>,
+    const T: usize,
+    const RATE: usize,
+>(
+    sponge: &mut DuplexSponge<C::Scalar, S, T, RATE>,
+) -> Challenge255<C> {
+    // The challenge is the squeezed scalar itself, reduced from its wide
+    // little-endian representation.
+    let scalar = sponge.squeeze();
+    let mut bytes = [0u8; 64];
+    let repr = scalar.to_repr();
+    bytes[..repr.as_ref().len()].copy_from_slice(repr.as_ref());
+    Challenge255::new(&bytes)
+}
+
+/// Poseidon transcript reader over the scalar field of `C`, with the
+/// permutation given by `S`, e.g. [`P128Pow5T3`](super::P128Pow5T3).
+///
+/// Proofs are encoded as with [`Blake2bRead`](halo2_proofs::transcript::Blake2bRead)
+/// with compressed points. Scalars are absorbed as they are and the
+/// coordinates of points as 128-bit limbs, so that a verifier circuit over
+/// the scalar field can recompute the challenges cheaply.
+#[derive(Debug)]
+pub struct PoseidonRead<
+    R: Read,
+    C: CurveAffine,
+    S: Spec<C::Scalar, T, RATE>,
+    const T: usize,
+    const RATE: usize,
+> {
+    sponge: DuplexSponge<C::Scalar, S, T, RATE>,
+    reader: R,
+}
+
+impl<R: Read, C: CurveAffine, S: Spec<C::Scalar, T, RATE>, const T: usize, const RATE: usize>
+    TranscriptReadBuffer<R, C, Challenge255<C>> for PoseidonRead<R, C, S, T, RATE>
+{
+    fn init(reader: R) -> Self {
+        PoseidonRead {
+            sponge: DuplexSponge::new(),
+            reader,
+        }
+    }
+
+    fn fork(&self, reader: R) -> Self {
+        PoseidonRead {
+            sponge: self.sponge.clone(),
+            reader,
+        }
+    }
+}
+
+impl<R: Read, C: CurveAffine, S: Spec<C::Scalar, T, RATE>, const T: usize, const RATE: usize>
+    TranscriptRead<C, Challenge255<C>> for PoseidonRead<R, C, S, T, RATE>
+{
+    fn read_point(&mut self) -> io::Result<C> {
+        let mut compressed = C::Repr::default();
+        self.reader.read_exact(compressed.as_mut())?;
+        let point: C = Option::from(C::from_bytes(&compressed)).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof")
+        })?;
+        self.common_point(point)?;
+
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        let mut data = <C::Scalar as PrimeField>::Repr::default();
+        self.reader.read_exact(data.as_mut())?;
+        let scalar: C::Scalar = Option::from(C::Scalar::from_repr(data)).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                "invalid field element encoding in proof",
+            )
+        })?;
+        self.common_scalar(scalar)?;
+
+        Ok(scalar)
+    }
+}
+
+impl<R: Read, C: CurveAffine, S: Spec<C::Scalar, T, RATE>, const T: usize, const RATE: usize>
+    Transcript<C, Challenge255<C>> for PoseidonRead<R, C, S, T, RATE>
+{
+    fn squeeze_challenge(&mut self) -> Challenge255<C> {
+        squeeze_challenge(&mut self.sponge)
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        absorb_point(&mut self.sponge, point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.sponge.absorb(scalar);
+
+        Ok(())
+    }
+}
+
+/// Poseidon transcript writer over the scalar field of `C`; see
+/// [`PoseidonRead`].
+#[derive(Debug)]
+pub struct PoseidonWrite<
+    W: Write,
+    C: CurveAffine,
+    S: Spec<C::Scalar, T, RATE>,
+    const T: usize,
+    const RATE: usize,
+> {
+    sponge: DuplexSponge<C::Scalar, S, T, RATE>,
+    writer: W,
+}
+
+impl<W: Write, C: CurveAffine, S: Spec<C::Scalar, T, RATE>, const T: usize, const RATE: usize>
+    TranscriptWriterBuffer<W, C, Challenge255<C>> for PoseidonWrite<W, C, S, T, RATE>
+{
+    fn init(writer: W) -> Self {
+        PoseidonWrite {
+            sponge: DuplexSponge::new(),
+            writer,
+        }
+    }
+
+    fn fork(&self, writer: W) -> Self {
+        PoseidonWrite {
+            sponge: self.sponge.clone(),
+            writer,
+        }
+    }
+
+    fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write, C: CurveAffine, S: Spec<C::Scalar, T, RATE>, const T: usize, const RATE: usize>
+    TranscriptWrite<C, Challenge255<C>> for PoseidonWrite<W, C, S, T, RATE>
+{
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        self.common_point(point)?;
+        self.writer.write_all(point.to_bytes().as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        self.writer.write_all(scalar.to_repr().as_ref())
+    }
+}
+
+impl<W: Write, C: CurveAffine, S: Spec<C::Scalar, T, RATE>, const T: usize, const RATE: usize>
+    Transcript<C, Challenge255<C>> for PoseidonWrite<W, C, S, T, RATE>
+{
+    fn squeeze_challenge(&mut self) -> Challenge255<C> {
+        squeeze_challenge(&mut self.sponge)
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        absorb_point(&mut self.sponge, point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.sponge.absorb(scalar);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use group::{ff::Field, Curve, Group};
+    use halo2_proofs::transcript::{
+        Transcript, TranscriptRead, TranscriptReadBuffer, TranscriptWrite, TranscriptWriterBuffer,
+    };
+    use halo2curves::pasta::pallas;
+    use rand::rngs::OsRng;
+
+    use super::{PoseidonRead, PoseidonWrite};
+    use crate::poseidon::primitives::P128Pow5T3;
+
+    type PallasWrite = PoseidonWrite<Vec<u8>, pallas::Affine, P128Pow5T3, 3, 2>;
+    type PallasRead<'a> = PoseidonRead<&'a [u8], pallas::Affine, P128Pow5T3, 3, 2>;
+
+    #[test]
+    fn round_trip() {
+        let points: Vec<pallas::Affine> = (0..3)
+            .map(|_| pallas::Point::random(OsRng).to_affine())
+            .collect();
+        let scalars: Vec<pallas::Scalar> = (0..3).map(|_| pallas::Scalar::random(OsRng)).collect();
+
+        let mut transcript = PallasWrite::init(vec![]);
+        transcript.common_scalar(scalars[0]).unwrap();
+        let mut challenges = vec![*transcript.squeeze_challenge_scalar::<()>()];
+        for (point, scalar) in points.iter().zip(scalars.iter()) {
+            transcript.write_point(*point).unwrap();
+            transcript.write_scalar(*scalar).unwrap();
+            challenges.push(*transcript.squeeze_challenge_scalar::<()>());
+        }
+        // Squeezing without absorbing in between still changes the challenge
+        challenges.push(*transcript.squeeze_challenge_scalar::<()>());
+        let proof = transcript.finalize();
+        assert_eq!(proof.len(), 3 * (32 + 32));
+
+        let mut transcript = PallasRead::init(&proof[..]);
+        transcript.common_scalar(scalars[0]).unwrap();
+        assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), challenges[0]);
+        for (i, (point, scalar)) in points.iter().zip(scalars.iter()).enumerate() {
+            assert_eq!(transcript.read_point().unwrap(), *point);
+            assert_eq!(transcript.read_scalar().unwrap(), *scalar);
+            assert_eq!(
+                *transcript.squeeze_challenge_scalar::<()>(),
+                challenges[i + 1]
+            );
+        }
+        assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), challenges[4]);
+        assert_ne!(challenges[3], challenges[4]);
+
+        // A different common input changes every challenge
+        let mut transcript = PallasRead::init(&proof[..]);
+        transcript.common_scalar(scalars[1]).unwrap();
+        assert_ne!(*transcript.squeeze_challenge_scalar::<()>(), challenges[0]);
+    }
+}
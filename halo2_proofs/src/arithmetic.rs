@@ -5,21 +5,129 @@ use super::multicore;
 pub use ff::Field;
 use group::{
     ff::{BatchInvert, PrimeField},
+    prime::PrimeCurveAffine,
     Curve, Group as _,
 };
 
 pub use halo2curves::{CurveAffine, CurveExt, FieldExt, Group};
+use rayon::prelude::*;
+use std::cell::Cell;
+#[cfg(feature = "timings")]
+use std::time::Instant;
 
-fn multiexp_serial<C: CurveAffine>(coeffs: &[C::Scalar], bases: &[C], acc: &mut C::Curve) {
-    let coeffs: Vec<_> = coeffs.iter().map(|a| a.to_repr()).collect();
+/// Numbers of FFTs, multi-exponentiations and terms of multi-exponentiations
+/// and, with the `timings` feature, the nanoseconds spent in them
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct OpCounts {
+    pub(crate) ffts: usize,
+    pub(crate) msms: usize,
+    pub(crate) msm_terms: usize,
+    #[cfg(feature = "timings")]
+    pub(crate) fft_nanos: u64,
+    #[cfg(feature = "timings")]
+    pub(crate) msm_nanos: u64,
+}
+
+thread_local! {
+    // The operations called on this thread since the innermost
+    // `OpCountScope` on it started, reported to `ProverObserver`s.
+    static OP_COUNTS: Cell<OpCounts> = Cell::new(OpCounts::default());
+}
+
+fn count(f: impl FnOnce(&mut OpCounts)) {
+    OP_COUNTS.with(|counts| {
+        let mut current = counts.get();
+        f(&mut current);
+        counts.set(current);
+    });
+}
+
+fn count_msm(terms: usize) {
+    count(|counts| {
+        counts.msms += 1;
+        counts.msm_terms += terms;
+    });
+}
 
-    let c = if bases.len() < 4 {
+/// Counts the FFTs and multi-exponentiations called on the current thread
+/// while it is alive, apart from those of the scope it is nested in. A proof
+/// thereby counts its own operations, even when rayon runs the task of
+/// another proof on its thread while it waits.
+#[derive(Debug)]
+pub(crate) struct OpCountScope {
+    outer: OpCounts,
+}
+
+impl OpCountScope {
+    pub(crate) fn new() -> Self {
+        OpCountScope {
+            outer: OP_COUNTS.with(|counts| counts.replace(OpCounts::default())),
+        }
+    }
+
+    /// Returns the operations counted so far in this scope
+    pub(crate) fn counts(&self) -> OpCounts {
+        OP_COUNTS.with(Cell::get)
+    }
+}
+
+impl Drop for OpCountScope {
+    fn drop(&mut self) {
+        OP_COUNTS.with(|counts| counts.set(self.outer));
+    }
+}
+
+/// The operations timed by [`OpTimer`]
+#[cfg(feature = "timings")]
+#[derive(Clone, Copy)]
+enum Op {
+    Fft,
+    Msm,
+}
+
+/// Adds the time until it is dropped to the counts of the current thread.
+#[cfg(feature = "timings")]
+struct OpTimer(Op, Instant);
+
+#[cfg(feature = "timings")]
+impl OpTimer {
+    fn new(op: Op) -> Self {
+        OpTimer(op, Instant::now())
+    }
+}
+
+#[cfg(feature = "timings")]
+impl Drop for OpTimer {
+    fn drop(&mut self) {
+        let nanos = self.1.elapsed().as_nanos() as u64;
+        count(|counts| match self.0 {
+            Op::Fft => counts.fft_nanos += nanos,
+            Op::Msm => counts.msm_nanos += nanos,
+        });
+    }
+}
+
+/// Returns the Pippenger window size, in bits, that [`best_multiexp`] uses for
+/// a multi-exponentiation of `n` bases on a single thread.
+pub fn multiexp_window_size(n: usize) -> usize {
+    if n < 4 {
         1
-    } else if bases.len() < 32 {
+    } else if n < 32 {
         3
     } else {
-        (f64::from(bases.len() as u32)).ln().ceil() as usize
-    };
+        (f64::from(n as u32)).ln().ceil() as usize
+    }
+}
+
+fn multiexp_serial<C: CurveAffine>(
+    coeffs: &[C::Scalar],
+    bases: &[C],
+    window: Option<usize>,
+    acc: &mut C::Curve,
+) {
+    let coeffs: Vec<_> = coeffs.iter().map(|a| a.to_repr()).collect();
+
+    let c = window.unwrap_or_else(|| multiexp_window_size(bases.len()));
 
     fn get_at<F: PrimeField>(segment: usize, c: usize, bytes: &F::Repr) -> usize {
         let skip_bits = segment * c;
@@ -130,7 +238,35 @@ pub fn small_multiexp<C: CurveAffine>(coeffs: &[C::Scalar], bases: &[C]) -> C::C
 ///
 /// This will use multithreading if beneficial.
 pub fn best_multiexp<C: CurveAffine>(coeffs: &[C::Scalar], bases: &[C]) -> C::Curve {
+    multiexp_parallel(coeffs, bases, None)
+}
+
+/// Performs a multi-exponentiation operation like [`best_multiexp`], with
+/// the window size of the bucket method fixed to `window` bits instead of
+/// chosen from the number of bases per thread. Each window uses
+/// `2^window - 1` buckets per thread. For bases that are reused across many
+/// multi-exponentiations, see [`MultiexpTable`].
+///
+/// This function will panic if coeffs and bases have a different length, or
+/// if `window` is not between 1 and 20.
+pub fn best_multiexp_with_window<C: CurveAffine>(
+    coeffs: &[C::Scalar],
+    bases: &[C],
+    window: usize,
+) -> C::Curve {
+    assert!((1..=20).contains(&window));
+    multiexp_parallel(coeffs, bases, Some(window))
+}
+
+fn multiexp_parallel<C: CurveAffine>(
+    coeffs: &[C::Scalar],
+    bases: &[C],
+    window: Option<usize>,
+) -> C::Curve {
     assert_eq!(coeffs.len(), bases.len());
+    count_msm(coeffs.len());
+    #[cfg(feature = "timings")]
+    let _timer = OpTimer::new(Op::Msm);
 
     let num_threads = multicore::current_num_threads();
     if coeffs.len() > num_threads {
@@ -146,14 +282,169 @@ pub fn best_multiexp<C: CurveAffine>(coeffs: &[C::Scalar], bases: &[C]) -> C::Cu
                 .zip(results.iter_mut())
             {
                 scope.spawn(move |_| {
-                    multiexp_serial(coeffs, bases, acc);
+                    multiexp_serial(coeffs, bases, window, acc);
                 });
             }
         });
         results.iter().fold(C::Curve::identity(), |a, b| a + b)
     } else {
         let mut acc = C::Curve::identity();
-        multiexp_serial(coeffs, bases, &mut acc);
+        multiexp_serial(coeffs, bases, window, &mut acc);
+        acc
+    }
+}
+
+/// Performs a multi-exponentiation of `bases` with coefficients that are
+/// provided as consecutive chunks, accumulating the partial results so that
+/// only one chunk of coefficients needs to be in memory at a time.
+///
+/// This function will panic if there are more coefficients than bases.
+pub fn best_multiexp_chunks<'a, C: CurveAffine, I>(chunks: I, bases: &[C]) -> C::Curve
+where
+    I: IntoIterator<Item = &'a [C::Scalar]>,
+{
+    let mut offset = 0;
+    let mut acc = C::Curve::identity();
+    for coeffs in chunks {
+        assert!(offset + coeffs.len() <= bases.len());
+        acc += best_multiexp(coeffs, &bases[offset..offset + coeffs.len()]);
+        offset += coeffs.len();
+    }
+    acc
+}
+
+/// Precomputed multiples `[2^(window * j)] G_i` of a fixed set of bases `G_i`,
+/// for every `window`-bit digit position `j` of a scalar.
+///
+/// A multi-exponentiation with the table then needs no doublings at all: every
+/// digit of every scalar is added to its bucket directly, at the cost of
+/// storing `ceil(NUM_BITS / window)` points per base.
+#[derive(Clone, Debug)]
+pub struct MultiexpTable<C: CurveAffine> {
+    window: usize,
+    segments: usize,
+    points: Vec<C>,
+}
+
+impl<C: CurveAffine> MultiexpTable<C> {
+    /// Builds the table for `bases` with digits of `window` bits, which must
+    /// be between 1 and 20.
+    pub fn new(bases: &[C], window: usize) -> Self {
+        assert!((1..=20).contains(&window));
+        let segments = (C::Scalar::NUM_BITS as usize + window - 1) / window;
+
+        let mut multiples = vec![C::Curve::identity(); bases.len() * segments];
+        let num_threads = multicore::current_num_threads();
+        let chunk = (bases.len() + num_threads - 1) / num_threads;
+        if chunk > 0 {
+            multicore::scope(|scope| {
+                for (bases, multiples) in bases
+                    .chunks(chunk)
+                    .zip(multiples.chunks_mut(chunk * segments))
+                {
+                    scope.spawn(move |_| {
+                        for (base, multiples) in bases.iter().zip(multiples.chunks_mut(segments)) {
+                            let mut acc = base.to_curve();
+                            for multiple in multiples.iter_mut() {
+                                *multiple = acc;
+                                for _ in 0..window {
+                                    acc = acc.double();
+                                }
+                            }
+                        }
+                    });
+                }
+            });
+        }
+
+        let mut points = vec![C::identity(); multiples.len()];
+        parallelize(&mut points, |points, start| {
+            C::Curve::batch_normalize(&multiples[start..start + points.len()], points);
+        });
+
+        MultiexpTable {
+            window,
+            segments,
+            points,
+        }
+    }
+
+    /// Returns the number of bases in the table.
+    pub fn len(&self) -> usize {
+        self.points.len() / self.segments
+    }
+
+    /// Returns `true` if the table has no bases.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Computes `sum_i coeffs[i] * G_i`. This function will panic if there
+    /// are more coefficients than bases.
+    pub fn multiexp(&self, coeffs: &[C::Scalar]) -> C::Curve {
+        self.multiexp_from(0, coeffs)
+    }
+
+    /// Computes `sum_i coeffs[i] * G_(offset + i)`. This function will panic
+    /// if `offset + coeffs.len()` exceeds the number of bases.
+    pub fn multiexp_from(&self, offset: usize, coeffs: &[C::Scalar]) -> C::Curve {
+        assert!(offset + coeffs.len() <= self.len());
+        count_msm(coeffs.len());
+        #[cfg(feature = "timings")]
+        let _timer = OpTimer::new(Op::Msm);
+        let points = &self.points[offset * self.segments..];
+
+        let num_threads = multicore::current_num_threads();
+        if coeffs.len() > num_threads {
+            let chunk = coeffs.len() / num_threads;
+            let num_chunks = coeffs.chunks(chunk).len();
+            let mut results = vec![C::Curve::identity(); num_chunks];
+            multicore::scope(|scope| {
+                for ((coeffs, points), acc) in coeffs
+                    .chunks(chunk)
+                    .zip(points.chunks(chunk * self.segments))
+                    .zip(results.iter_mut())
+                {
+                    scope.spawn(move |_| {
+                        *acc = self.multiexp_serial(coeffs, points);
+                    });
+                }
+            });
+            results.iter().fold(C::Curve::identity(), |a, b| a + b)
+        } else {
+            self.multiexp_serial(coeffs, points)
+        }
+    }
+
+    fn multiexp_serial(&self, coeffs: &[C::Scalar], points: &[C]) -> C::Curve {
+        fn get_at(bytes: &[u8], skip_bits: usize, window: usize) -> usize {
+            let skip_bytes = skip_bits / 8;
+            let mut v = [0; 8];
+            for (v, o) in v.iter_mut().zip(bytes.iter().skip(skip_bytes)) {
+                *v = *o;
+            }
+            let tmp = u64::from_le_bytes(v) >> (skip_bits - skip_bytes * 8);
+            (tmp % (1 << window)) as usize
+        }
+
+        let mut buckets = vec![C::Curve::identity(); (1 << self.window) - 1];
+        for (coeff, points) in coeffs.iter().zip(points.chunks(self.segments)) {
+            let coeff = coeff.to_repr();
+            for (segment, point) in points.iter().enumerate() {
+                let digit = get_at(coeff.as_ref(), segment * self.window, self.window);
+                if digit != 0 {
+                    buckets[digit - 1] += *point;
+                }
+            }
+        }
+
+        // Summation by parts, as in `multiexp_serial`
+        let mut acc = C::Curve::identity();
+        let mut running_sum = C::Curve::identity();
+        for bucket in buckets.into_iter().rev() {
+            running_sum += bucket;
+            acc += running_sum;
+        }
         acc
     }
 }
@@ -169,6 +460,36 @@ pub fn best_multiexp<C: CurveAffine>(coeffs: &[C::Scalar], bases: &[C]) -> C::Cu
 ///
 /// This will use multithreading if beneficial.
 pub fn best_fft<G: Group>(a: &mut [G], omega: G::Scalar, log_n: u32) {
+    count(|counts| counts.ffts += 1);
+    #[cfg(feature = "timings")]
+    let _timer = OpTimer::new(Op::Fft);
+    if log_n >= FOUR_STEP_FFT_THRESHOLD {
+        return four_step_fft(a, omega, log_n);
+    }
+
+    let threads = multicore::current_num_threads();
+    let log_threads = log2_floor(threads);
+    let n = a.len() as usize;
+    assert_eq!(n, 1 << log_n);
+
+    bitreverse_permutation(a, log_n);
+    let twiddles = fft_twiddles(omega, n);
+
+    if log_n <= log_threads {
+        serial_butterfly_arithmetic(a, log_n, &twiddles)
+    } else {
+        recursive_butterfly_arithmetic(a, n, 1, &twiddles)
+    }
+}
+
+/// Above this `log_n`, [`best_fft`] uses [`four_step_fft`], whose
+/// transforms operate on blocks that fit in cache.
+pub const FOUR_STEP_FFT_THRESHOLD: u32 = 22;
+
+/// Side length of the tiles in which [`four_step_fft`] transposes matrices
+const TRANSPOSE_BLOCK: usize = 32;
+
+fn bitreverse_permutation<G>(a: &mut [G], log_n: u32) {
     fn bitreverse(mut n: usize, l: usize) -> usize {
         let mut r = 0;
         for _ in 0..l {
@@ -178,61 +499,130 @@ pub fn best_fft<G: Group>(a: &mut [G], omega: G::Scalar, log_n: u32) {
         r
     }
 
-    let threads = multicore::current_num_threads();
-    let log_threads = log2_floor(threads);
-    let n = a.len() as usize;
-    assert_eq!(n, 1 << log_n);
-
-    for k in 0..n {
+    for k in 0..a.len() {
         let rk = bitreverse(k, log_n as usize);
         if k < rk {
             a.swap(rk, k);
         }
     }
+}
 
-    // precompute twiddle factors
-    let twiddles: Vec<_> = (0..(n / 2) as usize)
-        .scan(G::Scalar::one(), |w, _| {
+fn fft_twiddles<F: Field>(omega: F, n: usize) -> Vec<F> {
+    (0..n / 2)
+        .scan(F::one(), |w, _| {
             let tw = *w;
-            w.group_scale(&omega);
+            *w *= &omega;
             Some(tw)
         })
-        .collect();
+        .collect()
+}
 
-    if log_n <= log_threads {
-        let mut chunk = 2_usize;
-        let mut twiddle_chunk = (n / 2) as usize;
-        for _ in 0..log_n {
-            a.chunks_mut(chunk).for_each(|coeffs| {
-                let (left, right) = coeffs.split_at_mut(chunk / 2);
-
-                // case when twiddle factor is one
-                let (a, left) = left.split_at_mut(1);
-                let (b, right) = right.split_at_mut(1);
-                let t = b[0];
-                b[0] = a[0];
-                a[0].group_add(&t);
-                b[0].group_sub(&t);
-
-                left.iter_mut()
-                    .zip(right.iter_mut())
-                    .enumerate()
-                    .for_each(|(i, (a, b))| {
-                        let mut t = *b;
-                        t.group_scale(&twiddles[(i + 1) * twiddle_chunk]);
-                        *b = *a;
-                        a.group_add(&t);
-                        b.group_sub(&t);
-                    });
-            });
-            chunk *= 2;
-            twiddle_chunk /= 2;
-        }
-    } else {
-        recursive_butterfly_arithmetic(a, n, 1, &twiddles)
+/// Performs the butterflies of an FFT of size `2^log_n` on `a` in
+/// bit-reversed order, on the current thread.
+fn serial_butterfly_arithmetic<G: Group>(a: &mut [G], log_n: u32, twiddles: &[G::Scalar]) {
+    let mut chunk = 2_usize;
+    let mut twiddle_chunk = a.len() / 2;
+    for _ in 0..log_n {
+        a.chunks_mut(chunk).for_each(|coeffs| {
+            let (left, right) = coeffs.split_at_mut(chunk / 2);
+
+            // case when twiddle factor is one
+            let (a, left) = left.split_at_mut(1);
+            let (b, right) = right.split_at_mut(1);
+            let t = b[0];
+            b[0] = a[0];
+            a[0].group_add(&t);
+            b[0].group_sub(&t);
+
+            left.iter_mut()
+                .zip(right.iter_mut())
+                .enumerate()
+                .for_each(|(i, (a, b))| {
+                    let mut t = *b;
+                    t.group_scale(&twiddles[(i + 1) * twiddle_chunk]);
+                    *b = *a;
+                    a.group_add(&t);
+                    b.group_sub(&t);
+                });
+        });
+        chunk *= 2;
+        twiddle_chunk /= 2;
     }
 }
 
+/// Transposes the `rows x cols` matrix `src` into `dst`, tile by tile.
+fn transpose<T: Copy + Send + Sync>(src: &[T], dst: &mut [T], rows: usize, cols: usize) {
+    assert_eq!(src.len(), rows * cols);
+    assert_eq!(dst.len(), rows * cols);
+
+    // Each chunk of `dst` holds `TRANSPOSE_BLOCK` columns of `src`
+    dst.par_chunks_mut(TRANSPOSE_BLOCK * rows)
+        .enumerate()
+        .for_each(|(block, dst)| {
+            let col_start = block * TRANSPOSE_BLOCK;
+            let block_cols = dst.len() / rows;
+            for row_start in (0..rows).step_by(TRANSPOSE_BLOCK) {
+                let row_end = std::cmp::min(row_start + TRANSPOSE_BLOCK, rows);
+                for col in 0..block_cols {
+                    for row in row_start..row_end {
+                        dst[col * rows + row] = src[row * cols + col_start + col];
+                    }
+                }
+            }
+        });
+}
+
+/// Performs the same FFT as [`best_fft`] with the four-step algorithm: `a` is
+/// viewed as an `n_2 x n_1` matrix with `n_1 n_2 = n`, whose columns and then
+/// rows are transformed by small FFTs that fit in cache, with a twiddle
+/// multiplication in between. Transposes keep the small FFTs on contiguous
+/// memory. This uses a scratch buffer of the size of `a`.
+pub fn four_step_fft<G: Group>(a: &mut [G], omega: G::Scalar, log_n: u32) {
+    let n = a.len();
+    assert_eq!(n, 1 << log_n);
+
+    let log_n1 = log_n / 2;
+    let log_n2 = log_n - log_n1;
+    let n1 = 1 << log_n1;
+    let n2 = 1 << log_n2;
+
+    // With j = j1 + n1 j2 and k = k2 + n2 k1,
+    //   A[k] = sum_{j1} w^{n2 j1 k1} w^{j1 k2} sum_{j2} a[j] w^{n1 j2 k2}
+    let mut scratch = vec![G::group_zero(); n];
+
+    // Inner transforms over j2, one per row j1 of the transposed input
+    transpose(a, &mut scratch, n2, n1);
+    let omega_n2 = omega.pow_vartime(&[n1 as u64]);
+    let twiddles_n2 = fft_twiddles(omega_n2, n2);
+    scratch
+        .par_chunks_mut(n2)
+        .enumerate()
+        .for_each(|(j1, row)| {
+            bitreverse_permutation(row, log_n2);
+            serial_butterfly_arithmetic(row, log_n2, &twiddles_n2);
+
+            let w = omega.pow_vartime(&[j1 as u64]);
+            let mut cur = G::Scalar::one();
+            for value in row.iter_mut() {
+                value.group_scale(&cur);
+                cur *= &w;
+            }
+        });
+
+    // Outer transforms over j1, one per row k2
+    transpose(&scratch, a, n1, n2);
+    let omega_n1 = omega.pow_vartime(&[n2 as u64]);
+    let twiddles_n1 = fft_twiddles(omega_n1, n1);
+    a.par_chunks_mut(n1).for_each(|row| {
+        bitreverse_permutation(row, log_n1);
+        serial_butterfly_arithmetic(row, log_n1, &twiddles_n1);
+    });
+
+    // a[k2 n1 + k1] = A[k2 + n2 k1]
+    transpose(a, &mut scratch, n2, n1);
+    a.copy_from_slice(&scratch);
+}
+
 /// This perform recursive butterfly arithmetic
 pub fn recursive_butterfly_arithmetic<G: Group>(
     a: &mut [G],
@@ -332,49 +722,127 @@ pub fn eval_polynomial<F: Field>(poly: &[F], point: F) -> F {
 ///
 /// This function will panic if the two vectors are not the same size.
 pub fn compute_inner_product<F: Field>(a: &[F], b: &[F]) -> F {
-    // TODO: parallelize?
+    fn inner_product<F: Field>(a: &[F], b: &[F]) -> F {
+        a.iter()
+            .zip(b.iter())
+            .fold(F::zero(), |acc, (a, b)| acc + *a * b)
+    }
     assert_eq!(a.len(), b.len());
 
-    let mut acc = F::zero();
-    for (a, b) in a.iter().zip(b.iter()) {
-        acc += (*a) * (*b);
+    let n = a.len();
+    let num_threads = multicore::current_num_threads();
+    if n * 2 < num_threads {
+        inner_product(a, b)
+    } else {
+        let chunk_size = (n + num_threads - 1) / num_threads;
+        let mut parts = vec![F::zero(); num_threads];
+        multicore::scope(|scope| {
+            for (out, (a, b)) in parts
+                .chunks_mut(1)
+                .zip(a.chunks(chunk_size).zip(b.chunks(chunk_size)))
+            {
+                scope.spawn(move |_| {
+                    out[0] = inner_product(a, b);
+                });
+            }
+        });
+        parts.iter().fold(F::zero(), |acc, part| acc + part)
     }
-
-    acc
 }
 
 /// Divides polynomial `a` in `X` by `X - b` with
 /// no remainder.
-pub fn kate_division<'a, F: Field, I: IntoIterator<Item = &'a F>>(a: I, mut b: F) -> Vec<F>
+///
+/// The quotient coefficients satisfy `q_i = a_{i + 1} + b q_{i + 1}`. Each
+/// thread runs this recurrence on its own chunk of `q` as if the chunk were
+/// the top of the polynomial; the carry out of every chunk is then propagated
+/// serially and added back to the chunks in parallel, scaled by powers of `b`.
+pub fn kate_division<'a, F: Field, I: IntoIterator<Item = &'a F>>(a: I, b: F) -> Vec<F>
 where
     I::IntoIter: DoubleEndedIterator + ExactSizeIterator,
 {
-    b = -b;
     let a = a.into_iter();
+    assert!(a.len() > 0);
+    let mut q: Vec<F> = a.skip(1).cloned().collect();
 
-    let mut q = vec![F::zero(); a.len() - 1];
+    let n = q.len();
+    let num_threads = multicore::current_num_threads();
+    if n < num_threads * 2 {
+        for i in (0..n.saturating_sub(1)).rev() {
+            let carry = q[i + 1] * b;
+            q[i] += carry;
+        }
+        return q;
+    }
 
-    let mut tmp = F::zero();
-    for (q, r) in q.iter_mut().rev().zip(a.rev()) {
-        let mut lead_coeff = *r;
-        lead_coeff.sub_assign(&tmp);
-        *q = lead_coeff;
-        tmp = lead_coeff;
-        tmp.mul_assign(&b);
+    let chunk_size = (n + num_threads - 1) / num_threads;
+    multicore::scope(|scope| {
+        for chunk in q.chunks_mut(chunk_size) {
+            scope.spawn(move |_| {
+                for i in (0..chunk.len() - 1).rev() {
+                    let carry = chunk[i + 1] * b;
+                    chunk[i] += carry;
+                }
+            });
+        }
+    });
+
+    // The carry into a chunk is the true value of the coefficient right above
+    // it, which depends on the carries into all chunks above.
+    let num_chunks = (n + chunk_size - 1) / chunk_size;
+    let mut carries = vec![F::zero(); num_chunks];
+    for idx in (0..num_chunks - 1).rev() {
+        let above = (idx + 1) * chunk_size;
+        let len = std::cmp::min(chunk_size, n - above);
+        carries[idx] = q[above] + b.pow_vartime(&[len as u64, 0, 0, 0]) * carries[idx + 1];
     }
 
+    multicore::scope(|scope| {
+        for (chunk, carry) in q.chunks_mut(chunk_size).zip(carries.into_iter()) {
+            scope.spawn(move |_| {
+                let mut correction = carry;
+                for coeff in chunk.iter_mut().rev() {
+                    correction *= b;
+                    *coeff += correction;
+                }
+            });
+        }
+    });
+
     q
 }
 
+/// Smallest number of elements [`parallelize`] hands to a task, below which
+/// spawning the task costs more than the work it does.
+const PARALLELIZE_MIN_CHUNK_SIZE: usize = 32;
+
+/// How many chunks per thread [`parallelize`] splits a slice into, so that
+/// threads that finish early can pick up the work of slower ones.
+const PARALLELIZE_CHUNKS_PER_THREAD: usize = 4;
+
+fn parallelize_chunk_size(n: usize, min_chunk_size: usize) -> usize {
+    let num_chunks = multicore::current_num_threads() * PARALLELIZE_CHUNKS_PER_THREAD;
+    let chunk_size = (n + num_chunks - 1) / num_chunks;
+    std::cmp::max(std::cmp::max(chunk_size, min_chunk_size), 1)
+}
+
 /// This simple utility function will parallelize an operation that is to be
-/// performed over a mutable slice.
+/// performed over a mutable slice. `f` is called with each chunk of the slice
+/// and the index of the chunk's first element. Slices of up to 32 elements
+/// are processed in one chunk, larger ones in a few chunks per thread.
 pub fn parallelize<T: Send, F: Fn(&mut [T], usize) + Send + Sync + Clone>(v: &mut [T], f: F) {
-    let n = v.len();
-    let num_threads = multicore::current_num_threads();
-    let mut chunk = (n as usize) / num_threads;
-    if chunk < num_threads {
-        chunk = 1;
-    }
+    parallelize_with_min_chunk_size(v, PARALLELIZE_MIN_CHUNK_SIZE, f)
+}
+
+/// Like [`parallelize`], but with chunks of at least `min_chunk_size`
+/// elements, e.g. 1 when every element is expensive to process or a larger
+/// value when it is very cheap.
+pub fn parallelize_with_min_chunk_size<T: Send, F: Fn(&mut [T], usize) + Send + Sync + Clone>(
+    v: &mut [T],
+    min_chunk_size: usize,
+    f: F,
+) {
+    let chunk = parallelize_chunk_size(v.len(), min_chunk_size);
 
     multicore::scope(|scope| {
         for (chunk_num, v) in v.chunks_mut(chunk).enumerate() {
@@ -387,6 +855,32 @@ pub fn parallelize<T: Send, F: Fn(&mut [T], usize) + Send + Sync + Clone>(v: &mu
     });
 }
 
+/// Like [`parallelize`], but also passes `f` a state created by `init`, e.g.
+/// a scratch buffer. The state is created once per rayon job rather than once
+/// per chunk, so a thread processing several chunks reuses it.
+pub fn parallelize_with_init<T, S, I, F>(v: &mut [T], init: I, f: F)
+where
+    T: Send,
+    I: Fn() -> S + Send + Sync,
+    F: Fn(&mut S, &mut [T], usize) + Send + Sync,
+{
+    let chunk = parallelize_chunk_size(v.len(), PARALLELIZE_MIN_CHUNK_SIZE);
+
+    v.par_chunks_mut(chunk)
+        .enumerate()
+        .for_each_init(init, |state, (chunk_num, v)| f(state, v, chunk_num * chunk));
+}
+
+/// Inverts every element of `values` in place, leaving zeros as they are.
+///
+/// This uses Montgomery's trick on one chunk of `values` per thread, so it
+/// costs one field inversion per thread.
+pub fn batch_invert<F: Field>(values: &mut [F]) {
+    parallelize(values, |values, _| {
+        values.iter_mut().batch_invert();
+    });
+}
+
 fn log2_floor(num: usize) -> u32 {
     assert!(num > 0);
 
@@ -402,59 +896,84 @@ fn log2_floor(num: usize) -> u32 {
 /// Returns coefficients of an n - 1 degree polynomial given a set of n points
 /// and their evaluations. This function will panic if two values in `points`
 /// are the same.
+///
+/// With `m(X) = prod_k (X - x_k)`, the result is
+/// `sum_j evals[j] m(X) / ((X - x_j) m'(x_j))`. The n divisions by `X - x_j`
+/// take `O(n)` each and are spread over the available threads.
 pub fn lagrange_interpolate<F: FieldExt>(points: &[F], evals: &[F]) -> Vec<F> {
     assert_eq!(points.len(), evals.len());
-    if points.len() == 1 {
+    let n = points.len();
+    if n <= 1 {
         // Constant polynomial
-        vec![evals[0]]
-    } else {
-        let mut denoms = Vec::with_capacity(points.len());
-        for (j, x_j) in points.iter().enumerate() {
-            let mut denom = Vec::with_capacity(points.len() - 1);
-            for x_k in points
-                .iter()
-                .enumerate()
-                .filter(|&(k, _)| k != j)
-                .map(|a| a.1)
-            {
-                denom.push(*x_j - x_k);
-            }
-            denoms.push(denom);
+        return evals.to_vec();
+    }
+
+    // m(X) = prod_k (X - x_k)
+    let mut vanishing = vec![F::zero(); n + 1];
+    vanishing[0] = F::one();
+    for (i, point) in points.iter().enumerate() {
+        for j in (1..=i + 1).rev() {
+            vanishing[j] = vanishing[j - 1] - vanishing[j] * point;
         }
-        // Compute (x_j - x_k)^(-1) for each j != i
-        denoms.iter_mut().flat_map(|v| v.iter_mut()).batch_invert();
-
-        let mut final_poly = vec![F::zero(); points.len()];
-        for (j, (denoms, eval)) in denoms.into_iter().zip(evals.iter()).enumerate() {
-            let mut tmp: Vec<F> = Vec::with_capacity(points.len());
-            let mut product = Vec::with_capacity(points.len() - 1);
-            tmp.push(F::one());
-            for (x_k, denom) in points
+        vanishing[0] = -vanishing[0] * point;
+    }
+
+    // m'(x_j) = prod_{k != j} (x_j - x_k)
+    let derivative: Vec<F> = vanishing
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, coeff)| *coeff * F::from(i as u64))
+        .collect();
+    let mut denoms: Vec<F> = points
+        .par_iter()
+        .map(|point| {
+            derivative
                 .iter()
-                .enumerate()
-                .filter(|&(k, _)| k != j)
-                .map(|a| a.1)
-                .zip(denoms.into_iter())
-            {
-                product.resize(tmp.len() + 1, F::zero());
-                for ((a, b), product) in tmp
-                    .iter()
-                    .chain(std::iter::once(&F::zero()))
-                    .zip(std::iter::once(&F::zero()).chain(tmp.iter()))
-                    .zip(product.iter_mut())
-                {
-                    *product = *a * (-denom * x_k) + *b * denom;
+                .rev()
+                .fold(F::zero(), |acc, coeff| acc * point + coeff)
+        })
+        .collect();
+    assert!(
+        denoms.iter().all(|denom| !bool::from(denom.is_zero())),
+        "interpolation points must be distinct"
+    );
+    batch_invert(&mut denoms);
+
+    let num_threads = multicore::current_num_threads();
+    let chunk_size = (n + num_threads - 1) / num_threads;
+    let mut partials = vec![vec![F::zero(); n]; (n + chunk_size - 1) / chunk_size];
+    multicore::scope(|scope| {
+        for (partial, ((points, evals), denoms)) in partials.iter_mut().zip(
+            points
+                .chunks(chunk_size)
+                .zip(evals.chunks(chunk_size))
+                .zip(denoms.chunks(chunk_size)),
+        ) {
+            let vanishing = &vanishing;
+            scope.spawn(move |_| {
+                for ((point, eval), denom) in points.iter().zip(evals).zip(denoms) {
+                    // m(X) / (X - x_j), from the leading coefficient down
+                    let scale = *eval * denom;
+                    let mut quotient = F::zero();
+                    for (acc, coeff) in partial.iter_mut().zip(vanishing[1..].iter()).rev() {
+                        quotient = quotient * point + coeff;
+                        *acc += quotient * scale;
+                    }
                 }
-                std::mem::swap(&mut tmp, &mut product);
-            }
-            assert_eq!(tmp.len(), points.len());
-            assert_eq!(product.len(), points.len() - 1);
-            for (final_coeff, interpolation_coeff) in final_poly.iter_mut().zip(tmp.into_iter()) {
-                *final_coeff += interpolation_coeff * eval;
+            });
+        }
+    });
+
+    let mut final_poly = partials.pop().unwrap();
+    parallelize(&mut final_poly, |final_poly, start| {
+        for partial in partials.iter() {
+            for (final_coeff, coeff) in final_poly.iter_mut().zip(partial[start..].iter()) {
+                *final_coeff += coeff;
             }
         }
-        final_poly
-    }
+    });
+    final_poly
 }
 
 pub(crate) fn evaluate_vanishing_polynomial<F: FieldExt>(roots: &[F], z: F) -> F {
@@ -505,4 +1024,172 @@ fn test_lagrange_interpolate() {
             assert_eq!(eval_polynomial(&poly, *point), *eval);
         }
     }
+
+    // Enough points to be split over several threads
+    let points = (0..100).map(|_| Fp::random(rng)).collect::<Vec<_>>();
+    let evals = (0..100).map(|_| Fp::random(rng)).collect::<Vec<_>>();
+    let poly = lagrange_interpolate(&points, &evals);
+    assert_eq!(poly.len(), points.len());
+    for (point, eval) in points.iter().zip(evals.iter()) {
+        assert_eq!(eval_polynomial(&poly, *point), *eval);
+    }
+}
+
+#[test]
+fn test_multiexp_table() {
+    use crate::halo2curves::pasta::{Eq, EqAffine};
+
+    let rng = OsRng;
+
+    let bases: Vec<EqAffine> = (0..37).map(|_| Eq::random(rng).to_affine()).collect();
+    let coeffs: Vec<Fp> = (0..37).map(|_| Fp::random(rng)).collect();
+
+    for window in [1, 5, 8] {
+        let table = MultiexpTable::new(&bases, window);
+        assert_eq!(table.len(), bases.len());
+        assert_eq!(table.multiexp(&coeffs), best_multiexp(&coeffs, &bases));
+        assert_eq!(
+            table.multiexp_from(7, &coeffs[..20]),
+            best_multiexp(&coeffs[..20], &bases[7..27])
+        );
+    }
+}
+
+#[test]
+fn test_four_step_fft() {
+    let rng = OsRng;
+
+    for log_n in 0..10 {
+        let mut omega = Fp::root_of_unity();
+        for _ in log_n..Fp::S {
+            omega = omega.square();
+        }
+
+        let a: Vec<Fp> = (0..1 << log_n).map(|_| Fp::random(rng)).collect();
+        let mut expected = a.clone();
+        bitreverse_permutation(&mut expected, log_n);
+        let twiddles = fft_twiddles(omega, expected.len());
+        serial_butterfly_arithmetic(&mut expected, log_n, &twiddles);
+
+        let mut four_step = a.clone();
+        four_step_fft(&mut four_step, omega, log_n);
+        assert_eq!(four_step, expected);
+
+        let mut best = a;
+        best_fft(&mut best, omega, log_n);
+        assert_eq!(best, expected);
+    }
+}
+
+#[test]
+fn test_multiexp_window() {
+    use crate::halo2curves::pasta::{Eq, EqAffine};
+
+    let rng = OsRng;
+
+    let bases: Vec<EqAffine> = (0..50).map(|_| Eq::random(rng).to_affine()).collect();
+    let coeffs: Vec<Fp> = (0..50).map(|_| Fp::random(rng)).collect();
+
+    let expected = small_multiexp(&coeffs, &bases);
+    assert_eq!(best_multiexp(&coeffs, &bases), expected);
+    for window in [1, 2, 7, 13, 16] {
+        assert_eq!(best_multiexp_with_window(&coeffs, &bases, window), expected);
+    }
+}
+
+#[test]
+fn test_batch_invert() {
+    let rng = OsRng;
+
+    let values: Vec<Fp> = (0..1000)
+        .map(|i| {
+            if i % 7 == 0 {
+                Fp::zero()
+            } else {
+                Fp::random(rng)
+            }
+        })
+        .collect();
+    let mut inverted = values.clone();
+    batch_invert(&mut inverted);
+
+    for (value, inverted) in values.iter().zip(inverted.iter()) {
+        assert_eq!(*inverted, value.invert().unwrap_or(Fp::zero()));
+    }
+}
+
+#[test]
+fn test_kate_division() {
+    fn serial_kate_division(a: &[Fp], b: Fp) -> Vec<Fp> {
+        let mut q = vec![Fp::zero(); a.len() - 1];
+        let mut tmp = Fp::zero();
+        for (q, r) in q.iter_mut().rev().zip(a.iter().rev()) {
+            *q = *r + tmp;
+            tmp = *q * b;
+        }
+        q
+    }
+
+    let rng = OsRng;
+    for n in [1, 2, 3, 17, 1000, 1025] {
+        let a: Vec<Fp> = (0..n).map(|_| Fp::random(rng)).collect();
+        let b = Fp::random(rng);
+        let q = kate_division(&a, b);
+        assert_eq!(q, serial_kate_division(&a, b));
+
+        // a(X) - a(b) = (X - b) q(X)
+        let x = Fp::random(rng);
+        assert_eq!(
+            eval_polynomial(&a, x) - eval_polynomial(&a, b),
+            (x - b) * eval_polynomial(&q, x)
+        );
+    }
+}
+
+#[test]
+fn test_compute_inner_product() {
+    let rng = OsRng;
+    for n in [0, 1, 5, 1000] {
+        let a: Vec<Fp> = (0..n).map(|_| Fp::random(rng)).collect();
+        let b: Vec<Fp> = (0..n).map(|_| Fp::random(rng)).collect();
+        let expected = a
+            .iter()
+            .zip(b.iter())
+            .fold(Fp::zero(), |acc, (a, b)| acc + *a * b);
+        assert_eq!(compute_inner_product(&a, &b), expected);
+    }
+}
+
+#[test]
+fn test_parallelize() {
+    for n in [0, 1, 31, 32, 33, 1000, 4097] {
+        let mut v = vec![0usize; n];
+        parallelize(&mut v, |chunk, start| {
+            for (i, value) in chunk.iter_mut().enumerate() {
+                *value += start + i;
+            }
+        });
+        assert!(v.iter().enumerate().all(|(i, value)| i == *value));
+
+        let mut v = vec![0usize; n];
+        parallelize_with_min_chunk_size(&mut v, 1, |chunk, start| {
+            for (i, value) in chunk.iter_mut().enumerate() {
+                *value += start + i;
+            }
+        });
+        assert!(v.iter().enumerate().all(|(i, value)| i == *value));
+
+        let mut v = vec![0usize; n];
+        parallelize_with_init(
+            &mut v,
+            || vec![0usize; 8],
+            |scratch, chunk, start| {
+                for (i, value) in chunk.iter_mut().enumerate() {
+                    scratch[i % 8] = start + i;
+                    *value += scratch[i % 8];
+                }
+            },
+        );
+        assert!(v.iter().enumerate().all(|(i, value)| i == *value));
+    }
 }
@@ -434,6 +434,36 @@ pub trait Layouter<F: Field> {
         N: Fn() -> NR,
         NR: Into<String>;
 
+    /// Assigns independent regions, each with one of `assignments`, and returns
+    /// their results in order. The regions are all named `name`.
+    ///
+    /// This is the same as calling [`Layouter::assign_region`] with each of
+    /// `assignments` in turn, but lets the layouter run the closures in
+    /// parallel on the disjoint rows of their regions. The
+    /// [`SimpleFloorPlanner`](floor_planner::SimpleFloorPlanner) does so; other
+    /// layouters assign the regions one after another.
+    ///
+    /// Regions assigned in parallel may refer to cells of earlier regions, e.g.
+    /// in [`Region::constrain_equal`], but not to cells of each other, and they
+    /// cannot use [`Region::assign_advice_from_instance`]; copy instance cells
+    /// with [`Layouter::constrain_instance`] instead.
+    fn assign_regions<A, AR, N, NR>(
+        &mut self,
+        name: N,
+        assignments: Vec<A>,
+    ) -> Result<Vec<AR>, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error> + Send,
+        AR: Send,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        assignments
+            .into_iter()
+            .map(|assignment| self.assign_region(&name, assignment))
+            .collect()
+    }
+
     /// Assign a table region to an absolute row number.
     ///
     /// ```ignore
@@ -509,6 +539,20 @@ impl<'a, F: Field, L: Layouter<F> + 'a> Layouter<F> for NamespacedLayouter<'a, F
         self.0.assign_region(name, assignment)
     }
 
+    fn assign_regions<A, AR, N, NR>(
+        &mut self,
+        name: N,
+        assignments: Vec<A>,
+    ) -> Result<Vec<AR>, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error> + Send,
+        AR: Send,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.0.assign_regions(name, assignments)
+    }
+
     fn assign_table<A, N, NR>(&mut self, name: N, assignment: A) -> Result<(), Error>
     where
         A: FnMut(Table<'_, F>) -> Result<(), Error>,
@@ -4,6 +4,7 @@ use std::fmt;
 use std::marker::PhantomData;
 
 use ff::Field;
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
 
 use crate::{
     circuit::{
@@ -71,19 +72,17 @@ impl<'a, F: Field, CS: Assignment<F>> SingleChipLayouter<'a, F, CS> {
         };
         Ok(ret)
     }
-}
-
-impl<'a, F: Field, CS: Assignment<F> + 'a> Layouter<F> for SingleChipLayouter<'a, F, CS> {
-    type Root = Self;
 
-    fn assign_region<A, AR, N, NR>(&mut self, name: N, mut assignment: A) -> Result<AR, Error>
+    /// Returns the row at which the region assigned by `assignment` starts,
+    /// and marks its rows as used.
+    fn lay_out_region<A, AR>(
+        &mut self,
+        region_index: usize,
+        assignment: &mut A,
+    ) -> Result<usize, Error>
     where
         A: FnMut(Region<'_, F>) -> Result<AR, Error>,
-        N: Fn() -> NR,
-        NR: Into<String>,
     {
-        let region_index = self.regions.len();
-
         // Get shape of the region.
         let mut shape = RegionShape::new(region_index.into());
         {
@@ -97,23 +96,20 @@ impl<'a, F: Field, CS: Assignment<F> + 'a> Layouter<F> for SingleChipLayouter<'a
         for column in &shape.columns {
             region_start = cmp::max(region_start, self.columns.get(column).cloned().unwrap_or(0));
         }
-        self.regions.push(region_start.into());
 
         // Update column usage information.
         for column in shape.columns {
             self.columns.insert(column, region_start + shape.row_count);
         }
 
-        // Assign region cells.
-        self.cs.enter_region(name);
-        let mut region = SingleChipLayouterRegion::new(self, region_index.into());
-        let result = {
-            let region: &mut dyn RegionLayouter<F> = &mut region;
-            assignment(region.into())
-        }?;
-        let constants_to_assign = region.constants;
-        self.cs.exit_region();
+        Ok(region_start)
+    }
 
+    /// Assigns the constants of a region, and copies them to their cells.
+    fn assign_constants(
+        &mut self,
+        constants_to_assign: Vec<(Assigned<F>, Cell)>,
+    ) -> Result<(), Error> {
         // Assign constants. For the simple floor planner, we assign constants in order in
         // the first `constants` column.
         if self.constants.is_empty() {
@@ -143,9 +139,121 @@ impl<'a, F: Field, CS: Assignment<F> + 'a> Layouter<F> for SingleChipLayouter<'a
             }
         }
 
+        Ok(())
+    }
+}
+
+impl<'a, F: Field, CS: Assignment<F> + 'a> Layouter<F> for SingleChipLayouter<'a, F, CS> {
+    type Root = Self;
+
+    fn assign_region<A, AR, N, NR>(&mut self, name: N, mut assignment: A) -> Result<AR, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        let region_index = self.regions.len();
+        let region_start = self.lay_out_region(region_index, &mut assignment)?;
+        self.regions.push(region_start.into());
+
+        // Assign region cells.
+        self.cs.enter_region(name);
+        let mut region = SingleChipLayouterRegion::new(self, region_index.into());
+        let result = {
+            let region: &mut dyn RegionLayouter<F> = &mut region;
+            assignment(region.into())
+        }?;
+        let constants_to_assign = region.constants;
+        self.cs.exit_region();
+
+        self.assign_constants(constants_to_assign)?;
+
         Ok(result)
     }
 
+    fn assign_regions<A, AR, N, NR>(
+        &mut self,
+        name: N,
+        mut assignments: Vec<A>,
+    ) -> Result<Vec<AR>, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error> + Send,
+        AR: Send,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        // Lay out all the regions first, as `assign_region` does, so that the
+        // rows of each are known before any is assigned.
+        let first_index = self.regions.len();
+        for (i, assignment) in assignments.iter_mut().enumerate() {
+            let region_start = self.lay_out_region(first_index + i, assignment)?;
+            self.regions.push(region_start.into());
+        }
+
+        // Run the closures in parallel, each recording what it assigns on its
+        // own rows.
+        let regions = &self.regions;
+        let annotate = self.cs.uses_annotations();
+        let assigned = assignments
+            .par_iter_mut()
+            .enumerate()
+            .map(|(i, assignment)| {
+                let mut region =
+                    ParallelRegion::new(regions, first_index, (first_index + i).into(), annotate);
+                let result = {
+                    let region: &mut dyn RegionLayouter<F> = &mut region;
+                    assignment(region.into())
+                }?;
+                Ok((result, region.ops, region.constants))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // Apply the assignments to the constraint system in order.
+        let mut results = Vec::with_capacity(assigned.len());
+        for (result, ops, constants_to_assign) in assigned {
+            self.cs.enter_region(&name);
+            for op in ops {
+                match op {
+                    RegionOp::EnableSelector {
+                        annotation,
+                        selector,
+                        row,
+                    } => self.cs.enable_selector(|| annotation, &selector, row)?,
+                    RegionOp::NameColumn { annotation, column } => {
+                        self.cs.annotate_column(|| annotation, column)
+                    }
+                    RegionOp::Advice {
+                        annotation,
+                        column,
+                        row,
+                        value,
+                    } => self
+                        .cs
+                        .assign_advice(|| annotation, column, row, || value)?,
+                    RegionOp::Fixed {
+                        annotation,
+                        column,
+                        row,
+                        value,
+                    } => self.cs.assign_fixed(|| annotation, column, row, || value)?,
+                    RegionOp::Copy {
+                        left_column,
+                        left_row,
+                        right_column,
+                        right_row,
+                    } => self
+                        .cs
+                        .copy(left_column, left_row, right_column, right_row)?,
+                }
+            }
+            self.cs.exit_region();
+            self.assign_constants(constants_to_assign)?;
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+
     fn assign_table<A, N, NR>(&mut self, name: N, mut assignment: A) -> Result<(), Error>
     where
         A: FnMut(Table<'_, F>) -> Result<(), Error>,
@@ -382,6 +490,212 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> RegionLayouter<F>
     }
 }
 
+/// An assignment recorded by a [`ParallelRegion`], at an absolute row
+#[derive(Debug)]
+enum RegionOp<F: Field> {
+    EnableSelector {
+        annotation: String,
+        selector: Selector,
+        row: usize,
+    },
+    NameColumn {
+        annotation: String,
+        column: Column<Any>,
+    },
+    Advice {
+        annotation: String,
+        column: Column<Advice>,
+        row: usize,
+        value: Value<Assigned<F>>,
+    },
+    Fixed {
+        annotation: String,
+        column: Column<Fixed>,
+        row: usize,
+        value: Value<Assigned<F>>,
+    },
+    Copy {
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    },
+}
+
+/// A region assigned by [`Layouter::assign_regions`]. It computes the values
+/// of its cells and records its assignments, which are applied to the
+/// constraint system once all the regions are done.
+#[derive(Debug)]
+struct ParallelRegion<'r, F: Field> {
+    /// The starting row of each region laid out so far
+    regions: &'r [RegionStart],
+    /// The index of the first region assigned in parallel with this one
+    first_index: usize,
+    region_index: RegionIndex,
+    /// Whether the constraint system uses annotations, which are otherwise
+    /// left empty rather than evaluated on every assignment
+    annotate: bool,
+    ops: Vec<RegionOp<F>>,
+    /// Stores the constants to be assigned, and the cells to which they are copied.
+    constants: Vec<(Assigned<F>, Cell)>,
+}
+
+impl<'r, F: Field> ParallelRegion<'r, F> {
+    fn new(
+        regions: &'r [RegionStart],
+        first_index: usize,
+        region_index: RegionIndex,
+        annotate: bool,
+    ) -> Self {
+        ParallelRegion {
+            regions,
+            first_index,
+            region_index,
+            annotate,
+            ops: vec![],
+            constants: vec![],
+        }
+    }
+
+    fn row(&self, offset: usize) -> usize {
+        *self.regions[*self.region_index] + offset
+    }
+
+    fn annotation(&self, annotation: &dyn Fn() -> String) -> String {
+        if self.annotate {
+            annotation()
+        } else {
+            String::new()
+        }
+    }
+
+    /// Returns the absolute row of `cell`, which must be in this region or in
+    /// one assigned before the regions assigned in parallel.
+    fn cell_row(&self, cell: Cell) -> Result<usize, Error> {
+        if *cell.region_index >= self.first_index && *cell.region_index != *self.region_index {
+            return Err(Error::Synthesis);
+        }
+        Ok(*self.regions[*cell.region_index] + cell.row_offset)
+    }
+}
+
+impl<'r, F: Field> RegionLayouter<F> for ParallelRegion<'r, F> {
+    fn enable_selector<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        selector: &Selector,
+        offset: usize,
+    ) -> Result<(), Error> {
+        let row = self.row(offset);
+        self.ops.push(RegionOp::EnableSelector {
+            annotation: self.annotation(annotation),
+            selector: *selector,
+            row,
+        });
+        Ok(())
+    }
+
+    fn name_column<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        column: Column<Any>,
+    ) {
+        self.ops.push(RegionOp::NameColumn {
+            annotation: self.annotation(annotation),
+            column,
+        });
+    }
+
+    fn assign_advice<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        column: Column<Advice>,
+        offset: usize,
+        to: &'v mut (dyn FnMut() -> Value<Assigned<F>> + 'v),
+    ) -> Result<Cell, Error> {
+        let row = self.row(offset);
+        self.ops.push(RegionOp::Advice {
+            annotation: self.annotation(annotation),
+            column,
+            row,
+            value: to(),
+        });
+
+        Ok(Cell {
+            region_index: self.region_index,
+            row_offset: offset,
+            column: column.into(),
+        })
+    }
+
+    fn assign_advice_from_constant<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        column: Column<Advice>,
+        offset: usize,
+        constant: Assigned<F>,
+    ) -> Result<Cell, Error> {
+        let advice =
+            self.assign_advice(annotation, column, offset, &mut || Value::known(constant))?;
+        self.constrain_constant(advice, constant)?;
+
+        Ok(advice)
+    }
+
+    fn assign_advice_from_instance<'v>(
+        &mut self,
+        _annotation: &'v (dyn Fn() -> String + 'v),
+        _instance: Column<Instance>,
+        _row: usize,
+        _advice: Column<Advice>,
+        _offset: usize,
+    ) -> Result<(Cell, Value<F>), Error> {
+        // Only the constraint system knows the instance values, and it is not
+        // available to the regions while they are assigned in parallel.
+        Err(Error::Synthesis)
+    }
+
+    fn assign_fixed<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        column: Column<Fixed>,
+        offset: usize,
+        to: &'v mut (dyn FnMut() -> Value<Assigned<F>> + 'v),
+    ) -> Result<Cell, Error> {
+        let row = self.row(offset);
+        self.ops.push(RegionOp::Fixed {
+            annotation: self.annotation(annotation),
+            column,
+            row,
+            value: to(),
+        });
+
+        Ok(Cell {
+            region_index: self.region_index,
+            row_offset: offset,
+            column: column.into(),
+        })
+    }
+
+    fn constrain_constant(&mut self, cell: Cell, constant: Assigned<F>) -> Result<(), Error> {
+        self.constants.push((constant, cell));
+        Ok(())
+    }
+
+    fn constrain_equal(&mut self, left: Cell, right: Cell) -> Result<(), Error> {
+        let left_row = self.cell_row(left)?;
+        let right_row = self.cell_row(right)?;
+        self.ops.push(RegionOp::Copy {
+            left_column: left.column,
+            left_row,
+            right_column: right.column,
+            right_row,
+        });
+
+        Ok(())
+    }
+}
+
 /// The default value to fill a table column with.
 ///
 /// - The outer `Option` tracks whether the value in row 0 of the table column has been
@@ -515,4 +829,166 @@ mod tests {
             Error::NotEnoughColumnsForConstants,
         ));
     }
+
+    #[test]
+    fn assign_regions_in_parallel() {
+        use crate::circuit::{Layouter, Region, Value};
+        use crate::plonk::{ConstraintSystem, Fixed, Selector};
+        use crate::poly::Rotation;
+
+        #[derive(Clone, Copy)]
+        struct SquareConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            c: Column<Advice>,
+            q: Selector,
+        }
+
+        struct SquareCircuit;
+
+        impl Circuit<vesta::Scalar> for SquareCircuit {
+            type Config = SquareConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                SquareCircuit
+            }
+
+            fn configure(meta: &mut ConstraintSystem<vesta::Scalar>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let c = meta.advice_column();
+                let constants: Column<Fixed> = meta.fixed_column();
+                meta.enable_equality(a);
+                meta.enable_equality(c);
+                meta.enable_constant(constants);
+                let q = meta.selector();
+                meta.create_gate("square", |meta| {
+                    let q = meta.query_selector(q);
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    vec![q * (b - a.clone() * a)]
+                });
+                SquareConfig { a, b, c, q }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<vesta::Scalar>,
+            ) -> Result<(), Error> {
+                let two = layouter.assign_region(
+                    || "two",
+                    |mut region| {
+                        region.assign_advice(
+                            || "a",
+                            config.a,
+                            0,
+                            || Value::known(vesta::Scalar::from(2)),
+                        )
+                    },
+                )?;
+
+                let squares = (0..4u64)
+                    .map(|i| {
+                        let two = two.clone();
+                        move |mut region: Region<'_, vesta::Scalar>| {
+                            let value = vesta::Scalar::from(i + 2);
+                            config.q.enable(&mut region, 0)?;
+                            let a = region.assign_advice(
+                                || "a",
+                                config.a,
+                                0,
+                                || Value::known(value),
+                            )?;
+                            region.assign_advice(
+                                || "b",
+                                config.b,
+                                0,
+                                || Value::known(value * value),
+                            )?;
+                            region.assign_advice_from_constant(
+                                || "one",
+                                config.c,
+                                0,
+                                vesta::Scalar::one(),
+                            )?;
+                            if i == 0 {
+                                region.constrain_equal(two.cell(), a.cell())?;
+                            }
+                            Ok(a)
+                        }
+                    })
+                    .collect();
+                let squares = layouter.assign_regions(|| "square", squares)?;
+                assert_eq!(squares.len(), 4);
+
+                Ok(())
+            }
+        }
+
+        let prover = MockProver::run(5, &SquareCircuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn assign_regions_evaluates_annotations_lazily() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::circuit::{Layouter, Region, Value};
+        use crate::plonk::{keygen_vk, ConstraintSystem};
+        use crate::poly::{commitment::ParamsProver, ipa::commitment::ParamsIPA};
+        use halo2curves::pasta::EqAffine;
+
+        static EVALUATED: AtomicUsize = AtomicUsize::new(0);
+
+        fn annotation() -> String {
+            EVALUATED.fetch_add(1, Ordering::SeqCst);
+            "a".to_string()
+        }
+
+        #[derive(Clone, Default)]
+        struct AnnotatedCircuit;
+
+        impl Circuit<vesta::Scalar> for AnnotatedCircuit {
+            type Config = Column<Advice>;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                AnnotatedCircuit
+            }
+
+            fn configure(meta: &mut ConstraintSystem<vesta::Scalar>) -> Self::Config {
+                meta.advice_column()
+            }
+
+            fn synthesize(
+                &self,
+                a: Self::Config,
+                mut layouter: impl Layouter<vesta::Scalar>,
+            ) -> Result<(), Error> {
+                let assignments = (0..4)
+                    .map(|_| {
+                        move |mut region: Region<'_, vesta::Scalar>| {
+                            region.assign_advice(annotation, a, 0, || {
+                                Value::known(vesta::Scalar::one())
+                            })?;
+                            Ok(())
+                        }
+                    })
+                    .collect();
+                layouter.assign_regions(|| "regions", assignments)?;
+                Ok(())
+            }
+        }
+
+        // Keygen ignores annotations, so they are not evaluated
+        let params = ParamsIPA::<EqAffine>::new(4);
+        keygen_vk(&params, &AnnotatedCircuit).unwrap();
+        assert_eq!(EVALUATED.load(Ordering::SeqCst), 0);
+
+        // The mock prover reports them
+        MockProver::run(4, &AnnotatedCircuit, vec![]).unwrap();
+        assert_eq!(EVALUATED.load(Ordering::SeqCst), 4);
+    }
 }
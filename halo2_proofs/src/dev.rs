@@ -955,6 +955,112 @@ impl<F: FieldExt> MockProver<F> {
                         .collect::<Vec<_>>()
                 });
 
+        // Check that the inputs of each shuffle are a permutation of its shuffled values.
+        let shuffle_errors =
+            self.cs
+                .shuffles
+                .iter()
+                .enumerate()
+                .flat_map(|(shuffle_index, shuffle)| {
+                    let load = |expression: &Expression<F>, row| {
+                        expression.evaluate_lazy(
+                            &|scalar| Value::Real(scalar),
+                            &|_| panic!("virtual selectors are removed during optimization"),
+                            &|query| {
+                                let query = self.cs.fixed_queries[query.index];
+                                let column_index = query.0.index();
+                                let rotation = query.1 .0;
+                                self.fixed[column_index]
+                                    [(row as i32 + n + rotation) as usize % n as usize]
+                                    .into()
+                            },
+                            &|query| {
+                                let query = self.cs.advice_queries[query.index];
+                                let column_index = query.0.index();
+                                let rotation = query.1 .0;
+                                self.advice[column_index]
+                                    [(row as i32 + n + rotation) as usize % n as usize]
+                                    .into()
+                            },
+                            &|query| {
+                                let query = self.cs.instance_queries[query.index];
+                                let column_index = query.0.index();
+                                let rotation = query.1 .0;
+                                Value::Real(
+                                    self.instance[column_index]
+                                        [(row as i32 + n + rotation) as usize % n as usize],
+                                )
+                            },
+                            &|challenge| Value::Real(self.challenges[challenge.index()]),
+                            &|a| -a,
+                            &|a, b| a + b,
+                            &|a, b| a * b,
+                            &|a, scalar| a * scalar,
+                            &Value::Real(F::zero()),
+                        )
+                    };
+
+                    assert!(shuffle.shuffle_expressions.len() == shuffle.input_expressions.len());
+
+                    // In the real prover, the shuffle expressions are enforced on every usable
+                    // row, and never on unusable rows, so the inputs are checked on all of them.
+                    let mut inputs: Vec<(Vec<_>, usize)> = self
+                        .usable_rows
+                        .clone()
+                        .map(|input_row| {
+                            let t = shuffle
+                                .input_expressions
+                                .iter()
+                                .map(move |c| load(c, input_row))
+                                .collect();
+
+                            // Also keep track of the original input row, since we're going to sort.
+                            (t, input_row)
+                        })
+                        .collect();
+                    inputs.sort_unstable();
+
+                    let mut shuffled: Vec<Vec<_>> = self
+                        .usable_rows
+                        .clone()
+                        .map(|shuffle_row| {
+                            shuffle
+                                .shuffle_expressions
+                                .iter()
+                                .map(move |c| load(c, shuffle_row))
+                                .collect()
+                        })
+                        .collect();
+                    shuffled.sort_unstable();
+
+                    // Match the sorted inputs against the sorted shuffled values, and report
+                    // the inputs that are left without a match.
+                    let mut i = 0;
+                    inputs
+                        .into_iter()
+                        .filter_map(move |(input, input_row)| {
+                            while i < shuffled.len() && shuffled[i] < input {
+                                i += 1;
+                            }
+                            if i < shuffled.len() && shuffled[i] == input {
+                                i += 1;
+                                None
+                            } else {
+                                Some(VerifyFailure::Shuffle {
+                                    name: shuffle.name,
+                                    shuffle_index,
+                                    location: FailureLocation::find_expressions(
+                                        &self.cs,
+                                        &self.regions,
+                                        input_row,
+                                        shuffle.input_expressions.iter(),
+                                    ),
+                                })
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                });
+
         // Check that permutations preserve the original values of the cells.
         let perm_errors = {
             // Original values of columns involved in the permutation.
@@ -1004,6 +1110,7 @@ impl<F: FieldExt> MockProver<F> {
             .chain(selector_errors)
             .chain(gate_errors)
             .chain(lookup_errors)
+            .chain(shuffle_errors)
             .chain(perm_errors)
             .collect();
         if errors.is_empty() {
@@ -1316,6 +1423,105 @@ impl<F: FieldExt> MockProver<F> {
                         .collect::<Vec<_>>()
                 });
 
+        // Check that the inputs of each shuffle are a permutation of its shuffled values.
+        let shuffle_errors =
+            self.cs
+                .shuffles
+                .iter()
+                .enumerate()
+                .flat_map(|(shuffle_index, shuffle)| {
+                    let load = |expression: &Expression<F>, row| {
+                        expression.evaluate_lazy(
+                            &|scalar| Value::Real(scalar),
+                            &|_| panic!("virtual selectors are removed during optimization"),
+                            &|query| {
+                                self.fixed[query.column_index]
+                                    [(row as i32 + n + query.rotation.0) as usize % n as usize]
+                                    .into()
+                            },
+                            &|query| {
+                                self.advice[query.column_index]
+                                    [(row as i32 + n + query.rotation.0) as usize % n as usize]
+                                    .into()
+                            },
+                            &|query| {
+                                Value::Real(
+                                    self.instance[query.column_index]
+                                        [(row as i32 + n + query.rotation.0) as usize % n as usize],
+                                )
+                            },
+                            &|challenge| Value::Real(self.challenges[challenge.index()]),
+                            &|a| -a,
+                            &|a, b| a + b,
+                            &|a, b| a * b,
+                            &|a, scalar| a * scalar,
+                            &Value::Real(F::zero()),
+                        )
+                    };
+
+                    assert!(shuffle.shuffle_expressions.len() == shuffle.input_expressions.len());
+
+                    // In the real prover, the shuffle expressions are enforced on every usable
+                    // row, and never on unusable rows, so the inputs are checked on all of them.
+                    let mut inputs: Vec<(Vec<_>, usize)> = self
+                        .usable_rows
+                        .clone()
+                        .into_par_iter()
+                        .map(|input_row| {
+                            let t = shuffle
+                                .input_expressions
+                                .iter()
+                                .map(move |c| load(c, input_row))
+                                .collect();
+
+                            // Also keep track of the original input row, since we're going to sort.
+                            (t, input_row)
+                        })
+                        .collect();
+                    inputs.par_sort_unstable();
+
+                    let mut shuffled: Vec<Vec<_>> = self
+                        .usable_rows
+                        .clone()
+                        .into_par_iter()
+                        .map(|shuffle_row| {
+                            shuffle
+                                .shuffle_expressions
+                                .iter()
+                                .map(move |c| load(c, shuffle_row))
+                                .collect()
+                        })
+                        .collect();
+                    shuffled.par_sort_unstable();
+
+                    // Match the sorted inputs against the sorted shuffled values, and report
+                    // the inputs that are left without a match.
+                    let mut i = 0;
+                    inputs
+                        .into_iter()
+                        .filter_map(move |(input, input_row)| {
+                            while i < shuffled.len() && shuffled[i] < input {
+                                i += 1;
+                            }
+                            if i < shuffled.len() && shuffled[i] == input {
+                                i += 1;
+                                None
+                            } else {
+                                Some(VerifyFailure::Shuffle {
+                                    name: shuffle.name,
+                                    shuffle_index,
+                                    location: FailureLocation::find_expressions(
+                                        &self.cs,
+                                        &self.regions,
+                                        input_row,
+                                        shuffle.input_expressions.iter(),
+                                    ),
+                                })
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                });
+
         // Check that permutations preserve the original values of the cells.
         let perm_errors = {
             // Original values of columns involved in the permutation.
@@ -1369,6 +1575,7 @@ impl<F: FieldExt> MockProver<F> {
             .chain(selector_errors)
             .chain(gate_errors)
             .chain(lookup_errors)
+            .chain(shuffle_errors)
             .chain(perm_errors)
             .collect();
         if errors.is_empty() {
@@ -1472,8 +1679,9 @@ mod tests {
     use crate::{
         circuit::{Layouter, SimpleFloorPlanner, Value},
         plonk::{
-            sealed::SealedPhase, Advice, Any, Circuit, Column, ConstraintSystem, Error, Expression,
-            FirstPhase, Fixed, Instance, Selector, TableColumn,
+            sealed::SealedPhase, Advice, Any, Challenge, Circuit, Column, ConstraintSystem,
+            DynamicTable, Error, Expression, FirstPhase, Fixed, Instance, NthPhase, Selector,
+            TableColumn,
         },
         poly::Rotation,
     };
@@ -1563,6 +1771,92 @@ mod tests {
         );
     }
 
+    #[test]
+    fn many_phases() {
+        const K: u32 = 4;
+        const PHASES: u8 = 5;
+
+        #[derive(Clone)]
+        struct ChainConfig {
+            columns: Vec<Column<Advice>>,
+            challenges: Vec<Challenge>,
+            q: Selector,
+        }
+
+        // Each column holds the previous one times the challenge squeezed
+        // after the phase of the previous one
+        struct ChainCircuit;
+
+        impl Circuit<Fp> for ChainCircuit {
+            type Config = ChainConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let mut columns = vec![];
+                let mut challenges = vec![];
+                for phase in 0..PHASES {
+                    columns.push(meta.advice_column_in(NthPhase(phase)));
+                    if phase + 1 < PHASES {
+                        challenges.push(meta.challenge_usable_after(NthPhase(phase)));
+                    }
+                }
+                let q = meta.selector();
+
+                meta.create_gate("chain", |cells| {
+                    let q = cells.query_selector(q);
+                    (1..columns.len())
+                        .map(|i| {
+                            let prev = cells.query_advice(columns[i - 1], Rotation::cur());
+                            let cur = cells.query_advice(columns[i], Rotation::cur());
+                            let challenge = cells.query_challenge(challenges[i - 1]);
+                            q.clone() * (cur - prev * challenge)
+                        })
+                        .collect::<Vec<_>>()
+                });
+
+                ChainConfig {
+                    columns,
+                    challenges,
+                    q,
+                }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let challenges: Vec<_> = config
+                    .challenges
+                    .iter()
+                    .map(|challenge| layouter.get_challenge(*challenge))
+                    .collect();
+                layouter.assign_region(
+                    || "chain",
+                    |mut region| {
+                        config.q.enable(&mut region, 0)?;
+                        let mut value = Value::known(Fp::from(3));
+                        for (i, column) in config.columns.iter().enumerate() {
+                            if i > 0 {
+                                value = value * challenges[i - 1];
+                            }
+                            region.assign_advice(|| "value", *column, 0, || value)?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &ChainCircuit, vec![]).unwrap();
+        assert_eq!(prover.cs.num_phases(), PHASES as usize);
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
     #[test]
     fn bad_lookup_any() {
         const K: u32 = 4;
@@ -1733,6 +2027,187 @@ mod tests {
         );
     }
 
+    #[test]
+    fn bad_dynamic_lookup() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct FaultyCircuitConfig {
+            a: Column<Advice>,
+            q: Selector,
+            table: DynamicTable,
+        }
+
+        struct FaultyCircuit {}
+
+        impl Circuit<Fp> for FaultyCircuit {
+            type Config = FaultyCircuitConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let q = meta.complex_selector();
+                let t = meta.advice_column();
+                let table = meta.create_dynamic_table("table", &[t]);
+
+                meta.lookup_dynamic("lookup", &table, |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let q = cells.query_selector(q);
+                    (q, vec![a])
+                });
+
+                FaultyCircuitConfig { a, q, table }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {}
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "Table",
+                    |mut region| {
+                        for (offset, value) in [1, 2, 4, 6, 5].into_iter().enumerate() {
+                            region.assign_advice(
+                                || "table",
+                                config.table.columns()[0],
+                                offset,
+                                || Value::known(Fp::from(value)),
+                            )?;
+                            // The last row holds 5, but is not part of the table.
+                            if offset < 4 {
+                                config.table.selector().enable(&mut region, offset)?;
+                            }
+                        }
+
+                        Ok(())
+                    },
+                )?;
+
+                let mut assign = |name: &'static str, values: [u64; 2]| {
+                    layouter.assign_region(
+                        || name,
+                        |mut region| {
+                            for (offset, value) in values.into_iter().enumerate() {
+                                config.q.enable(&mut region, offset)?;
+                                region.assign_advice(
+                                    || "a",
+                                    config.a,
+                                    offset,
+                                    || Value::known(Fp::from(value)),
+                                )?;
+                            }
+
+                            Ok(())
+                        },
+                    )
+                };
+                assign("Good synthesis", [2, 6])?;
+                // BUG: 5 is in the table column, but on a disabled row.
+                assign("Faulty synthesis", [4, 5])
+            }
+        }
+
+        let prover = MockProver::run(K, &FaultyCircuit {}, vec![]).unwrap();
+        assert_eq!(
+            prover.verify(),
+            Err(vec![VerifyFailure::Lookup {
+                name: "lookup",
+                lookup_index: 0,
+                location: FailureLocation::InRegion {
+                    region: (2, "Faulty synthesis").into(),
+                    offset: 1,
+                }
+            }])
+        );
+    }
+
+    #[test]
+    fn bad_shuffle() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct FaultyCircuitConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            q: Selector,
+        }
+
+        struct FaultyCircuit {}
+
+        impl Circuit<Fp> for FaultyCircuit {
+            type Config = FaultyCircuitConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let q = meta.complex_selector();
+
+                meta.shuffle("shuffle", |cells| {
+                    let q = cells.query_selector(q);
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let b = cells.query_advice(b, Rotation::cur());
+
+                    // On the rows where q is enabled, b must be a permutation of a.
+                    vec![(q.clone() * a, q * b)]
+                });
+
+                FaultyCircuitConfig { a, b, q }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {}
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "Faulty synthesis",
+                    |mut region| {
+                        // BUG: a = 2 is shuffled into b = 5.
+                        for (offset, (a, b)) in [(1, 3), (2, 1), (3, 5)].into_iter().enumerate() {
+                            config.q.enable(&mut region, offset)?;
+                            region.assign_advice(
+                                || "a",
+                                config.a,
+                                offset,
+                                || Value::known(Fp::from(a)),
+                            )?;
+                            region.assign_advice(
+                                || "b",
+                                config.b,
+                                offset,
+                                || Value::known(Fp::from(b)),
+                            )?;
+                        }
+
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &FaultyCircuit {}, vec![]).unwrap();
+        let failure = || VerifyFailure::Shuffle {
+            name: "shuffle",
+            shuffle_index: 0,
+            location: FailureLocation::InRegion {
+                region: (0, "Faulty synthesis").into(),
+                offset: 1,
+            },
+        };
+        assert_eq!(prover.verify(), Err(vec![failure()]));
+        assert_eq!(prover.verify_par(), Err(vec![failure()]));
+    }
+
     #[test]
     fn bad_fixed_lookup() {
         const K: u32 = 4;
@@ -13,8 +13,8 @@ use group::prime::PrimeGroup;
 use crate::{
     circuit::Value,
     plonk::{
-        Advice, Any, Assigned, Assignment, Challenge, Circuit, Column, ConstraintSystem, Error,
-        Fixed, FloorPlanner, Instance, Selector,
+        permutation, Advice, Any, Assigned, Assignment, Challenge, Circuit, Column,
+        ConstraintSystem, Error, Fixed, FloorPlanner, Instance, LookupArgument, Selector,
     },
     poly::Rotation,
 };
@@ -32,10 +32,16 @@ pub struct CircuitCost<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> {
     instance_queries: usize,
     advice_queries: usize,
     fixed_queries: usize,
-    /// Number of lookup arguments.
+    /// Number of lookup arguments, counting each batch of lookups once.
     lookups: usize,
+    /// The argument proving the lookups.
+    lookup_argument: LookupArgument,
+    /// Number of shuffle arguments.
+    shuffles: usize,
     /// Number of columns in the global permutation.
     permutation_cols: usize,
+    /// Number of columns in each grand product of the global permutation.
+    permutation_chunk_len: usize,
     /// Number of distinct sets of points in the multiopening argument.
     point_sets: usize,
 
@@ -59,6 +65,10 @@ impl<F: Field> Assignment<F> for Assembly {
         // Do nothing; we don't care about regions in this context.
     }
 
+    fn uses_annotations(&self) -> bool {
+        false
+    }
+
     fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
     where
         A: FnOnce() -> AR,
@@ -193,15 +203,29 @@ impl<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> CircuitCost<G, Concrete
         }
 
         // Include lookup polynomials in point sets:
-        point_sets.insert(vec![0, 1]); // product_poly
-        point_sets.insert(vec![-1, 0]); // permuted_input_poly
-        point_sets.insert(vec![0]); // permuted_table_poly
+        match cs.lookup_argument {
+            LookupArgument::Permutation => {
+                point_sets.insert(vec![0, 1]); // product_poly
+                point_sets.insert(vec![-1, 0]); // permuted_input_poly
+                point_sets.insert(vec![0]); // permuted_table_poly
+            }
+            LookupArgument::LogDerivative => {
+                point_sets.insert(vec![0, 1]); // sum_poly
+                point_sets.insert(vec![0]); // multiplicities_poly
+            }
+        }
+
+        // Include shuffle polynomials in point sets.
+        if !cs.shuffles.is_empty() {
+            point_sets.insert(vec![0, 1]); // product_poly
+        }
 
         // Include permutation polynomials in point sets.
         point_sets.insert(vec![0, 1]); // permutation_product_poly
         let max_deg = cs.degree();
         let permutation_cols = cs.permutation.get_columns().len();
-        if permutation_cols > max_deg - 2 {
+        let permutation_chunk_len = permutation::Argument::chunk_len(max_deg);
+        if permutation_cols > permutation_chunk_len {
             // permutation_product_poly for chaining chunks.
             point_sets.insert(vec![-((cs.blinding_factors() + 1) as i32), 0, 1]);
         }
@@ -213,16 +237,27 @@ impl<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> CircuitCost<G, Concrete
             instance_queries: cs.instance_queries.len(),
             advice_queries: cs.advice_queries.len(),
             fixed_queries: cs.fixed_queries.len(),
-            lookups: cs.lookups.len(),
+            lookups: cs.lookup_batches().len(),
+            lookup_argument: cs.lookup_argument,
+            shuffles: cs.shuffles.len(),
             permutation_cols,
+            permutation_chunk_len,
             point_sets: point_sets.len(),
             _marker: PhantomData::default(),
         }
     }
 
-    fn permutation_chunks(&self) -> usize {
-        let chunk_size = self.max_deg - 2;
-        (self.permutation_cols + chunk_size - 1) / chunk_size
+    /// Returns the number of columns the global permutation packs into each
+    /// of its grand products (see
+    /// [`ConstraintSystem::set_permutation_chunk_len`]).
+    pub fn permutation_chunk_len(&self) -> usize {
+        self.permutation_chunk_len
+    }
+
+    /// Returns the number of grand products of the global permutation, each
+    /// committed to once per instance.
+    pub fn permutation_chunks(&self) -> usize {
+        (self.permutation_cols + self.permutation_chunk_len - 1) / self.permutation_chunk_len
     }
 
     /// Returns the marginal proof size per instance of this circuit.
@@ -238,9 +273,23 @@ impl<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> CircuitCost<G, Concrete
             advice: ProofContribution::new(self.advice_columns, self.advice_queries),
 
             // Lookup arguments:
-            // - 3 commitments per lookup argument per instance
-            // - 5 evals per lookup argument per instance
-            lookups: ProofContribution::new(3 * self.lookups, 5 * self.lookups),
+            // - 3 commitments per lookup argument per instance, or 2 with the
+            //   log-derivative argument
+            // - 5 evals per lookup argument per instance, or 3 with the
+            //   log-derivative argument
+            lookups: match self.lookup_argument {
+                LookupArgument::Permutation => {
+                    ProofContribution::new(3 * self.lookups, 5 * self.lookups)
+                }
+                LookupArgument::LogDerivative => {
+                    ProofContribution::new(2 * self.lookups, 3 * self.lookups)
+                }
+            },
+
+            // Shuffle arguments:
+            // - 1 commitment per shuffle argument per instance
+            // - 2 evals per shuffle argument per instance
+            shuffles: ProofContribution::new(self.shuffles, 2 * self.shuffles),
 
             // Global permutation argument:
             // - chunks commitments per instance
@@ -256,6 +305,10 @@ impl<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> CircuitCost<G, Concrete
         let marginal = self.marginal_proof_size();
 
         ProofSize {
+            // Circuit fingerprint:
+            // - 1 scalar
+            fingerprint: ProofContribution::new(0, 1),
+
             // Cells:
             // - marginal cost per instance
             // - 1 eval per fixed column query
@@ -267,6 +320,10 @@ impl<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> CircuitCost<G, Concrete
             // - marginal cost per instance
             lookups: marginal.lookups * instances,
 
+            // Shuffle arguments:
+            // - marginal cost per instance
+            shuffles: marginal.shuffles * instances,
+
             // Global permutation argument:
             // - marginal cost per instance
             // - 1 eval per column
@@ -343,6 +400,7 @@ pub struct MarginalProofSize<G: PrimeGroup> {
     instance: ProofContribution,
     advice: ProofContribution,
     lookups: ProofContribution,
+    shuffles: ProofContribution,
     equality: ProofContribution,
     _marker: PhantomData<G>,
 }
@@ -355,6 +413,7 @@ impl<G: PrimeGroup> From<MarginalProofSize<G>> for usize {
         proof.instance.len(point, scalar)
             + proof.advice.len(point, scalar)
             + proof.lookups.len(point, scalar)
+            + proof.shuffles.len(point, scalar)
             + proof.equality.len(point, scalar)
     }
 }
@@ -362,10 +421,12 @@ impl<G: PrimeGroup> From<MarginalProofSize<G>> for usize {
 /// The size of a Halo 2 proof, broken down into its contributing factors.
 #[derive(Debug)]
 pub struct ProofSize<G: PrimeGroup> {
+    fingerprint: ProofContribution,
     instance: ProofContribution,
     advice: ProofContribution,
     fixed: ProofContribution,
     lookups: ProofContribution,
+    shuffles: ProofContribution,
     equality: ProofContribution,
     vanishing: ProofContribution,
     multiopen: ProofContribution,
@@ -378,10 +439,12 @@ impl<G: PrimeGroup> From<ProofSize<G>> for usize {
         let point = G::Repr::default().as_ref().len();
         let scalar = <G::Scalar as PrimeField>::Repr::default().as_ref().len();
 
-        proof.instance.len(point, scalar)
+        proof.fingerprint.len(point, scalar)
+            + proof.instance.len(point, scalar)
             + proof.advice.len(point, scalar)
             + proof.fixed.len(point, scalar)
             + proof.lookups.len(point, scalar)
+            + proof.shuffles.len(point, scalar)
             + proof.equality.len(point, scalar)
             + proof.vanishing.len(point, scalar)
             + proof.multiopen.len(point, scalar)
@@ -179,6 +179,17 @@ pub enum VerifyFailure {
         ///   lookup is active on a row adjacent to an unrelated region.
         location: FailureLocation,
     },
+    /// A shuffle input did not match any of the remaining shuffled values.
+    Shuffle {
+        /// The name of the shuffle that is not satisfied.
+        name: &'static str,
+        /// The index of the shuffle that is not satisfied. These indices are assigned in
+        /// the order in which `ConstraintSystem::shuffle` is called during
+        /// `Circuit::configure`.
+        shuffle_index: usize,
+        /// The location of the input that is not matched by a shuffled value.
+        location: FailureLocation,
+    },
     /// A permutation did not preserve the original value of a cell.
     Permutation {
         /// The column in which this permutation is not satisfied.
@@ -242,6 +253,17 @@ impl fmt::Display for VerifyFailure {
                     name, lookup_index, location
                 )
             }
+            Self::Shuffle {
+                name,
+                shuffle_index,
+                location,
+            } => {
+                write!(
+                    f,
+                    "Shuffle {}(index: {}) is not satisfied {}",
+                    name, shuffle_index, location
+                )
+            }
             Self::Permutation { column, location } => {
                 write!(
                     f,
@@ -90,6 +90,10 @@ impl<F: Field> Assignment<F> for Graph {
         // Do nothing; we don't care about regions in this context.
     }
 
+    fn uses_annotations(&self) -> bool {
+        false
+    }
+
     fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, _: usize) -> Result<(), Error>
     where
         A: FnOnce() -> AR,
@@ -417,6 +417,10 @@ impl<F: Field> Assignment<F> for Layout {
         self.current_region = None;
     }
 
+    fn uses_annotations(&self) -> bool {
+        false
+    }
+
     fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
     where
         A: FnOnce() -> AR,
@@ -20,6 +20,9 @@
 #![deny(broken_intra_doc_links)]
 #![deny(missing_debug_implementations)]
 #![deny(missing_docs)]
+// Unsafe code is allowed only in the modules that opt in with
+// `allow(unsafe_code)`: the memory maps of `poly::kzg::mmap` and the
+// volatile writes of `poly::zeroize`.
 #![deny(unsafe_code)]
 // Remove this once we update pasta_curves
 #![allow(unused_imports)]
@@ -28,7 +31,7 @@
 pub mod arithmetic;
 pub mod circuit;
 pub use halo2curves;
-mod multicore;
+pub mod multicore;
 pub mod plonk;
 pub mod poly;
 pub mod transcript;
@@ -1,5 +1,53 @@
 //! An interface for dealing with the kinds of parallel computations involved in
 //! `halo2`. It's currently just a (very!) thin wrapper around [`rayon`] but may
 //! be extended in the future to allow for various parallelism strategies.
+//!
+//! By default all parallel work runs on the global rayon pool, whose size is
+//! controlled by `RAYON_NUM_THREADS`. Use [`with_thread_pool`] to run it on a
+//! pool of the caller's choosing instead.
 
-pub use rayon::{current_num_threads, scope, Scope};
+pub use rayon::{current_num_threads, scope, Scope, ThreadPool, ThreadPoolBuilder};
+
+/// Runs `op` on `pool`, so that all parallel work it does through this
+/// crate, such as [`parallelize`](crate::arithmetic::parallelize), FFTs,
+/// multi-exponentiations and the prover, is bounded to the threads of `pool`
+/// and isolated from the global rayon pool.
+///
+/// ```
+/// use halo2_proofs::multicore::{with_thread_pool, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+/// let threads = with_thread_pool(&pool, || {
+///     // e.g. keygen or create_proof
+///     halo2_proofs::multicore::current_num_threads()
+/// });
+/// assert_eq!(threads, 2);
+/// ```
+pub fn with_thread_pool<R: Send, OP: FnOnce() -> R + Send>(pool: &ThreadPool, op: OP) -> R {
+    pool.install(op)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{with_thread_pool, ThreadPoolBuilder};
+    use crate::arithmetic::parallelize;
+
+    #[test]
+    fn test_with_thread_pool() {
+        let pool = ThreadPoolBuilder::new().num_threads(3).build().unwrap();
+
+        let mut v = vec![0usize; 1000];
+        let threads = with_thread_pool(&pool, || {
+            parallelize(&mut v, |chunk, start| {
+                assert!(rayon::current_thread_index().is_some());
+                for (i, value) in chunk.iter_mut().enumerate() {
+                    *value = start + i;
+                }
+            });
+            super::current_num_threads()
+        });
+
+        assert_eq!(threads, 3);
+        assert!(v.iter().enumerate().all(|(i, value)| i == *value));
+    }
+}
@@ -25,11 +25,15 @@ mod assigned;
 mod circuit;
 mod error;
 mod evaluation;
+pub mod folding;
 mod keygen;
 mod lookup;
+mod observer;
 pub(crate) mod permutation;
+mod shuffle;
 mod vanishing;
 
+mod proof;
 mod prover;
 mod verifier;
 
@@ -37,17 +41,34 @@ pub use assigned::*;
 pub use circuit::*;
 pub use error::*;
 pub use keygen::*;
+pub use observer::*;
+pub use proof::*;
 pub use prover::*;
 pub use verifier::*;
 
-use evaluation::Evaluator;
+use evaluation::{Evaluator, FixedExpressionCosets};
 use std::io;
+use std::sync::Arc;
+
+/// The flags of a serialized verifying key are kept in the top byte of its
+/// `k`, and are all unset for a key in the default layout.
+const VK_FLAGS: u32 = 0xff00_0000;
+/// Set if each selector was converted into a fixed column of its own.
+const VK_FLAG_UNCOMPRESSED_SELECTORS: u32 = 1 << 31;
+/// Set if the extended domain is not the coset generated by $\zeta$, whose
+/// generator then follows `k`.
+const VK_FLAG_COSET: u32 = 1 << 30;
+
+/// The version of the canonical encoding of the constraint system hashed
+/// into [`VerifyingKey::fingerprint`], to be bumped whenever the fingerprint
+/// of a circuit changes.
+const FINGERPRINT_VERSION: u8 = 2;
 
 /// This is a verifying key which allows for the verification of proofs for a
 /// particular circuit.
 #[derive(Clone, Debug)]
 pub struct VerifyingKey<C: CurveAffine> {
-    domain: EvaluationDomain<C::Scalar>,
+    domain: Arc<EvaluationDomain<C::Scalar>>,
     fixed_commitments: Vec<C>,
     permutation: permutation::VerifyingKey<C>,
     cs: ConstraintSystem<C::Scalar>,
@@ -55,7 +76,12 @@ pub struct VerifyingKey<C: CurveAffine> {
     cs_degree: usize,
     /// The representative of this `VerifyingKey` in transcripts.
     transcript_repr: C::Scalar,
+    /// The fingerprint of `cs`, written at the start of each proof.
+    fingerprint: C::Scalar,
     selectors: Vec<Vec<bool>>,
+    /// Whether the selectors were combined into fewer fixed columns, or
+    /// each converted into a fixed column of its own.
+    compress_selectors: bool,
 }
 
 impl<C: SerdeCurveAffine> VerifyingKey<C>
@@ -71,8 +97,26 @@ where
     /// - Otherwise: Writes an uncompressed curve element with coordinates in Montgomery form
     /// Writes a field element into raw bytes in its internal Montgomery representation,
     /// WITHOUT performing the expensive Montgomery reduction.
+    ///
+    /// A key that does not compress its selectors (see
+    /// [`KeygenOptions::without_selector_compression`]) is flagged in the top
+    /// bit of `k`, and one with a custom coset (see
+    /// [`KeygenOptions::with_coset`]) in the next bit, followed by the
+    /// generator of its coset; other keys are written in the layout of
+    /// earlier versions.
     pub fn write<W: io::Write>(&self, writer: &mut W, format: SerdeFormat) -> io::Result<()> {
-        writer.write_all(&self.domain.k().to_be_bytes())?;
+        let mut k = self.domain.k();
+        if !self.compress_selectors {
+            k |= VK_FLAG_UNCOMPRESSED_SELECTORS;
+        }
+        let g_coset = self.g_coset();
+        if g_coset.is_some() {
+            k |= VK_FLAG_COSET;
+        }
+        writer.write_all(&k.to_be_bytes())?;
+        if let Some(g_coset) = g_coset {
+            g_coset.write(writer, format)?;
+        }
         writer.write_all(&(self.fixed_commitments.len() as u32).to_be_bytes())?;
         for commitment in &self.fixed_commitments {
             commitment.write(writer, format)?;
@@ -106,7 +150,22 @@ where
         let mut k = [0u8; 4];
         reader.read_exact(&mut k)?;
         let k = u32::from_be_bytes(k);
-        let (domain, cs, _) = keygen::create_domain::<C, ConcreteCircuit>(k);
+        let flags = k & VK_FLAGS;
+        if flags & !(VK_FLAG_UNCOMPRESSED_SELECTORS | VK_FLAG_COSET) != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected verifying key flags",
+            ));
+        }
+        let compress_selectors = flags & VK_FLAG_UNCOMPRESSED_SELECTORS == 0;
+        let k = k & !VK_FLAGS;
+        let g_coset = if flags & VK_FLAG_COSET != 0 {
+            Some(C::Scalar::read(reader, format)?)
+        } else {
+            None
+        };
+        let (domain, cs, _) = keygen::create_domain::<C, ConcreteCircuit>(k, None, g_coset)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid coset generator"))?;
         let mut num_fixed_columns = [0u8; 4];
         reader.read_exact(&mut num_fixed_columns)?;
         let num_fixed_columns = u32::from_be_bytes(num_fixed_columns);
@@ -129,7 +188,11 @@ where
                 Ok(selector)
             })
             .collect::<io::Result<_>>()?;
-        let (cs, _) = cs.compress_selectors(selectors.clone());
+        let (cs, _) = if compress_selectors {
+            cs.compress_selectors(selectors.clone())
+        } else {
+            cs.directly_convert_selectors_to_fixed(selectors.clone())
+        };
 
         Ok(Self::from_parts(
             domain,
@@ -137,6 +200,7 @@ where
             permutation,
             cs,
             selectors,
+            compress_selectors,
         ))
     }
 
@@ -158,7 +222,11 @@ where
 
 impl<C: CurveAffine> VerifyingKey<C> {
     fn bytes_length(&self) -> usize {
-        8 + (self.fixed_commitments.len() * C::default().to_bytes().as_ref().len())
+        8 + self
+            .g_coset()
+            .map(|g_coset| g_coset.to_repr().as_ref().len())
+            .unwrap_or(0)
+            + (self.fixed_commitments.len() * C::default().to_bytes().as_ref().len())
             + self.permutation.bytes_length()
             + self.selectors.len()
                 * (self
@@ -169,11 +237,12 @@ impl<C: CurveAffine> VerifyingKey<C> {
     }
 
     fn from_parts(
-        domain: EvaluationDomain<C::Scalar>,
+        domain: Arc<EvaluationDomain<C::Scalar>>,
         fixed_commitments: Vec<C>,
         permutation: permutation::VerifyingKey<C>,
         cs: ConstraintSystem<C::Scalar>,
         selectors: Vec<Vec<bool>>,
+        compress_selectors: bool,
     ) -> Self {
         // Compute cached values.
         let cs_degree = cs.degree();
@@ -186,7 +255,9 @@ impl<C: CurveAffine> VerifyingKey<C> {
             cs_degree,
             // Temporary, this is not pinned.
             transcript_repr: C::Scalar::zero(),
+            fingerprint: C::Scalar::zero(),
             selectors,
+            compress_selectors,
         };
 
         let mut hasher = Blake2bParams::new()
@@ -202,6 +273,26 @@ impl<C: CurveAffine> VerifyingKey<C> {
         // Hash in final Blake2bState
         vk.transcript_repr = C::Scalar::from_bytes_wide(hasher.finalize().as_array());
 
+        let mut hasher = Blake2bParams::new()
+            .hash_length(64)
+            .personal(b"Halo2-Circuit-FP")
+            .to_state();
+
+        // The fingerprint commits to the constraint system only, so that it
+        // does not depend on the params or on the fixed assignments, behind a
+        // version of its own encoding
+        let mut encoding = vec![];
+        vk.cs
+            .pinned()
+            .write_canonical(&mut encoding)
+            .expect("Writing to vector should not fail");
+
+        hasher.update(&[FINGERPRINT_VERSION]);
+        hasher.update(&(encoding.len() as u64).to_le_bytes());
+        hasher.update(&encoding);
+
+        vk.fingerprint = C::Scalar::from_bytes_wide(hasher.finalize().as_array());
+
         vk
     }
 
@@ -215,6 +306,22 @@ impl<C: CurveAffine> VerifyingKey<C> {
         Ok(())
     }
 
+    /// Returns the fingerprint of the circuit: a versioned hash of its
+    /// constraint system, i.e. its columns, gates, lookups, shuffles and
+    /// permutation columns. It is the same for keys generated with different
+    /// params, or with other fixed assignments. It is written at the start of
+    /// each proof, so that [`verify_proof`] rejects a proof for another
+    /// circuit with [`Error::CircuitMismatch`] before checking any of it.
+    pub fn fingerprint(&self) -> C::Scalar {
+        self.fingerprint
+    }
+
+    /// Returns the generator of the coset of the extended domain, unless it
+    /// is the default $\zeta$.
+    fn g_coset(&self) -> Option<C::Scalar> {
+        Some(self.domain.get_coset_generator()).filter(|g_coset| *g_coset != C::Scalar::ZETA)
+    }
+
     /// Obtains a pinned representation of this verification key that contains
     /// the minimal information necessary to reconstruct the verification key.
     pub fn pinned(&self) -> PinnedVerificationKey<'_, C> {
@@ -225,6 +332,7 @@ impl<C: CurveAffine> VerifyingKey<C> {
             fixed_commitments: &self.fixed_commitments,
             permutation: &self.permutation,
             cs: self.cs.pinned(),
+            compress_selectors: self.compress_selectors,
         }
     }
 
@@ -242,12 +350,18 @@ impl<C: CurveAffine> VerifyingKey<C> {
     pub fn cs(&self) -> &ConstraintSystem<C::Scalar> {
         &self.cs
     }
+
+    /// Returns whether the selectors were compressed into fewer fixed columns
+    /// at keygen (see [`KeygenOptions::without_selector_compression`]).
+    /// Otherwise, the fixed columns of the selectors follow those of the
+    /// circuit, one for each selector in the order they were allocated.
+    pub fn compress_selectors(&self) -> bool {
+        self.compress_selectors
+    }
 }
 
 /// Minimal representation of a verification key that can be used to identify
 /// its active contents.
-#[allow(dead_code)]
-#[derive(Debug)]
 pub struct PinnedVerificationKey<'a, C: CurveAffine> {
     base_modulus: &'static str,
     scalar_modulus: &'static str,
@@ -255,6 +369,25 @@ pub struct PinnedVerificationKey<'a, C: CurveAffine> {
     cs: PinnedConstraintSystem<'a, C::Scalar>,
     fixed_commitments: &'a Vec<C>,
     permutation: &'a permutation::VerifyingKey<C>,
+    compress_selectors: bool,
+}
+
+impl<'a, C: CurveAffine> std::fmt::Debug for PinnedVerificationKey<'a, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug_struct = f.debug_struct("PinnedVerificationKey");
+        debug_struct
+            .field("base_modulus", &self.base_modulus)
+            .field("scalar_modulus", &self.scalar_modulus)
+            .field("domain", &self.domain)
+            .field("cs", &self.cs)
+            .field("fixed_commitments", self.fixed_commitments)
+            .field("permutation", self.permutation);
+        // Only show the selector conversion if it is not the default one.
+        if !self.compress_selectors {
+            debug_struct.field("compress_selectors", &self.compress_selectors);
+        }
+        debug_struct.finish()
+    }
 }
 /// This is a proving key which allows for the creation of proofs for a
 /// particular circuit.
@@ -269,6 +402,7 @@ pub struct ProvingKey<C: CurveAffine> {
     fixed_cosets: Vec<Polynomial<C::Scalar, ExtendedLagrangeCoeff>>,
     permutation: permutation::ProvingKey<C>,
     ev: Evaluator<C>,
+    fixed_expression_cosets: FixedExpressionCosets<C>,
 }
 
 impl<C: CurveAffine> ProvingKey<C> {
@@ -340,6 +474,7 @@ where
         let fixed_cosets = read_polynomial_vec(reader, format)?;
         let permutation = permutation::ProvingKey::read(reader, format)?;
         let ev = Evaluator::new(vk.cs());
+        let fixed_expression_cosets = ev.evaluate_fixed_expressions(&vk.domain, &fixed_cosets);
         Ok(Self {
             vk,
             l0,
@@ -350,6 +485,7 @@ where
             fixed_cosets,
             permutation,
             ev,
+            fixed_expression_cosets,
         })
     }
 
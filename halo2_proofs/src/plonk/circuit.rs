@@ -1,13 +1,14 @@
 use core::cmp::max;
 use core::ops::{Add, Mul};
-use ff::Field;
+use ff::{Field, PrimeField};
 use std::collections::HashMap;
 use std::{
     convert::TryFrom,
+    io,
     ops::{Neg, Sub},
 };
 
-use super::{lookup, permutation, Assigned, Error};
+use super::{lookup, permutation, shuffle, Assigned, Error};
 use crate::dev::metadata;
 use crate::{
     circuit::{Layouter, Region, Value},
@@ -123,6 +124,19 @@ impl SealedPhase for super::ThirdPhase {
     }
 }
 
+/// The phase with the given index, counting from zero, for circuits that need
+/// more rounds of challenges than [`ThirdPhase`] allows. `NthPhase(0)`,
+/// `NthPhase(1)` and `NthPhase(2)` are [`FirstPhase`], [`SecondPhase`] and
+/// [`ThirdPhase`].
+#[derive(Clone, Copy, Debug)]
+pub struct NthPhase(pub u8);
+
+impl SealedPhase for super::NthPhase {
+    fn to_sealed(self) -> sealed::Phase {
+        sealed::Phase(self.0)
+    }
+}
+
 /// An advice column
 #[derive(Clone, Copy, Eq, PartialEq, Hash)]
 pub struct Advice {
@@ -498,6 +512,38 @@ impl TableColumn {
     }
 }
 
+/// A lookup table stored in advice columns, filled in by the circuit itself
+/// at proving time, together with the selector marking its rows.
+///
+/// Created with [`ConstraintSystem::create_dynamic_table`] and looked up with
+/// [`ConstraintSystem::lookup_dynamic`]. The table holds the rows of its
+/// columns on which its selector is enabled; the circuit must leave at least
+/// one usable row with the selector disabled, so that inactive inputs have a
+/// row to match (see [`ConstraintSystem::lookup_dynamic`]).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DynamicTable {
+    name: &'static str,
+    selector: Selector,
+    columns: Vec<Column<Advice>>,
+}
+
+impl DynamicTable {
+    /// Name of this table.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Selector enabling the rows of this table.
+    pub fn selector(&self) -> Selector {
+        self.selector
+    }
+
+    /// Advice columns holding this table.
+    pub fn columns(&self) -> &[Column<Advice>] {
+        &self.columns
+    }
+}
+
 /// A challenge squeezed from transcript after advice columns at the phase have been committed.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct Challenge {
@@ -549,6 +595,15 @@ pub trait Assignment<F: Field> {
     /// [`Layouter::assign_region`]: crate::circuit::Layouter#method.assign_region
     fn exit_region(&mut self);
 
+    /// Returns whether this backend uses the annotations it is given. If not,
+    /// [`Layouter::assign_regions`] passes it empty annotations instead of
+    /// evaluating and storing them.
+    ///
+    /// [`Layouter::assign_regions`]: crate::circuit::Layouter#method.assign_regions
+    fn uses_annotations(&self) -> bool {
+        true
+    }
+
     /// Enables a selector at the given row.
     fn enable_selector<A, AR>(
         &mut self,
@@ -995,6 +1050,62 @@ impl<F: Field> Expression<F> {
         }
     }
 
+    /// Writes a canonical encoding of this expression, which unlike its
+    /// identifier does not go through the `Debug` representation of the field.
+    fn write_canonical<W: io::Write>(&self, writer: &mut W) -> io::Result<()>
+    where
+        F: PrimeField,
+    {
+        match self {
+            Expression::Constant(scalar) => {
+                writer.write_all(&[0])?;
+                writer.write_all(scalar.to_repr().as_ref())
+            }
+            Expression::Selector(selector) => {
+                writer.write_all(&[1, selector.1 as u8])?;
+                write_usize(writer, selector.0)
+            }
+            Expression::Fixed(query) => {
+                writer.write_all(&[2])?;
+                write_usize(writer, query.column_index)?;
+                writer.write_all(&query.rotation.0.to_le_bytes())
+            }
+            Expression::Advice(query) => {
+                writer.write_all(&[3, query.phase.0])?;
+                write_usize(writer, query.column_index)?;
+                writer.write_all(&query.rotation.0.to_le_bytes())
+            }
+            Expression::Instance(query) => {
+                writer.write_all(&[4])?;
+                write_usize(writer, query.column_index)?;
+                writer.write_all(&query.rotation.0.to_le_bytes())
+            }
+            Expression::Challenge(challenge) => {
+                writer.write_all(&[5, challenge.phase.0])?;
+                write_usize(writer, challenge.index)
+            }
+            Expression::Negated(a) => {
+                writer.write_all(&[6])?;
+                a.write_canonical(writer)
+            }
+            Expression::Sum(a, b) => {
+                writer.write_all(&[7])?;
+                a.write_canonical(writer)?;
+                b.write_canonical(writer)
+            }
+            Expression::Product(a, b) => {
+                writer.write_all(&[8])?;
+                a.write_canonical(writer)?;
+                b.write_canonical(writer)
+            }
+            Expression::Scaled(a, f) => {
+                writer.write_all(&[9])?;
+                a.write_canonical(writer)?;
+                writer.write_all(f.to_repr().as_ref())
+            }
+        }
+    }
+
     /// Identifier for this expression. Expressions with identical identifiers
     /// do the same calculation (but the expressions don't need to be exactly equal
     /// in how they are composed e.g. `1 + 2` and `2 + 1` can have the same identifier).
@@ -1353,6 +1464,26 @@ impl<F: Field> Gate<F> {
     }
 }
 
+/// The argument proving the lookups of a circuit, chosen at configure time with
+/// [`ConstraintSystem::set_lookup_argument`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LookupArgument {
+    /// The argument of the original Halo 2 protocol, committing to a permuted
+    /// input column, a permuted table column and a grand product for each
+    /// lookup
+    Permutation,
+    /// The logarithmic-derivative (logUp) argument, committing to the
+    /// multiplicity of each table row and a running sum of fractions for each
+    /// lookup, which saves a column per lookup and the sorting of the inputs
+    LogDerivative,
+}
+
+impl Default for LookupArgument {
+    fn default() -> Self {
+        LookupArgument::Permutation
+    }
+}
+
 /// This is a description of the circuit environment, such as the gate, column and
 /// permutation arrangements.
 #[derive(Debug, Clone)]
@@ -1389,6 +1520,17 @@ pub struct ConstraintSystem<F: Field> {
     // input expressions and a sequence of table expressions involved in the lookup.
     pub(crate) lookups: Vec<lookup::Argument<F>>,
 
+    // The argument proving all the lookups of the circuit.
+    pub(crate) lookup_argument: LookupArgument,
+
+    // Whether the log-derivative argument proves lookups into the same table
+    // together.
+    pub(crate) batch_lookups: bool,
+
+    // Vector of shuffle arguments, where each corresponds to a sequence of
+    // input expressions and a sequence of shuffle expressions involved in the shuffle.
+    pub(crate) shuffles: Vec<shuffle::Argument<F>>,
+
     // List of indexes of Fixed columns which are associated to a circuit-general Column tied to their annotation.
     pub(crate) general_column_annotations: HashMap<metadata::Column, String>,
 
@@ -1397,6 +1539,14 @@ pub struct ConstraintSystem<F: Field> {
     pub(crate) constants: Vec<Column<Fixed>>,
 
     pub(crate) minimum_degree: Option<usize>,
+
+    // The number of columns the permutation argument packs into each grand
+    // product, if it is not derived from the degree of the gates.
+    pub(crate) permutation_chunk_len: Option<usize>,
+
+    // The number of blinding rows at the end of each column, if it is not
+    // derived from the queries.
+    pub(crate) blinding_factors: Option<usize>,
 }
 
 /// Represents the minimal parameters that determine a `ConstraintSystem`.
@@ -1415,8 +1565,13 @@ pub struct PinnedConstraintSystem<'a, F: Field> {
     fixed_queries: &'a Vec<(Column<Fixed>, Rotation)>,
     permutation: &'a permutation::Argument,
     lookups: &'a Vec<lookup::Argument<F>>,
+    lookup_argument: &'a LookupArgument,
+    batch_lookups: &'a bool,
+    shuffles: &'a Vec<shuffle::Argument<F>>,
     constants: &'a Vec<Column<Fixed>>,
     minimum_degree: &'a Option<usize>,
+    permutation_chunk_len: &'a Option<usize>,
+    blinding_factors: &'a Option<usize>,
 }
 
 impl<'a, F: Field> std::fmt::Debug for PinnedConstraintSystem<'a, F> {
@@ -1440,13 +1595,140 @@ impl<'a, F: Field> std::fmt::Debug for PinnedConstraintSystem<'a, F> {
             .field("instance_queries", self.instance_queries)
             .field("fixed_queries", self.fixed_queries)
             .field("permutation", self.permutation)
-            .field("lookups", self.lookups)
+            .field("lookups", self.lookups);
+        // Only show the lookup argument if it is not the default one.
+        if *self.lookup_argument != LookupArgument::default() {
+            debug_struct.field("lookup_argument", self.lookup_argument);
+        }
+        // Only show lookup batching if it is enabled.
+        if *self.batch_lookups {
+            debug_struct.field("batch_lookups", self.batch_lookups);
+        }
+        // Only show the shuffles if there are any.
+        if !self.shuffles.is_empty() {
+            debug_struct.field("shuffles", self.shuffles);
+        }
+        debug_struct
             .field("constants", self.constants)
             .field("minimum_degree", self.minimum_degree);
+        // Only show the permutation chunk length if it was set.
+        if self.permutation_chunk_len.is_some() {
+            debug_struct.field("permutation_chunk_len", self.permutation_chunk_len);
+        }
+        // Only show the number of blinding rows if it was set.
+        if self.blinding_factors.is_some() {
+            debug_struct.field("blinding_factors", self.blinding_factors);
+        }
         debug_struct.finish()
     }
 }
 
+impl<'a, F: PrimeField> PinnedConstraintSystem<'a, F> {
+    /// Writes a canonical encoding of the pinned constraint system, which
+    /// unlike its `Debug` representation only changes with the constraint
+    /// system itself. Each list is prefixed by its length, and each optional
+    /// value by whether it is set.
+    pub(crate) fn write_canonical<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        let expressions = |writer: &mut W, expressions: &[Expression<F>]| -> io::Result<()> {
+            write_usize(writer, expressions.len())?;
+            for expression in expressions {
+                expression.write_canonical(writer)?;
+            }
+            Ok(())
+        };
+        let option = |writer: &mut W, value: &Option<usize>| -> io::Result<()> {
+            match value {
+                Some(value) => {
+                    writer.write_all(&[1])?;
+                    write_usize(writer, *value)
+                }
+                None => writer.write_all(&[0]),
+            }
+        };
+
+        write_usize(writer, *self.num_fixed_columns)?;
+        write_usize(writer, *self.num_advice_columns)?;
+        write_usize(writer, *self.num_instance_columns)?;
+        write_usize(writer, *self.num_selectors)?;
+        write_usize(writer, *self.num_challenges)?;
+        for phases in [self.advice_column_phase, self.challenge_phase] {
+            write_usize(writer, phases.len())?;
+            for phase in phases {
+                writer.write_all(&[phase.0])?;
+            }
+        }
+
+        let polynomials = self
+            .gates
+            .0
+            .iter()
+            .flat_map(|gate| gate.polynomials().iter().cloned())
+            .collect::<Vec<_>>();
+        expressions(writer, &polynomials)?;
+
+        write_usize(writer, self.advice_queries.len())?;
+        for (column, rotation) in self.advice_queries {
+            write_column(writer, &(*column).into())?;
+            writer.write_all(&rotation.0.to_le_bytes())?;
+        }
+        write_usize(writer, self.instance_queries.len())?;
+        for (column, rotation) in self.instance_queries {
+            write_column(writer, &(*column).into())?;
+            writer.write_all(&rotation.0.to_le_bytes())?;
+        }
+        write_usize(writer, self.fixed_queries.len())?;
+        for (column, rotation) in self.fixed_queries {
+            write_column(writer, &(*column).into())?;
+            writer.write_all(&rotation.0.to_le_bytes())?;
+        }
+
+        let columns = self.permutation.get_columns();
+        write_usize(writer, columns.len())?;
+        for column in &columns {
+            write_column(writer, column)?;
+        }
+
+        write_usize(writer, self.lookups.len())?;
+        for lookup in self.lookups {
+            expressions(writer, &lookup.input_expressions)?;
+            expressions(writer, &lookup.table_expressions)?;
+        }
+        writer.write_all(&[
+            match self.lookup_argument {
+                LookupArgument::Permutation => 0,
+                LookupArgument::LogDerivative => 1,
+            },
+            *self.batch_lookups as u8,
+        ])?;
+        write_usize(writer, self.shuffles.len())?;
+        for shuffle in self.shuffles {
+            expressions(writer, &shuffle.input_expressions)?;
+            expressions(writer, &shuffle.shuffle_expressions)?;
+        }
+
+        write_usize(writer, self.constants.len())?;
+        for column in self.constants {
+            write_column(writer, &(*column).into())?;
+        }
+        option(writer, self.minimum_degree)?;
+        option(writer, self.permutation_chunk_len)?;
+        option(writer, self.blinding_factors)
+    }
+}
+
+fn write_usize<W: io::Write>(writer: &mut W, value: usize) -> io::Result<()> {
+    writer.write_all(&(value as u64).to_le_bytes())
+}
+
+fn write_column<W: io::Write>(writer: &mut W, column: &Column<Any>) -> io::Result<()> {
+    match column.column_type() {
+        Any::Advice(advice) => writer.write_all(&[0, advice.phase.0])?,
+        Any::Fixed => writer.write_all(&[1])?,
+        Any::Instance => writer.write_all(&[2])?,
+    }
+    write_usize(writer, column.index())
+}
+
 struct PinnedGates<'a, F: Field>(&'a Vec<Gate<F>>);
 
 impl<'a, F: Field> std::fmt::Debug for PinnedGates<'a, F> {
@@ -1475,9 +1757,14 @@ impl<F: Field> Default for ConstraintSystem<F> {
             instance_queries: Vec::new(),
             permutation: permutation::Argument::new(),
             lookups: Vec::new(),
+            lookup_argument: LookupArgument::default(),
+            batch_lookups: false,
+            shuffles: Vec::new(),
             general_column_annotations: HashMap::new(),
             constants: vec![],
             minimum_degree: None,
+            permutation_chunk_len: None,
+            blinding_factors: None,
         }
     }
 }
@@ -1501,8 +1788,13 @@ impl<F: Field> ConstraintSystem<F> {
             instance_queries: &self.instance_queries,
             permutation: &self.permutation,
             lookups: &self.lookups,
+            lookup_argument: &self.lookup_argument,
+            batch_lookups: &self.batch_lookups,
+            shuffles: &self.shuffles,
             constants: &self.constants,
             minimum_degree: &self.minimum_degree,
+            permutation_chunk_len: &self.permutation_chunk_len,
+            blinding_factors: &self.blinding_factors,
         }
     }
 
@@ -1574,6 +1866,166 @@ impl<F: Field> ConstraintSystem<F> {
         index
     }
 
+    /// Creates a dynamic lookup table over the advice `columns`, whose rows
+    /// are those on which the returned table's selector is enabled.
+    pub fn create_dynamic_table(
+        &mut self,
+        name: &'static str,
+        columns: &[Column<Advice>],
+    ) -> DynamicTable {
+        DynamicTable {
+            name,
+            selector: self.complex_selector(),
+            columns: columns.to_vec(),
+        }
+    }
+
+    /// Add a lookup argument into the dynamic `table`.
+    ///
+    /// `inputs` returns a condition and the input expressions, one for each
+    /// column of the table; on the rows where the condition is one, the
+    /// inputs must match an enabled row of the table. The condition must be
+    /// boolean, which holds for instance for a selector; it may not contain a
+    /// simple selector.
+    ///
+    /// The argument looks up the tuple `(condition, condition * input, ...)`
+    /// into `(selector, selector * column, ...)`. Active inputs therefore only
+    /// match enabled rows of the table, and inactive inputs are all zero, which
+    /// is matched by any usable row on which the table's selector is
+    /// disabled. The circuit must leave such a row, or the lookup fails.
+    pub fn lookup_dynamic(
+        &mut self,
+        name: &'static str,
+        table: &DynamicTable,
+        inputs: impl FnOnce(&mut VirtualCells<'_, F>) -> (Expression<F>, Vec<Expression<F>>),
+    ) -> usize {
+        let mut cells = VirtualCells::new(self);
+        let (condition, inputs) = inputs(&mut cells);
+        if condition.contains_simple_selector() {
+            panic!("expression containing simple selector supplied to lookup argument");
+        }
+        assert_eq!(
+            inputs.len(),
+            table.columns.len(),
+            "dynamic lookup {} has {} inputs for the {} columns of table {}",
+            name,
+            inputs.len(),
+            table.columns.len(),
+            table.name
+        );
+
+        let selector = cells.query_selector(table.selector);
+        let table_map = std::iter::once((condition.clone(), selector.clone()))
+            .chain(
+                inputs
+                    .into_iter()
+                    .zip(table.columns.iter())
+                    .map(|(input, column)| {
+                        let column = cells.query_advice(*column, Rotation::cur());
+                        (condition.clone() * input, selector.clone() * column)
+                    }),
+            )
+            .collect();
+
+        let index = self.lookups.len();
+
+        self.lookups.push(lookup::Argument::new(name, table_map));
+
+        index
+    }
+
+    /// Add a shuffle argument for some input expressions and shuffle expressions.
+    ///
+    /// `shuffle_map` returns a map between input expressions and the shuffle
+    /// expressions they need to match. The argument enforces that, over the
+    /// usable rows, the tuples of inputs are a permutation of the tuples of
+    /// shuffle expressions. Both sides are evaluated on every usable row, so
+    /// conditional shuffles multiply both sides by selectors enabled on the
+    /// same number of rows.
+    pub fn shuffle(
+        &mut self,
+        name: &'static str,
+        shuffle_map: impl FnOnce(&mut VirtualCells<'_, F>) -> Vec<(Expression<F>, Expression<F>)>,
+    ) -> usize {
+        let mut cells = VirtualCells::new(self);
+        let shuffle_map = shuffle_map(&mut cells)
+            .into_iter()
+            .map(|(input, shuffle)| {
+                if input.contains_simple_selector() || shuffle.contains_simple_selector() {
+                    panic!("expression containing simple selector supplied to shuffle argument");
+                }
+
+                (input, shuffle)
+            })
+            .collect();
+
+        let index = self.shuffles.len();
+
+        self.shuffles
+            .push(shuffle::Argument::new(name, shuffle_map));
+
+        index
+    }
+
+    /// Sets the argument proving the lookups of the circuit, which is
+    /// [`LookupArgument::Permutation`] by default. The lookups themselves are
+    /// added in the same way for either argument.
+    pub fn set_lookup_argument(&mut self, argument: LookupArgument) {
+        self.lookup_argument = argument;
+    }
+
+    /// Sets whether lookups into the same table are proven together, which is
+    /// disabled by default.
+    ///
+    /// With [`LookupArgument::LogDerivative`], lookups whose table
+    /// expressions are the same share a single multiplicities column and
+    /// running sum, saving two commitments and three evaluations for each
+    /// lookup batched into another. Lookups are only batched as long as this
+    /// does not raise the degree of the constraint system. This has no effect
+    /// with [`LookupArgument::Permutation`], which proves each lookup on its
+    /// own.
+    pub fn set_lookup_batching(&mut self, batch: bool) {
+        self.batch_lookups = batch;
+    }
+
+    /// Returns the lookups proven by each lookup argument of the proof, in
+    /// order: each lookup on its own or, with [`Self::set_lookup_batching`],
+    /// groups of lookups into the same table.
+    pub(crate) fn lookup_batches(&self) -> Vec<Vec<&lookup::Argument<F>>> {
+        if !self.batch_lookups || self.lookup_argument != LookupArgument::LogDerivative {
+            return self.lookups.iter().map(|lookup| vec![lookup]).collect();
+        }
+
+        // The constraint of a batch has degree 2 + the sum of the input
+        // degrees + the table degree, which must fit in the degree of the
+        // lookups proven on their own.
+        let degree = self.degree();
+        let mut batches: Vec<(String, usize, Vec<&lookup::Argument<F>>)> = vec![];
+        for lookup in self.lookups.iter() {
+            let table: String = lookup
+                .table_expressions
+                .iter()
+                .map(|expression| expression.identifier() + ";")
+                .collect();
+            let batch = batches.iter_mut().find(|(identifier, batch_degree, _)| {
+                *identifier == table && batch_degree + lookup.input_degree() <= degree
+            });
+            match batch {
+                Some((_, batch_degree, batch)) => {
+                    *batch_degree += lookup.input_degree();
+                    batch.push(lookup);
+                }
+                None => batches.push((
+                    table,
+                    2 + lookup.input_degree() + lookup.table_degree(),
+                    vec![lookup],
+                )),
+            }
+        }
+
+        batches.into_iter().map(|(_, _, batch)| batch).collect()
+    }
+
     fn query_fixed_index(&mut self, column: Column<Fixed>, at: Rotation) -> usize {
         // Return existing query, if it exists
         for (index, fixed_query) in self.fixed_queries.iter().enumerate() {
@@ -1683,6 +2135,34 @@ impl<F: Field> ConstraintSystem<F> {
         self.minimum_degree = Some(degree);
     }
 
+    /// Sets the number of columns the permutation argument packs into each
+    /// of its grand products, raising the degree of the circuit to
+    /// `chunk_len + 2` if it is lower.
+    ///
+    /// By default, each grand product holds as many columns as the degree of
+    /// the gates allows, and a circuit with many equality-enabled columns
+    /// commits to one product, and opens it at up to three points, for each
+    /// chunk of them. Packing more columns per chunk trades these for a
+    /// larger quotient polynomial. This has no effect on a circuit whose
+    /// degree already fits larger chunks.
+    ///
+    /// Panics if `chunk_len` is zero.
+    pub fn set_permutation_chunk_len(&mut self, chunk_len: usize) {
+        assert!(chunk_len > 0, "permutation chunks must hold a column");
+        self.permutation_chunk_len = Some(chunk_len);
+    }
+
+    /// Sets the number of blinding rows at the end of each column, in place of
+    /// the number [`Self::blinding_factors`] derives from the queries of the
+    /// circuit. Fewer rows leave more rows usable, but then the proofs are no
+    /// longer zero-knowledge; they remain sound.
+    ///
+    /// Keygen fails with [`Error::NotEnoughRowsAvailable`] if the rows left do
+    /// not meet [`Self::minimum_rows`].
+    pub fn set_blinding_factors(&mut self, factors: usize) {
+        self.blinding_factors = Some(factors);
+    }
+
     /// Creates a new gate.
     ///
     /// # Panics
@@ -1788,7 +2268,50 @@ impl<F: Field> ConstraintSystem<F> {
             .map(|a| a.unwrap())
             .collect::<Vec<_>>();
 
-        fn replace_selectors<F: Field>(
+        self.replace_selectors(&selector_replacements);
+
+        (self, polys)
+    }
+
+    /// Converts each selector into a fixed column of its own, in the order
+    /// of the selectors, in place of [`Self::compress_selectors`]. The
+    /// fixed columns then map one-to-one to the selectors of the circuit, at
+    /// the cost of a commitment and an evaluation for each selector.
+    pub(crate) fn directly_convert_selectors_to_fixed(
+        mut self,
+        selectors: Vec<Vec<bool>>,
+    ) -> (Self, Vec<Vec<F>>) {
+        // The number of provided selector assignments must be the number we
+        // counted for this constraint system.
+        assert_eq!(selectors.len(), self.num_selectors);
+
+        let (polys, selector_replacements): (Vec<_>, Vec<_>) = selectors
+            .into_iter()
+            .map(|activations| {
+                let poly = activations
+                    .into_iter()
+                    .map(|active| if active { F::one() } else { F::zero() })
+                    .collect();
+                let column = self.fixed_column();
+                self.selector_map.push(column);
+                let query = Expression::Fixed(FixedQuery {
+                    index: self.query_fixed_index(column, Rotation::cur()),
+                    column_index: column.index,
+                    rotation: Rotation::cur(),
+                });
+                (poly, query)
+            })
+            .unzip();
+
+        self.replace_selectors(&selector_replacements);
+
+        (self, polys)
+    }
+
+    /// Substitutes `selector_replacements` for the selectors in all gates,
+    /// lookups and shuffles.
+    fn replace_selectors(&mut self, selector_replacements: &[Expression<F>]) {
+        fn replace<F: Field>(
             expr: &mut Expression<F>,
             selector_replacements: &[Expression<F>],
             must_be_nonsimple: bool,
@@ -1818,7 +2341,7 @@ impl<F: Field> ConstraintSystem<F> {
 
         // Substitute selectors for the real fixed columns in all gates
         for expr in self.gates.iter_mut().flat_map(|gate| gate.polys.iter_mut()) {
-            replace_selectors(expr, &selector_replacements, false);
+            replace(expr, selector_replacements, false);
         }
 
         // Substitute non-simple selectors for the real fixed columns in all
@@ -1829,10 +2352,19 @@ impl<F: Field> ConstraintSystem<F> {
                 .iter_mut()
                 .chain(lookup.table_expressions.iter_mut())
         }) {
-            replace_selectors(expr, &selector_replacements, true);
+            replace(expr, selector_replacements, true);
         }
 
-        (self, polys)
+        // Substitute non-simple selectors for the real fixed columns in all
+        // shuffle expressions
+        for expr in self.shuffles.iter_mut().flat_map(|shuffle| {
+            shuffle
+                .input_expressions
+                .iter_mut()
+                .chain(shuffle.shuffle_expressions.iter_mut())
+        }) {
+            replace(expr, selector_replacements, true);
+        }
     }
 
     /// Allocate a new (simple) selector. Simple selectors cannot be added to
@@ -1977,10 +2509,26 @@ impl<F: Field> ConstraintSystem<F> {
 
     /// Compute the degree of the constraint system (the maximum degree of all
     /// constraints).
+    ///
+    /// Only the arguments the circuit actually uses are accounted for, so a
+    /// circuit with low-degree gates and no copy constraints, lookups or
+    /// shuffles gets the smallest extended domain. The degree is at least 2,
+    /// as the vanishing argument commits to at least one piece of the
+    /// quotient polynomial.
     pub fn degree(&self) -> usize {
         // The permutation argument will serve alongside the gates, so must be
-        // accounted for.
-        let mut degree = self.permutation.required_degree();
+        // accounted for unless no columns are involved in it.
+        let mut degree = if self.permutation.columns.is_empty() {
+            2
+        } else {
+            // Each grand product is multiplied by z(X) and the active rows,
+            // on top of its columns.
+            std::cmp::max(
+                self.permutation.required_degree(),
+                self.permutation_chunk_len
+                    .map_or(0, |chunk_len| chunk_len + 2),
+            )
+        };
 
         // The lookup argument also serves alongside the gates and must be accounted
         // for.
@@ -1993,6 +2541,16 @@ impl<F: Field> ConstraintSystem<F> {
                 .unwrap_or(1),
         );
 
+        // So does the shuffle argument.
+        degree = std::cmp::max(
+            degree,
+            self.shuffles
+                .iter()
+                .map(|s| s.required_degree())
+                .max()
+                .unwrap_or(1),
+        );
+
         // Account for each gate to ensure our quotient polynomial is the
         // correct degree and that our extended domain is the right size.
         degree = std::cmp::max(
@@ -2008,8 +2566,13 @@ impl<F: Field> ConstraintSystem<F> {
     }
 
     /// Compute the number of blinding factors necessary to perfectly blind
-    /// each of the prover's witness polynomials.
+    /// each of the prover's witness polynomials, unless it was set with
+    /// [`Self::set_blinding_factors`].
     pub fn blinding_factors(&self) -> usize {
+        if let Some(factors) = self.blinding_factors {
+            return factors;
+        }
+
         // All of the prover's advice columns are evaluated at no more than
         let factors = *self.num_advice_queries.iter().max().unwrap_or(&1);
         // distinct points during gate checks.
@@ -2017,6 +2580,8 @@ impl<F: Field> ConstraintSystem<F> {
         // - The permutation argument witness polynomials are evaluated at most 3 times.
         // - Each lookup argument has independent witness polynomials, and they are
         //   evaluated at most 2 times.
+        // - Each shuffle argument has an independent product polynomial, which is
+        //   evaluated 2 times.
         let factors = std::cmp::max(3, factors);
 
         // Each polynomial is evaluated at most an additional time during
@@ -2068,6 +2633,12 @@ impl<F: Field> ConstraintSystem<F> {
         self.num_challenges
     }
 
+    /// Returns the number of phases, i.e. one more than the latest phase of
+    /// an advice column
+    pub fn num_phases(&self) -> usize {
+        self.phases().count()
+    }
+
     /// Returns phase of advice columns
     pub fn advice_column_phase(&self) -> Vec<u8> {
         self.advice_column_phase
@@ -2111,6 +2682,16 @@ impl<F: Field> ConstraintSystem<F> {
         &self.lookups
     }
 
+    /// Returns shuffle arguments
+    pub fn shuffles(&self) -> &Vec<shuffle::Argument<F>> {
+        &self.shuffles
+    }
+
+    /// Returns the argument proving the lookups
+    pub fn lookup_argument(&self) -> LookupArgument {
+        self.lookup_argument
+    }
+
     /// Returns constants
     pub fn constants(&self) -> &Vec<Column<Fixed>> {
         &self.constants
@@ -2187,3 +2768,71 @@ impl<'a, F: Field> VirtualCells<'a, F> {
         Expression::Challenge(challenge)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ConstraintSystem, Expression};
+    use crate::plonk::permutation::Argument;
+    use crate::poly::{EvaluationDomain, Rotation};
+    use ff::Field;
+    use halo2curves::pasta::Fp;
+
+    #[test]
+    fn test_degree_low_degree_gates() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let a = cs.advice_column();
+        let b = cs.advice_column();
+        cs.create_gate("mul", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::next());
+            vec![a * b - Expression::Constant(Fp::one())]
+        });
+
+        // Without copy constraints the permutation argument does not bound
+        // the degree, and no extended domain beyond 2^k is needed.
+        assert_eq!(cs.degree(), 2);
+        let domain = EvaluationDomain::<Fp>::new(cs.degree() as u32, 4);
+        assert_eq!(domain.extended_k(), domain.k());
+
+        cs.enable_equality(a);
+        assert_eq!(cs.degree(), 3);
+    }
+
+    #[test]
+    fn test_permutation_chunk_len() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        for _ in 0..5 {
+            let column = cs.advice_column();
+            cs.enable_equality(column);
+        }
+        assert_eq!(Argument::chunk_len(cs.degree()), 1);
+
+        // Packing the columns into two chunks raises the degree
+        cs.set_permutation_chunk_len(3);
+        assert_eq!(cs.degree(), 5);
+        assert_eq!(Argument::chunk_len(cs.degree()), 3);
+
+        // but a degree fitting larger chunks already is left as it is.
+        cs.set_minimum_degree(6);
+        assert_eq!(Argument::chunk_len(cs.degree()), 4);
+    }
+
+    #[test]
+    fn test_set_blinding_factors() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let a = cs.advice_column();
+        cs.create_gate("square", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            vec![a.clone() * a]
+        });
+        assert_eq!(cs.blinding_factors(), 5);
+        let pinned = format!("{:?}", cs.pinned());
+        assert!(!pinned.contains("blinding_factors"));
+
+        cs.set_blinding_factors(1);
+        assert_eq!(cs.blinding_factors(), 1);
+        assert_eq!(cs.minimum_rows(), 4);
+        // The number of blinding rows is part of the verifying key
+        assert_ne!(format!("{:?}", cs.pinned()), pinned);
+    }
+}
@@ -37,6 +37,31 @@ pub enum Error {
     /// The instance sets up a copy constraint involving a column that has not been
     /// included in the permutation.
     ColumnNotInPermutation(Column<Any>),
+    /// The provided witness does not match the circuit parameters.
+    InvalidWitness,
+    /// The witness of the circuit depends on challenges, so it can only be
+    /// generated while creating the proof.
+    WitnessDependsOnChallenges,
+    /// Proof creation was cancelled with a
+    /// [`CancellationToken`](crate::plonk::CancellationToken).
+    Cancelled,
+    /// The proof was created for a circuit whose constraint system differs
+    /// from that of the verifying key (see
+    /// [`VerifyingKey::fingerprint`](crate::plonk::VerifyingKey::fingerprint)).
+    CircuitMismatch,
+    /// The proof was created with another multiopen scheme than that of the
+    /// verifier (see [`Prover::scheme_id`](crate::poly::commitment::Prover::scheme_id)).
+    MultiopenMismatch,
+    /// The constraint system uses challenges, which
+    /// [`folding`](crate::plonk::folding) does not support.
+    ChallengesNotFoldable,
+    /// The parameters only hold what a verifier needs, and cannot commit to
+    /// polynomials (see [`Params::can_commit`](crate::poly::commitment::Params::can_commit)).
+    VerifierOnlyParams,
+    /// The coset generator is zero, or the coset it generates intersects the
+    /// evaluation domain (see
+    /// [`EvaluationDomain::is_valid_coset`](crate::poly::EvaluationDomain::is_valid_coset)).
+    InvalidCoset,
 }
 
 impl From<io::Error> for Error {
@@ -79,6 +104,29 @@ impl fmt::Display for Error {
                 "Column {:?} must be included in the permutation. Help: try applying `meta.enable_equalty` on the column",
                 column
             ),
+            Error::InvalidWitness => write!(f, "Provided witness does not match the circuit"),
+            Error::WitnessDependsOnChallenges => write!(
+                f,
+                "The witness depends on challenges and cannot be generated ahead of the proof"
+            ),
+            Error::Cancelled => write!(f, "Proof creation was cancelled"),
+            Error::CircuitMismatch => write!(
+                f,
+                "The proof was created for a different circuit than the verifying key"
+            ),
+            Error::MultiopenMismatch => write!(
+                f,
+                "The proof was created with a different multiopen scheme than the verifier"
+            ),
+            Error::ChallengesNotFoldable => {
+                write!(f, "Constraint systems using challenges cannot be folded")
+            }
+            Error::VerifierOnlyParams => {
+                write!(f, "Verifier-only parameters cannot commit to polynomials")
+            }
+            Error::InvalidCoset => {
+                write!(f, "The coset must not intersect the evaluation domain")
+            }
         }
     }
 }
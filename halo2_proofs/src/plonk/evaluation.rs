@@ -1,8 +1,10 @@
 use crate::multicore;
 use crate::plonk::lookup::prover::Committed;
 use crate::plonk::permutation::Argument;
-use crate::plonk::{lookup, permutation, AdviceQuery, Any, FixedQuery, InstanceQuery, ProvingKey};
-use crate::poly::Basis;
+use crate::plonk::{
+    lookup, permutation, shuffle, AdviceQuery, Any, FixedQuery, InstanceQuery, ProvingKey,
+};
+use crate::poly::{Basis, Zeroizing};
 use crate::{
     arithmetic::{eval_polynomial, parallelize, CurveAffine, FieldExt},
     poly::{
@@ -26,7 +28,7 @@ use std::{
     ops::{Index, Mul, MulAssign},
 };
 
-use super::{ConstraintSystem, Expression};
+use super::{ConstraintSystem, Expression, LookupArgument};
 
 /// Return the index in the polynomial of size `isize` after rotation `rot`.
 fn get_rotation_idx(idx: usize, rot: i32, rot_scale: i32, isize: i32) -> usize {
@@ -42,6 +44,8 @@ pub enum ValueSource {
     Intermediate(usize),
     /// This is a fixed column
     Fixed(usize, usize),
+    /// This is an expression over the fixed columns evaluated at keygen
+    FixedExpression(usize, usize),
     /// This is an advice (witness) column
     Advice(usize, usize),
     /// This is an instance (external) column
@@ -74,6 +78,7 @@ impl ValueSource {
         constants: &[F],
         intermediates: &[F],
         fixed_values: &[Polynomial<F, B>],
+        fixed_expression_values: &[Polynomial<F, B>],
         advice_values: &[Polynomial<F, B>],
         instance_values: &[Polynomial<F, B>],
         challenges: &[F],
@@ -89,6 +94,9 @@ impl ValueSource {
             ValueSource::Fixed(column_index, rotation) => {
                 fixed_values[*column_index][rotations[*rotation]]
             }
+            ValueSource::FixedExpression(index, rotation) => {
+                fixed_expression_values[*index][rotations[*rotation]]
+            }
             ValueSource::Advice(column_index, rotation) => {
                 advice_values[*column_index][rotations[*rotation]]
             }
@@ -134,6 +142,7 @@ impl Calculation {
         constants: &[F],
         intermediates: &[F],
         fixed_values: &[Polynomial<F, B>],
+        fixed_expression_values: &[Polynomial<F, B>],
         advice_values: &[Polynomial<F, B>],
         instance_values: &[Polynomial<F, B>],
         challenges: &[F],
@@ -149,6 +158,7 @@ impl Calculation {
                 constants,
                 intermediates,
                 fixed_values,
+                fixed_expression_values,
                 advice_values,
                 instance_values,
                 challenges,
@@ -186,6 +196,13 @@ pub struct Evaluator<C: CurveAffine> {
     pub custom_gates: GraphEvaluator<C>,
     ///  Lookups evalution
     pub lookups: Vec<GraphEvaluator<C>>,
+    /// The intermediates holding the input and table terms of each batch of
+    /// lookups of the log-derivative argument
+    pub log_derivative_terms: Vec<(usize, usize)>,
+    ///  Shuffles evalution
+    pub shuffles: Vec<GraphEvaluator<C>>,
+    /// The intermediates holding the input and shuffle terms of each shuffle
+    pub shuffle_terms: Vec<(usize, usize)>,
 }
 
 /// GraphEvaluator
@@ -199,6 +216,21 @@ pub struct GraphEvaluator<C: CurveAffine> {
     pub calculations: Vec<CalculationInfo>,
     /// Number of intermediates
     pub num_intermediates: usize,
+    /// Subexpressions over the fixed columns only, which are the same for
+    /// every proof and are therefore evaluated once at keygen
+    pub fixed_expressions: Vec<Expression<C::ScalarExt>>,
+}
+
+/// The evaluations on the extended domain of the
+/// [`GraphEvaluator::fixed_expressions`] of each graph of an [`Evaluator`]
+#[derive(Clone, Debug)]
+pub struct FixedExpressionCosets<C: CurveAffine> {
+    /// Custom gates evaluations
+    pub custom_gates: Vec<Polynomial<C::ScalarExt, ExtendedLagrangeCoeff>>,
+    /// Lookups evaluations
+    pub lookups: Vec<Vec<Polynomial<C::ScalarExt, ExtendedLagrangeCoeff>>>,
+    /// Shuffles evaluations
+    pub shuffles: Vec<Vec<Polynomial<C::ScalarExt, ExtendedLagrangeCoeff>>>,
 }
 
 /// EvaluationData
@@ -240,7 +272,7 @@ impl<C: CurveAffine> Evaluator<C> {
         ));
 
         // Lookups
-        for lookup in cs.lookups.iter() {
+        for batch in cs.lookup_batches().iter() {
             let mut graph = GraphEvaluator::default();
 
             let mut evaluate_lc = |expressions: &Vec<Expression<_>>| {
@@ -255,10 +287,67 @@ impl<C: CurveAffine> Evaluator<C> {
                 ))
             };
 
-            // Input coset
-            let compressed_input_coset = evaluate_lc(&lookup.input_expressions);
+            // Input cosets
+            let compressed_input_cosets: Vec<_> = batch
+                .iter()
+                .map(|lookup| evaluate_lc(&lookup.input_expressions))
+                .collect();
             // table coset
-            let compressed_table_coset = evaluate_lc(&lookup.table_expressions);
+            let compressed_table_coset = evaluate_lc(&batch[0].table_expressions);
+
+            if cs.lookup_argument == LookupArgument::LogDerivative {
+                // \prod_j (a_j(X) + \beta) (s(X) + \beta), keeping the product
+                // of the input terms and
+                // \sum_j \prod_{k != j} (a_k(X) + \beta) (s(X) + \beta)
+                let inputs_beta: Vec<_> = compressed_input_cosets
+                    .into_iter()
+                    .map(|input| {
+                        graph.add_calculation(Calculation::Add(input, ValueSource::Beta()))
+                    })
+                    .collect();
+                let table_beta = graph.add_calculation(Calculation::Add(
+                    compressed_table_coset,
+                    ValueSource::Beta(),
+                ));
+                let mut product = |terms: Vec<ValueSource>| {
+                    terms[1..].iter().fold(terms[0], |acc, term| {
+                        graph.add_calculation(Calculation::Mul(acc, *term))
+                    })
+                };
+                let input_term = product(inputs_beta.clone());
+                let table_term = if inputs_beta.len() == 1 {
+                    table_beta
+                } else {
+                    let others: Vec<_> = (0..inputs_beta.len())
+                        .map(|j| {
+                            product(
+                                inputs_beta
+                                    .iter()
+                                    .enumerate()
+                                    .filter(|(k, _)| *k != j)
+                                    .map(|(_, term)| *term)
+                                    .collect(),
+                            )
+                        })
+                        .collect();
+                    let sum = others[1..].iter().fold(others[0], |acc, term| {
+                        graph.add_calculation(Calculation::Add(acc, *term))
+                    });
+                    graph.add_calculation(Calculation::Mul(sum, table_beta))
+                };
+                graph.add_calculation(Calculation::Mul(input_term, table_beta));
+
+                let target = |source: ValueSource| match source {
+                    ValueSource::Intermediate(target) => target,
+                    _ => unreachable!("calculations are stored in intermediates"),
+                };
+                ev.log_derivative_terms
+                    .push((target(input_term), target(table_term)));
+                ev.lookups.push(graph);
+                continue;
+            }
+            let compressed_input_coset = compressed_input_cosets[0];
+
             // z(\omega X) (a'(X) + \beta) (s'(X) + \gamma)
             let right_gamma = graph.add_calculation(Calculation::Add(
                 compressed_table_coset,
@@ -273,27 +362,105 @@ impl<C: CurveAffine> Evaluator<C> {
             ev.lookups.push(graph);
         }
 
+        // Shuffles
+        for shuffle in cs.shuffles.iter() {
+            let mut graph = GraphEvaluator::default();
+
+            let mut evaluate_lc = |expressions: &Vec<Expression<_>>| {
+                let parts = expressions
+                    .iter()
+                    .map(|expr| graph.add_expression(expr))
+                    .collect();
+                graph.add_calculation(Calculation::Horner(
+                    ValueSource::Constant(0),
+                    parts,
+                    ValueSource::Theta(),
+                ))
+            };
+
+            // Input coset
+            let compressed_input_coset = evaluate_lc(&shuffle.input_expressions);
+            // Shuffle coset
+            let compressed_shuffle_coset = evaluate_lc(&shuffle.shuffle_expressions);
+
+            // (a(X) + \gamma) and (s(X) + \gamma), keeping both terms
+            let input_gamma = graph.add_calculation(Calculation::Add(
+                compressed_input_coset,
+                ValueSource::Gamma(),
+            ));
+            let shuffle_gamma = graph.add_calculation(Calculation::Add(
+                compressed_shuffle_coset,
+                ValueSource::Gamma(),
+            ));
+
+            let target = |source: ValueSource| match source {
+                ValueSource::Intermediate(target) => target,
+                _ => unreachable!("calculations are stored in intermediates"),
+            };
+            ev.shuffle_terms
+                .push((target(input_gamma), target(shuffle_gamma)));
+            ev.shuffles.push(graph);
+        }
+
         ev
     }
 
-    /// Evaluate h poly
+    /// Evaluates the fixed-only subexpressions of every graph on the extended
+    /// domain from the cosets of the fixed columns.
+    pub(in crate::plonk) fn evaluate_fixed_expressions(
+        &self,
+        domain: &EvaluationDomain<C::ScalarExt>,
+        fixed_cosets: &[Polynomial<C::ScalarExt, ExtendedLagrangeCoeff>],
+    ) -> FixedExpressionCosets<C> {
+        let evaluate_graph = |graph: &GraphEvaluator<C>| -> Vec<_> {
+            graph
+                .fixed_expressions
+                .iter()
+                .map(|expression| {
+                    domain.extended_from_vec(evaluate(
+                        expression,
+                        domain.extended_len(),
+                        1 << (domain.extended_k() - domain.k()),
+                        fixed_cosets,
+                        &[],
+                        &[],
+                        &[],
+                    ))
+                })
+                .collect()
+        };
+        FixedExpressionCosets {
+            custom_gates: evaluate_graph(&self.custom_gates),
+            lookups: self.lookups.iter().map(evaluate_graph).collect(),
+            shuffles: self.shuffles.iter().map(evaluate_graph).collect(),
+        }
+    }
+
+    /// Adds the constraints of a single proof to `values`, the evaluations
+    /// of the h poly on the extended domain so far, starting from
+    /// `domain.empty_extended()`. The prover adds one proof at a time, so
+    /// that the polynomials and cosets of a single proof are in memory at
+    /// once.
     pub(in crate::plonk) fn evaluate_h(
         &self,
         pk: &ProvingKey<C>,
-        advice_polys: &[&[Polynomial<C::ScalarExt, Coeff>]],
-        instance_polys: &[&[Polynomial<C::ScalarExt, Coeff>]],
+        values: &mut Polynomial<C::ScalarExt, ExtendedLagrangeCoeff>,
+        advice_polys: &[Polynomial<C::ScalarExt, Coeff>],
+        instance_polys: &[Polynomial<C::ScalarExt, Coeff>],
         challenges: &[C::ScalarExt],
         y: C::ScalarExt,
         beta: C::ScalarExt,
         gamma: C::ScalarExt,
         theta: C::ScalarExt,
-        lookups: &[Vec<lookup::prover::Committed<C>>],
-        permutations: &[permutation::prover::Committed<C>],
-    ) -> Polynomial<C::ScalarExt, ExtendedLagrangeCoeff> {
+        lookups: &[lookup::prover::Committed<C>],
+        shuffles: &[shuffle::prover::Committed<C>],
+        permutation: &permutation::prover::Committed<C>,
+    ) {
         let domain = &pk.vk.domain;
         let size = domain.extended_len();
         let rot_scale = 1 << (domain.extended_k() - domain.k());
         let fixed = &pk.fixed_cosets[..];
+        let fixed_expressions = &pk.fixed_expression_cosets;
         let extended_omega = domain.get_extended_omega();
         let isize = size as i32;
         let one = C::ScalarExt::one();
@@ -302,48 +469,165 @@ impl<C: CurveAffine> Evaluator<C> {
         let l_active_row = &pk.l_active_row;
         let p = &pk.vk.cs.permutation;
 
-        // Calculate the advice and instance cosets
-        let advice: Vec<Vec<Polynomial<C::Scalar, ExtendedLagrangeCoeff>>> = advice_polys
-            .iter()
-            .map(|advice_polys| {
-                advice_polys
-                    .iter()
-                    .map(|poly| domain.coeff_to_extended(poly.clone()))
-                    .collect()
-            })
-            .collect();
-        let instance: Vec<Vec<Polynomial<C::Scalar, ExtendedLagrangeCoeff>>> = instance_polys
-            .iter()
-            .map(|instance_polys| {
-                instance_polys
-                    .iter()
-                    .map(|poly| domain.coeff_to_extended(poly.clone()))
-                    .collect()
-            })
-            .collect();
-
-        let mut values = domain.empty_extended();
-
         // Core expression evaluations
         let num_threads = multicore::current_num_threads();
-        for (((advice, instance), lookups), permutation) in advice
+        let advice = Zeroizing::new(
+            advice_polys
+                .iter()
+                .map(|poly| domain.coeff_to_extended_copy(poly))
+                .collect::<Vec<_>>(),
+        );
+        let advice: &[Polynomial<C::Scalar, ExtendedLagrangeCoeff>] = &advice;
+        let instance: &[Polynomial<C::Scalar, ExtendedLagrangeCoeff>] = &instance_polys
             .iter()
-            .zip(instance.iter())
-            .zip(lookups.iter())
-            .zip(permutations.iter())
-        {
-            // Custom gates
-            multicore::scope(|scope| {
-                let chunk_size = (size + num_threads - 1) / num_threads;
-                for (thread_idx, values) in values.chunks_mut(chunk_size).enumerate() {
-                    let start = thread_idx * chunk_size;
-                    scope.spawn(move |_| {
-                        let mut eval_data = self.custom_gates.instance();
+            .map(|poly| domain.coeff_to_extended(poly.clone()))
+            .collect::<Vec<_>>();
+
+        // Custom gates
+        multicore::scope(|scope| {
+            let chunk_size = (size + num_threads - 1) / num_threads;
+            for (thread_idx, values) in values.chunks_mut(chunk_size).enumerate() {
+                let start = thread_idx * chunk_size;
+                scope.spawn(move |_| {
+                    let mut eval_data = self.custom_gates.instance();
+                    for (i, value) in values.iter_mut().enumerate() {
+                        let idx = start + i;
+                        *value = self.custom_gates.evaluate(
+                            &mut eval_data,
+                            fixed,
+                            &fixed_expressions.custom_gates,
+                            advice,
+                            instance,
+                            challenges,
+                            &beta,
+                            &gamma,
+                            &theta,
+                            &y,
+                            value,
+                            idx,
+                            rot_scale,
+                            isize,
+                        );
+                    }
+                });
+            }
+        });
+
+        // Permutations
+        let sets = &permutation.sets;
+        if !sets.is_empty() {
+            let blinding_factors = pk.vk.cs.blinding_factors();
+            let last_rotation = Rotation(-((blinding_factors + 1) as i32));
+            let chunk_len = Argument::chunk_len(pk.vk.cs.degree());
+            let delta_start = beta * &domain.get_coset_generator();
+
+            // The grand products on the extended domain, calculated here so
+            // that only those of this proof are in memory
+            let product_cosets = Zeroizing::new(
+                sets.iter()
+                    .map(|set| domain.coeff_to_extended_copy(&set.permutation_product_poly))
+                    .collect::<Vec<_>>(),
+            );
+            let product_cosets: &[Polynomial<C::Scalar, ExtendedLagrangeCoeff>] = &product_cosets;
+            let first_coset = product_cosets.first().unwrap();
+            let last_coset = product_cosets.last().unwrap();
+
+            // Permutation constraints
+            parallelize(values, |values, start| {
+                let mut beta_term = extended_omega.pow_vartime(&[start as u64, 0, 0, 0]);
+                for (i, value) in values.iter_mut().enumerate() {
+                    let idx = start + i;
+                    let r_next = get_rotation_idx(idx, 1, rot_scale, isize);
+                    let r_last = get_rotation_idx(idx, last_rotation.0, rot_scale, isize);
+
+                    // Enforce only for the first set.
+                    // l_0(X) * (1 - z_0(X)) = 0
+                    *value = *value * y + ((one - first_coset[idx]) * l0[idx]);
+                    // Enforce only for the last set.
+                    // l_last(X) * (z_l(X)^2 - z_l(X)) = 0
+                    *value = *value * y
+                        + ((last_coset[idx] * last_coset[idx] - last_coset[idx]) * l_last[idx]);
+                    // Except for the first set, enforce.
+                    // l_0(X) * (z_i(X) - z_{i-1}(\omega^(last) X)) = 0
+                    for (set_idx, coset) in product_cosets.iter().enumerate() {
+                        if set_idx != 0 {
+                            *value = *value * y
+                                + ((coset[idx] - product_cosets[set_idx - 1][r_last]) * l0[idx]);
+                        }
+                    }
+                    // And for all the sets we enforce:
+                    // (1 - (l_last(X) + l_blind(X))) * (
+                    //   z_i(\omega X) \prod_j (p(X) + \beta s_j(X) + \gamma)
+                    // - z_i(X) \prod_j (p(X) + \delta^j \beta X + \gamma)
+                    // )
+                    let mut current_delta = delta_start * beta_term;
+                    for ((coset, columns), cosets) in product_cosets
+                        .iter()
+                        .zip(p.columns.chunks(chunk_len))
+                        .zip(pk.permutation.cosets.chunks(chunk_len))
+                    {
+                        let mut left = coset[r_next];
+                        for (values, permutation) in columns
+                            .iter()
+                            .map(|&column| match column.column_type() {
+                                Any::Advice(_) => &advice[column.index()],
+                                Any::Fixed => &fixed[column.index()],
+                                Any::Instance => &instance[column.index()],
+                            })
+                            .zip(cosets.iter())
+                        {
+                            left *= values[idx] + beta * permutation[idx] + gamma;
+                        }
+
+                        let mut right = coset[idx];
+                        for values in columns.iter().map(|&column| match column.column_type() {
+                            Any::Advice(_) => &advice[column.index()],
+                            Any::Fixed => &fixed[column.index()],
+                            Any::Instance => &instance[column.index()],
+                        }) {
+                            right *= values[idx] + current_delta + gamma;
+                            current_delta *= &C::Scalar::DELTA;
+                        }
+
+                        *value = *value * y + ((left - right) * l_active_row[idx]);
+                    }
+                    beta_term *= &extended_omega;
+                }
+            });
+        }
+
+        // Lookups
+        for (n, lookup) in lookups.iter().enumerate() {
+            let (permuted_input_poly, permuted_table_poly, product_poly) = match lookup {
+                Committed::Permutation {
+                    permuted_input_poly,
+                    permuted_table_poly,
+                    product_poly,
+                    ..
+                } => (permuted_input_poly, permuted_table_poly, product_poly),
+                Committed::LogDerivative {
+                    multiplicities_poly,
+                    sum_poly,
+                    ..
+                } => {
+                    let sum_coset = Zeroizing::new(pk.vk.domain.coeff_to_extended_copy(sum_poly));
+                    let multiplicities_coset =
+                        Zeroizing::new(pk.vk.domain.coeff_to_extended_copy(multiplicities_poly));
+                    let sum_coset: &[_] = &sum_coset;
+                    let multiplicities_coset: &[_] = &multiplicities_coset;
+                    let (input_target, table_target) = self.log_derivative_terms[n];
+
+                    // Lookup constraints
+                    parallelize(values, |values, start| {
+                        let lookup_evaluator = &self.lookups[n];
+                        let mut eval_data = lookup_evaluator.instance();
                         for (i, value) in values.iter_mut().enumerate() {
                             let idx = start + i;
-                            *value = self.custom_gates.evaluate(
+
+                            let product = lookup_evaluator.evaluate(
                                 &mut eval_data,
                                 fixed,
+                                &fixed_expressions.lookups[n],
                                 advice,
                                 instance,
                                 challenges,
@@ -351,174 +635,160 @@ impl<C: CurveAffine> Evaluator<C> {
                                 &gamma,
                                 &theta,
                                 &y,
-                                value,
+                                &C::ScalarExt::zero(),
                                 idx,
                                 rot_scale,
                                 isize,
                             );
+                            let input_term = eval_data.intermediates[input_target];
+                            let table_term = eval_data.intermediates[table_target];
+
+                            let r_next = get_rotation_idx(idx, 1, rot_scale, isize);
+
+                            // l_0(X) * phi(X) = 0
+                            *value = *value * y + (sum_coset[idx] * l0[idx]);
+                            // l_last(X) * phi(X) = 0
+                            *value = *value * y + (sum_coset[idx] * l_last[idx]);
+                            // (1 - (l_last(X) + l_blind(X))) * (
+                            //   (phi(\omega X) - phi(X)) \prod_j (a_j(X) + \beta) (s(X) + \beta)
+                            //   - \sum_j \prod_{k != j} (a_k(X) + \beta) (s(X) + \beta)
+                            //   + m(X) \prod_j (a_j(X) + \beta)
+                            // ) = 0
+                            *value = *value * y
+                                + (((sum_coset[r_next] - sum_coset[idx]) * product - table_term
+                                    + multiplicities_coset[idx] * input_term)
+                                    * l_active_row[idx]);
                         }
                     });
+                    continue;
                 }
-            });
+            };
 
-            // Permutations
-            let sets = &permutation.sets;
-            if !sets.is_empty() {
-                let blinding_factors = pk.vk.cs.blinding_factors();
-                let last_rotation = Rotation(-((blinding_factors + 1) as i32));
-                let chunk_len = pk.vk.cs.degree() - 2;
-                let delta_start = beta * &C::Scalar::ZETA;
+            // Polynomials required for this lookup.
+            // Calculated here so these only have to be kept in memory for the short time
+            // they are actually needed.
+            let product_coset = Zeroizing::new(pk.vk.domain.coeff_to_extended_copy(product_poly));
+            let permuted_input_coset =
+                Zeroizing::new(pk.vk.domain.coeff_to_extended_copy(permuted_input_poly));
+            let permuted_table_coset =
+                Zeroizing::new(pk.vk.domain.coeff_to_extended_copy(permuted_table_poly));
+            let product_coset: &[_] = &product_coset;
+            let permuted_input_coset: &[_] = &permuted_input_coset;
+            let permuted_table_coset: &[_] = &permuted_table_coset;
 
-                let first_set = sets.first().unwrap();
-                let last_set = sets.last().unwrap();
+            // Lookup constraints
+            parallelize(values, |values, start| {
+                let lookup_evaluator = &self.lookups[n];
+                let mut eval_data = lookup_evaluator.instance();
+                for (i, value) in values.iter_mut().enumerate() {
+                    let idx = start + i;
 
-                // Permutation constraints
-                parallelize(&mut values, |values, start| {
-                    let mut beta_term = extended_omega.pow_vartime(&[start as u64, 0, 0, 0]);
-                    for (i, value) in values.iter_mut().enumerate() {
-                        let idx = start + i;
-                        let r_next = get_rotation_idx(idx, 1, rot_scale, isize);
-                        let r_last = get_rotation_idx(idx, last_rotation.0, rot_scale, isize);
-
-                        // Enforce only for the first set.
-                        // l_0(X) * (1 - z_0(X)) = 0
-                        *value = *value * y
-                            + ((one - first_set.permutation_product_coset[idx]) * l0[idx]);
-                        // Enforce only for the last set.
-                        // l_last(X) * (z_l(X)^2 - z_l(X)) = 0
-                        *value = *value * y
-                            + ((last_set.permutation_product_coset[idx]
-                                * last_set.permutation_product_coset[idx]
-                                - last_set.permutation_product_coset[idx])
-                                * l_last[idx]);
-                        // Except for the first set, enforce.
-                        // l_0(X) * (z_i(X) - z_{i-1}(\omega^(last) X)) = 0
-                        for (set_idx, set) in sets.iter().enumerate() {
-                            if set_idx != 0 {
-                                *value = *value * y
-                                    + ((set.permutation_product_coset[idx]
-                                        - permutation.sets[set_idx - 1].permutation_product_coset
-                                            [r_last])
-                                        * l0[idx]);
-                            }
-                        }
-                        // And for all the sets we enforce:
-                        // (1 - (l_last(X) + l_blind(X))) * (
-                        //   z_i(\omega X) \prod_j (p(X) + \beta s_j(X) + \gamma)
-                        // - z_i(X) \prod_j (p(X) + \delta^j \beta X + \gamma)
-                        // )
-                        let mut current_delta = delta_start * beta_term;
-                        for ((set, columns), cosets) in sets
-                            .iter()
-                            .zip(p.columns.chunks(chunk_len))
-                            .zip(pk.permutation.cosets.chunks(chunk_len))
-                        {
-                            let mut left = set.permutation_product_coset[r_next];
-                            for (values, permutation) in columns
-                                .iter()
-                                .map(|&column| match column.column_type() {
-                                    Any::Advice(_) => &advice[column.index()],
-                                    Any::Fixed => &fixed[column.index()],
-                                    Any::Instance => &instance[column.index()],
-                                })
-                                .zip(cosets.iter())
-                            {
-                                left *= values[idx] + beta * permutation[idx] + gamma;
-                            }
-
-                            let mut right = set.permutation_product_coset[idx];
-                            for values in columns.iter().map(|&column| match column.column_type() {
-                                Any::Advice(_) => &advice[column.index()],
-                                Any::Fixed => &fixed[column.index()],
-                                Any::Instance => &instance[column.index()],
-                            }) {
-                                right *= values[idx] + current_delta + gamma;
-                                current_delta *= &C::Scalar::DELTA;
-                            }
+                    let table_value = lookup_evaluator.evaluate(
+                        &mut eval_data,
+                        fixed,
+                        &fixed_expressions.lookups[n],
+                        advice,
+                        instance,
+                        challenges,
+                        &beta,
+                        &gamma,
+                        &theta,
+                        &y,
+                        &C::ScalarExt::zero(),
+                        idx,
+                        rot_scale,
+                        isize,
+                    );
 
-                            *value = *value * y + ((left - right) * l_active_row[idx]);
-                        }
-                        beta_term *= &extended_omega;
-                    }
-                });
-            }
+                    let r_next = get_rotation_idx(idx, 1, rot_scale, isize);
+                    let r_prev = get_rotation_idx(idx, -1, rot_scale, isize);
 
-            // Lookups
-            for (n, lookup) in lookups.iter().enumerate() {
-                // Polynomials required for this lookup.
-                // Calculated here so these only have to be kept in memory for the short time
-                // they are actually needed.
-                let product_coset = pk.vk.domain.coeff_to_extended(lookup.product_poly.clone());
-                let permuted_input_coset = pk
-                    .vk
-                    .domain
-                    .coeff_to_extended(lookup.permuted_input_poly.clone());
-                let permuted_table_coset = pk
-                    .vk
-                    .domain
-                    .coeff_to_extended(lookup.permuted_table_poly.clone());
-
-                // Lookup constraints
-                parallelize(&mut values, |values, start| {
-                    let lookup_evaluator = &self.lookups[n];
-                    let mut eval_data = lookup_evaluator.instance();
-                    for (i, value) in values.iter_mut().enumerate() {
-                        let idx = start + i;
+                    let a_minus_s = permuted_input_coset[idx] - permuted_table_coset[idx];
+                    // l_0(X) * (1 - z(X)) = 0
+                    *value = *value * y + ((one - product_coset[idx]) * l0[idx]);
+                    // l_last(X) * (z(X)^2 - z(X)) = 0
+                    *value = *value * y
+                        + ((product_coset[idx] * product_coset[idx] - product_coset[idx])
+                            * l_last[idx]);
+                    // (1 - (l_last(X) + l_blind(X))) * (
+                    //   z(\omega X) (a'(X) + \beta) (s'(X) + \gamma)
+                    //   - z(X) (\theta^{m-1} a_0(X) + ... + a_{m-1}(X) + \beta)
+                    //          (\theta^{m-1} s_0(X) + ... + s_{m-1}(X) + \gamma)
+                    // ) = 0
+                    *value = *value * y
+                        + ((product_coset[r_next]
+                            * (permuted_input_coset[idx] + beta)
+                            * (permuted_table_coset[idx] + gamma)
+                            - product_coset[idx] * table_value)
+                            * l_active_row[idx]);
+                    // Check that the first values in the permuted input expression and permuted
+                    // fixed expression are the same.
+                    // l_0(X) * (a'(X) - s'(X)) = 0
+                    *value = *value * y + (a_minus_s * l0[idx]);
+                    // Check that each value in the permuted lookup input expression is either
+                    // equal to the value above it, or the value at the same index in the
+                    // permuted table expression.
+                    // (1 - (l_last + l_blind)) * (a′(X) − s′(X))⋅(a′(X) − a′(\omega^{-1} X)) = 0
+                    *value = *value * y
+                        + (a_minus_s
+                            * (permuted_input_coset[idx] - permuted_input_coset[r_prev])
+                            * l_active_row[idx]);
+                }
+            });
+        }
 
-                        let table_value = lookup_evaluator.evaluate(
-                            &mut eval_data,
-                            fixed,
-                            advice,
-                            instance,
-                            challenges,
-                            &beta,
-                            &gamma,
-                            &theta,
-                            &y,
-                            &C::ScalarExt::zero(),
-                            idx,
-                            rot_scale,
-                            isize,
-                        );
+        // Shuffles
+        for (n, shuffle) in shuffles.iter().enumerate() {
+            let product_coset =
+                Zeroizing::new(pk.vk.domain.coeff_to_extended_copy(&shuffle.product_poly));
+            let product_coset: &[_] = &product_coset;
+            let (input_target, shuffle_target) = self.shuffle_terms[n];
 
-                        let r_next = get_rotation_idx(idx, 1, rot_scale, isize);
-                        let r_prev = get_rotation_idx(idx, -1, rot_scale, isize);
-
-                        let a_minus_s = permuted_input_coset[idx] - permuted_table_coset[idx];
-                        // l_0(X) * (1 - z(X)) = 0
-                        *value = *value * y + ((one - product_coset[idx]) * l0[idx]);
-                        // l_last(X) * (z(X)^2 - z(X)) = 0
-                        *value = *value * y
-                            + ((product_coset[idx] * product_coset[idx] - product_coset[idx])
-                                * l_last[idx]);
-                        // (1 - (l_last(X) + l_blind(X))) * (
-                        //   z(\omega X) (a'(X) + \beta) (s'(X) + \gamma)
-                        //   - z(X) (\theta^{m-1} a_0(X) + ... + a_{m-1}(X) + \beta)
-                        //          (\theta^{m-1} s_0(X) + ... + s_{m-1}(X) + \gamma)
-                        // ) = 0
-                        *value = *value * y
-                            + ((product_coset[r_next]
-                                * (permuted_input_coset[idx] + beta)
-                                * (permuted_table_coset[idx] + gamma)
-                                - product_coset[idx] * table_value)
-                                * l_active_row[idx]);
-                        // Check that the first values in the permuted input expression and permuted
-                        // fixed expression are the same.
-                        // l_0(X) * (a'(X) - s'(X)) = 0
-                        *value = *value * y + (a_minus_s * l0[idx]);
-                        // Check that each value in the permuted lookup input expression is either
-                        // equal to the value above it, or the value at the same index in the
-                        // permuted table expression.
-                        // (1 - (l_last + l_blind)) * (a′(X) − s′(X))⋅(a′(X) − a′(\omega^{-1} X)) = 0
-                        *value = *value * y
-                            + (a_minus_s
-                                * (permuted_input_coset[idx] - permuted_input_coset[r_prev])
-                                * l_active_row[idx]);
-                    }
-                });
-            }
+            // Shuffle constraints
+            parallelize(values, |values, start| {
+                let shuffle_evaluator = &self.shuffles[n];
+                let mut eval_data = shuffle_evaluator.instance();
+                for (i, value) in values.iter_mut().enumerate() {
+                    let idx = start + i;
+
+                    shuffle_evaluator.evaluate(
+                        &mut eval_data,
+                        fixed,
+                        &fixed_expressions.shuffles[n],
+                        advice,
+                        instance,
+                        challenges,
+                        &beta,
+                        &gamma,
+                        &theta,
+                        &y,
+                        &C::ScalarExt::zero(),
+                        idx,
+                        rot_scale,
+                        isize,
+                    );
+                    let input_term = eval_data.intermediates[input_target];
+                    let shuffle_term = eval_data.intermediates[shuffle_target];
+
+                    let r_next = get_rotation_idx(idx, 1, rot_scale, isize);
+
+                    // l_0(X) * (1 - z(X)) = 0
+                    *value = *value * y + ((one - product_coset[idx]) * l0[idx]);
+                    // l_last(X) * (z(X)^2 - z(X)) = 0
+                    *value = *value * y
+                        + ((product_coset[idx] * product_coset[idx] - product_coset[idx])
+                            * l_last[idx]);
+                    // (1 - (l_last(X) + l_blind(X))) * (
+                    //   z(\omega X) (\theta^{m-1} s_0(X) + ... + s_{m-1}(X) + \gamma)
+                    //   - z(X) (\theta^{m-1} a_0(X) + ... + a_{m-1}(X) + \gamma)
+                    // ) = 0
+                    *value = *value * y
+                        + ((product_coset[r_next] * shuffle_term
+                            - product_coset[idx] * input_term)
+                            * l_active_row[idx]);
+                }
+            });
         }
-        values
     }
 }
 
@@ -534,6 +804,7 @@ impl<C: CurveAffine> Default for GraphEvaluator<C> {
             rotations: Vec::new(),
             calculations: Vec::new(),
             num_intermediates: 0,
+            fixed_expressions: Vec::new(),
         }
     }
 }
@@ -564,9 +835,10 @@ impl<C: CurveAffine> GraphEvaluator<C> {
     }
 
     /// Adds a calculation.
-    /// Currently does the simplest thing possible: just stores the
-    /// resulting value so the result can be reused  when that calculation
-    /// is done multiple times.
+    /// Stores the resulting value so the result can be reused when that
+    /// calculation is done multiple times, within a gate or across gates.
+    /// The operands of commutative calculations are ordered by the caller,
+    /// so that e.g. `a * b` and `b * a` are the same calculation.
     fn add_calculation(&mut self, calculation: Calculation) -> ValueSource {
         let existing_calculation = self
             .calculations
@@ -586,8 +858,32 @@ impl<C: CurveAffine> GraphEvaluator<C> {
         }
     }
 
+    /// Adds an expression over the fixed columns only, to be evaluated at
+    /// keygen rather than for every proof
+    fn add_fixed_expression(&mut self, expr: &Expression<C::ScalarExt>) -> ValueSource {
+        let identifier = expr.identifier();
+        let position = self
+            .fixed_expressions
+            .iter()
+            .position(|e| e.identifier() == identifier);
+        let index = match position {
+            Some(pos) => pos,
+            None => {
+                self.fixed_expressions.push(expr.clone());
+                self.fixed_expressions.len() - 1
+            }
+        };
+        let rot_idx = self.add_rotation(&Rotation::cur());
+        self.add_calculation(Calculation::Store(ValueSource::FixedExpression(
+            index, rot_idx,
+        )))
+    }
+
     /// Generates an optimized evaluation for the expression
     fn add_expression(&mut self, expr: &Expression<C::ScalarExt>) -> ValueSource {
+        if is_compound_fixed_expression(expr) {
+            return self.add_fixed_expression(expr);
+        }
         match expr {
             Expression::Constant(scalar) => self.add_constant(scalar),
             Expression::Selector(_selector) => unreachable!(),
@@ -681,9 +977,15 @@ impl<C: CurveAffine> GraphEvaluator<C> {
                 } else if *f == C::ScalarExt::one() {
                     self.add_expression(a)
                 } else {
+                    // Ordered as for products, so that scaling by a constant
+                    // and multiplying by it are the same calculation
                     let cst = self.add_constant(f);
                     let result_a = self.add_expression(a);
-                    self.add_calculation(Calculation::Mul(result_a, cst))
+                    if cst <= result_a {
+                        self.add_calculation(Calculation::Mul(cst, result_a))
+                    } else {
+                        self.add_calculation(Calculation::Mul(result_a, cst))
+                    }
                 }
             }
         }
@@ -701,6 +1003,7 @@ impl<C: CurveAffine> GraphEvaluator<C> {
         &self,
         data: &mut EvaluationData<C>,
         fixed: &[Polynomial<C::ScalarExt, B>],
+        fixed_expressions: &[Polynomial<C::ScalarExt, B>],
         advice: &[Polynomial<C::ScalarExt, B>],
         instance: &[Polynomial<C::ScalarExt, B>],
         challenges: &[C::ScalarExt],
@@ -725,6 +1028,7 @@ impl<C: CurveAffine> GraphEvaluator<C> {
                 &self.constants,
                 &data.intermediates,
                 fixed,
+                fixed_expressions,
                 advice,
                 instance,
                 challenges,
@@ -745,6 +1049,39 @@ impl<C: CurveAffine> GraphEvaluator<C> {
     }
 }
 
+/// Returns `true` if `expr` combines fixed columns, and nothing but fixed
+/// columns and constants, with at least one binary operation. A single fixed
+/// query, even negated or scaled, is read from the column directly and is not
+/// worth caching.
+fn is_compound_fixed_expression<F: Field>(expr: &Expression<F>) -> bool {
+    fn fixed_only<F: Field>(expr: &Expression<F>) -> (bool, bool) {
+        // (only fixed columns and constants, at least one fixed column)
+        match expr {
+            Expression::Constant(_) => (true, false),
+            Expression::Fixed(_) => (true, true),
+            Expression::Selector(_)
+            | Expression::Advice(_)
+            | Expression::Instance(_)
+            | Expression::Challenge(_) => (false, false),
+            Expression::Negated(a) | Expression::Scaled(a, _) => fixed_only(a),
+            Expression::Sum(a, b) | Expression::Product(a, b) => {
+                let (only_a, any_a) = fixed_only(a);
+                let (only_b, any_b) = fixed_only(b);
+                (only_a && only_b, any_a || any_b)
+            }
+        }
+    }
+
+    let mut inner = expr;
+    while let Expression::Negated(a) | Expression::Scaled(a, _) = inner {
+        inner = a;
+    }
+    match inner {
+        Expression::Sum(_, _) | Expression::Product(_, _) => fixed_only(expr) == (true, true),
+        _ => false,
+    }
+}
+
 /// Simple evaluation of an expression
 pub fn evaluate<F: FieldExt, B: Basis>(
     expression: &Expression<F>,
@@ -757,7 +1094,7 @@ pub fn evaluate<F: FieldExt, B: Basis>(
 ) -> Vec<F> {
     let mut values = vec![F::zero(); size];
     let isize = size as i32;
-    parallelize(&mut values, |values, start| {
+    parallelize(values, |values, start| {
         for (i, value) in values.iter_mut().enumerate() {
             let idx = start + i;
             *value = expression.evaluate(
@@ -785,3 +1122,89 @@ pub fn evaluate<F: FieldExt, B: Basis>(
     });
     values
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Calculation, Evaluator};
+    use crate::plonk::{ConstraintSystem, Expression};
+    use crate::poly::Rotation;
+    use halo2curves::pasta::{EqAffine, Fp};
+
+    #[test]
+    fn fixed_expressions_are_cached() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let a = cs.advice_column();
+        let f1 = cs.fixed_column();
+        let f2 = cs.fixed_column();
+        for name in ["first", "second"] {
+            cs.create_gate(name, |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let f1 = meta.query_fixed(f1, Rotation::cur());
+                let f2 = meta.query_fixed(f2, Rotation::next());
+                vec![f1.clone() * f2 * a + f1]
+            });
+        }
+
+        // Both gates share `f1 * f2`, and `f1` alone is read from its column
+        let ev = Evaluator::<EqAffine>::new(&cs);
+        assert_eq!(ev.custom_gates.fixed_expressions.len(), 1);
+    }
+
+    #[test]
+    fn negated_and_scaled_fixed_columns_are_not_cached() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let a = cs.advice_column();
+        let f1 = cs.fixed_column();
+        let f2 = cs.fixed_column();
+        cs.create_gate("unary", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let f1 = meta.query_fixed(f1, Rotation::cur());
+            let f2 = meta.query_fixed(f2, Rotation::cur());
+            vec![
+                -f1.clone() * a.clone(),
+                f2.clone() * Fp::from(3) * a.clone(),
+                -(f1.clone() * Fp::from(5)) * a.clone(),
+                -(f1 + f2) * a,
+            ]
+        });
+
+        // Only `-(f1 + f2)` combines columns
+        let ev = Evaluator::<EqAffine>::new(&cs);
+        assert_eq!(ev.custom_gates.fixed_expressions.len(), 1);
+    }
+
+    #[test]
+    fn shared_terms_are_computed_once() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let q = cs.fixed_column();
+        let a = cs.advice_column();
+        let b = cs.advice_column();
+        let gate = |cs: &mut ConstraintSystem<Fp>, name, scale: bool| {
+            cs.create_gate(name, |meta| {
+                let q = meta.query_fixed(q, Rotation::cur());
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                let term = q * (a - b);
+                vec![if scale {
+                    term * Fp::from(3)
+                } else {
+                    Expression::Constant(Fp::from(3)) * term
+                }]
+            });
+        };
+        gate(&mut cs, "scaled", true);
+        gate(&mut cs, "product", false);
+
+        // `q * (a - b)` and its product with 3 are each computed once
+        let ev = Evaluator::<EqAffine>::new(&cs);
+        let calculations = &ev.custom_gates.calculations;
+        let count = |f: fn(&Calculation) -> bool| {
+            calculations
+                .iter()
+                .filter(|info| f(&info.calculation))
+                .count()
+        };
+        assert_eq!(count(|c| matches!(c, Calculation::Sub(_, _))), 1);
+        assert_eq!(count(|c| matches!(c, Calculation::Mul(_, _))), 2);
+    }
+}
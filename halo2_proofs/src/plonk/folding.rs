@@ -0,0 +1,721 @@
+//! Folding of witnesses of a constraint system into relaxed instances, in the
+//! style of Sangria and ProtoStar, for experimenting with incrementally
+//! verifiable computation.
+//!
+//! This module is experimental: its API may change in any release.
+//!
+//! Each constraint `G` of the custom gates is homogenised with a scalar `u` to
+//! the [`degree`] `d` of the constraint system in the advice and instance
+//! cells, the fixed cells being shared by all instances. The relaxed relation
+//! `G(u, w) = e`, for an error column `e` of each constraint, is then closed
+//! under random linear combinations: for a challenge `r`,
+//!
+//! ```text
+//! G(u_1 + r u_2, w_1 + r w_2) = e_1 + sum_{k=1}^{d-1} r^k t_k + r^d e_2
+//! ```
+//!
+//! where the prover commits to the cross terms `t_k` before `r` is squeezed.
+//! A witness of the circuit is a relaxed witness with `u = 1` and `e = 0`, see
+//! [`relax`].
+//!
+//! Only the custom gates are folded: a relaxed instance says nothing about
+//! the copy constraints, the lookups and the shuffles of the circuit. Constraint systems
+//! using challenges are not supported: [`relax`], [`fold`] and
+//! [`fold_instances`] return [`Error::ChallengesNotFoldable`] for them.
+
+use ff::Field;
+use group::{prime::PrimeCurveAffine, Curve};
+use rand_core::RngCore;
+use std::io;
+
+use super::{ConstraintSystem, Error, Expression, ProvingKey, VerifyingKey, Witness};
+use crate::arithmetic::{lagrange_interpolate, parallelize, CurveAffine};
+use crate::poly::{
+    commitment::{Blind, Params},
+    LagrangeCoeff, Polynomial,
+};
+use crate::transcript::{
+    read_n_points, EncodedChallenge, Transcript, TranscriptRead, TranscriptWrite,
+};
+
+/// The public part of a relaxed witness
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RelaxedInstance<C: CurveAffine> {
+    /// The scalar homogenising the constraints, one for a witness of the
+    /// circuit
+    pub u: C::Scalar,
+    /// The instance columns, on every row
+    pub instance: Vec<Vec<C::Scalar>>,
+    /// The commitments to the advice columns
+    pub advice_commitments: Vec<C>,
+    /// The commitments to the error column of each constraint
+    pub error_commitments: Vec<C>,
+}
+
+/// A relaxed witness, opening the commitments of a [`RelaxedInstance`]
+#[derive(Clone, Debug)]
+pub struct RelaxedWitness<F: Field> {
+    /// The advice columns
+    pub advice: Vec<Polynomial<F, LagrangeCoeff>>,
+    /// The blinding factors of the advice commitments
+    pub advice_blinds: Vec<Blind<F>>,
+    /// The error column of each constraint
+    pub error: Vec<Polynomial<F, LagrangeCoeff>>,
+    /// The blinding factors of the error commitments
+    pub error_blinds: Vec<Blind<F>>,
+}
+
+/// Returns the degree to which the constraints of `cs` are homogenised, i.e.
+/// the highest degree of a constraint in the advice and instance cells, and
+/// at least one. Challenges count as constants.
+pub fn degree<F: Field>(cs: &ConstraintSystem<F>) -> usize {
+    constraints(cs)
+        .map(|constraint| {
+            constraint.evaluate(
+                &|_| 0,
+                &|_| 0,
+                &|_| 0,
+                &|_| 1,
+                &|_| 1,
+                &|_| 0,
+                &|a| a,
+                &|a, b| a.max(b),
+                &|a, b| a + b,
+                &|a, _| a,
+            )
+        })
+        .max()
+        .unwrap_or(0)
+        .max(1)
+}
+
+fn constraints<F: Field>(cs: &ConstraintSystem<F>) -> impl Iterator<Item = &Expression<F>> {
+    cs.gates.iter().flat_map(|gate| gate.polynomials().iter())
+}
+
+/// Returns [`Error::ChallengesNotFoldable`] if a constraint of `cs` uses a
+/// challenge, whose value would differ between the folded witnesses.
+fn check_foldable<F: Field>(cs: &ConstraintSystem<F>) -> Result<(), Error> {
+    let uses_challenges = constraints(cs).any(|constraint| {
+        constraint.evaluate(
+            &|_| false,
+            &|_| false,
+            &|_| false,
+            &|_| false,
+            &|_| false,
+            &|_| true,
+            &|a| a,
+            &|a, b| a || b,
+            &|a, b| a || b,
+            &|a, _| a,
+        )
+    });
+    if uses_challenges {
+        return Err(Error::ChallengesNotFoldable);
+    }
+    Ok(())
+}
+
+/// Turns the witness of a circuit, generated with
+/// [`generate_witness`](super::generate_witness), and its instance columns
+/// into a relaxed witness with `u = 1` and zero error columns.
+pub fn relax<'params, C: CurveAffine, P: Params<'params, C>, R: RngCore>(
+    params: &P,
+    pk: &ProvingKey<C>,
+    witness: &Witness<C::Scalar>,
+    instance: &[&[C::Scalar]],
+    mut rng: R,
+) -> Result<(RelaxedInstance<C>, RelaxedWitness<C::Scalar>), Error> {
+    let cs = &pk.vk.cs;
+    check_foldable(cs)?;
+    let n = params.n() as usize;
+    if instance.len() != cs.num_instance_columns {
+        return Err(Error::InvalidInstances);
+    }
+    if witness.advice().len() != cs.num_advice_columns {
+        return Err(Error::InvalidWitness);
+    }
+    let instance = instance
+        .iter()
+        .map(|values| {
+            if values.len() > n {
+                return Err(Error::InstanceTooLarge);
+            }
+            let mut column = values.to_vec();
+            column.resize(n, C::Scalar::zero());
+            Ok(column)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let advice = witness.advice().to_vec();
+    let advice_blinds: Vec<_> = advice
+        .iter()
+        .map(|_| Blind(C::Scalar::random(&mut rng)))
+        .collect();
+    let error = vec![pk.vk.domain.empty_lagrange(); constraints(cs).count()];
+    let error_blinds: Vec<_> = error
+        .iter()
+        .map(|_| Blind(C::Scalar::random(&mut rng)))
+        .collect();
+
+    let instance = RelaxedInstance {
+        u: C::Scalar::one(),
+        instance,
+        advice_commitments: commit(params, &advice, &advice_blinds),
+        error_commitments: commit(params, &error, &error_blinds),
+    };
+    let witness = RelaxedWitness {
+        advice,
+        advice_blinds,
+        error,
+        error_blinds,
+    };
+
+    Ok((instance, witness))
+}
+
+/// Folds the relaxed witnesses `first` and `second` into one, writing the
+/// commitments to the cross terms to `transcript`. The verifier folds the
+/// instances with [`fold_instances`].
+pub fn fold<
+    'params,
+    C: CurveAffine,
+    P: Params<'params, C>,
+    E: EncodedChallenge<C>,
+    T: TranscriptWrite<C, E>,
+    R: RngCore,
+>(
+    params: &P,
+    pk: &ProvingKey<C>,
+    first: (&RelaxedInstance<C>, &RelaxedWitness<C::Scalar>),
+    second: (&RelaxedInstance<C>, &RelaxedWitness<C::Scalar>),
+    mut rng: R,
+    transcript: &mut T,
+) -> Result<(RelaxedInstance<C>, RelaxedWitness<C::Scalar>), Error> {
+    let vk = &pk.vk;
+    check_foldable(&vk.cs)?;
+    for (instance, witness) in [first, second] {
+        instance.check_shape(vk)?;
+        if witness.advice.len() != instance.advice_commitments.len()
+            || witness.error.len() != instance.error_commitments.len()
+        {
+            return Err(Error::InvalidWitness);
+        }
+    }
+    vk.hash_into(transcript)?;
+    first.0.absorb(transcript)?;
+    second.0.absorb(transcript)?;
+
+    let degree = degree(&vk.cs);
+    let cross_terms = cross_terms(pk, degree, first, second);
+    let cross_term_blinds: Vec<_> = cross_terms
+        .iter()
+        .map(|_| Blind(C::Scalar::random(&mut rng)))
+        .collect();
+    let cross_term_commitments = commit(params, &cross_terms, &cross_term_blinds);
+    for commitment in cross_term_commitments.iter() {
+        transcript.write_point(*commitment)?;
+    }
+
+    let r = *transcript.squeeze_challenge_scalar::<()>();
+    let instance = first.0.fold(second.0, &cross_term_commitments, r, degree);
+
+    let combine = |a: &Polynomial<C::Scalar, LagrangeCoeff>,
+                   b: &Polynomial<C::Scalar, LagrangeCoeff>,
+                   scale: C::Scalar| {
+        let mut folded = a.clone();
+        for (folded, b) in folded.iter_mut().zip(b.iter()) {
+            *folded += *b * scale;
+        }
+        folded
+    };
+    let (first, second) = (first.1, second.1);
+    let advice = first
+        .advice
+        .iter()
+        .zip(second.advice.iter())
+        .map(|(a, b)| combine(a, b, r))
+        .collect();
+    let advice_blinds = first
+        .advice_blinds
+        .iter()
+        .zip(second.advice_blinds.iter())
+        .map(|(a, b)| Blind(a.0 + b.0 * r))
+        .collect();
+    let r_d = r.pow_vartime(&[degree as u64]);
+    let mut error: Vec<_> = first
+        .error
+        .iter()
+        .zip(second.error.iter())
+        .map(|(a, b)| combine(a, b, r_d))
+        .collect::<Vec<_>>();
+    let mut error_blinds: Vec<_> = first
+        .error_blinds
+        .iter()
+        .zip(second.error_blinds.iter())
+        .map(|(a, b)| Blind(a.0 + b.0 * r_d))
+        .collect();
+    for (index, (cross_term, blind)) in cross_terms.iter().zip(cross_term_blinds).enumerate() {
+        // The cross terms are ordered by constraint, then by power of `r`
+        let constraint = index / (degree - 1);
+        let r_k = r.pow_vartime(&[(index % (degree - 1) + 1) as u64]);
+        error[constraint] = combine(&error[constraint], cross_term, r_k);
+        error_blinds[constraint] = Blind(error_blinds[constraint].0 + blind.0 * r_k);
+    }
+
+    let witness = RelaxedWitness {
+        advice,
+        advice_blinds,
+        error,
+        error_blinds,
+    };
+
+    Ok((instance, witness))
+}
+
+/// Folds the relaxed instances `first` and `second` into the instance of the
+/// witness folded by [`fold`], reading the commitments to the cross terms
+/// from `transcript`.
+pub fn fold_instances<C: CurveAffine, E: EncodedChallenge<C>, T: TranscriptRead<C, E>>(
+    vk: &VerifyingKey<C>,
+    first: &RelaxedInstance<C>,
+    second: &RelaxedInstance<C>,
+    transcript: &mut T,
+) -> Result<RelaxedInstance<C>, Error> {
+    check_foldable(&vk.cs)?;
+    first.check_shape(vk)?;
+    second.check_shape(vk)?;
+    vk.hash_into(transcript)?;
+    first.absorb(transcript)?;
+    second.absorb(transcript)?;
+
+    let degree = degree(&vk.cs);
+    let cross_term_commitments =
+        read_n_points(transcript, (degree - 1) * constraints(&vk.cs).count())?;
+    let r = *transcript.squeeze_challenge_scalar::<()>();
+
+    Ok(first.fold(second, &cross_term_commitments, r, degree))
+}
+
+/// Returns `true` if `witness` opens the commitments of `instance` and
+/// satisfies the relaxed constraints of the custom gates on every row.
+pub fn is_satisfied<'params, C: CurveAffine, P: Params<'params, C>>(
+    params: &P,
+    pk: &ProvingKey<C>,
+    instance: &RelaxedInstance<C>,
+    witness: &RelaxedWitness<C::Scalar>,
+) -> bool {
+    if check_foldable(&pk.vk.cs).is_err()
+        || instance.check_shape(&pk.vk).is_err()
+        || witness.advice.len() != instance.advice_commitments.len()
+        || witness.advice_blinds.len() != witness.advice.len()
+        || witness.error.len() != instance.error_commitments.len()
+        || witness.error_blinds.len() != witness.error.len()
+        || witness
+            .advice
+            .iter()
+            .chain(witness.error.iter())
+            .any(|column| column.len() != params.n() as usize)
+    {
+        return false;
+    }
+
+    let error = evaluate_constraints(
+        pk,
+        degree(&pk.vk.cs),
+        instance.u,
+        |column, row| witness.advice[column][row],
+        |column, row| instance.instance[column][row],
+    );
+
+    commit(params, &witness.advice, &witness.advice_blinds) == instance.advice_commitments
+        && commit(params, &witness.error, &witness.error_blinds) == instance.error_commitments
+        && error
+            .iter()
+            .zip(witness.error.iter())
+            .all(|(expected, error)| expected.iter().eq(error.iter()))
+}
+
+impl<C: CurveAffine> RelaxedInstance<C> {
+    fn check_shape(&self, vk: &VerifyingKey<C>) -> Result<(), Error> {
+        if self.instance.len() != vk.cs.num_instance_columns
+            || self
+                .instance
+                .iter()
+                .any(|column| column.len() != vk.domain.n as usize)
+            || self.advice_commitments.len() != vk.cs.num_advice_columns
+            || self.error_commitments.len() != constraints(&vk.cs).count()
+        {
+            return Err(Error::InvalidInstances);
+        }
+        Ok(())
+    }
+
+    fn absorb<E: EncodedChallenge<C>, T: Transcript<C, E>>(
+        &self,
+        transcript: &mut T,
+    ) -> io::Result<()> {
+        transcript.common_scalar(self.u)?;
+        for value in self.instance.iter().flatten() {
+            transcript.common_scalar(*value)?;
+        }
+        for commitment in self
+            .advice_commitments
+            .iter()
+            .chain(self.error_commitments.iter())
+        {
+            transcript.common_point(*commitment)?;
+        }
+        Ok(())
+    }
+
+    fn fold(
+        &self,
+        other: &Self,
+        cross_term_commitments: &[C],
+        r: C::Scalar,
+        degree: usize,
+    ) -> Self {
+        let instance = self
+            .instance
+            .iter()
+            .zip(other.instance.iter())
+            .map(|(a, b)| a.iter().zip(b.iter()).map(|(a, b)| *a + *b * r).collect())
+            .collect();
+        let advice_commitments = self
+            .advice_commitments
+            .iter()
+            .zip(other.advice_commitments.iter())
+            .map(|(a, b)| (a.to_curve() + *b * r).to_affine())
+            .collect();
+        let error_commitments = self
+            .error_commitments
+            .iter()
+            .zip(other.error_commitments.iter())
+            .enumerate()
+            .map(|(constraint, (a, b))| {
+                let cross_terms = &cross_term_commitments
+                    [constraint * (degree - 1)..(constraint + 1) * (degree - 1)];
+                let mut folded = a.to_curve() + *b * r.pow_vartime(&[degree as u64]);
+                for (k, cross_term) in cross_terms.iter().enumerate() {
+                    folded += *cross_term * r.pow_vartime(&[k as u64 + 1]);
+                }
+                folded.to_affine()
+            })
+            .collect();
+
+        RelaxedInstance {
+            u: self.u + other.u * r,
+            instance,
+            advice_commitments,
+            error_commitments,
+        }
+    }
+}
+
+fn commit<'params, C: CurveAffine, P: Params<'params, C>>(
+    params: &P,
+    columns: &[Polynomial<C::Scalar, LagrangeCoeff>],
+    blinds: &[Blind<C::Scalar>],
+) -> Vec<C> {
+    columns
+        .iter()
+        .zip(blinds.iter())
+        .map(|(column, blind)| params.commit_lagrange(column, *blind).to_affine())
+        .collect()
+}
+
+/// Returns the cross terms `t_1, ..., t_{d-1}` of each constraint, in that
+/// order.
+fn cross_terms<C: CurveAffine>(
+    pk: &ProvingKey<C>,
+    degree: usize,
+    first: (&RelaxedInstance<C>, &RelaxedWitness<C::Scalar>),
+    second: (&RelaxedInstance<C>, &RelaxedWitness<C::Scalar>),
+) -> Vec<Polynomial<C::Scalar, LagrangeCoeff>> {
+    // On each row, the constraints evaluated on `w_1 + X w_2` are polynomials
+    // of degree `d` in `X`, interpolated from their values at `X = 0, ..., d`.
+    // Coefficient `k` of the interpolation is `sum_t inverse[t][k] v_t` for
+    // the values `v_t`.
+    let points: Vec<_> = (0..=degree as u64).map(C::Scalar::from).collect();
+    let inverse: Vec<_> = (0..=degree)
+        .map(|t| {
+            let mut unit = vec![C::Scalar::zero(); degree + 1];
+            unit[t] = C::Scalar::one();
+            lagrange_interpolate(&points, &unit)
+        })
+        .collect();
+
+    let values: Vec<_> = points
+        .iter()
+        .map(|t| {
+            evaluate_constraints(
+                pk,
+                degree,
+                first.0.u + second.0.u * t,
+                |column, row| first.1.advice[column][row] + second.1.advice[column][row] * t,
+                |column, row| first.0.instance[column][row] + second.0.instance[column][row] * t,
+            )
+        })
+        .collect();
+
+    (0..constraints(&pk.vk.cs).count())
+        .flat_map(|constraint| {
+            let values = &values;
+            let inverse = &inverse;
+            (1..degree).map(move |k| {
+                let mut cross_term = pk.vk.domain.empty_lagrange();
+                parallelize(&mut cross_term, |cross_term, start| {
+                    for (row, cross_term) in (start..).zip(cross_term.iter_mut()) {
+                        *cross_term = values
+                            .iter()
+                            .zip(inverse.iter())
+                            .fold(C::Scalar::zero(), |acc, (values, inverse)| {
+                                acc + values[constraint][row] * inverse[k]
+                            });
+                    }
+                });
+                cross_term
+            })
+        })
+        .collect()
+}
+
+/// Returns the values of each constraint on every row, homogenised to
+/// `degree` with `u`, where `advice` and `instance` return the value of a
+/// column on a row.
+fn evaluate_constraints<C: CurveAffine>(
+    pk: &ProvingKey<C>,
+    degree: usize,
+    u: C::Scalar,
+    advice: impl Fn(usize, usize) -> C::Scalar + Sync,
+    instance: impl Fn(usize, usize) -> C::Scalar + Sync,
+) -> Vec<Polynomial<C::Scalar, LagrangeCoeff>> {
+    let n = pk.vk.domain.n as i32;
+    let fixed = &pk.fixed_values;
+    let (advice, instance) = (&advice, &instance);
+
+    constraints(&pk.vk.cs)
+        .map(|constraint| {
+            let mut values = pk.vk.domain.empty_lagrange();
+            parallelize(&mut values, |values, start| {
+                for (row, value) in (start..).zip(values.iter_mut()) {
+                    let rotate = |rotation: i32| (row as i32 + rotation).rem_euclid(n) as usize;
+                    let pad = |(value, current): (C::Scalar, usize), target: usize| {
+                        value * u.pow_vartime(&[(target - current) as u64])
+                    };
+                    let homogeneous = constraint.evaluate(
+                        &|scalar| (scalar, 0),
+                        &|_| panic!("virtual selectors are removed during optimization"),
+                        &|query| (fixed[query.column_index][rotate(query.rotation.0)], 0),
+                        &|query| (advice(query.column_index, rotate(query.rotation.0)), 1),
+                        &|query| (instance(query.column_index, rotate(query.rotation.0)), 1),
+                        &|_| unreachable!("challenges are rejected by check_foldable"),
+                        &|(a, degree)| (-a, degree),
+                        &|a, b| {
+                            let degree = a.1.max(b.1);
+                            (pad(a, degree) + pad(b, degree), degree)
+                        },
+                        &|(a, a_degree), (b, b_degree)| (a * b, a_degree + b_degree),
+                        &|(a, degree), scalar| (a * scalar, degree),
+                    );
+                    *value = pad(homogeneous, degree);
+                }
+            });
+            values
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{degree, fold, fold_instances, is_satisfied, relax, RelaxedInstance};
+    use crate::circuit::{Layouter, SimpleFloorPlanner, Value};
+    use crate::plonk::{
+        generate_witness, keygen_pk, keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error,
+        FirstPhase, Selector,
+    };
+    use crate::poly::{commitment::ParamsProver, ipa::commitment::ParamsIPA, Rotation};
+    use crate::transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    };
+    use assert_matches::assert_matches;
+    use group::prime::PrimeCurveAffine;
+    use halo2curves::pasta::{EqAffine, Fp};
+    use rand_core::OsRng;
+
+    #[derive(Clone)]
+    struct MulConfig {
+        a: Column<Advice>,
+        b: Column<Advice>,
+        c: Column<Advice>,
+        s: Selector,
+    }
+
+    #[derive(Clone, Default)]
+    struct MulCircuit {
+        a: Fp,
+        b: Fp,
+        c: Fp,
+    }
+
+    impl Circuit<Fp> for MulCircuit {
+        type Config = MulConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> MulConfig {
+            let (a, b, c) = (
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            );
+            let s = meta.selector();
+            meta.create_gate("mul", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                let c = meta.query_advice(c, Rotation::cur());
+                let s = meta.query_selector(s);
+                vec![s * (a * b - c)]
+            });
+            MulConfig { a, b, c, s }
+        }
+
+        fn synthesize(
+            &self,
+            config: MulConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "mul",
+                |mut region| {
+                    config.s.enable(&mut region, 0)?;
+                    region.assign_advice(|| "a", config.a, 0, || Value::known(self.a))?;
+                    region.assign_advice(|| "b", config.b, 0, || Value::known(self.b))?;
+                    region.assign_advice(|| "c", config.c, 0, || Value::known(self.c))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn fold_mul_gate() {
+        let params = ParamsIPA::<EqAffine>::new(4);
+        let vk = keygen_vk(&params, &MulCircuit::default()).unwrap();
+        let pk = keygen_pk(&params, vk, &MulCircuit::default()).unwrap();
+        let relaxed = |a: u64, b: u64, c: u64| {
+            let circuit = MulCircuit {
+                a: Fp::from(a),
+                b: Fp::from(b),
+                c: Fp::from(c),
+            };
+            let witness = generate_witness(pk.get_vk(), &circuit, &[]).unwrap();
+            relax(&params, &pk, &witness, &[], OsRng).unwrap()
+        };
+        let fold_with = |first: &(_, _), second: &(_, _)| {
+            let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+            let folded = fold(
+                &params,
+                &pk,
+                (&first.0, &first.1),
+                (&second.0, &second.1),
+                OsRng,
+                &mut transcript,
+            )
+            .unwrap();
+            (folded, transcript.finalize())
+        };
+
+        let first = relaxed(2, 3, 6);
+        let second = relaxed(4, 5, 20);
+        assert!(is_satisfied(&params, &pk, &first.0, &first.1));
+        let (folded, proof) = fold_with(&first, &second);
+        assert!(is_satisfied(&params, &pk, &folded.0, &folded.1));
+
+        // The verifier folds the instances to the same one
+        let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(&proof[..]);
+        let instance = fold_instances(pk.get_vk(), &first.0, &second.0, &mut transcript).unwrap();
+        assert_eq!(instance, folded.0);
+
+        // Folded relaxed witnesses keep folding
+        let third = relaxed(7, 8, 56);
+        let (folded, _) = fold_with(&folded, &third);
+        assert!(is_satisfied(&params, &pk, &folded.0, &folded.1));
+
+        // A witness that does not satisfy the gate cannot be folded away
+        let bad = relaxed(2, 3, 7);
+        assert!(!is_satisfied(&params, &pk, &bad.0, &bad.1));
+        let (folded, _) = fold_with(&first, &bad);
+        assert!(!is_satisfied(&params, &pk, &folded.0, &folded.1));
+    }
+
+    #[derive(Clone, Default)]
+    struct ChallengeCircuit;
+
+    impl Circuit<Fp> for ChallengeCircuit {
+        type Config = (Column<Advice>, Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let a = meta.advice_column();
+            let s = meta.selector();
+            let challenge = meta.challenge_usable_after(FirstPhase);
+            meta.create_gate("scaled square", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let s = meta.query_selector(s);
+                let challenge = meta.query_challenge(challenge);
+                vec![s * (challenge * a.clone() * a)]
+            });
+            (a, s)
+        }
+
+        fn synthesize(
+            &self,
+            (a, s): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "square",
+                |mut region| {
+                    s.enable(&mut region, 0)?;
+                    region.assign_advice(|| "a", a, 0, || Value::known(Fp::zero()))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn challenges_are_rejected() {
+        let params = ParamsIPA::<EqAffine>::new(4);
+        let vk = keygen_vk(&params, &ChallengeCircuit).unwrap();
+        let pk = keygen_pk(&params, vk, &ChallengeCircuit).unwrap();
+        assert_eq!(degree(&pk.get_vk().cs), 2);
+
+        let witness = generate_witness(pk.get_vk(), &ChallengeCircuit, &[]).unwrap();
+        assert_matches!(
+            relax(&params, &pk, &witness, &[], OsRng),
+            Err(Error::ChallengesNotFoldable)
+        );
+
+        let instance = RelaxedInstance {
+            u: Fp::one(),
+            instance: vec![],
+            advice_commitments: vec![EqAffine::identity()],
+            error_commitments: vec![EqAffine::identity()],
+        };
+        let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(&[][..]);
+        assert_matches!(
+            fold_instances(pk.get_vk(), &instance, &instance, &mut transcript),
+            Err(Error::ChallengesNotFoldable)
+        );
+    }
+}
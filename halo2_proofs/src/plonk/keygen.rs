@@ -1,6 +1,7 @@
 #![allow(clippy::int_plus_one)]
 
 use std::ops::Range;
+use std::sync::Arc;
 
 use ff::Field;
 use group::Curve;
@@ -15,22 +16,27 @@ use super::{
     VerifyingKey,
 };
 use crate::{
-    arithmetic::{parallelize, CurveAffine},
+    arithmetic::{parallelize, CurveAffine, FieldExt},
     circuit::Value,
     poly::{
         batch_invert_assigned,
         commitment::{Blind, Params, MSM},
-        EvaluationDomain,
+        DomainCache, EvaluationDomain,
     },
 };
 
 pub(crate) fn create_domain<C, ConcreteCircuit>(
     k: u32,
-) -> (
-    EvaluationDomain<C::Scalar>,
-    ConstraintSystem<C::Scalar>,
-    ConcreteCircuit::Config,
-)
+    cache: Option<&DomainCache<C::Scalar>>,
+    g_coset: Option<C::Scalar>,
+) -> Result<
+    (
+        Arc<EvaluationDomain<C::Scalar>>,
+        ConstraintSystem<C::Scalar>,
+        ConcreteCircuit::Config,
+    ),
+    Error,
+>
 where
     C: CurveAffine,
     ConcreteCircuit: Circuit<C::Scalar>,
@@ -40,9 +46,15 @@ where
 
     let degree = cs.degree();
 
-    let domain = EvaluationDomain::new(degree as u32, k);
+    let domain = match (g_coset, cache) {
+        (Some(g_coset), _) => {
+            Arc::new(EvaluationDomain::new_with_coset(degree as u32, k, g_coset)?)
+        }
+        (None, Some(cache)) => cache.get(degree as u32, k),
+        (None, None) => Arc::new(EvaluationDomain::new(degree as u32, k)),
+    };
 
-    (domain, cs, config)
+    Ok((domain, cs, config))
 }
 
 /// Assembly to be used in circuit synthesis.
@@ -70,6 +82,10 @@ impl<F: Field> Assignment<F> for Assembly<F> {
         // Do nothing; we don't care about regions in this context.
     }
 
+    fn uses_annotations(&self) -> bool {
+        false
+    }
+
     fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
     where
         A: FnOnce() -> AR,
@@ -199,6 +215,70 @@ impl<F: Field> Assignment<F> for Assembly<F> {
     }
 }
 
+/// Options of [`keygen_vk_with_options`] and [`keygen`], starting from
+/// [`KeygenOptions::new`], which generates keys like [`keygen_vk`]. The
+/// options combine, e.g. a key can have a custom coset and uncompressed
+/// selectors at once.
+#[derive(Clone, Copy, Debug)]
+pub struct KeygenOptions<'a, F: FieldExt> {
+    cache: Option<&'a DomainCache<F>>,
+    g_coset: Option<F>,
+    compress_selectors: bool,
+}
+
+impl<'a, F: FieldExt> KeygenOptions<'a, F> {
+    /// Returns the options of [`keygen_vk`]
+    pub fn new() -> Self {
+        KeygenOptions {
+            cache: None,
+            g_coset: None,
+            compress_selectors: true,
+        }
+    }
+
+    /// The extended domain of the key is the coset generated by `g_coset`
+    /// (see [`EvaluationDomain::new_with_coset`]) instead of $\zeta$.
+    ///
+    /// Proofs don't depend on the coset, but the cosets of the fixed and
+    /// permutation polynomials in the proving key do: [`keygen_pk`] follows
+    /// the key, and the coset is recorded when the key is serialized. Keygen
+    /// returns [`Error::InvalidCoset`] if `g_coset` is not
+    /// [valid](EvaluationDomain::is_valid_coset).
+    pub fn with_coset(mut self, g_coset: F) -> Self {
+        self.g_coset = Some(g_coset);
+        self
+    }
+
+    /// Commits to each selector directly as a fixed column of its own, rather
+    /// than compressing the selectors into fewer fixed columns. The columns
+    /// of the selectors follow those of the circuit in the order the
+    /// selectors were allocated.
+    ///
+    /// This saves compressing the selectors of large circuits and keeps their
+    /// layout simple for external tooling, at the cost of a commitment and an
+    /// evaluation for each selector. The choice is recorded in the verifying
+    /// key, and [`keygen_pk`] follows it.
+    pub fn without_selector_compression(mut self) -> Self {
+        self.compress_selectors = false;
+        self
+    }
+
+    /// Takes the evaluation domain of the key from `cache`. Keys generated
+    /// with the same cache for circuits of the same size and degree share the
+    /// domain, as do the proving keys built from them. A custom coset (see
+    /// [`Self::with_coset`]) takes precedence over the cache.
+    pub fn with_domain_cache(mut self, cache: &'a DomainCache<F>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+}
+
+impl<'a, F: FieldExt> Default for KeygenOptions<'a, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Generate a `VerifyingKey` from an instance of `Circuit`.
 pub fn keygen_vk<'params, C, P, ConcreteCircuit>(
     params: &P,
@@ -209,52 +289,44 @@ where
     P: Params<'params, C>,
     ConcreteCircuit: Circuit<C::Scalar>,
 {
-    let (domain, cs, config) = create_domain::<C, ConcreteCircuit>(params.k());
-
-    if (params.n() as usize) < cs.minimum_rows() {
-        return Err(Error::not_enough_rows_available(params.k()));
-    }
-
-    let mut assembly: Assembly<C::Scalar> = Assembly {
-        k: params.k(),
-        fixed: vec![domain.empty_lagrange_assigned(); cs.num_fixed_columns],
-        permutation: permutation::keygen::Assembly::new(params.n() as usize, &cs.permutation),
-        selectors: vec![vec![false; params.n() as usize]; cs.num_selectors],
-        usable_rows: 0..params.n() as usize - (cs.blinding_factors() + 1),
-        _marker: std::marker::PhantomData,
-    };
+    keygen_vk_with_options(params, circuit, KeygenOptions::new())
+}
 
-    // Synthesize the circuit to obtain URS
-    ConcreteCircuit::FloorPlanner::synthesize(
-        &mut assembly,
-        circuit,
+/// Same as [`keygen_vk`], but generates the key with `options`.
+pub fn keygen_vk_with_options<'params, C, P, ConcreteCircuit>(
+    params: &P,
+    circuit: &ConcreteCircuit,
+    options: KeygenOptions<'_, C::Scalar>,
+) -> Result<VerifyingKey<C>, Error>
+where
+    C: CurveAffine,
+    P: Params<'params, C>,
+    ConcreteCircuit: Circuit<C::Scalar>,
+{
+    let (domain, cs, config) =
+        create_domain::<C, ConcreteCircuit>(params.k(), options.cache, options.g_coset)?;
+    let synthesized = assemble::<C, _, _>(
+        params,
+        &domain,
+        cs,
         config,
-        cs.constants.clone(),
+        circuit,
+        options.compress_selectors,
     )?;
 
-    let mut fixed = batch_invert_assigned(assembly.fixed);
-    let (cs, selector_polys) = cs.compress_selectors(assembly.selectors.clone());
-    fixed.extend(
-        selector_polys
-            .into_iter()
-            .map(|poly| domain.lagrange_from_vec(poly)),
-    );
-
-    let permutation_vk = assembly
-        .permutation
-        .build_vk(params, &domain, &cs.permutation);
-
-    let fixed_commitments = fixed
-        .iter()
-        .map(|poly| params.commit_lagrange(poly, Blind::default()).to_affine())
-        .collect();
+    let permutation_vk =
+        synthesized
+            .permutation
+            .build_vk(params, &domain, &synthesized.cs.permutation);
 
-    Ok(VerifyingKey::from_parts(
+    Ok(vk_from_parts(
+        params,
         domain,
-        fixed_commitments,
+        synthesized.cs,
+        &synthesized.fixed,
         permutation_vk,
-        cs,
-        assembly.selectors,
+        synthesized.selectors,
+        options.compress_selectors,
     ))
 }
 
@@ -271,16 +343,100 @@ where
 {
     let mut cs = ConstraintSystem::default();
     let config = ConcreteCircuit::configure(&mut cs);
+    let synthesized = assemble::<C, _, _>(
+        params,
+        &vk.domain,
+        cs,
+        config,
+        circuit,
+        vk.compress_selectors,
+    )?;
 
-    let cs = cs;
+    let permutation_pk =
+        synthesized
+            .permutation
+            .build_pk(params, &vk.domain, &synthesized.cs.permutation);
 
+    Ok(pk_from_parts(params, vk, synthesized.fixed, permutation_pk))
+}
+
+/// Generate both a `VerifyingKey` and a `ProvingKey` from an instance of
+/// `Circuit` with `options`, as [`keygen_vk_with_options`] followed by
+/// [`keygen_pk`] would, but synthesizing the circuit and computing its fixed
+/// columns and permutation only once.
+pub fn keygen<'params, C, P, ConcreteCircuit>(
+    params: &P,
+    circuit: &ConcreteCircuit,
+    options: KeygenOptions<'_, C::Scalar>,
+) -> Result<(VerifyingKey<C>, ProvingKey<C>), Error>
+where
+    C: CurveAffine,
+    P: Params<'params, C>,
+    ConcreteCircuit: Circuit<C::Scalar>,
+{
+    let (domain, cs, config) =
+        create_domain::<C, ConcreteCircuit>(params.k(), options.cache, options.g_coset)?;
+    let synthesized = assemble::<C, _, _>(
+        params,
+        &domain,
+        cs,
+        config,
+        circuit,
+        options.compress_selectors,
+    )?;
+
+    let (permutation_vk, permutation_pk) =
+        synthesized
+            .permutation
+            .build_keys(params, &domain, &synthesized.cs.permutation);
+
+    let vk = vk_from_parts(
+        params,
+        domain,
+        synthesized.cs,
+        &synthesized.fixed,
+        permutation_vk,
+        synthesized.selectors,
+        options.compress_selectors,
+    );
+    let pk = pk_from_parts(params, vk.clone(), synthesized.fixed, permutation_pk);
+
+    Ok((vk, pk))
+}
+
+/// The fixed columns and the copy constraints of a circuit, from which both
+/// of its keys are built.
+struct Synthesized<F: Field> {
+    /// The constraint system, with the selectors converted into fixed columns
+    cs: ConstraintSystem<F>,
+    /// The fixed columns, followed by those of the selectors
+    fixed: Vec<Polynomial<F, LagrangeCoeff>>,
+    permutation: permutation::keygen::Assembly,
+    selectors: Vec<Vec<bool>>,
+}
+
+/// Synthesizes `circuit` to obtain its fixed columns and copy constraints,
+/// converting the selectors into fixed columns.
+fn assemble<'params, C, P, ConcreteCircuit>(
+    params: &P,
+    domain: &EvaluationDomain<C::Scalar>,
+    cs: ConstraintSystem<C::Scalar>,
+    config: ConcreteCircuit::Config,
+    circuit: &ConcreteCircuit,
+    compress_selectors: bool,
+) -> Result<Synthesized<C::Scalar>, Error>
+where
+    C: CurveAffine,
+    P: Params<'params, C>,
+    ConcreteCircuit: Circuit<C::Scalar>,
+{
     if (params.n() as usize) < cs.minimum_rows() {
         return Err(Error::not_enough_rows_available(params.k()));
     }
 
     let mut assembly: Assembly<C::Scalar> = Assembly {
         k: params.k(),
-        fixed: vec![vk.domain.empty_lagrange_assigned(); cs.num_fixed_columns],
+        fixed: vec![domain.empty_lagrange_assigned(); cs.num_fixed_columns],
         permutation: permutation::keygen::Assembly::new(params.n() as usize, &cs.permutation),
         selectors: vec![vec![false; params.n() as usize]; cs.num_selectors],
         usable_rows: 0..params.n() as usize - (cs.blinding_factors() + 1),
@@ -296,27 +452,74 @@ where
     )?;
 
     let mut fixed = batch_invert_assigned(assembly.fixed);
-    let (cs, selector_polys) = cs.compress_selectors(assembly.selectors);
+    let (cs, selector_polys) = if compress_selectors {
+        cs.compress_selectors(assembly.selectors.clone())
+    } else {
+        cs.directly_convert_selectors_to_fixed(assembly.selectors.clone())
+    };
     fixed.extend(
         selector_polys
             .into_iter()
-            .map(|poly| vk.domain.lagrange_from_vec(poly)),
+            .map(|poly| domain.lagrange_from_vec(poly)),
     );
 
+    Ok(Synthesized {
+        cs,
+        fixed,
+        permutation: assembly.permutation,
+        selectors: assembly.selectors,
+    })
+}
+
+fn vk_from_parts<'params, C, P>(
+    params: &P,
+    domain: Arc<EvaluationDomain<C::Scalar>>,
+    cs: ConstraintSystem<C::Scalar>,
+    fixed: &[Polynomial<C::Scalar, LagrangeCoeff>],
+    permutation_vk: permutation::VerifyingKey<C>,
+    selectors: Vec<Vec<bool>>,
+    compress_selectors: bool,
+) -> VerifyingKey<C>
+where
+    C: CurveAffine,
+    P: Params<'params, C>,
+{
+    let fixed_commitments = fixed
+        .iter()
+        .map(|poly| params.commit_lagrange(poly, Blind::default()).to_affine())
+        .collect();
+
+    VerifyingKey::from_parts(
+        domain,
+        fixed_commitments,
+        permutation_vk,
+        cs,
+        selectors,
+        compress_selectors,
+    )
+}
+
+fn pk_from_parts<'params, C, P>(
+    params: &P,
+    vk: VerifyingKey<C>,
+    fixed: Vec<Polynomial<C::Scalar, LagrangeCoeff>>,
+    permutation_pk: permutation::ProvingKey<C>,
+) -> ProvingKey<C>
+where
+    C: CurveAffine,
+    P: Params<'params, C>,
+{
+    let cs = &vk.cs;
     let fixed_polys: Vec<_> = fixed
         .iter()
         .map(|poly| vk.domain.lagrange_to_coeff(poly.clone()))
         .collect();
 
-    let fixed_cosets = fixed_polys
+    let fixed_cosets: Vec<_> = fixed_polys
         .iter()
         .map(|poly| vk.domain.coeff_to_extended(poly.clone()))
         .collect();
 
-    let permutation_pk = assembly
-        .permutation
-        .build_pk(params, &vk.domain, &cs.permutation);
-
     // Compute l_0(X)
     // TODO: this can be done more efficiently
     let mut l0 = vk.domain.empty_lagrange();
@@ -352,8 +555,9 @@ where
 
     // Compute the optimized evaluation data structure
     let ev = Evaluator::new(&vk.cs);
+    let fixed_expression_cosets = ev.evaluate_fixed_expressions(&vk.domain, &fixed_cosets);
 
-    Ok(ProvingKey {
+    ProvingKey {
         vk,
         l0,
         l_last,
@@ -363,5 +567,6 @@ where
         fixed_cosets,
         permutation: permutation_pk,
         ev,
-    })
+        fixed_expression_cosets,
+    }
 }
@@ -61,19 +61,21 @@ impl<F: Field> Argument<F> {
         // value of a' is the same as the current value.
         // degree 3:
         // (1 - (l_last(X) + l_blind(X))) * (a′(X) − s′(X))⋅(a′(X) − a′(\omega^{-1} X)) = 0
-        let mut input_degree = 1;
-        for expr in self.input_expressions.iter() {
-            input_degree = std::cmp::max(input_degree, expr.degree());
-        }
-        let mut table_degree = 1;
-        for expr in self.table_expressions.iter() {
-            table_degree = std::cmp::max(table_degree, expr.degree());
-        }
+        //
+        // The constraints of the log-derivative argument are of the same
+        // degrees or lower:
+        // l_0(X) * phi(X) = 0 and l_last(X) * phi(X) = 0, of degree 2, and
+        // (1 - (l_last(X) + l_blind(X))) * (
+        //   (phi(\omega X) - phi(X)) (a(X) + \beta) (s(X) + \beta)
+        //   - (s(X) + \beta) + m(X) (a(X) + \beta)
+        // ) = 0, of degree (2 + input_degree + table_degree)
+        let input_degree = self.input_degree();
+        let table_degree = self.table_degree();
 
-        // In practice because input_degree and table_degree are initialized to
-        // one, the latter half of this max() invocation is at least 4 always,
+        // In practice because input_degree and table_degree are at least one,
+        // the latter half of this max() invocation is at least 4 always,
         // rendering this call pointless except to be explicit in case we change
-        // the initialization of input_degree/table_degree in the future.
+        // the definition of input_degree/table_degree in the future.
         std::cmp::max(
             // (1 - (l_last + l_blind)) z(\omega X) (a'(X) + \beta) (s'(X) + \gamma)
             4,
@@ -82,6 +84,22 @@ impl<F: Field> Argument<F> {
         )
     }
 
+    /// Returns the degree of the compressed input expressions, at least 1.
+    pub(crate) fn input_degree(&self) -> usize {
+        self.input_expressions
+            .iter()
+            .map(|expr| expr.degree())
+            .fold(1, std::cmp::max)
+    }
+
+    /// Returns the degree of the compressed table expressions, at least 1.
+    pub(crate) fn table_degree(&self) -> usize {
+        self.table_expressions
+            .iter()
+            .map(|expr| expr.degree())
+            .fold(1, std::cmp::max)
+    }
+
     /// Returns input of this argument
     pub fn input_expressions(&self) -> &Vec<Expression<F>> {
         &self.input_expressions
@@ -1,15 +1,15 @@
 use super::super::{
     circuit::Expression, ChallengeBeta, ChallengeGamma, ChallengeTheta, ChallengeX, Error,
-    ProvingKey,
+    LookupArgument, ProvingKey,
 };
 use super::Argument;
 use crate::plonk::evaluation::evaluate;
 use crate::{
-    arithmetic::{eval_polynomial, parallelize, CurveAffine, FieldExt},
+    arithmetic::{batch_invert, eval_polynomial, parallelize, CurveAffine, FieldExt},
     poly::{
         commitment::{Blind, Params},
         Coeff, EvaluationDomain, ExtendedLagrangeCoeff, LagrangeCoeff, Polynomial, ProverQuery,
-        Rotation,
+        Rotation, Wipe, Zeroizing,
     },
     transcript::{EncodedChallenge, TranscriptWrite},
 };
@@ -25,26 +25,52 @@ use std::{
     ops::{Mul, MulAssign},
 };
 
+/// The columns of a lookup committed to after `theta`
 #[derive(Debug)]
-pub(in crate::plonk) struct Permuted<C: CurveAffine> {
-    compressed_input_expression: Polynomial<C::Scalar, LagrangeCoeff>,
-    permuted_input_expression: Polynomial<C::Scalar, LagrangeCoeff>,
-    permuted_input_poly: Polynomial<C::Scalar, Coeff>,
-    permuted_input_blind: Blind<C::Scalar>,
-    compressed_table_expression: Polynomial<C::Scalar, LagrangeCoeff>,
-    permuted_table_expression: Polynomial<C::Scalar, LagrangeCoeff>,
-    permuted_table_poly: Polynomial<C::Scalar, Coeff>,
-    permuted_table_blind: Blind<C::Scalar>,
+pub(in crate::plonk) enum Permuted<C: CurveAffine> {
+    /// The permuted input and table columns of [`LookupArgument::Permutation`]
+    Permutation {
+        compressed_input_expression: Polynomial<C::Scalar, LagrangeCoeff>,
+        permuted_input_expression: Polynomial<C::Scalar, LagrangeCoeff>,
+        permuted_input_poly: Polynomial<C::Scalar, Coeff>,
+        permuted_input_blind: Blind<C::Scalar>,
+        compressed_table_expression: Polynomial<C::Scalar, LagrangeCoeff>,
+        permuted_table_expression: Polynomial<C::Scalar, LagrangeCoeff>,
+        permuted_table_poly: Polynomial<C::Scalar, Coeff>,
+        permuted_table_blind: Blind<C::Scalar>,
+    },
+    /// The multiplicities of the table rows of
+    /// [`LookupArgument::LogDerivative`]
+    LogDerivative {
+        compressed_input_expressions: Vec<Polynomial<C::Scalar, LagrangeCoeff>>,
+        compressed_table_expression: Polynomial<C::Scalar, LagrangeCoeff>,
+        multiplicities: Polynomial<C::Scalar, LagrangeCoeff>,
+        multiplicities_poly: Polynomial<C::Scalar, Coeff>,
+        multiplicities_blind: Blind<C::Scalar>,
+    },
 }
 
+/// The columns of a lookup committed to once `beta` and `gamma` are known
 #[derive(Debug)]
-pub(in crate::plonk) struct Committed<C: CurveAffine> {
-    pub(in crate::plonk) permuted_input_poly: Polynomial<C::Scalar, Coeff>,
-    permuted_input_blind: Blind<C::Scalar>,
-    pub(in crate::plonk) permuted_table_poly: Polynomial<C::Scalar, Coeff>,
-    permuted_table_blind: Blind<C::Scalar>,
-    pub(in crate::plonk) product_poly: Polynomial<C::Scalar, Coeff>,
-    product_blind: Blind<C::Scalar>,
+pub(in crate::plonk) enum Committed<C: CurveAffine> {
+    /// The permuted columns and the grand product of
+    /// [`LookupArgument::Permutation`]
+    Permutation {
+        permuted_input_poly: Polynomial<C::Scalar, Coeff>,
+        permuted_input_blind: Blind<C::Scalar>,
+        permuted_table_poly: Polynomial<C::Scalar, Coeff>,
+        permuted_table_blind: Blind<C::Scalar>,
+        product_poly: Polynomial<C::Scalar, Coeff>,
+        product_blind: Blind<C::Scalar>,
+    },
+    /// The multiplicities and the running sum of
+    /// [`LookupArgument::LogDerivative`]
+    LogDerivative {
+        multiplicities_poly: Polynomial<C::Scalar, Coeff>,
+        multiplicities_blind: Blind<C::Scalar>,
+        sum_poly: Polynomial<C::Scalar, Coeff>,
+        sum_blind: Blind<C::Scalar>,
+    },
 }
 
 pub(in crate::plonk) struct Evaluated<C: CurveAffine> {
@@ -61,6 +87,11 @@ impl<F: FieldExt> Argument<F> {
     /// - constructs Permuted<C> struct using permuted_input_value = A', and
     ///   permuted_table_expression = S'.
     /// The Permuted<C> struct is used to update the Lookup, and is then returned.
+    ///
+    /// With [`LookupArgument::LogDerivative`], this method commits instead to
+    /// the number of times each row of S_compressed occurs in the
+    /// A_compressed of every lookup of `batch`, which all share the table
+    /// expressions. Otherwise, `batch` holds a single lookup.
     pub(in crate::plonk) fn commit_permuted<
         'a,
         'params: 'a,
@@ -70,7 +101,7 @@ impl<F: FieldExt> Argument<F> {
         R: RngCore,
         T: TranscriptWrite<C, E>,
     >(
-        &self,
+        batch: &[&Self],
         pk: &ProvingKey<C>,
         params: &P,
         domain: &EvaluationDomain<C::Scalar>,
@@ -101,17 +132,65 @@ impl<F: FieldExt> Argument<F> {
                         challenges,
                     ))
                 })
-                .fold(domain.empty_lagrange(), |acc, expression| {
-                    acc * *theta + &expression
+                .fold(domain.empty_lagrange(), |mut acc, expression| {
+                    acc *= *theta;
+                    acc += &expression;
+                    acc
                 });
             compressed_expression
         };
 
-        // Get values of input expressions involved in the lookup and compress them
-        let compressed_input_expression = compress_expressions(&self.input_expressions);
-
         // Get values of table expressions involved in the lookup and compress them
-        let compressed_table_expression = compress_expressions(&self.table_expressions);
+        let compressed_table_expression =
+            Zeroizing::new(compress_expressions(&batch[0].table_expressions));
+
+        // Closure to construct commitment to vector of values
+        let commit_values = |values: &Polynomial<C::Scalar, LagrangeCoeff>, rng: &mut R| {
+            let poly = pk.vk.domain.lagrange_to_coeff(values.clone());
+            let blind = Blind(C::Scalar::random(rng));
+            let commitment = params.commit_lagrange(values, blind).to_affine();
+            (poly, blind, commitment)
+        };
+
+        if pk.vk.cs.lookup_argument == LookupArgument::LogDerivative {
+            // Get values of input expressions involved in each lookup and
+            // compress them
+            let compressed_input_expressions = Zeroizing::new(
+                batch
+                    .iter()
+                    .map(|lookup| compress_expressions(&lookup.input_expressions))
+                    .collect::<Vec<_>>(),
+            );
+
+            let multiplicities = Zeroizing::new(compute_multiplicities(
+                pk,
+                params,
+                domain,
+                &mut rng,
+                &compressed_input_expressions,
+                &compressed_table_expression,
+            )?);
+            let (multiplicities_poly, multiplicities_blind, multiplicities_commitment) =
+                commit_values(&multiplicities, &mut rng);
+            let multiplicities_poly = Zeroizing::new(multiplicities_poly);
+
+            // Hash multiplicities commitment
+            transcript.write_point(multiplicities_commitment)?;
+
+            return Ok(Permuted::LogDerivative {
+                compressed_input_expressions: compressed_input_expressions.into_inner(),
+                compressed_table_expression: compressed_table_expression.into_inner(),
+                multiplicities: multiplicities.into_inner(),
+                multiplicities_poly: multiplicities_poly.into_inner(),
+                multiplicities_blind,
+            });
+        }
+
+        assert_eq!(batch.len(), 1, "only logUp lookups are batched");
+
+        // Get values of input expressions involved in the lookup and compress them
+        let compressed_input_expression =
+            Zeroizing::new(compress_expressions(&batch[0].input_expressions));
 
         // Permute compressed (InputExpression, TableExpression) pair
         let (permuted_input_expression, permuted_table_expression) = permute_expression_pair(
@@ -122,22 +201,18 @@ impl<F: FieldExt> Argument<F> {
             &compressed_input_expression,
             &compressed_table_expression,
         )?;
-
-        // Closure to construct commitment to vector of values
-        let mut commit_values = |values: &Polynomial<C::Scalar, LagrangeCoeff>| {
-            let poly = pk.vk.domain.lagrange_to_coeff(values.clone());
-            let blind = Blind(C::Scalar::random(&mut rng));
-            let commitment = params.commit_lagrange(values, blind).to_affine();
-            (poly, blind, commitment)
-        };
+        let permuted_input_expression = Zeroizing::new(permuted_input_expression);
+        let permuted_table_expression = Zeroizing::new(permuted_table_expression);
 
         // Commit to permuted input expression
         let (permuted_input_poly, permuted_input_blind, permuted_input_commitment) =
-            commit_values(&permuted_input_expression);
+            commit_values(&permuted_input_expression, &mut rng);
+        let permuted_input_poly = Zeroizing::new(permuted_input_poly);
 
         // Commit to permuted table expression
         let (permuted_table_poly, permuted_table_blind, permuted_table_commitment) =
-            commit_values(&permuted_table_expression);
+            commit_values(&permuted_table_expression, &mut rng);
+        let permuted_table_poly = Zeroizing::new(permuted_table_poly);
 
         // Hash permuted input commitment
         transcript.write_point(permuted_input_commitment)?;
@@ -145,14 +220,14 @@ impl<F: FieldExt> Argument<F> {
         // Hash permuted table commitment
         transcript.write_point(permuted_table_commitment)?;
 
-        Ok(Permuted {
-            compressed_input_expression,
-            permuted_input_expression,
-            permuted_input_poly,
+        Ok(Permuted::Permutation {
+            compressed_input_expression: compressed_input_expression.into_inner(),
+            permuted_input_expression: permuted_input_expression.into_inner(),
+            permuted_input_poly: permuted_input_poly.into_inner(),
             permuted_input_blind,
-            compressed_table_expression,
-            permuted_table_expression,
-            permuted_table_poly,
+            compressed_table_expression: compressed_table_expression.into_inner(),
+            permuted_table_expression: permuted_table_expression.into_inner(),
+            permuted_table_poly: permuted_table_poly.into_inner(),
             permuted_table_blind,
         })
     }
@@ -164,6 +239,9 @@ impl<C: CurveAffine> Permuted<C> {
     /// grand product polynomial over the lookup. The grand product polynomial
     /// is used to populate the Product<C> struct. The Product<C> struct is
     /// added to the Lookup and finally returned by the method.
+    ///
+    /// With [`LookupArgument::LogDerivative`], this method constructs the
+    /// running sum of the fractions instead, see [`Self::commit_sum`].
     pub(in crate::plonk) fn commit_product<
         'params,
         P: Params<'params, C>,
@@ -179,6 +257,47 @@ impl<C: CurveAffine> Permuted<C> {
         mut rng: R,
         transcript: &mut T,
     ) -> Result<Committed<C>, Error> {
+        let (
+            compressed_input_expression,
+            permuted_input_expression,
+            permuted_input_poly,
+            permuted_input_blind,
+            compressed_table_expression,
+            permuted_table_expression,
+            permuted_table_poly,
+            permuted_table_blind,
+        ) = match self {
+            Permuted::Permutation {
+                compressed_input_expression,
+                permuted_input_expression,
+                permuted_input_poly,
+                permuted_input_blind,
+                compressed_table_expression,
+                permuted_table_expression,
+                permuted_table_poly,
+                permuted_table_blind,
+            } => (
+                compressed_input_expression,
+                permuted_input_expression,
+                permuted_input_poly,
+                permuted_input_blind,
+                compressed_table_expression,
+                permuted_table_expression,
+                permuted_table_poly,
+                permuted_table_blind,
+            ),
+            Permuted::LogDerivative { .. } => {
+                return self.commit_sum(pk, params, beta, rng, transcript);
+            }
+        };
+        // Wipe the columns that are not part of the committed lookup, and
+        // the others if committing fails
+        let compressed_input_expression = Zeroizing::new(compressed_input_expression);
+        let permuted_input_expression = Zeroizing::new(permuted_input_expression);
+        let permuted_input_poly = Zeroizing::new(permuted_input_poly);
+        let compressed_table_expression = Zeroizing::new(compressed_table_expression);
+        let permuted_table_expression = Zeroizing::new(permuted_table_expression);
+        let permuted_table_poly = Zeroizing::new(permuted_table_poly);
         let blinding_factors = pk.vk.cs.blinding_factors();
         // Goal is to compute the products of fractions
         //
@@ -196,8 +315,8 @@ impl<C: CurveAffine> Permuted<C> {
         parallelize(&mut lookup_product, |lookup_product, start| {
             for ((lookup_product, permuted_input_value), permuted_table_value) in lookup_product
                 .iter_mut()
-                .zip(self.permuted_input_expression[start..].iter())
-                .zip(self.permuted_table_expression[start..].iter())
+                .zip(permuted_input_expression[start..].iter())
+                .zip(permuted_table_expression[start..].iter())
             {
                 *lookup_product = (*beta + permuted_input_value) * &(*gamma + permuted_table_value);
             }
@@ -205,7 +324,7 @@ impl<C: CurveAffine> Permuted<C> {
 
         // Batch invert to obtain the denominators for the lookup product
         // polynomials
-        lookup_product.iter_mut().batch_invert();
+        batch_invert(&mut lookup_product);
 
         // Finish the computation of the entire fraction by computing the numerators
         // (\theta^{m-1} a_0(\omega^i) + \theta^{m-2} a_1(\omega^i) + ... + \theta a_{m-2}(\omega^i) + a_{m-1}(\omega^i) + \beta)
@@ -214,8 +333,8 @@ impl<C: CurveAffine> Permuted<C> {
             for (i, product) in product.iter_mut().enumerate() {
                 let i = i + start;
 
-                *product *= &(self.compressed_input_expression[i] + &*beta);
-                *product *= &(self.compressed_table_expression[i] + &*gamma);
+                *product *= &(compressed_input_expression[i] + &*beta);
+                *product *= &(compressed_table_expression[i] + &*gamma);
             }
         });
 
@@ -263,16 +382,16 @@ impl<C: CurveAffine> Permuted<C> {
             // - z(X) (\theta^{m-1} a_0(X) + ... + a_{m-1}(X) + \beta) (\theta^{m-1} s_0(X) + ... + s_{m-1}(X) + \gamma)
             for i in 0..u {
                 let mut left = z[i + 1];
-                let permuted_input_value = &self.permuted_input_expression[i];
+                let permuted_input_value = &permuted_input_expression[i];
 
-                let permuted_table_value = &self.permuted_table_expression[i];
+                let permuted_table_value = &permuted_table_expression[i];
 
                 left *= &(*beta + permuted_input_value);
                 left *= &(*gamma + permuted_table_value);
 
                 let mut right = z[i];
-                let mut input_term = self.compressed_input_expression[i];
-                let mut table_term = self.compressed_table_expression[i];
+                let mut input_term = compressed_input_expression[i];
+                let mut table_term = compressed_table_expression[i];
 
                 input_term += &(*beta);
                 table_term += &(*gamma);
@@ -294,15 +413,146 @@ impl<C: CurveAffine> Permuted<C> {
         // Hash product commitment
         transcript.write_point(product_commitment)?;
 
-        Ok(Committed::<C> {
-            permuted_input_poly: self.permuted_input_poly,
-            permuted_input_blind: self.permuted_input_blind,
-            permuted_table_poly: self.permuted_table_poly,
-            permuted_table_blind: self.permuted_table_blind,
+        Ok(Committed::Permutation {
+            permuted_input_poly: permuted_input_poly.into_inner(),
+            permuted_input_blind,
+            permuted_table_poly: permuted_table_poly.into_inner(),
+            permuted_table_blind,
             product_poly: z,
             product_blind,
         })
     }
+
+    /// Constructs the running sum of the log-derivative argument, which starts
+    /// at zero and adds on each usable row
+    ///
+    /// \sum_j 1 / (A_{j,compressed}(\omega^i) + \beta) - m(\omega^i) / (S_compressed(\omega^i) + \beta)
+    ///
+    /// where A_j are the inputs of the lookups of the batch and m is the
+    /// number of times each row of the table occurs in them, so that it is
+    /// back at zero on the last row.
+    fn commit_sum<
+        'params,
+        P: Params<'params, C>,
+        E: EncodedChallenge<C>,
+        R: RngCore,
+        T: TranscriptWrite<C, E>,
+    >(
+        self,
+        pk: &ProvingKey<C>,
+        params: &P,
+        beta: ChallengeBeta<C>,
+        mut rng: R,
+        transcript: &mut T,
+    ) -> Result<Committed<C>, Error> {
+        let (
+            compressed_input_expressions,
+            compressed_table_expression,
+            multiplicities,
+            multiplicities_poly,
+            multiplicities_blind,
+        ) = match self {
+            Permuted::LogDerivative {
+                compressed_input_expressions,
+                compressed_table_expression,
+                multiplicities,
+                multiplicities_poly,
+                multiplicities_blind,
+            } => (
+                compressed_input_expressions,
+                compressed_table_expression,
+                multiplicities,
+                multiplicities_poly,
+                multiplicities_blind,
+            ),
+            Permuted::Permutation { .. } => unreachable!("the lookup commits to a product"),
+        };
+        let compressed_input_expressions = Zeroizing::new(compressed_input_expressions);
+        let compressed_table_expression = Zeroizing::new(compressed_table_expression);
+        let multiplicities = Zeroizing::new(multiplicities);
+        let multiplicities_poly = Zeroizing::new(multiplicities_poly);
+        let blinding_factors = pk.vk.cs.blinding_factors();
+        let usable_rows = params.n() as usize - (blinding_factors + 1);
+
+        // Invert (A_{j,compressed}(\omega^i) + \beta) and (S_compressed(\omega^i) + \beta)
+        // on the usable rows, summing the inverses of the inputs
+        let mut input_inverses = vec![C::Scalar::zero(); usable_rows];
+        for compressed_input_expression in compressed_input_expressions.iter() {
+            let mut inverses = vec![C::Scalar::zero(); usable_rows];
+            parallelize(&mut inverses, |inverses, start| {
+                for (inverse, input_value) in inverses
+                    .iter_mut()
+                    .zip(compressed_input_expression[start..].iter())
+                {
+                    *inverse = *beta + input_value;
+                }
+            });
+            batch_invert(&mut inverses);
+            parallelize(&mut input_inverses, |input_inverses, start| {
+                for (input_inverse, inverse) in
+                    input_inverses.iter_mut().zip(inverses[start..].iter())
+                {
+                    *input_inverse += inverse;
+                }
+            });
+        }
+        let mut table_inverses = vec![C::Scalar::zero(); usable_rows];
+        parallelize(&mut table_inverses, |table_inverses, start| {
+            for (table_inverse, table_value) in table_inverses
+                .iter_mut()
+                .zip(compressed_table_expression[start..].iter())
+            {
+                *table_inverse = *beta + table_value;
+            }
+        });
+        batch_invert(&mut table_inverses);
+
+        // Compute the evaluations of the running sum over our domain, starting
+        // with phi[0] = 0
+        let phi = iter::once(C::Scalar::zero())
+            .chain(
+                input_inverses
+                    .iter()
+                    .zip(table_inverses.iter())
+                    .zip(multiplicities.iter())
+                    .map(|((input_inverse, table_inverse), multiplicity)| {
+                        *input_inverse - *multiplicity * table_inverse
+                    }),
+            )
+            .scan(C::Scalar::zero(), |state, cur| {
+                *state += &cur;
+                Some(*state)
+            })
+            // Take all rows including the "last" row which should be zero
+            .take(params.n() as usize - blinding_factors)
+            // Chain random blinding factors.
+            .chain((0..blinding_factors).map(|_| C::Scalar::random(&mut rng)))
+            .collect::<Vec<_>>();
+        assert_eq!(phi.len(), params.n() as usize);
+        let phi = pk.vk.domain.lagrange_from_vec(phi);
+
+        #[cfg(feature = "sanity-checks")]
+        {
+            // l_last(X) * phi(X) = 0
+            // The sum is back at zero on the last row unless an input is
+            // missing from the table, which commit_permuted rejects.
+            assert_eq!(phi[usable_rows], C::Scalar::zero());
+        }
+
+        let sum_blind = Blind(C::Scalar::random(rng));
+        let sum_commitment = params.commit_lagrange(&phi, sum_blind).to_affine();
+        let phi = pk.vk.domain.lagrange_to_coeff(phi);
+
+        // Hash running sum commitment
+        transcript.write_point(sum_commitment)?;
+
+        Ok(Committed::LogDerivative {
+            multiplicities_poly: multiplicities_poly.into_inner(),
+            multiplicities_blind,
+            sum_poly: phi,
+            sum_blind,
+        })
+    }
 }
 
 impl<C: CurveAffine> Committed<C> {
@@ -316,20 +566,32 @@ impl<C: CurveAffine> Committed<C> {
         let x_inv = domain.rotate_omega(*x, Rotation::prev());
         let x_next = domain.rotate_omega(*x, Rotation::next());
 
-        let product_eval = eval_polynomial(&self.product_poly, *x);
-        let product_next_eval = eval_polynomial(&self.product_poly, x_next);
-        let permuted_input_eval = eval_polynomial(&self.permuted_input_poly, *x);
-        let permuted_input_inv_eval = eval_polynomial(&self.permuted_input_poly, x_inv);
-        let permuted_table_eval = eval_polynomial(&self.permuted_table_poly, *x);
+        let evals = match &self {
+            Committed::Permutation {
+                permuted_input_poly,
+                permuted_table_poly,
+                product_poly,
+                ..
+            } => vec![
+                eval_polynomial(product_poly, *x),
+                eval_polynomial(product_poly, x_next),
+                eval_polynomial(permuted_input_poly, *x),
+                eval_polynomial(permuted_input_poly, x_inv),
+                eval_polynomial(permuted_table_poly, *x),
+            ],
+            Committed::LogDerivative {
+                multiplicities_poly,
+                sum_poly,
+                ..
+            } => vec![
+                eval_polynomial(sum_poly, *x),
+                eval_polynomial(sum_poly, x_next),
+                eval_polynomial(multiplicities_poly, *x),
+            ],
+        };
 
         // Hash each advice evaluation
-        for eval in iter::empty()
-            .chain(Some(product_eval))
-            .chain(Some(product_next_eval))
-            .chain(Some(permuted_input_eval))
-            .chain(Some(permuted_input_inv_eval))
-            .chain(Some(permuted_table_eval))
-        {
+        for eval in evals {
             transcript.write_scalar(eval)?;
         }
 
@@ -337,6 +599,87 @@ impl<C: CurveAffine> Committed<C> {
     }
 }
 
+impl<C: CurveAffine> Wipe for Permuted<C> {
+    #[cfg(feature = "zeroize")]
+    fn wipe(&mut self) {
+        match self {
+            Permuted::Permutation {
+                compressed_input_expression,
+                permuted_input_expression,
+                permuted_input_poly,
+                permuted_input_blind,
+                compressed_table_expression,
+                permuted_table_expression,
+                permuted_table_poly,
+                permuted_table_blind,
+            } => {
+                compressed_input_expression.wipe();
+                permuted_input_expression.wipe();
+                permuted_input_poly.wipe();
+                permuted_input_blind.wipe();
+                compressed_table_expression.wipe();
+                permuted_table_expression.wipe();
+                permuted_table_poly.wipe();
+                permuted_table_blind.wipe();
+            }
+            Permuted::LogDerivative {
+                compressed_input_expressions,
+                compressed_table_expression,
+                multiplicities,
+                multiplicities_poly,
+                multiplicities_blind,
+            } => {
+                compressed_input_expressions.wipe();
+                compressed_table_expression.wipe();
+                multiplicities.wipe();
+                multiplicities_poly.wipe();
+                multiplicities_blind.wipe();
+            }
+        }
+    }
+}
+
+impl<C: CurveAffine> Wipe for Committed<C> {
+    #[cfg(feature = "zeroize")]
+    fn wipe(&mut self) {
+        match self {
+            Committed::Permutation {
+                permuted_input_poly,
+                permuted_input_blind,
+                permuted_table_poly,
+                permuted_table_blind,
+                product_poly,
+                product_blind,
+            } => {
+                permuted_input_poly.wipe();
+                permuted_input_blind.wipe();
+                permuted_table_poly.wipe();
+                permuted_table_blind.wipe();
+                product_poly.wipe();
+                product_blind.wipe();
+            }
+            Committed::LogDerivative {
+                multiplicities_poly,
+                multiplicities_blind,
+                sum_poly,
+                sum_blind,
+            } => {
+                multiplicities_poly.wipe();
+                multiplicities_blind.wipe();
+                sum_poly.wipe();
+                sum_blind.wipe();
+            }
+        }
+    }
+}
+
+impl<C: CurveAffine> Wipe for Evaluated<C> {
+    #[cfg(feature = "zeroize")]
+    fn wipe(&mut self) {
+        self.constructed.wipe();
+    }
+}
+
 impl<C: CurveAffine> Evaluated<C> {
     pub(in crate::plonk) fn open<'a>(
         &'a self,
@@ -346,40 +689,119 @@ impl<C: CurveAffine> Evaluated<C> {
         let x_inv = pk.vk.domain.rotate_omega(*x, Rotation::prev());
         let x_next = pk.vk.domain.rotate_omega(*x, Rotation::next());
 
-        iter::empty()
-            // Open lookup product commitments at x
-            .chain(Some(ProverQuery {
-                point: *x,
-                poly: &self.constructed.product_poly,
-                blind: self.constructed.product_blind,
-            }))
-            // Open lookup input commitments at x
-            .chain(Some(ProverQuery {
-                point: *x,
-                poly: &self.constructed.permuted_input_poly,
-                blind: self.constructed.permuted_input_blind,
-            }))
-            // Open lookup table commitments at x
-            .chain(Some(ProverQuery {
-                point: *x,
-                poly: &self.constructed.permuted_table_poly,
-                blind: self.constructed.permuted_table_blind,
-            }))
-            // Open lookup input commitments at x_inv
-            .chain(Some(ProverQuery {
-                point: x_inv,
-                poly: &self.constructed.permuted_input_poly,
-                blind: self.constructed.permuted_input_blind,
-            }))
-            // Open lookup product commitments at x_next
-            .chain(Some(ProverQuery {
-                point: x_next,
-                poly: &self.constructed.product_poly,
-                blind: self.constructed.product_blind,
-            }))
+        match &self.constructed {
+            Committed::Permutation {
+                permuted_input_poly,
+                permuted_input_blind,
+                permuted_table_poly,
+                permuted_table_blind,
+                product_poly,
+                product_blind,
+            } => vec![
+                // Open lookup product commitments at x
+                ProverQuery {
+                    point: *x,
+                    poly: product_poly,
+                    blind: *product_blind,
+                },
+                // Open lookup input commitments at x
+                ProverQuery {
+                    point: *x,
+                    poly: permuted_input_poly,
+                    blind: *permuted_input_blind,
+                },
+                // Open lookup table commitments at x
+                ProverQuery {
+                    point: *x,
+                    poly: permuted_table_poly,
+                    blind: *permuted_table_blind,
+                },
+                // Open lookup input commitments at x_inv
+                ProverQuery {
+                    point: x_inv,
+                    poly: permuted_input_poly,
+                    blind: *permuted_input_blind,
+                },
+                // Open lookup product commitments at x_next
+                ProverQuery {
+                    point: x_next,
+                    poly: product_poly,
+                    blind: *product_blind,
+                },
+            ],
+            Committed::LogDerivative {
+                multiplicities_poly,
+                multiplicities_blind,
+                sum_poly,
+                sum_blind,
+            } => vec![
+                // Open lookup running sum commitments at x
+                ProverQuery {
+                    point: *x,
+                    poly: sum_poly,
+                    blind: *sum_blind,
+                },
+                // Open lookup multiplicities commitments at x
+                ProverQuery {
+                    point: *x,
+                    poly: multiplicities_poly,
+                    blind: *multiplicities_blind,
+                },
+                // Open lookup running sum commitments at x_next
+                ProverQuery {
+                    point: x_next,
+                    poly: sum_poly,
+                    blind: *sum_blind,
+                },
+            ],
+        }
+        .into_iter()
     }
 }
 
+/// Given a vector of input values A and a vector of table values S, this
+/// method returns the number of times each row of S occurs in A on the usable
+/// rows, where the occurrences of a value repeated in S all count towards its
+/// first row.
+fn compute_multiplicities<'params, C: CurveAffine, P: Params<'params, C>, R: RngCore>(
+    pk: &ProvingKey<C>,
+    params: &P,
+    domain: &EvaluationDomain<C::Scalar>,
+    mut rng: R,
+    input_expressions: &[Polynomial<C::Scalar, LagrangeCoeff>],
+    table_expression: &Polynomial<C::Scalar, LagrangeCoeff>,
+) -> Result<Polynomial<C::Scalar, LagrangeCoeff>, Error> {
+    let blinding_factors = pk.vk.cs.blinding_factors();
+    let usable_rows = params.n() as usize - (blinding_factors + 1);
+
+    // A BTreeMap of each unique element in the table expression and its first row
+    let table_rows: BTreeMap<C::Scalar, usize> = table_expression
+        .iter()
+        .take(usable_rows)
+        .enumerate()
+        .fold(BTreeMap::new(), |mut acc, (row, coeff)| {
+            acc.entry(*coeff).or_insert(row);
+            acc
+        });
+
+    let mut multiplicities = vec![C::Scalar::zero(); usable_rows];
+    for input_value in input_expressions
+        .iter()
+        .flat_map(|input_expression| input_expression.iter().take(usable_rows))
+    {
+        // Return error if input_value not found
+        let row = table_rows
+            .get(input_value)
+            .ok_or(Error::ConstraintSystemFailure)?;
+        multiplicities[*row] += C::Scalar::one();
+    }
+
+    multiplicities.extend((0..(blinding_factors + 1)).map(|_| C::Scalar::random(&mut rng)));
+    assert_eq!(multiplicities.len(), params.n() as usize);
+
+    Ok(domain.lagrange_from_vec(multiplicities))
+}
+
 type ExpressionPair<F> = (Polynomial<F, LagrangeCoeff>, Polynomial<F, LagrangeCoeff>);
 
 /// Given a vector of input values A and a vector of table values S,
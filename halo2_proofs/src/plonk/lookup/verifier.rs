@@ -1,24 +1,30 @@
 use std::iter;
 
 use super::super::{
-    circuit::Expression, ChallengeBeta, ChallengeGamma, ChallengeTheta, ChallengeX,
+    circuit::Expression, ChallengeBeta, ChallengeGamma, ChallengeTheta, ChallengeX, LookupArgument,
 };
 use super::Argument;
 use crate::{
     arithmetic::{CurveAffine, FieldExt},
     plonk::{Error, VerifyingKey},
     poly::{commitment::MSM, Rotation, VerifierQuery},
-    transcript::{EncodedChallenge, TranscriptRead},
+    transcript::{read_n_scalars, EncodedChallenge, TranscriptRead},
 };
 use ff::Field;
 
-pub struct PermutationCommitments<C: CurveAffine> {
-    permuted_input_commitment: C,
-    permuted_table_commitment: C,
+pub enum PermutationCommitments<C: CurveAffine> {
+    Permutation {
+        permuted_input_commitment: C,
+        permuted_table_commitment: C,
+    },
+    LogDerivative {
+        multiplicities_commitment: C,
+    },
 }
 
 pub struct Committed<C: CurveAffine> {
     permuted: PermutationCommitments<C>,
+    // The grand product, or the running sum of the log-derivative argument
     product_commitment: C,
 }
 
@@ -26,9 +32,8 @@ pub struct Evaluated<C: CurveAffine> {
     committed: Committed<C>,
     product_eval: C::Scalar,
     product_next_eval: C::Scalar,
-    permuted_input_eval: C::Scalar,
-    permuted_input_inv_eval: C::Scalar,
-    permuted_table_eval: C::Scalar,
+    // The evaluations of the permuted columns, or of the multiplicities
+    permuted_evals: Vec<C::Scalar>,
 }
 
 impl<F: FieldExt> Argument<F> {
@@ -38,12 +43,21 @@ impl<F: FieldExt> Argument<F> {
         T: TranscriptRead<C, E>,
     >(
         &self,
+        vk: &VerifyingKey<C>,
         transcript: &mut T,
     ) -> Result<PermutationCommitments<C>, Error> {
+        if vk.cs.lookup_argument == LookupArgument::LogDerivative {
+            let multiplicities_commitment = transcript.read_point()?;
+
+            return Ok(PermutationCommitments::LogDerivative {
+                multiplicities_commitment,
+            });
+        }
+
         let permuted_input_commitment = transcript.read_point()?;
         let permuted_table_commitment = transcript.read_point()?;
 
-        Ok(PermutationCommitments {
+        Ok(PermutationCommitments::Permutation {
             permuted_input_commitment,
             permuted_table_commitment,
         })
@@ -74,17 +88,16 @@ impl<C: CurveAffine> Committed<C> {
     ) -> Result<Evaluated<C>, Error> {
         let product_eval = transcript.read_scalar()?;
         let product_next_eval = transcript.read_scalar()?;
-        let permuted_input_eval = transcript.read_scalar()?;
-        let permuted_input_inv_eval = transcript.read_scalar()?;
-        let permuted_table_eval = transcript.read_scalar()?;
+        let permuted_evals = match self.permuted {
+            PermutationCommitments::Permutation { .. } => read_n_scalars(transcript, 3)?,
+            PermutationCommitments::LogDerivative { .. } => read_n_scalars(transcript, 1)?,
+        };
 
         Ok(Evaluated {
             committed: self,
             product_eval,
             product_next_eval,
-            permuted_input_eval,
-            permuted_input_inv_eval,
-            permuted_table_eval,
+            permuted_evals,
         })
     }
 }
@@ -95,7 +108,7 @@ impl<C: CurveAffine> Evaluated<C> {
         l_0: C::Scalar,
         l_last: C::Scalar,
         l_blind: C::Scalar,
-        argument: &'a Argument<C::Scalar>,
+        batch: &[&'a Argument<C::Scalar>],
         theta: ChallengeTheta<C>,
         beta: ChallengeBeta<C>,
         gamma: ChallengeGamma<C>,
@@ -106,32 +119,86 @@ impl<C: CurveAffine> Evaluated<C> {
     ) -> impl Iterator<Item = C::Scalar> + 'a {
         let active_rows = C::Scalar::one() - (l_last + l_blind);
 
+        let compress_expressions = move |expressions: &[Expression<C::Scalar>]| {
+            expressions
+                .iter()
+                .map(|expression| {
+                    expression.evaluate(
+                        &|scalar| scalar,
+                        &|_| panic!("virtual selectors are removed during optimization"),
+                        &|query| fixed_evals[query.index],
+                        &|query| advice_evals[query.index],
+                        &|query| instance_evals[query.index],
+                        &|challenge| challenges[challenge.index()],
+                        &|a| -a,
+                        &|a, b| a + &b,
+                        &|a, b| a * &b,
+                        &|a, scalar| a * &scalar,
+                    )
+                })
+                .fold(C::Scalar::zero(), |acc, eval| acc * &*theta + &eval)
+        };
+
+        if let PermutationCommitments::LogDerivative { .. } = self.committed.permuted {
+            let multiplicities_eval = self.permuted_evals[0];
+            let inputs_beta: Vec<_> = batch
+                .iter()
+                .map(|argument| compress_expressions(&argument.input_expressions) + &*beta)
+                .collect();
+            let table_term = compress_expressions(&batch[0].table_expressions) + &*beta;
+            let input_term = inputs_beta
+                .iter()
+                .fold(C::Scalar::one(), |acc, term| acc * term);
+            let others_term = (0..inputs_beta.len())
+                .map(|j| {
+                    inputs_beta
+                        .iter()
+                        .enumerate()
+                        .filter(|(k, _)| *k != j)
+                        .fold(C::Scalar::one(), |acc, (_, term)| acc * term)
+                })
+                .fold(C::Scalar::zero(), |acc, term| acc + &term)
+                * &table_term;
+
+            return std::iter::empty()
+                .chain(
+                    // l_0(X) * phi(X) = 0
+                    Some(l_0 * &self.product_eval),
+                )
+                .chain(
+                    // l_last(X) * phi(X) = 0
+                    Some(l_last * &self.product_eval),
+                )
+                .chain(
+                    // (1 - (l_last(X) + l_blind(X))) * (
+                    //   (phi(\omega X) - phi(X)) \prod_j (a_j(X) + \beta) (s(X) + \beta)
+                    //   - \sum_j \prod_{k != j} (a_k(X) + \beta) (s(X) + \beta)
+                    //   + m(X) \prod_j (a_j(X) + \beta)
+                    // ) = 0
+                    Some(
+                        ((self.product_next_eval - &self.product_eval) * &input_term * &table_term
+                            - &others_term
+                            + &(multiplicities_eval * &input_term))
+                            * &active_rows,
+                    ),
+                )
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+        let argument = batch[0];
+        let (permuted_input_eval, permuted_input_inv_eval, permuted_table_eval) = (
+            self.permuted_evals[0],
+            self.permuted_evals[1],
+            self.permuted_evals[2],
+        );
+
         let product_expression = || {
             // z(\omega X) (a'(X) + \beta) (s'(X) + \gamma)
             // - z(X) (\theta^{m-1} a_0(X) + ... + a_{m-1}(X) + \beta) (\theta^{m-1} s_0(X) + ... + s_{m-1}(X) + \gamma)
             let left = self.product_next_eval
-                * &(self.permuted_input_eval + &*beta)
-                * &(self.permuted_table_eval + &*gamma);
-
-            let compress_expressions = |expressions: &[Expression<C::Scalar>]| {
-                expressions
-                    .iter()
-                    .map(|expression| {
-                        expression.evaluate(
-                            &|scalar| scalar,
-                            &|_| panic!("virtual selectors are removed during optimization"),
-                            &|query| fixed_evals[query.index],
-                            &|query| advice_evals[query.index],
-                            &|query| instance_evals[query.index],
-                            &|challenge| challenges[challenge.index()],
-                            &|a| -a,
-                            &|a, b| a + &b,
-                            &|a, b| a * &b,
-                            &|a, scalar| a * &scalar,
-                        )
-                    })
-                    .fold(C::Scalar::zero(), |acc, eval| acc * &*theta + &eval)
-            };
+                * &(permuted_input_eval + &*beta)
+                * &(permuted_table_eval + &*gamma);
+
             let right = self.product_eval
                 * &(compress_expressions(&argument.input_expressions) + &*beta)
                 * &(compress_expressions(&argument.table_expressions) + &*gamma);
@@ -157,14 +224,16 @@ impl<C: CurveAffine> Evaluated<C> {
             )
             .chain(Some(
                 // l_0(X) * (a'(X) - s'(X)) = 0
-                l_0 * &(self.permuted_input_eval - &self.permuted_table_eval),
+                l_0 * &(permuted_input_eval - &permuted_table_eval),
             ))
             .chain(Some(
                 // (1 - (l_last(X) + l_blind(X))) * (a′(X) − s′(X))⋅(a′(X) − a′(\omega^{-1} X)) = 0
-                (self.permuted_input_eval - &self.permuted_table_eval)
-                    * &(self.permuted_input_eval - &self.permuted_input_inv_eval)
+                (permuted_input_eval - &permuted_table_eval)
+                    * &(permuted_input_eval - &permuted_input_inv_eval)
                     * &active_rows,
             ))
+            .collect::<Vec<_>>()
+            .into_iter()
     }
 
     pub(in crate::plonk) fn queries<'r, M: MSM<C> + 'r>(
@@ -175,6 +244,42 @@ impl<C: CurveAffine> Evaluated<C> {
         let x_inv = vk.domain.rotate_omega(*x, Rotation::prev());
         let x_next = vk.domain.rotate_omega(*x, Rotation::next());
 
+        let permuted = match &self.committed.permuted {
+            PermutationCommitments::Permutation {
+                permuted_input_commitment,
+                permuted_table_commitment,
+            } => vec![
+                // Open lookup input commitments at x
+                VerifierQuery::new_commitment(
+                    permuted_input_commitment,
+                    *x,
+                    self.permuted_evals[0],
+                ),
+                // Open lookup table commitments at x
+                VerifierQuery::new_commitment(
+                    permuted_table_commitment,
+                    *x,
+                    self.permuted_evals[2],
+                ),
+                // Open lookup input commitments at \omega^{-1} x
+                VerifierQuery::new_commitment(
+                    permuted_input_commitment,
+                    x_inv,
+                    self.permuted_evals[1],
+                ),
+            ],
+            PermutationCommitments::LogDerivative {
+                multiplicities_commitment,
+            } => vec![
+                // Open lookup multiplicities commitment at x
+                VerifierQuery::new_commitment(
+                    multiplicities_commitment,
+                    *x,
+                    self.permuted_evals[0],
+                ),
+            ],
+        };
+
         iter::empty()
             // Open lookup product commitment at x
             .chain(Some(VerifierQuery::new_commitment(
@@ -182,24 +287,7 @@ impl<C: CurveAffine> Evaluated<C> {
                 *x,
                 self.product_eval,
             )))
-            // Open lookup input commitments at x
-            .chain(Some(VerifierQuery::new_commitment(
-                &self.committed.permuted.permuted_input_commitment,
-                *x,
-                self.permuted_input_eval,
-            )))
-            // Open lookup table commitments at x
-            .chain(Some(VerifierQuery::new_commitment(
-                &self.committed.permuted.permuted_table_commitment,
-                *x,
-                self.permuted_table_eval,
-            )))
-            // Open lookup input commitments at \omega^{-1} x
-            .chain(Some(VerifierQuery::new_commitment(
-                &self.committed.permuted.permuted_input_commitment,
-                x_inv,
-                self.permuted_input_inv_eval,
-            )))
+            .chain(permuted)
             // Open lookup product commitment at \omega x
             .chain(Some(VerifierQuery::new_commitment(
                 &self.committed.product_commitment,
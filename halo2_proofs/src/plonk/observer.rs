@@ -0,0 +1,333 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "timings")]
+use std::time::{Duration, Instant};
+
+use super::Error;
+use crate::arithmetic::{OpCountScope, OpCounts};
+use crate::transcript::TranscriptLabeler;
+
+/// A stage of proof creation, in the order the prover goes through them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProverStage {
+    /// Committing to the instance columns, or absorbing their values
+    Instance,
+    /// Synthesizing the circuits and committing to the advice columns of each
+    /// phase
+    Advice,
+    /// Committing to the permuted columns, or the multiplicities, of the
+    /// lookups
+    LookupPermuted,
+    /// Committing to the products of the permutation, the lookups and the
+    /// shuffles, or the running sums of the lookups
+    Products,
+    /// Computing and committing to the quotient polynomial
+    Vanishing,
+    /// Evaluating the polynomials at the challenge `x`
+    Evaluations,
+    /// Creating the multiopen argument
+    Opening,
+}
+
+/// Work done during a [`ProverStage`]
+///
+/// The counts are those of the proof alone, also when other proofs are
+/// created at the same time, e.g. by a [`ParallelProver`](super::ParallelProver).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StageStats {
+    /// Number of FFTs
+    pub ffts: usize,
+    /// Number of multi-exponentiations
+    pub msms: usize,
+    /// Total size of the scalars of the multi-exponentiations, i.e. of the
+    /// values committed to
+    pub committed_bytes: usize,
+    /// Wall-clock time of the stage
+    #[cfg(feature = "timings")]
+    pub elapsed: Duration,
+    /// Time spent in FFTs
+    #[cfg(feature = "timings")]
+    pub fft_time: Duration,
+    /// Time spent in multi-exponentiations
+    #[cfg(feature = "timings")]
+    pub msm_time: Duration,
+}
+
+/// Receives events while a proof is created with
+/// [`ProverOptions::with_observer`](super::ProverOptions::with_observer), e.g.
+/// to report progress or to time each stage. All methods do nothing by
+/// default.
+pub trait ProverObserver {
+    /// Called when the prover starts `stage`
+    fn stage_started(&mut self, _stage: ProverStage) {}
+
+    /// Called when the prover finishes `stage`, with the work done during it
+    fn stage_finished(&mut self, _stage: ProverStage, _stats: &StageStats) {}
+}
+
+impl ProverObserver for () {}
+
+/// A [`ProverObserver`] collecting the timing of each stage, for finding
+/// where the time of a proof goes. The stages map to the parts of the prover
+/// as follows:
+///
+/// - [`ProverStage::LookupPermuted`] and [`ProverStage::Products`] construct
+///   the lookup, permutation and shuffle arguments;
+/// - [`ProverStage::Vanishing`] evaluates and commits to the quotient
+///   polynomial;
+/// - [`ProverStage::Opening`] creates the multiopen argument.
+///
+/// Its [`Display`](fmt::Display) implementation prints the breakdown as a
+/// table.
+#[cfg(feature = "timings")]
+#[derive(Clone, Debug, Default)]
+pub struct ProverTimings {
+    /// The stages the prover went through, in order, with the work done
+    /// during each
+    pub stages: Vec<(ProverStage, StageStats)>,
+}
+
+#[cfg(feature = "timings")]
+impl ProverTimings {
+    /// Creates an empty breakdown
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the wall-clock time spent in `stage`
+    pub fn stage(&self, stage: ProverStage) -> Duration {
+        self.sum(|current, stats| {
+            if current == stage {
+                stats.elapsed
+            } else {
+                Duration::ZERO
+            }
+        })
+    }
+
+    /// Returns the wall-clock time of all stages
+    pub fn total(&self) -> Duration {
+        self.sum(|_, stats| stats.elapsed)
+    }
+
+    /// Returns the time spent in FFTs
+    pub fn fft_time(&self) -> Duration {
+        self.sum(|_, stats| stats.fft_time)
+    }
+
+    /// Returns the time spent in multi-exponentiations, i.e. in commitments
+    /// and in the multiopen argument
+    pub fn msm_time(&self) -> Duration {
+        self.sum(|_, stats| stats.msm_time)
+    }
+
+    fn sum(&self, f: impl Fn(ProverStage, &StageStats) -> Duration) -> Duration {
+        self.stages
+            .iter()
+            .map(|(stage, stats)| f(*stage, stats))
+            .sum()
+    }
+}
+
+#[cfg(feature = "timings")]
+impl ProverObserver for ProverTimings {
+    fn stage_finished(&mut self, stage: ProverStage, stats: &StageStats) {
+        self.stages.push((stage, *stats));
+    }
+}
+
+#[cfg(feature = "timings")]
+impl fmt::Display for ProverTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<16} {:>12} {:>12} {:>12}",
+            "stage", "elapsed", "fft", "msm"
+        )?;
+        for (stage, stats) in &self.stages {
+            writeln!(
+                f,
+                "{:<16} {:>12?} {:>12?} {:>12?}",
+                format!("{:?}", stage),
+                stats.elapsed,
+                stats.fft_time,
+                stats.msm_time
+            )?;
+        }
+        writeln!(
+            f,
+            "{:<16} {:>12?} {:>12?} {:>12?}",
+            "total",
+            self.total(),
+            self.fft_time(),
+            self.msm_time()
+        )
+    }
+}
+
+/// A [`ProverObserver`] labelling the operations of a
+/// [`TranscriptRecorder`](crate::transcript::TranscriptRecorder) with the
+/// [`ProverStage`] they happen in, e.g. `Advice`, from the time the stage
+/// starts.
+#[derive(Clone, Debug)]
+pub struct StageLabels(TranscriptLabeler);
+
+impl StageLabels {
+    /// Labels the operations through `labeler`, from
+    /// [`TranscriptRecorder::labeler`](crate::transcript::TranscriptRecorder::labeler)
+    pub fn new(labeler: TranscriptLabeler) -> Self {
+        StageLabels(labeler)
+    }
+}
+
+impl ProverObserver for StageLabels {
+    fn stage_started(&mut self, stage: ProverStage) {
+        self.0.set_label(format!("{:?}", stage));
+    }
+}
+
+/// Cancels proof creation with
+/// [`ProverOptions::with_cancellation`](super::ProverOptions::with_cancellation)
+/// from another thread, e.g. when a deadline passes. Clones of a token share
+/// its state.
+///
+/// The prover checks the token between stages, and between the circuits
+/// and phases it synthesizes, and then returns [`Error::Cancelled`]; it does
+/// not interrupt a stage that is running.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the proof creation using this token
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if [`Self::cancel`] was called
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Reports the stages the prover enters to an observer, together with the
+/// work done during each.
+pub(crate) struct StageTracker<'a> {
+    observer: &'a mut dyn ProverObserver,
+    scalar_bytes: usize,
+    cancellation: Option<&'a CancellationToken>,
+    ops: OpCountScope,
+    current: Option<(ProverStage, OpCounts)>,
+    /// When the current stage started and the span of the stage
+    #[cfg(feature = "timings")]
+    timing: Option<(Instant, tracing::span::EnteredSpan)>,
+}
+
+impl<'a> fmt::Debug for StageTracker<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StageTracker")
+            .field("cancellation", &self.cancellation)
+            .field("current", &self.current.map(|(stage, _)| stage))
+            .finish()
+    }
+}
+
+impl<'a> StageTracker<'a> {
+    /// Reports to `observer`, where scalars take `scalar_bytes` bytes, and
+    /// checks `cancellation`, if any
+    pub(crate) fn new(
+        observer: &'a mut dyn ProverObserver,
+        scalar_bytes: usize,
+        cancellation: Option<&'a CancellationToken>,
+    ) -> Self {
+        StageTracker {
+            observer,
+            scalar_bytes,
+            cancellation,
+            ops: OpCountScope::new(),
+            current: None,
+            #[cfg(feature = "timings")]
+            timing: None,
+        }
+    }
+
+    /// Returns [`Error::Cancelled`] if proof creation was cancelled
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        match self.cancellation {
+            Some(token) if token.is_cancelled() => Err(Error::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Finishes the current stage, if any, and starts `stage` unless proof
+    /// creation was cancelled
+    pub(crate) fn start(&mut self, stage: ProverStage) -> Result<(), Error> {
+        self.finish();
+        self.check()?;
+        self.observer.stage_started(stage);
+        #[cfg(feature = "timings")]
+        {
+            let span = tracing::debug_span!("prover_stage", stage = ?stage).entered();
+            self.timing = Some((Instant::now(), span));
+        }
+        self.current = Some((stage, self.ops.counts()));
+        Ok(())
+    }
+
+    /// Finishes the current stage, if any
+    pub(crate) fn finish(&mut self) {
+        if let Some((stage, started)) = self.current.take() {
+            let now = self.ops.counts();
+            #[cfg(feature = "timings")]
+            let (start, _span) = self.timing.take().expect("started with the stage");
+            let stats = StageStats {
+                ffts: now.ffts - started.ffts,
+                msms: now.msms - started.msms,
+                committed_bytes: (now.msm_terms - started.msm_terms) * self.scalar_bytes,
+                #[cfg(feature = "timings")]
+                elapsed: start.elapsed(),
+                #[cfg(feature = "timings")]
+                fft_time: Duration::from_nanos(now.fft_nanos - started.fft_nanos),
+                #[cfg(feature = "timings")]
+                msm_time: Duration::from_nanos(now.msm_nanos - started.msm_nanos),
+            };
+            self.observer.stage_finished(stage, &stats);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProverObserver, ProverStage, StageLabels, StageStats};
+    use crate::transcript::{
+        Blake2bWrite, Challenge255, Transcript, TranscriptRecorder, TranscriptWrite,
+        TranscriptWriterBuffer,
+    };
+    use ff::Field;
+    use halo2curves::pasta::{EqAffine, Fp};
+
+    #[test]
+    fn test_stage_labels() {
+        let mut transcript =
+            TranscriptRecorder::new(Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]));
+        let mut labels = StageLabels::new(transcript.labeler());
+        transcript.write_scalar(Fp::one()).unwrap();
+        labels.stage_started(ProverStage::Advice);
+        transcript.write_scalar(Fp::one()).unwrap();
+        labels.stage_finished(ProverStage::Advice, &StageStats::default());
+        labels.stage_started(ProverStage::Vanishing);
+        transcript.squeeze_challenge_scalar::<()>();
+
+        let labels: Vec<_> = transcript
+            .events()
+            .iter()
+            .map(|event| event.label.as_deref())
+            .collect();
+        assert_eq!(labels, vec![None, Some("Advice"), Some("Vanishing")]);
+    }
+}
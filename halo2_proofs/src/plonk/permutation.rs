@@ -66,6 +66,15 @@ impl Argument {
         3
     }
 
+    /// Returns how many columns are included in a single permutation
+    /// polynomial of a circuit of degree `cs_degree`. We need to multiply by
+    /// z(X) and (1 - (l_last(X) + l_blind(X))), which leaves `cs_degree - 2`
+    /// for the columns. A circuit without permutation columns may have degree
+    /// less than 3, but then it has no sets to chunk the columns into.
+    pub(crate) fn chunk_len(cs_degree: usize) -> usize {
+        std::cmp::max(cs_degree, 3) - 2
+    }
+
     pub(crate) fn add_column(&mut self, column: Column<Any>) {
         if !self.columns.contains(&column) {
             self.columns.push(column);
@@ -7,7 +7,7 @@ use crate::{
     plonk::{Any, Column, Error},
     poly::{
         commitment::{Blind, CommitmentScheme, Params},
-        EvaluationDomain,
+        EvaluationDomain, LagrangeCoeff, Polynomial,
     },
 };
 
@@ -108,60 +108,8 @@ impl Assembly {
         domain: &EvaluationDomain<C::Scalar>,
         p: &Argument,
     ) -> VerifyingKey<C> {
-        // Compute [omega^0, omega^1, ..., omega^{params.n - 1}]
-        let mut omega_powers = vec![C::Scalar::zero(); params.n() as usize];
-        {
-            let omega = domain.get_omega();
-            parallelize(&mut omega_powers, |o, start| {
-                let mut cur = omega.pow_vartime(&[start as u64]);
-                for v in o.iter_mut() {
-                    *v = cur;
-                    cur *= &omega;
-                }
-            })
-        }
-
-        // Compute [omega_powers * \delta^0, omega_powers * \delta^1, ..., omega_powers * \delta^m]
-        let mut deltaomega = vec![omega_powers; p.columns.len()];
-        {
-            parallelize(&mut deltaomega, |o, start| {
-                let mut cur = C::Scalar::DELTA.pow_vartime(&[start as u64]);
-                for omega_powers in o.iter_mut() {
-                    for v in omega_powers {
-                        *v *= &cur;
-                    }
-                    cur *= &C::Scalar::DELTA;
-                }
-            });
-        }
-
-        // Computes the permutation polynomial based on the permutation
-        // description in the assembly.
-        let mut permutations = vec![domain.empty_lagrange(); p.columns.len()];
-        {
-            parallelize(&mut permutations, |o, start| {
-                for (x, permutation_poly) in o.iter_mut().enumerate() {
-                    let i = start + x;
-                    for (j, p) in permutation_poly.iter_mut().enumerate() {
-                        let (permuted_i, permuted_j) = self.mapping[i][j];
-                        *p = deltaomega[permuted_i][permuted_j];
-                    }
-                }
-            });
-        }
-
-        // Pre-compute commitments for the URS.
-        let mut commitments = Vec::with_capacity(p.columns.len());
-        for permutation in &permutations {
-            // Compute commitment to permutation polynomial
-            commitments.push(
-                params
-                    .commit_lagrange(permutation, Blind::default())
-                    .to_affine(),
-            );
-        }
-
-        VerifyingKey { commitments }
+        let permutations = self.build_permutations(params, domain, p);
+        commit_permutations(params, &permutations)
     }
 
     pub(crate) fn build_pk<'params, C: CurveAffine, P: Params<'params, C>>(
@@ -170,6 +118,31 @@ impl Assembly {
         domain: &EvaluationDomain<C::Scalar>,
         p: &Argument,
     ) -> ProvingKey<C> {
+        let permutations = self.build_permutations(params, domain, p);
+        pk_from_permutations(domain, permutations)
+    }
+
+    /// Builds both the verifying key and the proving key of the permutation
+    /// argument, computing the permutation polynomials once.
+    pub(crate) fn build_keys<'params, C: CurveAffine, P: Params<'params, C>>(
+        self,
+        params: &P,
+        domain: &EvaluationDomain<C::Scalar>,
+        p: &Argument,
+    ) -> (VerifyingKey<C>, ProvingKey<C>) {
+        let permutations = self.build_permutations(params, domain, p);
+        let vk = commit_permutations(params, &permutations);
+        (vk, pk_from_permutations(domain, permutations))
+    }
+
+    /// Computes the permutation polynomials based on the permutation
+    /// description in the assembly.
+    fn build_permutations<'params, C: CurveAffine, P: Params<'params, C>>(
+        &self,
+        params: &P,
+        domain: &EvaluationDomain<C::Scalar>,
+        p: &Argument,
+    ) -> Vec<Polynomial<C::Scalar, LagrangeCoeff>> {
         // Compute [omega^0, omega^1, ..., omega^{params.n - 1}]
         let mut omega_powers = vec![C::Scalar::zero(); params.n() as usize];
         {
@@ -197,7 +170,6 @@ impl Assembly {
             });
         }
 
-        // Compute permutation polynomials, convert to coset form.
         let mut permutations = vec![domain.empty_lagrange(); p.columns.len()];
         {
             parallelize(&mut permutations, |o, start| {
@@ -211,32 +183,58 @@ impl Assembly {
             });
         }
 
-        let mut polys = vec![domain.empty_coeff(); p.columns.len()];
-        {
-            parallelize(&mut polys, |o, start| {
-                for (x, poly) in o.iter_mut().enumerate() {
-                    let i = start + x;
-                    let permutation_poly = permutations[i].clone();
-                    *poly = domain.lagrange_to_coeff(permutation_poly);
-                }
-            });
-        }
+        permutations
+    }
+}
 
-        let mut cosets = vec![domain.empty_extended(); p.columns.len()];
-        {
-            parallelize(&mut cosets, |o, start| {
-                for (x, coset) in o.iter_mut().enumerate() {
-                    let i = start + x;
-                    let poly = polys[i].clone();
-                    *coset = domain.coeff_to_extended(poly);
-                }
-            });
-        }
+/// Pre-computes commitments for the URS.
+fn commit_permutations<'params, C: CurveAffine, P: Params<'params, C>>(
+    params: &P,
+    permutations: &[Polynomial<C::Scalar, LagrangeCoeff>],
+) -> VerifyingKey<C> {
+    let commitments = permutations
+        .iter()
+        .map(|permutation| {
+            // Compute commitment to permutation polynomial
+            params
+                .commit_lagrange(permutation, Blind::default())
+                .to_affine()
+        })
+        .collect();
 
-        ProvingKey {
-            permutations,
-            polys,
-            cosets,
-        }
+    VerifyingKey { commitments }
+}
+
+/// Converts the permutation polynomials to coefficient and coset form.
+fn pk_from_permutations<C: CurveAffine>(
+    domain: &EvaluationDomain<C::Scalar>,
+    permutations: Vec<Polynomial<C::Scalar, LagrangeCoeff>>,
+) -> ProvingKey<C> {
+    let mut polys = vec![domain.empty_coeff(); permutations.len()];
+    {
+        parallelize(&mut polys, |o, start| {
+            for (x, poly) in o.iter_mut().enumerate() {
+                let i = start + x;
+                let permutation_poly = permutations[i].clone();
+                *poly = domain.lagrange_to_coeff(permutation_poly);
+            }
+        });
+    }
+
+    let mut cosets = vec![domain.empty_extended(); permutations.len()];
+    {
+        parallelize(&mut cosets, |o, start| {
+            for (x, coset) in o.iter_mut().enumerate() {
+                let i = start + x;
+                let poly = polys[i].clone();
+                *coset = domain.coeff_to_extended(poly);
+            }
+        });
+    }
+
+    ProvingKey {
+        permutations,
+        polys,
+        cosets,
     }
 }
@@ -8,19 +8,18 @@ use std::iter::{self, ExactSizeIterator};
 use super::super::{circuit::Any, ChallengeBeta, ChallengeGamma, ChallengeX};
 use super::{Argument, ProvingKey};
 use crate::{
-    arithmetic::{eval_polynomial, parallelize, CurveAffine, FieldExt},
+    arithmetic::{batch_invert, eval_polynomial, parallelize, CurveAffine, FieldExt},
     plonk::{self, Error},
     poly::{
         self,
         commitment::{Blind, Params},
-        Coeff, ExtendedLagrangeCoeff, LagrangeCoeff, Polynomial, ProverQuery, Rotation,
+        Coeff, LagrangeCoeff, Polynomial, ProverQuery, Rotation,
     },
     transcript::{EncodedChallenge, TranscriptWrite},
 };
 
 pub(crate) struct CommittedSet<C: CurveAffine> {
     pub(crate) permutation_product_poly: Polynomial<C::Scalar, Coeff>,
-    pub(crate) permutation_product_coset: Polynomial<C::Scalar, ExtendedLagrangeCoeff>,
     permutation_product_blind: Blind<C::Scalar>,
 }
 
@@ -65,11 +64,9 @@ impl Argument {
         let domain = &pk.vk.domain;
 
         // How many columns can be included in a single permutation polynomial?
-        // We need to multiply by z(X) and (1 - (l_last(X) + l_blind(X))). This
-        // will never underflow because of the requirement of at least a degree
-        // 3 circuit for the permutation argument.
-        assert!(pk.vk.cs_degree >= 3);
-        let chunk_len = pk.vk.cs_degree - 2;
+        // Circuits with permutation columns are at least of degree 3.
+        assert!(self.columns.is_empty() || pk.vk.cs_degree >= 3);
+        let chunk_len = Self::chunk_len(pk.vk.cs_degree);
         let blinding_factors = pk.vk.cs.blinding_factors();
 
         // Each column gets its own delta power.
@@ -114,7 +111,7 @@ impl Argument {
             }
 
             // Invert to obtain the denominator for the permutation product polynomial
-            modified_values.batch_invert();
+            batch_invert(&mut modified_values);
 
             // Iterate over each column again, this time finishing the computation
             // of the entire fraction by computing the numerators
@@ -169,10 +166,7 @@ impl Argument {
 
             let permutation_product_commitment_projective = params.commit_lagrange(&z, blind);
             let permutation_product_blind = blind;
-            let z = domain.lagrange_to_coeff(z);
-            let permutation_product_poly = z.clone();
-
-            let permutation_product_coset = domain.coeff_to_extended(z.clone());
+            let permutation_product_poly = domain.lagrange_to_coeff(z);
 
             let permutation_product_commitment =
                 permutation_product_commitment_projective.to_affine();
@@ -182,7 +176,6 @@ impl Argument {
 
             sets.push(CommittedSet {
                 permutation_product_poly,
-                permutation_product_coset,
                 permutation_product_blind,
             });
         }
@@ -0,0 +1,439 @@
+use ff::PrimeField;
+use rand_core::RngCore;
+use std::io;
+
+use super::{
+    create_proof_with_options, permutation, Circuit, Error, LookupArgument, ProverOptions,
+    ProvingKey, VerifyingKey,
+};
+use crate::arithmetic::CurveAffine;
+use crate::poly::commitment::{CommitmentScheme, Prover};
+use crate::transcript::{
+    read_point_encoded, write_point_encoded, EncodedChallenge, PointEncoding,
+    TranscriptWriterBuffer,
+};
+
+/// A proof split into its sections, in the order they appear in the
+/// transcript. Proofs for several circuits at once interleave the sections of
+/// each circuit, so most sections hold one entry per circuit.
+///
+/// The layout of a proof depends on the verifying key and on the options it
+/// was created with (see [`ProofLayout`]), but the multiopen argument at its
+/// end is specific to the scheme and is kept as bytes. The lookups of a
+/// circuit using [`LookupArgument::LogDerivative`] commit to multiplicities
+/// and running sums in place of permuted columns and products, once for each
+/// batch of lookups (see [`ConstraintSystem::set_lookup_batching`](super::ConstraintSystem::set_lookup_batching)).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof<C: CurveAffine> {
+    /// The scalar standing for the domain-separation tag the proof starts
+    /// with, if any (see [`TranscriptWrite::write_domain_tag`](crate::transcript::TranscriptWrite::write_domain_tag))
+    pub domain_tag: Option<C::Scalar>,
+    /// The fingerprint of the circuit (see [`VerifyingKey::fingerprint`])
+    pub fingerprint: C::Scalar,
+    /// The multiopen scheme the proof records, if any (see
+    /// [`Prover::scheme_id`])
+    pub scheme_id: Option<C::Scalar>,
+    /// The advice commitments of each phase, for each circuit
+    pub advice_commitments: Vec<Vec<Vec<C>>>,
+    /// The permuted input and table commitments of each lookup, for each
+    /// circuit; empty with [`LookupArgument::LogDerivative`]
+    pub lookup_permuted_commitments: Vec<Vec<(C, C)>>,
+    /// The multiplicities commitment of each lookup, for each circuit; empty
+    /// with [`LookupArgument::Permutation`]
+    pub lookup_multiplicities_commitments: Vec<Vec<C>>,
+    /// The permutation product commitments, for each circuit
+    pub permutation_product_commitments: Vec<Vec<C>>,
+    /// The product, or running sum, commitment of each lookup, for each
+    /// circuit
+    pub lookup_product_commitments: Vec<Vec<C>>,
+    /// The product commitment of each shuffle, for each circuit
+    pub shuffle_product_commitments: Vec<Vec<C>>,
+    /// The commitment to the random polynomial of the vanishing argument
+    pub vanishing_random_commitment: C,
+    /// The commitments to the pieces of the quotient polynomial
+    pub quotient_commitments: Vec<C>,
+    /// The evaluations of the instance queries, for each circuit; empty when
+    /// the proof does not open the instance columns (see
+    /// [`ProofLayout::query_instance`])
+    pub instance_evals: Vec<Vec<C::Scalar>>,
+    /// The evaluations of the advice queries, for each circuit
+    pub advice_evals: Vec<Vec<C::Scalar>>,
+    /// The evaluations of the fixed queries
+    pub fixed_evals: Vec<C::Scalar>,
+    /// The evaluation of the random polynomial of the vanishing argument
+    pub vanishing_random_eval: C::Scalar,
+    /// The evaluations of the permutation polynomials
+    pub permutation_common_evals: Vec<C::Scalar>,
+    /// The evaluations of the permutation products, for each circuit: the
+    /// evaluation at `x`, at `omega x` and, for all but the last product, at
+    /// the last usable row
+    pub permutation_product_evals: Vec<Vec<C::Scalar>>,
+    /// The evaluations of each lookup, for each circuit: the product at `x`
+    /// and `omega x`, the permuted input at `x` and `omega^{-1} x`, and the
+    /// permuted table at `x`; or, with [`LookupArgument::LogDerivative`], the
+    /// running sum at `x` and `omega x` and the multiplicities at `x`
+    pub lookup_evals: Vec<Vec<Vec<C::Scalar>>>,
+    /// The evaluations of each shuffle, for each circuit: the product at `x`
+    /// and `omega x`
+    pub shuffle_evals: Vec<Vec<Vec<C::Scalar>>>,
+    /// The multiopen argument
+    pub opening: Vec<u8>,
+}
+
+/// The options a proof was created with that change its layout, for
+/// splitting it with [`Proof::from_bytes`]. Hashing the instance values with
+/// [`ProverOptions::with_instance_hash`] does not change the layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofLayout {
+    /// The number of circuits proven at once
+    pub num_proofs: usize,
+    /// Whether the proof opens the instance columns, because the multiopen
+    /// argument queries them (see [`Prover::QUERY_INSTANCE`]) or because the
+    /// proof was created with [`ProverOptions::with_committed_instances`]
+    pub query_instance: bool,
+    /// Whether the proof starts with a domain-separation tag, as written by
+    /// [`create_proof_with_domain_tag`](super::create_proof_with_domain_tag)
+    pub domain_tag: bool,
+    /// Whether the prover recorded its multiopen scheme (see
+    /// [`Prover::scheme_id`])
+    pub scheme_id: bool,
+    /// The encoding of the points of the proof
+    pub encoding: PointEncoding,
+}
+
+impl ProofLayout {
+    /// Returns the layout of a proof for `num_proofs` circuits created by
+    /// [`create_proof`](super::create_proof) with a prover neither querying the
+    /// instance columns nor recording its scheme, and a transcript encoding
+    /// points in their default, compressed, form
+    pub fn new(num_proofs: usize) -> Self {
+        ProofLayout {
+            num_proofs,
+            query_instance: false,
+            domain_tag: false,
+            scheme_id: false,
+            encoding: PointEncoding::default(),
+        }
+    }
+
+    /// Returns the layout of the proofs for `num_proofs` circuits that
+    /// `prover` creates with `options`
+    pub fn of<'params, Scheme: CommitmentScheme, P: Prover<'params, Scheme>>(
+        prover: &P,
+        num_proofs: usize,
+        options: &ProverOptions<'_, Scheme::Scalar>,
+    ) -> Self {
+        ProofLayout {
+            query_instance: P::QUERY_INSTANCE || options.commits_instances(),
+            scheme_id: prover.scheme_id().is_some(),
+            ..Self::new(num_proofs)
+        }
+    }
+}
+
+impl<C: CurveAffine> Proof<C> {
+    /// Splits the proof `bytes` with the verifying key `vk`, for a proof
+    /// created with the options given by `layout`. Fails if the proof was
+    /// created for a circuit with another fingerprint than `vk`, as its
+    /// sections would then be split at the wrong places.
+    pub fn from_bytes(
+        vk: &VerifyingKey<C>,
+        layout: &ProofLayout,
+        mut bytes: &[u8],
+    ) -> io::Result<Self> {
+        let ProofLayout {
+            num_proofs,
+            query_instance,
+            domain_tag,
+            scheme_id,
+            encoding,
+        } = *layout;
+        let reader = &mut bytes;
+        let cs = &vk.cs;
+        let points = |reader: &mut &[u8], n: usize| {
+            (0..n)
+                .map(|_| read_point_encoded(reader, encoding))
+                .collect::<io::Result<Vec<C>>>()
+        };
+        let per_proof = |reader: &mut &[u8], n: usize| {
+            (0..num_proofs)
+                .map(|_| read_scalars::<C>(reader, n))
+                .collect::<io::Result<Vec<_>>>()
+        };
+        let num_products = permutation_products(vk);
+        let log_derivative = cs.lookup_argument == LookupArgument::LogDerivative;
+        let num_lookups = cs.lookup_batches().len();
+
+        let domain_tag = if domain_tag {
+            Some(read_scalars::<C>(reader, 1)?[0])
+        } else {
+            None
+        };
+        let fingerprint = read_scalars::<C>(reader, 1)?[0];
+        if fingerprint != vk.fingerprint {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "proof was created for a different circuit",
+            ));
+        }
+        let scheme_id = if scheme_id {
+            Some(read_scalars::<C>(reader, 1)?[0])
+        } else {
+            None
+        };
+
+        // The lookups commit either to permuted columns or to multiplicities
+        let (num_permuted, num_multiplicities) = if log_derivative {
+            (0, num_lookups)
+        } else {
+            (num_lookups, 0)
+        };
+
+        let advice_commitments = cs
+            .phases()
+            .map(|current_phase| {
+                let columns = cs
+                    .advice_column_phase
+                    .iter()
+                    .filter(|phase| **phase == current_phase)
+                    .count();
+                (0..num_proofs)
+                    .map(|_| points(reader, columns))
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let lookup_permuted_commitments = (0..num_proofs)
+            .map(|_| {
+                (0..num_permuted)
+                    .map(|_| {
+                        let input = read_point_encoded(reader, encoding)?;
+                        let table = read_point_encoded(reader, encoding)?;
+                        Ok((input, table))
+                    })
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let lookup_multiplicities_commitments = (0..num_proofs)
+            .map(|_| points(reader, num_multiplicities))
+            .collect::<io::Result<Vec<_>>>()?;
+        let permutation_product_commitments = (0..num_proofs)
+            .map(|_| points(reader, num_products))
+            .collect::<io::Result<Vec<_>>>()?;
+        let lookup_product_commitments = (0..num_proofs)
+            .map(|_| points(reader, num_lookups))
+            .collect::<io::Result<Vec<_>>>()?;
+        let shuffle_product_commitments = (0..num_proofs)
+            .map(|_| points(reader, cs.shuffles.len()))
+            .collect::<io::Result<Vec<_>>>()?;
+        let vanishing_random_commitment = read_point_encoded(reader, encoding)?;
+        let quotient_commitments = points(reader, vk.domain.get_quotient_poly_degree())?;
+
+        let instance_evals = if query_instance {
+            per_proof(reader, cs.instance_queries.len())?
+        } else {
+            vec![vec![]; num_proofs]
+        };
+        let advice_evals = per_proof(reader, cs.advice_queries.len())?;
+        let fixed_evals = read_scalars::<C>(reader, cs.fixed_queries.len())?;
+        let vanishing_random_eval = read_scalars::<C>(reader, 1)?[0];
+        let permutation_common_evals =
+            read_scalars::<C>(reader, vk.permutation.commitments().len())?;
+        // Each product but the last is also evaluated at the last usable row
+        let permutation_product_evals = per_proof(reader, (3 * num_products).saturating_sub(1))?;
+        let lookup_evals = (0..num_proofs)
+            .map(|_| {
+                (0..num_lookups)
+                    .map(|_| read_scalars::<C>(reader, if log_derivative { 3 } else { 5 }))
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let shuffle_evals = (0..num_proofs)
+            .map(|_| {
+                (0..cs.shuffles.len())
+                    .map(|_| read_scalars::<C>(reader, 2))
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(Proof {
+            domain_tag,
+            fingerprint,
+            scheme_id,
+            advice_commitments,
+            lookup_permuted_commitments,
+            lookup_multiplicities_commitments,
+            permutation_product_commitments,
+            lookup_product_commitments,
+            shuffle_product_commitments,
+            vanishing_random_commitment,
+            quotient_commitments,
+            instance_evals,
+            advice_evals,
+            fixed_evals,
+            vanishing_random_eval,
+            permutation_common_evals,
+            permutation_product_evals,
+            lookup_evals,
+            shuffle_evals,
+            opening: bytes.to_vec(),
+        })
+    }
+
+    /// Returns the bytes of the proof, with points encoded as `encoding`.
+    /// This is the inverse of [`Proof::from_bytes`].
+    pub fn to_bytes(&self, encoding: PointEncoding) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![];
+        let writer = &mut bytes;
+        let points = |writer: &mut Vec<u8>, commitments: &[C]| -> io::Result<()> {
+            for point in commitments {
+                write_point_encoded(writer, *point, encoding)?;
+            }
+            Ok(())
+        };
+        let scalars = |writer: &mut Vec<u8>, scalars: &[C::Scalar]| {
+            for scalar in scalars {
+                writer.extend_from_slice(scalar.to_repr().as_ref());
+            }
+        };
+
+        if let Some(tag) = self.domain_tag {
+            scalars(writer, &[tag]);
+        }
+        scalars(writer, &[self.fingerprint]);
+        if let Some(id) = self.scheme_id {
+            scalars(writer, &[id]);
+        }
+        for phase in &self.advice_commitments {
+            for commitments in phase {
+                points(writer, commitments)?;
+            }
+        }
+        for lookups in &self.lookup_permuted_commitments {
+            for (input, table) in lookups {
+                points(writer, &[*input, *table])?;
+            }
+        }
+        for commitments in &self.lookup_multiplicities_commitments {
+            points(writer, commitments)?;
+        }
+        for commitments in self
+            .permutation_product_commitments
+            .iter()
+            .chain(self.lookup_product_commitments.iter())
+            .chain(self.shuffle_product_commitments.iter())
+        {
+            points(writer, commitments)?;
+        }
+        points(writer, &[self.vanishing_random_commitment])?;
+        points(writer, &self.quotient_commitments)?;
+
+        for evals in self.instance_evals.iter().chain(self.advice_evals.iter()) {
+            scalars(writer, evals);
+        }
+        scalars(writer, &self.fixed_evals);
+        scalars(writer, &[self.vanishing_random_eval]);
+        scalars(writer, &self.permutation_common_evals);
+        for evals in &self.permutation_product_evals {
+            scalars(writer, evals);
+        }
+        for arguments in self.lookup_evals.iter().chain(self.shuffle_evals.iter()) {
+            for evals in arguments {
+                scalars(writer, evals);
+            }
+        }
+        writer.extend_from_slice(&self.opening);
+
+        Ok(bytes)
+    }
+}
+
+/// Same as [`create_proof`](super::create_proof), but returns the proof split
+/// into its sections.
+/// The transcript `T` is created with [`TranscriptWriterBuffer::init`], and
+/// therefore encodes points in its default, compressed, form.
+pub fn create_typed_proof<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWriterBuffer<Vec<u8>, Scheme::Curve, E>,
+    ConcreteCircuit: Circuit<Scheme::Scalar>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+) -> Result<Proof<Scheme::Curve>, Error> {
+    create_typed_proof_with_options::<Scheme, P, E, R, T, ConcreteCircuit>(
+        params,
+        P::new(params),
+        pk,
+        circuits,
+        instances,
+        rng,
+        ProverOptions::new(),
+    )
+}
+
+/// Same as [`create_proof_with_options`], but returns the proof split into
+/// its sections, with the [layout](ProofLayout::of) that `prover` and
+/// `options` give it. The transcript `T` is created with
+/// [`TranscriptWriterBuffer::init`].
+#[allow(clippy::too_many_arguments)]
+pub fn create_typed_proof_with_options<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWriterBuffer<Vec<u8>, Scheme::Curve, E>,
+    ConcreteCircuit: Circuit<Scheme::Scalar>,
+>(
+    params: &'params Scheme::ParamsProver,
+    prover: P,
+    pk: &ProvingKey<Scheme::Curve>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    options: ProverOptions<'_, Scheme::Scalar>,
+) -> Result<Proof<Scheme::Curve>, Error> {
+    let layout = ProofLayout::of(&prover, circuits.len(), &options);
+    let mut transcript = T::init(vec![]);
+    create_proof_with_options::<Scheme, P, E, R, T, ConcreteCircuit>(
+        params,
+        prover,
+        pk,
+        circuits,
+        instances,
+        rng,
+        &mut transcript,
+        options,
+    )?;
+    let proof = transcript.finalize();
+
+    Proof::from_bytes(pk.get_vk(), &layout, &proof).map_err(Error::from)
+}
+
+/// Returns the number of permutation product commitments of each proof.
+pub(super) fn permutation_products<C: CurveAffine>(vk: &VerifyingKey<C>) -> usize {
+    let columns = vk.permutation.commitments().len();
+    let chunk_len = permutation::Argument::chunk_len(vk.cs_degree);
+    (columns + chunk_len - 1) / chunk_len
+}
+
+fn read_scalars<C: CurveAffine>(reader: &mut &[u8], n: usize) -> io::Result<Vec<C::Scalar>> {
+    (0..n)
+        .map(|_| {
+            let mut data = <C::Scalar as PrimeField>::Repr::default();
+            io::Read::read_exact(reader, data.as_mut())?;
+            Option::from(C::Scalar::from_repr(data)).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    "invalid field element encoding in proof",
+                )
+            })
+        })
+        .collect()
+}
@@ -1,10 +1,11 @@
-use ff::Field;
+use ff::{Field, PrimeField};
 use group::Curve;
 use halo2curves::CurveExt;
 use rand_core::RngCore;
-use std::collections::BTreeSet;
 use std::env::var;
+use std::io;
 use std::ops::RangeTo;
+use std::path::Path;
 use std::sync::atomic::AtomicUsize;
 use std::time::Instant;
 use std::{collections::HashMap, iter, mem, sync::atomic::Ordering};
@@ -15,29 +16,47 @@ use super::{
         Advice, Any, Assignment, Challenge, Circuit, Column, ConstraintSystem, FirstPhase, Fixed,
         FloorPlanner, Instance, Selector,
     },
-    lookup, permutation, vanishing, ChallengeBeta, ChallengeGamma, ChallengeTheta, ChallengeX,
-    ChallengeY, Error, Expression, ProvingKey,
+    lookup,
+    observer::StageTracker,
+    permutation, shuffle, vanishing, CancellationToken, ChallengeBeta, ChallengeGamma,
+    ChallengeTheta, ChallengeX, ChallengeY, Error, Expression, ProverObserver, ProverStage,
+    ProvingKey, VerifyingKey,
 };
 use crate::{
-    arithmetic::{eval_polynomial, CurveAffine, FieldExt},
+    arithmetic::{batch_invert, eval_polynomial, CurveAffine, FieldExt},
     circuit::Value,
     plonk::Assigned,
     poly::{
         self,
         commitment::{Blind, CommitmentScheme, Params, Prover},
-        Basis, Coeff, ExtendedLagrangeCoeff, LagrangeCoeff, Polynomial, ProverQuery,
+        Coeff, ExtendedLagrangeCoeff, LagrangeCoeff, Polynomial, PolynomialId, PolynomialStore,
+        ProverQuery, Wipe, Zeroizing,
     },
 };
 use crate::{
-    poly::batch_invert_assigned,
-    transcript::{EncodedChallenge, TranscriptWrite},
+    helpers::{read_polynomial_vec, write_polynomial_slice, SerdePrimeField},
+    transcript::{
+        common_instance_commitment, instance_hash, Digest, EncodedChallenge, TranscriptEvent,
+        TranscriptOp, TranscriptRecorder, TranscriptValue, TranscriptWrite,
+    },
+    SerdeFormat,
 };
 use group::prime::PrimeCurveAffine;
 
+mod parallel;
+pub use parallel::ParallelProver;
+
 /// This creates a proof for the provided `circuit` when given the public
 /// parameters `params` and the proving key [`ProvingKey`] that was
 /// generated previously for the same circuit. The provided `instances`
 /// are zero-padded internally.
+///
+/// With the `zeroize` feature, the advice columns, the values derived from
+/// them by the lookups and shuffles, and their blinding factors are
+/// overwritten with zeros as soon as they are no longer needed, so that the
+/// witness does not linger in freed memory. This includes their cosets on the
+/// extended domain and the files they are spilled to, and happens as well
+/// when proof creation fails or is cancelled.
 pub fn create_proof<
     'params,
     Scheme: CommitmentScheme,
@@ -51,8 +70,1079 @@ pub fn create_proof<
     pk: &ProvingKey<Scheme::Curve>,
     circuits: &[ConcreteCircuit],
     instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    transcript: &mut T,
+) -> Result<(), Error> {
+    create_proof_with_options(
+        params,
+        P::new(params),
+        pk,
+        circuits,
+        instances,
+        rng,
+        transcript,
+        ProverOptions::new(),
+    )
+}
+
+/// Same as [`create_proof`], but starts the proof with the domain-separation
+/// tag `domain_tag`, e.g. the name and version of the application, so that
+/// [`verify_proof_with_domain_tag`](super::verify_proof_with_domain_tag)
+/// rejects the proof right away when it expects another tag.
+pub fn create_proof_with_domain_tag<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+    ConcreteCircuit: Circuit<Scheme::Scalar>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    domain_tag: &[u8],
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    transcript: &mut T,
+) -> Result<(), Error> {
+    transcript.write_domain_tag(domain_tag)?;
+    create_proof::<Scheme, P, E, R, T, ConcreteCircuit>(
+        params, pk, circuits, instances, rng, transcript,
+    )
+}
+
+/// The challenges squeezed from the transcript while creating a proof, for
+/// reproducing the intermediate values of the prover when debugging a proof
+/// that does not verify.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofChallenges<F> {
+    /// The challenges of the circuit, by [`Challenge::index`]
+    pub challenges: Vec<F>,
+    /// The challenge compressing the expressions of each lookup
+    pub theta: F,
+    /// The first challenge of the permutation and lookup arguments
+    pub beta: F,
+    /// The second challenge of the permutation and lookup arguments
+    pub gamma: F,
+    /// The challenge combining the constraints into the quotient polynomial
+    pub y: F,
+    /// The challenge at which the polynomials are evaluated
+    pub x: F,
+    /// The challenges squeezed by the multiopen argument, in order; how many
+    /// there are depends on the commitment scheme
+    pub multiopen: Vec<F>,
+}
+
+/// Same as [`create_proof`], but also returns the challenges squeezed from
+/// `transcript`.
+pub fn create_proof_with_challenges<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+    ConcreteCircuit: Circuit<Scheme::Scalar>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    transcript: &mut T,
+) -> Result<ProofChallenges<Scheme::Scalar>, Error> {
+    let mut recorder = TranscriptRecorder::new(transcript);
+    create_proof::<Scheme, P, E, R, _, ConcreteCircuit>(
+        params,
+        pk,
+        circuits,
+        instances,
+        rng,
+        &mut recorder,
+    )?;
+
+    let mut squeezed = recorder.events().iter().filter_map(|event| match event {
+        TranscriptEvent {
+            op: TranscriptOp::SqueezeChallenge,
+            value: TranscriptValue::Scalar(challenge),
+            ..
+        } => Some(*challenge),
+        _ => None,
+    });
+    let mut next = || {
+        squeezed
+            .next()
+            .expect("the prover squeezes every challenge")
+    };
+
+    // The challenges of the circuit are squeezed after their phases, in order
+    let cs = &pk.vk.cs;
+    let mut challenges = vec![Scheme::Scalar::zero(); cs.num_challenges];
+    let mut indices = (0..cs.num_challenges).collect::<Vec<_>>();
+    indices.sort_by_key(|index| cs.challenge_phase[*index]);
+    for index in indices {
+        challenges[index] = next();
+    }
+    let theta = next();
+    let beta = next();
+    let gamma = next();
+    let y = next();
+    let x = next();
+
+    Ok(ProofChallenges {
+        challenges,
+        theta,
+        beta,
+        gamma,
+        y,
+        x,
+        multiopen: squeezed.collect(),
+    })
+}
+
+/// Options of [`create_proof_with_options`], starting from
+/// [`ProverOptions::new`], which proves like [`create_proof`]. The options
+/// combine, e.g. a proof can be observed and cancellable and have its
+/// instances hashed all at once.
+pub struct ProverOptions<'a, F> {
+    instance_mode: InstanceMode<F>,
+    observer: Option<&'a mut dyn ProverObserver>,
+    cancellation: Option<&'a CancellationToken>,
+    store: Option<Box<dyn ColumnStore<F> + 'a>>,
+    /// Whether the transcript has absorbed the verification key already,
+    /// e.g. before a [`ParallelProver`] forked it
+    vk_hashed: bool,
+}
+
+impl<'a, F> ProverOptions<'a, F> {
+    /// Returns the options of [`create_proof`]
+    pub fn new() -> Self {
+        ProverOptions {
+            instance_mode: InstanceMode::Default,
+            observer: None,
+            cancellation: None,
+            store: None,
+            vk_hashed: false,
+        }
+    }
+
+    /// Reports the stages of proof creation and the work done during each to
+    /// `observer`.
+    pub fn with_observer(mut self, observer: &'a mut dyn ProverObserver) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Returns [`Error::Cancelled`] once `cancellation` is cancelled. The
+    /// prover checks the token between the stages of proof creation, so a
+    /// proof is abandoned without finishing the stage it is in; the
+    /// transcript then holds a partial proof and should be discarded. With
+    /// the `zeroize` feature, the witness is wiped as the prover returns.
+    ///
+    /// This lets a proof run on a worker thread, e.g. one spawned with
+    /// `spawn_blocking` by an async runtime, and be cancelled from the task
+    /// that awaits it.
+    pub fn with_cancellation(mut self, cancellation: &'a CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// The transcript absorbs the commitments to the instance columns of
+    /// each proof, computed with [`commit_instances`](super::commit_instances),
+    /// and the proof opens them, whether or not the multiopen argument `P`
+    /// queries the instance columns (see [`Prover::QUERY_INSTANCE`]). The
+    /// verifier then only needs the commitments rather than the public
+    /// inputs, which for circuits with large public inputs is much cheaper
+    /// than evaluating them itself.
+    ///
+    /// The proof must be verified with
+    /// [`verify_proof_with_instance_commitments`](super::verify_proof_with_instance_commitments).
+    /// This replaces [`Self::with_instance_hash`].
+    pub fn with_committed_instances(mut self) -> Self {
+        self.instance_mode = InstanceMode::Committed;
+        self
+    }
+
+    /// Whether the proof opens the instance columns whatever the multiopen
+    /// argument, see [`Self::with_committed_instances`]
+    pub(crate) fn commits_instances(&self) -> bool {
+        matches!(self.instance_mode, InstanceMode::Committed)
+    }
+
+    /// The transcript has absorbed the verification key already, so the
+    /// prover only writes the fingerprint of the circuit.
+    pub(crate) fn with_vk_hashed(mut self) -> Self {
+        self.vk_hashed = true;
+        self
+    }
+}
+
+impl<'a, F: FieldExt> ProverOptions<'a, F> {
+    /// The transcript absorbs a hash with `D` of the instance values of each
+    /// proof, computed by [`instance_hash`], rather than each of the values.
+    /// This keeps the cost of verifiers that recompute the transcript, such
+    /// as on-chain verifiers, independent of the number of instance values.
+    /// Commitment schemes that commit to the instance columns (see
+    /// [`Prover::QUERY_INSTANCE`]) absorb the commitments either way.
+    ///
+    /// The proof must be verified with
+    /// [`verify_proof_with_instance_hash`](super::verify_proof_with_instance_hash)
+    /// and the same `D`. This replaces [`Self::with_committed_instances`].
+    pub fn with_instance_hash<D: Digest + Clone>(mut self) -> Self {
+        self.instance_mode = InstanceMode::Hashed(instance_hash::<F, D>);
+        self
+    }
+}
+
+impl<'a, F: SerdePrimeField> ProverOptions<'a, F> {
+    /// Keeps at most `max_memory_bytes` of the polynomials of the proofs the
+    /// prover is not working on in memory, e.g. to prove circuits with many
+    /// advice columns, or many circuits at once, on a machine with little
+    /// memory. Polynomials beyond the budget are written to files in
+    /// `spill_dir`: the advice columns while they are synthesized and until
+    /// the h(X) polynomial is evaluated, and the permutation, lookup and
+    /// shuffle products of each proof from their commitment until then. The
+    /// h(X) polynomial is evaluated one proof at a time, and the openings
+    /// hold all polynomials in memory.
+    pub fn with_memory_limit<P: AsRef<Path>>(
+        mut self,
+        max_memory_bytes: usize,
+        spill_dir: P,
+    ) -> Self {
+        self.store = Some(Box::new(SpillStore {
+            store: Zeroizing::new(PolynomialStore::with_dir(max_memory_bytes, spill_dir)),
+            lagrange: vec![],
+            coeff: vec![],
+        }));
+        self
+    }
+}
+
+impl<'a, F> Default for ProverOptions<'a, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, F> std::fmt::Debug for ProverOptions<'a, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let instance_mode = match self.instance_mode {
+            InstanceMode::Default => "Default",
+            InstanceMode::Hashed(_) => "Hashed",
+            InstanceMode::Committed => "Committed",
+        };
+        f.debug_struct("ProverOptions")
+            .field("instance_mode", &instance_mode)
+            .field("observer", &self.observer.is_some())
+            .field("cancellation", &self.cancellation)
+            .field("memory_limit", &self.store.is_some())
+            .field("vk_hashed", &self.vk_hashed)
+            .finish()
+    }
+}
+
+/// Same as [`create_proof`], but opens the commitments with the given
+/// multiopen `prover` rather than one created with `P::new(params)`, e.g. one
+/// whose opening scheme is chosen at runtime, and proves with `options`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_proof_with_options<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+    ConcreteCircuit: Circuit<Scheme::Scalar>,
+>(
+    params: &'params Scheme::ParamsProver,
+    prover: P,
+    pk: &ProvingKey<Scheme::Curve>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    transcript: &mut T,
+    options: ProverOptions<'_, Scheme::Scalar>,
+) -> Result<(), Error> {
+    if circuits.len() != instances.len() {
+        return Err(Error::InvalidInstances);
+    }
+    let mut meta = ConstraintSystem::default();
+    let config = ConcreteCircuit::configure(&mut meta);
+
+    create_proof_inner(
+        params,
+        prover,
+        pk,
+        instances,
+        |proof_index, phase, column_indices, challenges| {
+            synthesize_advice(
+                &pk.vk,
+                &config,
+                &circuits[proof_index],
+                instances[proof_index],
+                phase,
+                column_indices,
+                challenges,
+            )
+        },
+        rng,
+        transcript,
+        options,
+    )
+}
+
+/// Same as [`create_proof`], but with every blinding factor, and every other
+/// value the prover would sample at random, set to the same known constant.
+///
+/// **The proof is not zero-knowledge**: it reveals information about the
+/// witness. It is meant for benchmarks, which become deterministic, and for
+/// applications that only need succinctness, e.g. to compare commitments
+/// across runs. The proof verifies as usual.
+pub fn create_proof_without_blinding<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    T: TranscriptWrite<Scheme::Curve, E>,
+    ConcreteCircuit: Circuit<Scheme::Scalar>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[Scheme::Scalar]]],
+    transcript: &mut T,
+) -> Result<(), Error> {
+    create_proof::<Scheme, P, E, _, T, ConcreteCircuit>(
+        params,
+        pk,
+        circuits,
+        instances,
+        ConstantRng,
+        transcript,
+    )
+}
+
+/// An RNG that only returns `0x01` bytes. Every field element sampled from it
+/// is the same non-zero constant, so that blinded commitments are never the
+/// identity.
+#[derive(Debug)]
+struct ConstantRng;
+
+impl RngCore for ConstantRng {
+    fn next_u32(&mut self) -> u32 {
+        0x0101_0101
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        0x0101_0101_0101_0101
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.fill(1);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// The advice columns of a circuit, generated with [`generate_witness`] ahead
+/// of creating the proof with [`create_proof_from_witness`], e.g. on another
+/// machine or to retry proving without synthesizing the circuit again.
+#[derive(Clone, Debug)]
+pub struct Witness<F: Field> {
+    advice: Vec<Polynomial<F, LagrangeCoeff>>,
+}
+
+impl<F: Field> Witness<F> {
+    /// Returns the advice columns, in the order they were allocated. The rows
+    /// that are not usable are zero; they are blinded when creating the proof.
+    pub fn advice(&self) -> &[Polynomial<F, LagrangeCoeff>] {
+        &self.advice
+    }
+}
+
+impl<F: SerdePrimeField> Witness<F> {
+    /// Writes the witness to a buffer.
+    pub fn write<W: io::Write>(&self, writer: &mut W, format: SerdeFormat) -> io::Result<()> {
+        write_polynomial_slice(&self.advice, writer, format)
+    }
+
+    /// Reads a witness from a buffer.
+    pub fn read<R: io::Read>(reader: &mut R, format: SerdeFormat) -> io::Result<Self> {
+        Ok(Witness {
+            advice: read_polynomial_vec(reader, format)?,
+        })
+    }
+}
+
+/// Synthesizes `circuit` with the instance columns `instances` to obtain its
+/// witness, without creating a proof. Circuits with advice columns in later
+/// phases depend on challenges, which are only known while creating the
+/// proof, and return [`Error::WitnessDependsOnChallenges`].
+pub fn generate_witness<C: CurveAffine, ConcreteCircuit: Circuit<C::Scalar>>(
+    vk: &VerifyingKey<C>,
+    circuit: &ConcreteCircuit,
+    instances: &[&[C::Scalar]],
+) -> Result<Witness<C::Scalar>, Error> {
+    if instances.len() != vk.cs.num_instance_columns {
+        return Err(Error::InvalidInstances);
+    }
+    if vk.cs.phases().count() > 1 {
+        return Err(Error::WitnessDependsOnChallenges);
+    }
+
+    let mut meta = ConstraintSystem::default();
+    let config = ConcreteCircuit::configure(&mut meta);
+    let column_indices = (0..vk.cs.num_advice_columns).collect::<Vec<_>>();
+    let advice = synthesize_advice(
+        vk,
+        &config,
+        circuit,
+        instances,
+        FirstPhase.to_sealed(),
+        &column_indices,
+        &HashMap::new(),
+    )?;
+
+    Ok(Witness { advice })
+}
+
+/// Same as [`create_proof`], but takes the witness of each proof, generated
+/// with [`generate_witness`], rather than the circuits. The proof is the same
+/// as one created from the circuits with the same randomness.
+pub fn create_proof_from_witness<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    witnesses: Vec<Witness<Scheme::Scalar>>,
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    transcript: &mut T,
+) -> Result<(), Error> {
+    if witnesses.len() != instances.len() {
+        return Err(Error::InvalidInstances);
+    }
+    if witnesses
+        .iter()
+        .any(|witness| witness.advice.len() != pk.vk.cs.num_advice_columns)
+    {
+        return Err(Error::InvalidWitness);
+    }
+
+    let mut witnesses = Zeroizing::new(
+        witnesses
+            .into_iter()
+            .map(|witness| witness.advice.into_iter().map(Some).collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+    );
+    create_proof_inner(
+        params,
+        P::new(params),
+        pk,
+        instances,
+        |proof_index, _, column_indices, _| {
+            column_indices
+                .iter()
+                .map(|column_index| {
+                    witnesses[proof_index][*column_index]
+                        .take()
+                        .ok_or(Error::InvalidWitness)
+                })
+                .collect()
+        },
+        rng,
+        transcript,
+        ProverOptions::new(),
+    )
+}
+
+/// Same as [`create_proof`], but takes the values of the advice columns of
+/// each proof, e.g. from a witness generator outside of Rust, rather than
+/// synthesizing circuits. The columns are in the order they were allocated,
+/// and each holds at most as many values as there are usable rows; the
+/// remaining rows are zero. As with [`generate_witness`], circuits with
+/// advice columns in later phases are not supported.
+pub fn create_proof_with_advice<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    advice: &[&[&[Scheme::Scalar]]],
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    transcript: &mut T,
+) -> Result<(), Error> {
+    let meta = &pk.vk.cs;
+    if meta.phases().count() > 1 {
+        return Err(Error::WitnessDependsOnChallenges);
+    }
+
+    let domain = &pk.vk.domain;
+    let usable_rows = params.n() as usize - (meta.blinding_factors() + 1);
+    let witnesses = advice
+        .iter()
+        .map(|columns| {
+            if columns.len() != meta.num_advice_columns {
+                return Err(Error::InvalidWitness);
+            }
+            let advice = columns
+                .iter()
+                .map(|values| {
+                    if values.len() > usable_rows {
+                        return Err(Error::not_enough_rows_available(domain.k()));
+                    }
+                    let mut poly = domain.empty_lagrange();
+                    for (poly, value) in poly.iter_mut().zip(values.iter()) {
+                        *poly = *value;
+                    }
+                    Ok(poly)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Witness { advice })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    create_proof_from_witness::<Scheme, P, E, R, T>(
+        params, pk, witnesses, instances, rng, transcript,
+    )
+}
+
+/// Synthesizes `circuit` to obtain its advice columns `column_indices`, all
+/// of the phase `phase`. The rows that are not usable are left zero.
+fn synthesize_advice<C: CurveAffine, ConcreteCircuit: Circuit<C::Scalar>>(
+    vk: &VerifyingKey<C>,
+    config: &ConcreteCircuit::Config,
+    circuit: &ConcreteCircuit,
+    instances: &[&[C::Scalar]],
+    phase: sealed::Phase,
+    column_indices: &[usize],
+    challenges: &HashMap<usize, C::Scalar>,
+) -> Result<Vec<Polynomial<C::Scalar, LagrangeCoeff>>, Error> {
+    // Selector optimizations cannot be applied here; use the ConstraintSystem
+    // from the verification key.
+    let meta = &vk.cs;
+    let domain = &vk.domain;
+
+    let mut advice_slots = vec![None; meta.num_advice_columns];
+    for (slot, column_index) in column_indices.iter().enumerate() {
+        advice_slots[*column_index] = Some(slot);
+    }
+
+    let unusable_rows_start = (1usize << domain.k()) - (meta.blinding_factors() + 1);
+    let mut witness = WitnessCollection {
+        k: domain.k(),
+        current_phase: phase,
+        // Only the columns of this phase are held while synthesizing
+        advice: vec![domain.empty_lagrange(); column_indices.len()],
+        denominators: vec![HashMap::new(); column_indices.len()],
+        advice_slots,
+        instances,
+        challenges,
+        // The prover will not be allowed to assign values to advice
+        // cells that exist within inactive rows, which include some
+        // number of blinding factors and an extra row for use in the
+        // permutation argument.
+        usable_rows: ..unusable_rows_start,
+        _marker: std::marker::PhantomData,
+    };
+
+    // Synthesize the circuit to obtain the witness and other information.
+    ConcreteCircuit::FloorPlanner::synthesize(
+        &mut witness,
+        circuit,
+        config.clone(),
+        meta.constants.clone(),
+    )?;
+
+    // Divide the cells assigned fractions by their denominators in place
+    let mut advice = mem::take(&mut witness.advice);
+    for (column, denominators) in advice.iter_mut().zip(witness.denominators.iter_mut()) {
+        let (rows, mut inverses): (Vec<_>, Vec<_>) = denominators.drain().unzip();
+        batch_invert(&mut inverses);
+        for (row, inverse) in rows.into_iter().zip(inverses.iter()) {
+            column[row] *= inverse;
+        }
+        #[cfg(feature = "zeroize")]
+        poly::zeroize(&mut inverses, C::Scalar::zero());
+    }
+
+    Ok(advice)
+}
+
+struct WitnessCollection<'a, F: Field> {
+    k: u32,
+    current_phase: sealed::Phase,
+    /// The advice columns of the current phase. Cells assigned a fraction
+    /// hold its numerator until synthesis finishes, so that the columns take
+    /// half the memory of [`Assigned`] values.
+    advice: Vec<Polynomial<F, LagrangeCoeff>>,
+    /// The denominators of the fractions assigned to each column of `advice`,
+    /// by row
+    denominators: Vec<HashMap<usize, F>>,
+    /// The index in `advice` of each advice column of the current phase
+    advice_slots: Vec<Option<usize>>,
+    challenges: &'a HashMap<usize, F>,
+    instances: &'a [&'a [F]],
+    usable_rows: RangeTo<usize>,
+    _marker: std::marker::PhantomData<F>,
+}
+
+/// The assigned values are wiped by the prover once committed to, or here if
+/// synthesizing the circuit fails
+#[cfg(feature = "zeroize")]
+impl<'a, F: Field> Drop for WitnessCollection<'a, F> {
+    fn drop(&mut self) {
+        self.advice.wipe();
+        for denominators in self.denominators.iter_mut() {
+            for denominator in denominators.values_mut() {
+                poly::zeroize(std::slice::from_mut(denominator), F::zero());
+            }
+        }
+    }
+}
+
+impl<'a, F: Field> Assignment<F> for WitnessCollection<'a, F> {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about regions in this context.
+    }
+
+    fn exit_region(&mut self) {
+        // Do nothing; we don't care about regions in this context.
+    }
+
+    fn uses_annotations(&self) -> bool {
+        false
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, _: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // We only care about advice columns here
+
+        Ok(())
+    }
+
+    fn annotate_column<A, AR>(&mut self, _annotation: A, _column: Column<Any>)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // Do nothing
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        if !self.usable_rows.contains(&row) {
+            return Err(Error::not_enough_rows_available(self.k));
+        }
+
+        self.instances
+            .get(column.index())
+            .and_then(|column| column.get(row))
+            .map(|v| Value::known(*v))
+            .ok_or(Error::BoundsFailure)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // Ignore assignment of advice column in different phase than current one.
+        if self.current_phase != column.column_type().phase {
+            return Ok(());
+        }
+
+        if !self.usable_rows.contains(&row) {
+            return Err(Error::not_enough_rows_available(self.k));
+        }
+
+        let slot = self
+            .advice_slots
+            .get(column.index())
+            .copied()
+            .flatten()
+            .ok_or(Error::BoundsFailure)?;
+        let value = to().into_field().assign()?;
+        *self
+            .advice
+            .get_mut(slot)
+            .and_then(|v| v.get_mut(row))
+            .ok_or(Error::BoundsFailure)? = value.numerator();
+        let denominators = &mut self.denominators[slot];
+        match value.denominator() {
+            Some(denominator) => {
+                denominators.insert(row, denominator);
+            }
+            // The cell may have been assigned a fraction before
+            None if !denominators.is_empty() => {
+                denominators.remove(&row);
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Fixed>,
+        _: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // We only care about advice columns here
+
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        // We only care about advice columns here
+
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn get_challenge(&self, challenge: Challenge) -> Value<F> {
+        self.challenges
+            .get(&challenge.index())
+            .cloned()
+            .map(Value::known)
+            .unwrap_or_else(Value::unknown)
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+}
+
+/// Holds the polynomials of the proofs the prover is not working on
+trait ColumnStore<F> {
+    fn insert_lagrange(&mut self, poly: Polynomial<F, LagrangeCoeff>) -> io::Result<usize>;
+
+    fn take_lagrange(&mut self, id: usize) -> io::Result<Polynomial<F, LagrangeCoeff>>;
+
+    fn insert_coeff(&mut self, poly: Polynomial<F, Coeff>) -> io::Result<usize>;
+
+    fn take_coeff(&mut self, id: usize) -> io::Result<Polynomial<F, Coeff>>;
+
+    /// Takes the advice columns `ids` of a proof out of the store
+    fn take_columns(
+        &mut self,
+        ids: &[usize],
+    ) -> io::Result<Zeroizing<Vec<Polynomial<F, LagrangeCoeff>>>>
+    where
+        F: Field,
+    {
+        Zeroizing::try_collect(ids.iter().map(|id| self.take_lagrange(*id)))
+    }
+
+    /// Puts the advice columns of a proof back into the store, returning
+    /// their new ids
+    fn insert_columns(
+        &mut self,
+        mut columns: Zeroizing<Vec<Polynomial<F, LagrangeCoeff>>>,
+    ) -> io::Result<Vec<usize>>
+    where
+        F: Field,
+    {
+        let mut ids = Vec::with_capacity(columns.len());
+        columns.reverse();
+        while let Some(column) = columns.pop() {
+            ids.push(self.insert_lagrange(column)?);
+        }
+        Ok(ids)
+    }
+}
+
+/// Keeps all polynomials in memory
+struct MemoryStore<F: Field> {
+    lagrange: Zeroizing<Vec<Option<Polynomial<F, LagrangeCoeff>>>>,
+    coeff: Zeroizing<Vec<Option<Polynomial<F, Coeff>>>>,
+}
+
+impl<F: Field> MemoryStore<F> {
+    fn new() -> Self {
+        MemoryStore {
+            lagrange: Zeroizing::new(vec![]),
+            coeff: Zeroizing::new(vec![]),
+        }
+    }
+}
+
+impl<F: Field> ColumnStore<F> for MemoryStore<F> {
+    fn insert_lagrange(&mut self, poly: Polynomial<F, LagrangeCoeff>) -> io::Result<usize> {
+        self.lagrange.push(Some(poly));
+        Ok(self.lagrange.len() - 1)
+    }
+
+    fn take_lagrange(&mut self, id: usize) -> io::Result<Polynomial<F, LagrangeCoeff>> {
+        Ok(self.lagrange[id]
+            .take()
+            .expect("column was taken out of the store"))
+    }
+
+    fn insert_coeff(&mut self, poly: Polynomial<F, Coeff>) -> io::Result<usize> {
+        self.coeff.push(Some(poly));
+        Ok(self.coeff.len() - 1)
+    }
+
+    fn take_coeff(&mut self, id: usize) -> io::Result<Polynomial<F, Coeff>> {
+        Ok(self.coeff[id]
+            .take()
+            .expect("polynomial was taken out of the store"))
+    }
+}
+
+/// Keeps the polynomials in a [`PolynomialStore`], which spills them to
+/// files beyond its budget
+struct SpillStore<F: SerdePrimeField> {
+    store: Zeroizing<PolynomialStore<F>>,
+    lagrange: Vec<PolynomialId<LagrangeCoeff>>,
+    coeff: Vec<PolynomialId<Coeff>>,
+}
+
+impl<F: SerdePrimeField> ColumnStore<F> for SpillStore<F> {
+    fn insert_lagrange(&mut self, poly: Polynomial<F, LagrangeCoeff>) -> io::Result<usize> {
+        self.lagrange.push(self.store.insert(poly)?);
+        Ok(self.lagrange.len() - 1)
+    }
+
+    fn take_lagrange(&mut self, id: usize) -> io::Result<Polynomial<F, LagrangeCoeff>> {
+        self.store.take(self.lagrange[id])
+    }
+
+    fn insert_coeff(&mut self, poly: Polynomial<F, Coeff>) -> io::Result<usize> {
+        self.coeff.push(self.store.insert(poly)?);
+        Ok(self.coeff.len() - 1)
+    }
+
+    fn take_coeff(&mut self, id: usize) -> io::Result<Polynomial<F, Coeff>> {
+        self.store.take(self.coeff[id])
+    }
+}
+
+/// Values of a proof whose polynomials the prover moves into a
+/// [`ColumnStore`] while it works on the other proofs
+trait Stash<F: Field> {
+    fn polys_mut(&mut self) -> Vec<&mut Polynomial<F, Coeff>>;
+
+    /// Moves the polynomials into `store`, leaving them empty, and returns
+    /// their ids
+    fn stash<S: ColumnStore<F> + ?Sized>(&mut self, store: &mut S) -> io::Result<Vec<usize>> {
+        self.polys_mut()
+            .into_iter()
+            .map(|poly| store.insert_coeff(poly.take()))
+            .collect()
+    }
+
+    /// Moves the polynomials `ids` back from `store`
+    fn unstash<S: ColumnStore<F> + ?Sized>(
+        &mut self,
+        store: &mut S,
+        ids: &[usize],
+    ) -> io::Result<()> {
+        for (poly, id) in self.polys_mut().into_iter().zip(ids.iter()) {
+            *poly = store.take_coeff(*id)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: Field, T: Stash<F>> Stash<F> for Vec<T> {
+    fn polys_mut(&mut self) -> Vec<&mut Polynomial<F, Coeff>> {
+        self.iter_mut()
+            .flat_map(|value| value.polys_mut())
+            .collect()
+    }
+}
+
+impl<C: CurveAffine> Stash<C::Scalar> for permutation::prover::Committed<C> {
+    fn polys_mut(&mut self) -> Vec<&mut Polynomial<C::Scalar, Coeff>> {
+        self.sets
+            .iter_mut()
+            .map(|set| &mut set.permutation_product_poly)
+            .collect()
+    }
+}
+
+impl<C: CurveAffine> Stash<C::Scalar> for lookup::prover::Committed<C> {
+    fn polys_mut(&mut self) -> Vec<&mut Polynomial<C::Scalar, Coeff>> {
+        match self {
+            lookup::prover::Committed::Permutation {
+                permuted_input_poly,
+                permuted_table_poly,
+                product_poly,
+                ..
+            } => vec![permuted_input_poly, permuted_table_poly, product_poly],
+            lookup::prover::Committed::LogDerivative {
+                multiplicities_poly,
+                sum_poly,
+                ..
+            } => vec![multiplicities_poly, sum_poly],
+        }
+    }
+}
+
+impl<C: CurveAffine> Stash<C::Scalar> for shuffle::prover::Committed<C> {
+    fn polys_mut(&mut self) -> Vec<&mut Polynomial<C::Scalar, Coeff>> {
+        vec![&mut self.product_poly]
+    }
+}
+
+/// A value of a proof whose polynomials are held in a [`ColumnStore`] while
+/// they are stashed, and by the value itself once restored
+struct Stashed<T> {
+    value: T,
+    ids: Option<Vec<usize>>,
+}
+
+impl<T> Stashed<T> {
+    /// Moves the polynomials of `value` into `store`
+    fn new<F: Field, S: ColumnStore<F> + ?Sized>(mut value: T, store: &mut S) -> io::Result<Self>
+    where
+        T: Stash<F>,
+    {
+        let ids = value.stash(store)?;
+        Ok(Stashed {
+            value,
+            ids: Some(ids),
+        })
+    }
+
+    /// Moves the polynomials back from `store`, if they are stashed
+    fn restore<F: Field, S: ColumnStore<F> + ?Sized>(&mut self, store: &mut S) -> io::Result<()>
+    where
+        T: Stash<F>,
+    {
+        if let Some(ids) = self.ids.take() {
+            self.value.unstash(store, &ids)?;
+        }
+        Ok(())
+    }
+
+    /// Moves the polynomials into `store` again, if they are restored
+    fn stash<F: Field, S: ColumnStore<F> + ?Sized>(&mut self, store: &mut S) -> io::Result<()>
+    where
+        T: Stash<F>,
+    {
+        if self.ids.is_none() {
+            self.ids = Some(self.value.stash(store)?);
+        }
+        Ok(())
+    }
+
+    /// Restores the polynomials and returns the value
+    fn into_inner<F: Field, S: ColumnStore<F> + ?Sized>(mut self, store: &mut S) -> io::Result<T>
+    where
+        T: Stash<F>,
+    {
+        self.restore(store)?;
+        Ok(self.value)
+    }
+}
+
+impl<T> std::ops::Deref for Stashed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Wipe> Wipe for Stashed<T> {
+    #[cfg(feature = "zeroize")]
+    fn wipe(&mut self) {
+        self.value.wipe();
+    }
+}
+
+/// How the transcript takes in the instance columns of each proof
+#[derive(Clone, Copy)]
+pub(crate) enum InstanceMode<F> {
+    /// Their commitments if the multiopen argument queries the instance
+    /// columns, and their values otherwise
+    Default,
+    /// Like `Default`, but the hash of the values with the given function
+    /// rather than each of them
+    Hashed(fn(&[&[F]]) -> F),
+    /// Their commitments, with the instance columns queried whether or not
+    /// the multiopen argument does so
+    Committed,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_proof_inner<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+    S: FnMut(
+        usize,
+        sealed::Phase,
+        &[usize],
+        &HashMap<usize, Scheme::Scalar>,
+    ) -> Result<Vec<Polynomial<Scheme::Scalar, LagrangeCoeff>>, Error>,
+>(
+    params: &'params Scheme::ParamsProver,
+    prover: P,
+    pk: &ProvingKey<Scheme::Curve>,
+    instances: &[&[&[Scheme::Scalar]]],
+    mut synthesize: S,
     mut rng: R,
     transcript: &mut T,
+    options: ProverOptions<'_, Scheme::Scalar>,
 ) -> Result<(), Error> {
     for instance in instances.iter() {
         if instance.len() != pk.vk.cs.num_instance_columns {
@@ -60,16 +1150,44 @@ pub fn create_proof<
         }
     }
 
+    let ProverOptions {
+        instance_mode,
+        observer,
+        cancellation,
+        store,
+        vk_hashed,
+    } = options;
+    let mut unobserved = ();
+    let observer: &mut dyn ProverObserver = match observer {
+        Some(observer) => observer,
+        None => &mut unobserved,
+    };
+    let mut store = store.unwrap_or_else(|| Box::new(MemoryStore::new()));
+
+    let mut tracker = StageTracker::new(
+        observer,
+        <Scheme::Scalar as PrimeField>::Repr::default()
+            .as_ref()
+            .len(),
+        cancellation,
+    );
+    tracker.start(ProverStage::Instance)?;
+
     // Hash verification key into transcript
-    pk.vk.hash_into(transcript)?;
+    if !vk_hashed {
+        pk.vk.hash_into(transcript)?;
+    }
 
-    let domain = &pk.vk.domain;
-    let mut meta = ConstraintSystem::default();
-    let config = ConcreteCircuit::configure(&mut meta);
+    // Start the proof with the fingerprint of the circuit, and the multiopen
+    // scheme if the prover chooses it at runtime
+    transcript.write_scalar(pk.vk.fingerprint)?;
+    if let Some(id) = prover.scheme_id() {
+        transcript.write_scalar(Scheme::Scalar::from(id as u64))?;
+    }
 
-    // Selector optimizations cannot be applied here; use the ConstraintSystem
-    // from the verification key.
+    let domain = &pk.vk.domain;
     let meta = &pk.vk.cs;
+    let query_instance = P::QUERY_INSTANCE || matches!(instance_mode, InstanceMode::Committed);
 
     struct InstanceSingle<C: CurveAffine> {
         pub instance_values: Vec<Polynomial<C::Scalar, LagrangeCoeff>>,
@@ -79,6 +1197,13 @@ pub fn create_proof<
     let instance: Vec<InstanceSingle<Scheme::Curve>> = instances
         .iter()
         .map(|instance| -> Result<InstanceSingle<Scheme::Curve>, Error> {
+            let absorb_values = match instance_mode {
+                InstanceMode::Hashed(hasher) if !query_instance => {
+                    transcript.common_scalar(hasher(instance))?;
+                    false
+                }
+                _ => !query_instance,
+            };
             let instance_values = instance
                 .iter()
                 .map(|values| {
@@ -88,7 +1213,7 @@ pub fn create_proof<
                         return Err(Error::InstanceTooLarge);
                     }
                     for (poly, value) in poly.iter_mut().zip(values.iter()) {
-                        if !P::QUERY_INSTANCE {
+                        if absorb_values {
                             transcript.common_scalar(*value)?;
                         }
                         *poly = *value;
@@ -97,7 +1222,7 @@ pub fn create_proof<
                 })
                 .collect::<Result<Vec<_>, _>>()?;
 
-            if P::QUERY_INSTANCE {
+            if query_instance {
                 let instance_commitments_projective: Vec<_> = instance_values
                     .iter()
                     .map(|poly| params.commit_lagrange(poly, Blind::default()))
@@ -112,7 +1237,7 @@ pub fn create_proof<
                 drop(instance_commitments_projective);
 
                 for commitment in &instance_commitments {
-                    transcript.common_point(*commitment)?;
+                    common_instance_commitment(transcript, *commitment)?;
                 }
             }
 
@@ -131,164 +1256,35 @@ pub fn create_proof<
         })
         .collect::<Result<Vec<_>, _>>()?;
 
-    #[derive(Clone)]
-    struct AdviceSingle<C: CurveAffine, B: Basis> {
-        pub advice_polys: Vec<Polynomial<C::Scalar, B>>,
+    struct AdviceSingle<C: CurveAffine> {
+        pub advice_polys: Vec<Polynomial<C::Scalar, Coeff>>,
         pub advice_blinds: Vec<Blind<C::Scalar>>,
     }
 
-    struct WitnessCollection<'a, F: Field> {
-        k: u32,
-        current_phase: sealed::Phase,
-        advice: Vec<Polynomial<Assigned<F>, LagrangeCoeff>>,
-        challenges: &'a HashMap<usize, F>,
-        instances: &'a [&'a [F]],
-        usable_rows: RangeTo<usize>,
-        _marker: std::marker::PhantomData<F>,
-    }
-
-    impl<'a, F: Field> Assignment<F> for WitnessCollection<'a, F> {
-        fn enter_region<NR, N>(&mut self, _: N)
-        where
-            NR: Into<String>,
-            N: FnOnce() -> NR,
-        {
-            // Do nothing; we don't care about regions in this context.
-        }
-
-        fn exit_region(&mut self) {
-            // Do nothing; we don't care about regions in this context.
-        }
-
-        fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, _: usize) -> Result<(), Error>
-        where
-            A: FnOnce() -> AR,
-            AR: Into<String>,
-        {
-            // We only care about advice columns here
-
-            Ok(())
-        }
-
-        fn annotate_column<A, AR>(&mut self, _annotation: A, _column: Column<Any>)
-        where
-            A: FnOnce() -> AR,
-            AR: Into<String>,
-        {
-            // Do nothing
-        }
-
-        fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
-            if !self.usable_rows.contains(&row) {
-                return Err(Error::not_enough_rows_available(self.k));
-            }
-
-            self.instances
-                .get(column.index())
-                .and_then(|column| column.get(row))
-                .map(|v| Value::known(*v))
-                .ok_or(Error::BoundsFailure)
-        }
-
-        fn assign_advice<V, VR, A, AR>(
-            &mut self,
-            _: A,
-            column: Column<Advice>,
-            row: usize,
-            to: V,
-        ) -> Result<(), Error>
-        where
-            V: FnOnce() -> Value<VR>,
-            VR: Into<Assigned<F>>,
-            A: FnOnce() -> AR,
-            AR: Into<String>,
-        {
-            // Ignore assignment of advice column in different phase than current one.
-            if self.current_phase != column.column_type().phase {
-                return Ok(());
-            }
-
-            if !self.usable_rows.contains(&row) {
-                return Err(Error::not_enough_rows_available(self.k));
-            }
-
-            *self
-                .advice
-                .get_mut(column.index())
-                .and_then(|v| v.get_mut(row))
-                .ok_or(Error::BoundsFailure)? = to().into_field().assign()?;
-
-            Ok(())
-        }
-
-        fn assign_fixed<V, VR, A, AR>(
-            &mut self,
-            _: A,
-            _: Column<Fixed>,
-            _: usize,
-            _: V,
-        ) -> Result<(), Error>
-        where
-            V: FnOnce() -> Value<VR>,
-            VR: Into<Assigned<F>>,
-            A: FnOnce() -> AR,
-            AR: Into<String>,
-        {
-            // We only care about advice columns here
-
-            Ok(())
-        }
-
-        fn copy(
-            &mut self,
-            _: Column<Any>,
-            _: usize,
-            _: Column<Any>,
-            _: usize,
-        ) -> Result<(), Error> {
-            // We only care about advice columns here
-
-            Ok(())
-        }
-
-        fn fill_from_row(
-            &mut self,
-            _: Column<Fixed>,
-            _: usize,
-            _: Value<Assigned<F>>,
-        ) -> Result<(), Error> {
-            Ok(())
-        }
-
-        fn get_challenge(&self, challenge: Challenge) -> Value<F> {
-            self.challenges
-                .get(&challenge.index())
-                .cloned()
-                .map(Value::known)
-                .unwrap_or_else(Value::unknown)
-        }
-
-        fn push_namespace<NR, N>(&mut self, _: N)
-        where
-            NR: Into<String>,
-            N: FnOnce() -> NR,
-        {
-            // Do nothing; we don't care about namespaces in this context.
+    impl<C: CurveAffine> Wipe for AdviceSingle<C> {
+        #[cfg(feature = "zeroize")]
+        fn wipe(&mut self) {
+            self.advice_polys.wipe();
+            self.advice_blinds.wipe();
         }
+    }
 
-        fn pop_namespace(&mut self, _: Option<String>) {
-            // Do nothing; we don't care about namespaces in this context.
+    impl<C: CurveAffine> Stash<C::Scalar> for AdviceSingle<C> {
+        fn polys_mut(&mut self) -> Vec<&mut Polynomial<C::Scalar, Coeff>> {
+            self.advice_polys.iter_mut().collect()
         }
     }
 
-    let (advice, challenges) = {
-        let mut advice = vec![
-            AdviceSingle::<Scheme::Curve, LagrangeCoeff> {
-                advice_polys: vec![domain.empty_lagrange(); meta.num_advice_columns],
-                advice_blinds: vec![Blind::default(); meta.num_advice_columns],
-            };
+    tracker.start(ProverStage::Advice)?;
+
+    let (mut advice_ids, advice_blinds, challenges) = {
+        // The committed columns are kept in the store while the prover works
+        // on the other columns and proofs
+        let mut advice_ids = vec![vec![None; meta.num_advice_columns]; instances.len()];
+        let mut advice_blinds = Zeroizing::new(vec![
+            vec![Blind::default(); meta.num_advice_columns];
             instances.len()
-        ];
+        ]);
         let mut challenges = HashMap::<usize, Scheme::Scalar>::with_capacity(meta.num_challenges);
 
         let unusable_rows_start = params.n() as usize - (meta.blinding_factors() + 1);
@@ -304,82 +1300,44 @@ pub fn create_proof<
                         None
                     }
                 })
-                .collect::<BTreeSet<_>>();
+                .collect::<Vec<_>>();
 
-            for ((circuit, advice), instances) in
-                circuits.iter().zip(advice.iter_mut()).zip(instances)
+            for (proof_index, (advice_ids, advice_blinds)) in advice_ids
+                .iter_mut()
+                .zip(advice_blinds.iter_mut())
+                .enumerate()
             {
-                let mut witness = WitnessCollection {
-                    k: params.k(),
+                tracker.check()?;
+                let mut columns = Zeroizing::new(synthesize(
+                    proof_index,
                     current_phase,
-                    advice: vec![domain.empty_lagrange_assigned(); meta.num_advice_columns],
-                    instances,
-                    challenges: &challenges,
-                    // The prover will not be allowed to assign values to advice
-                    // cells that exist within inactive rows, which include some
-                    // number of blinding factors and an extra row for use in the
-                    // permutation argument.
-                    usable_rows: ..unusable_rows_start,
-                    _marker: std::marker::PhantomData,
-                };
+                    &column_indices,
+                    &challenges,
+                )?);
+                if columns.len() != column_indices.len() {
+                    return Err(Error::InvalidWitness);
+                }
 
-                // Synthesize the circuit to obtain the witness and other information.
-                ConcreteCircuit::FloorPlanner::synthesize(
-                    &mut witness,
-                    circuit,
-                    config.clone(),
-                    meta.constants.clone(),
-                )?;
-
-                let mut advice_values = batch_invert_assigned::<Scheme::Scalar>(
-                    witness
-                        .advice
-                        .into_iter()
-                        .enumerate()
-                        .filter_map(|(column_index, advice)| {
-                            if column_indices.contains(&column_index) {
-                                Some(advice)
-                            } else {
-                                None
-                            }
-                        })
-                        .collect(),
-                );
+                // Blind and commit to the columns one at a time
+                columns.reverse();
+                for column_index in column_indices.iter() {
+                    let mut advice_values = Zeroizing::new(columns.pop().unwrap());
+                    if advice_values.len() != params.n() as usize {
+                        return Err(Error::InvalidWitness);
+                    }
 
-                // Add blinding factors to advice columns
-                for advice_values in &mut advice_values {
+                    // Add blinding factors to the advice column
                     for cell in &mut advice_values[unusable_rows_start..] {
                         *cell = Scheme::Scalar::random(&mut rng);
                     }
-                }
 
-                // Compute commitments to advice column polynomials
-                let blinds: Vec<_> = advice_values
-                    .iter()
-                    .map(|_| Blind(Scheme::Scalar::random(&mut rng)))
-                    .collect();
-                let advice_commitments_projective: Vec<_> = advice_values
-                    .iter()
-                    .zip(blinds.iter())
-                    .map(|(poly, blind)| params.commit_lagrange(poly, *blind))
-                    .collect();
-                let mut advice_commitments =
-                    vec![Scheme::Curve::identity(); advice_commitments_projective.len()];
-                <Scheme::Curve as CurveAffine>::CurveExt::batch_normalize(
-                    &advice_commitments_projective,
-                    &mut advice_commitments,
-                );
-                let advice_commitments = advice_commitments;
-                drop(advice_commitments_projective);
+                    let blind = Blind(Scheme::Scalar::random(&mut rng));
+                    let commitment = params.commit_lagrange(&advice_values, blind).to_affine();
+                    transcript.write_point(commitment)?;
 
-                for commitment in &advice_commitments {
-                    transcript.write_point(*commitment)?;
-                }
-                for ((column_index, advice_values), blind) in
-                    column_indices.iter().zip(advice_values).zip(blinds)
-                {
-                    advice.advice_polys[*column_index] = advice_values;
-                    advice.advice_blinds[*column_index] = blind;
+                    advice_ids[*column_index] =
+                        Some(store.insert_lagrange(advice_values.into_inner())?);
+                    advice_blinds[*column_index] = blind;
                 }
             }
 
@@ -397,38 +1355,44 @@ pub fn create_proof<
             .map(|index| challenges.remove(&index).unwrap())
             .collect::<Vec<_>>();
 
-        (advice, challenges)
+        let advice_ids = advice_ids
+            .into_iter()
+            .map(|ids| ids.into_iter().map(Option::unwrap).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        (advice_ids, advice_blinds, challenges)
     };
 
+    tracker.start(ProverStage::LookupPermuted)?;
+
     // Sample theta challenge for keeping lookup columns linearly independent
     let theta: ChallengeTheta<_> = transcript.squeeze_challenge_scalar();
 
-    let lookups: Vec<Vec<lookup::prover::Permuted<Scheme::Curve>>> = instance
-        .iter()
-        .zip(advice.iter())
-        .map(|(instance, advice)| -> Result<Vec<_>, Error> {
-            // Construct and commit to permuted values for each lookup
-            pk.vk
-                .cs
-                .lookups
-                .iter()
-                .map(|lookup| {
-                    lookup.commit_permuted(
+    let lookup_batches = pk.vk.cs.lookup_batches();
+    let lookups: Zeroizing<Vec<Vec<lookup::prover::Permuted<Scheme::Curve>>>> =
+        Zeroizing::try_collect(instance.iter().zip(advice_ids.iter_mut()).map(
+            |(instance, advice_ids)| -> Result<Vec<_>, Error> {
+                let advice = store.take_columns(advice_ids)?;
+                // Construct and commit to permuted values for each lookup
+                let lookups = Zeroizing::try_collect(lookup_batches.iter().map(|batch| {
+                    lookup::Argument::commit_permuted(
+                        batch,
                         pk,
                         params,
                         domain,
                         theta,
-                        &advice.advice_polys,
+                        &advice,
                         &pk.fixed_values,
                         &instance.instance_values,
                         &challenges,
                         &mut rng,
                         transcript,
                     )
-                })
-                .collect()
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+                }))?;
+                *advice_ids = store.insert_columns(advice)?;
+                Ok(lookups.into_inner())
+            },
+        ))?;
 
     // Sample beta challenge
     let beta: ChallengeBeta<_> = transcript.squeeze_challenge_scalar();
@@ -436,36 +1400,64 @@ pub fn create_proof<
     // Sample gamma challenge
     let gamma: ChallengeGamma<_> = transcript.squeeze_challenge_scalar();
 
+    tracker.start(ProverStage::Products)?;
+
     // Commit to permutations.
-    let permutations: Vec<permutation::prover::Committed<Scheme::Curve>> = instance
-        .iter()
-        .zip(advice.iter())
-        .map(|(instance, advice)| {
-            pk.vk.cs.permutation.commit(
-                params,
-                pk,
-                &pk.permutation,
-                &advice.advice_polys,
-                &pk.fixed_values,
-                &instance.instance_values,
-                beta,
-                gamma,
-                &mut rng,
-                transcript,
-            )
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+    let mut permutations: Vec<Stashed<permutation::prover::Committed<Scheme::Curve>>> =
+        Vec::with_capacity(instances.len());
+    for (instance, advice_ids) in instance.iter().zip(advice_ids.iter_mut()) {
+        let advice = store.take_columns(advice_ids)?;
+        let permutation = pk.vk.cs.permutation.commit(
+            params,
+            pk,
+            &pk.permutation,
+            &advice,
+            &pk.fixed_values,
+            &instance.instance_values,
+            beta,
+            gamma,
+            &mut rng,
+            transcript,
+        )?;
+        *advice_ids = store.insert_columns(advice)?;
+        permutations.push(Stashed::new(permutation, &mut *store)?);
+    }
 
-    let lookups: Vec<Vec<lookup::prover::Committed<Scheme::Curve>>> = lookups
-        .into_iter()
-        .map(|lookups| -> Result<Vec<_>, _> {
+    let mut lookups: Zeroizing<Vec<Stashed<Vec<lookup::prover::Committed<Scheme::Curve>>>>> =
+        lookups.try_map(|lookups| -> Result<_, Error> {
             // Construct and commit to products for each lookup
-            lookups
-                .into_iter()
-                .map(|lookup| lookup.commit_product(pk, params, beta, gamma, &mut rng, transcript))
-                .collect::<Result<Vec<_>, _>>()
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+            let lookups = Zeroizing::new(lookups).try_map(|lookup| {
+                lookup.commit_product(pk, params, beta, gamma, &mut rng, transcript)
+            })?;
+            Ok(Stashed::new(lookups.into_inner(), &mut *store)?)
+        })?;
+
+    let mut shuffles: Zeroizing<Vec<Stashed<Vec<shuffle::prover::Committed<Scheme::Curve>>>>> =
+        Zeroizing::try_collect(instance.iter().zip(advice_ids.iter_mut()).map(
+            |(instance, advice_ids)| -> Result<_, Error> {
+                let advice = store.take_columns(advice_ids)?;
+                // Construct and commit to products for each shuffle
+                let shuffles = Zeroizing::try_collect(pk.vk.cs.shuffles.iter().map(|shuffle| {
+                    shuffle.commit_product(
+                        pk,
+                        params,
+                        domain,
+                        theta,
+                        gamma,
+                        &advice,
+                        &pk.fixed_values,
+                        &instance.instance_values,
+                        &challenges,
+                        &mut rng,
+                        transcript,
+                    )
+                }))?;
+                *advice_ids = store.insert_columns(advice)?;
+                Ok(Stashed::new(shuffles.into_inner(), &mut *store)?)
+            },
+        ))?;
+
+    tracker.start(ProverStage::Vanishing)?;
 
     // Commit to the vanishing argument's random polynomial for blinding h(x_3)
     let vanishing = vanishing::Argument::commit(params, domain, &mut rng, transcript)?;
@@ -474,43 +1466,52 @@ pub fn create_proof<
     let y: ChallengeY<_> = transcript.squeeze_challenge_scalar();
 
     // Calculate the advice polys
-    let advice: Vec<AdviceSingle<Scheme::Curve, Coeff>> = advice
-        .into_iter()
-        .map(
-            |AdviceSingle {
-                 advice_polys,
-                 advice_blinds,
-             }| {
-                AdviceSingle {
-                    advice_polys: advice_polys
-                        .into_iter()
+    let mut advice: Zeroizing<Vec<Stashed<AdviceSingle<Scheme::Curve>>>> =
+        Zeroizing::try_collect(advice_ids.iter().zip(advice_blinds.iter()).map(
+            |(advice_ids, advice_blinds)| -> Result<_, Error> {
+                let advice = AdviceSingle {
+                    advice_polys: store
+                        .take_columns(advice_ids)?
                         .map(|poly| domain.lagrange_to_coeff(poly))
-                        .collect::<Vec<_>>(),
-                    advice_blinds,
-                }
+                        .into_inner(),
+                    advice_blinds: advice_blinds.clone(),
+                };
+                Ok(Stashed::new(advice, &mut *store)?)
             },
-        )
-        .collect();
+        ))?;
 
-    // Evaluate the h(X) polynomial
-    let h_poly = pk.ev.evaluate_h(
-        pk,
-        &advice
-            .iter()
-            .map(|a| a.advice_polys.as_slice())
-            .collect::<Vec<_>>(),
-        &instance
-            .iter()
-            .map(|i| i.instance_polys.as_slice())
-            .collect::<Vec<_>>(),
-        &challenges,
-        *y,
-        *beta,
-        *gamma,
-        *theta,
-        &lookups,
-        &permutations,
-    );
+    // Evaluate the h(X) polynomial, one proof at a time
+    let mut h_poly = domain.empty_extended();
+    for ((((instance, advice), permutation), lookups), shuffles) in instance
+        .iter()
+        .zip(advice.iter_mut())
+        .zip(permutations.iter_mut())
+        .zip(lookups.iter_mut())
+        .zip(shuffles.iter_mut())
+    {
+        advice.restore(&mut *store)?;
+        permutation.restore(&mut *store)?;
+        lookups.restore(&mut *store)?;
+        shuffles.restore(&mut *store)?;
+        pk.ev.evaluate_h(
+            pk,
+            &mut h_poly,
+            &advice.advice_polys,
+            &instance.instance_polys,
+            &challenges,
+            *y,
+            *beta,
+            *gamma,
+            *theta,
+            lookups,
+            shuffles,
+            permutation,
+        );
+        advice.stash(&mut *store)?;
+        permutation.stash(&mut *store)?;
+        lookups.stash(&mut *store)?;
+        shuffles.stash(&mut *store)?;
+    }
 
     // Construct the vanishing argument's h(X) commitments
     let vanishing = vanishing.construct(params, domain, h_poly, &mut rng, transcript)?;
@@ -518,7 +1519,18 @@ pub fn create_proof<
     let x: ChallengeX<_> = transcript.squeeze_challenge_scalar();
     let xn = x.pow(&[params.n() as u64, 0, 0, 0]);
 
-    if P::QUERY_INSTANCE {
+    tracker.start(ProverStage::Evaluations)?;
+
+    // The openings need the polynomials of all proofs at once
+    let advice = advice.try_map(|advice| advice.into_inner(&mut *store))?;
+    let permutations = permutations
+        .into_iter()
+        .map(|permutation| permutation.into_inner(&mut *store))
+        .collect::<Result<Vec<_>, _>>()?;
+    let lookups = lookups.try_map(|lookups| lookups.into_inner(&mut *store))?;
+    let shuffles = shuffles.try_map(|shuffles| shuffles.into_inner(&mut *store))?;
+
+    if query_instance {
         // Compute and hash instance evals for each circuit instance
         for instance in instance.iter() {
             // Evaluate polynomials at omega^i x
@@ -586,25 +1598,31 @@ pub fn create_proof<
         .collect::<Result<Vec<_>, _>>()?;
 
     // Evaluate the lookups, if any, at omega^i x.
-    let lookups: Vec<Vec<lookup::prover::Evaluated<Scheme::Curve>>> = lookups
-        .into_iter()
-        .map(|lookups| -> Result<Vec<_>, _> {
-            lookups
-                .into_iter()
-                .map(|p| p.evaluate(pk, x, transcript))
-                .collect::<Result<Vec<_>, _>>()
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+    let lookups: Zeroizing<Vec<Vec<lookup::prover::Evaluated<Scheme::Curve>>>> =
+        lookups.try_map(|lookups| -> Result<Vec<_>, _> {
+            Zeroizing::new(lookups)
+                .try_map(|p| p.evaluate(pk, x, transcript))
+                .map(Zeroizing::into_inner)
+        })?;
+
+    // Evaluate the shuffles, if any, at omega^i x.
+    let shuffles: Zeroizing<Vec<Vec<shuffle::prover::Evaluated<Scheme::Curve>>>> = shuffles
+        .try_map(|shuffles| -> Result<Vec<_>, _> {
+            Zeroizing::new(shuffles)
+                .try_map(|p| p.evaluate(pk, x, transcript))
+                .map(Zeroizing::into_inner)
+        })?;
 
     let instances = instance
         .iter()
         .zip(advice.iter())
         .zip(permutations.iter())
         .zip(lookups.iter())
-        .flat_map(|(((instance, advice), permutation), lookups)| {
+        .zip(shuffles.iter())
+        .flat_map(|((((instance, advice), permutation), lookups), shuffles)| {
             iter::empty()
                 .chain(
-                    P::QUERY_INSTANCE
+                    query_instance
                         .then_some(pk.vk.cs.instance_queries.iter().map(move |&(column, at)| {
                             ProverQuery {
                                 point: domain.rotate_omega(*x, at),
@@ -628,6 +1646,7 @@ pub fn create_proof<
                 )
                 .chain(permutation.open(pk, x))
                 .chain(lookups.iter().flat_map(move |p| p.open(pk, x)).into_iter())
+                .chain(shuffles.iter().flat_map(move |p| p.open(pk, x)).into_iter())
         })
         .chain(
             pk.vk
@@ -644,8 +1663,9 @@ pub fn create_proof<
         // We query the h(X) polynomial at x
         .chain(vanishing.open(x));
 
-    let prover = P::new(params);
-    prover
-        .create_proof(rng, transcript, instances)
-        .map_err(|_| Error::ConstraintSystemFailure)
+    tracker.start(ProverStage::Opening)?;
+    prover.create_proof(rng, transcript, instances)?;
+    tracker.finish();
+
+    Ok(())
 }
@@ -0,0 +1,161 @@
+use std::fmt;
+
+use rand_core::RngCore;
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+
+use super::{create_proof_inner, synthesize_advice, ProverOptions};
+use crate::{
+    plonk::{Circuit, ConstraintSystem, Error, ProvingKey},
+    poly::commitment::{CommitmentScheme, Prover},
+    transcript::{EncodedChallenge, TranscriptWrite, TranscriptWriterBuffer},
+};
+
+/// A convenience wrapper to create many proofs of the same circuit with the
+/// same proving key in parallel.
+///
+/// The circuit is configured once, when the prover is constructed, and
+/// [`ParallelProver::create_proofs`] forks each proof from a transcript that
+/// has absorbed the verification key once. This is all it saves: every proof
+/// is otherwise created as by [`create_proof`](crate::plonk::create_proof),
+/// with its own query plan, multiopen argument and evaluation buffers. The
+/// fixed columns and the permutation are precomputed in the proving key, so
+/// the proofs share them as any proofs with the same proving key do.
+pub struct ParallelProver<
+    'a,
+    'params,
+    Scheme: CommitmentScheme,
+    ConcreteCircuit: Circuit<Scheme::Scalar>,
+> {
+    params: &'params Scheme::ParamsProver,
+    pk: &'a ProvingKey<Scheme::Curve>,
+    config: ConcreteCircuit::Config,
+}
+
+impl<'a, 'params, Scheme: CommitmentScheme, ConcreteCircuit: Circuit<Scheme::Scalar>> fmt::Debug
+    for ParallelProver<'a, 'params, Scheme, ConcreteCircuit>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParallelProver").finish()
+    }
+}
+
+impl<'a, 'params, Scheme: CommitmentScheme, ConcreteCircuit: Circuit<Scheme::Scalar>>
+    ParallelProver<'a, 'params, Scheme, ConcreteCircuit>
+{
+    /// Constructs a prover of proofs with the public parameters `params` and
+    /// the proving key `pk` of `ConcreteCircuit`.
+    pub fn new(params: &'params Scheme::ParamsProver, pk: &'a ProvingKey<Scheme::Curve>) -> Self {
+        let mut meta = ConstraintSystem::default();
+        let config = ConcreteCircuit::configure(&mut meta);
+
+        ParallelProver { params, pk, config }
+    }
+
+    /// Creates a proof for `circuit` with the instance columns `instances`,
+    /// like [`create_proof`](crate::plonk::create_proof) for a single circuit.
+    pub fn create_proof<
+        P: Prover<'params, Scheme>,
+        E: EncodedChallenge<Scheme::Curve>,
+        R: RngCore,
+        T: TranscriptWrite<Scheme::Curve, E>,
+    >(
+        &self,
+        circuit: &ConcreteCircuit,
+        instances: &[&[Scheme::Scalar]],
+        rng: R,
+        transcript: &mut T,
+    ) -> Result<(), Error> {
+        create_proof_inner(
+            self.params,
+            P::new(self.params),
+            self.pk,
+            &[instances],
+            |_, phase, column_indices, challenges| {
+                synthesize_advice(
+                    &self.pk.vk,
+                    &self.config,
+                    circuit,
+                    instances,
+                    phase,
+                    column_indices,
+                    challenges,
+                )
+            },
+            rng,
+            transcript,
+            ProverOptions::new(),
+        )
+    }
+
+    /// Creates a proof for each of `circuits`, with the instance columns of
+    /// the same index in `instances`, in parallel, and returns the proofs in
+    /// order.
+    ///
+    /// `transcript` is the fresh transcript each proof starts from, e.g. a
+    /// [`Blake2bWrite`](crate::transcript::Blake2bWrite) created with the
+    /// [`Blake2bConfig`](crate::transcript::Blake2bConfig) of the
+    /// application. It absorbs the verification key once, and each proof is
+    /// written to a [fork](TranscriptWriterBuffer::fork) of it; anything
+    /// `transcript` wrote before is not copied into the proofs. The proofs
+    /// verify like ones from [`create_proof`](crate::plonk::create_proof)
+    /// with the same kind of transcript.
+    ///
+    /// `rngs(i)` returns the RNG of the `i`-th proof. Each proof has an RNG of
+    /// its own, rather than sharing one between the threads, so an RNG that
+    /// clones its state stays safe to use, as long as `rngs` returns
+    /// differently seeded ones.
+    pub fn create_proofs<P, E, R, G, T>(
+        &self,
+        circuits: &[ConcreteCircuit],
+        instances: &[&[&[Scheme::Scalar]]],
+        rngs: G,
+        mut transcript: T,
+    ) -> Result<Vec<Vec<u8>>, Error>
+    where
+        P: Prover<'params, Scheme>,
+        E: EncodedChallenge<Scheme::Curve>,
+        R: RngCore,
+        G: Fn(usize) -> R + Sync,
+        T: TranscriptWriterBuffer<Vec<u8>, Scheme::Curve, E> + Sync,
+        ConcreteCircuit: Sync,
+        ConcreteCircuit::Config: Sync,
+        Scheme::ParamsProver: Sync,
+        ProvingKey<Scheme::Curve>: Sync,
+    {
+        if circuits.len() != instances.len() {
+            return Err(Error::InvalidInstances);
+        }
+
+        self.pk.vk.hash_into(&mut transcript)?;
+
+        circuits
+            .into_par_iter()
+            .zip(instances.into_par_iter())
+            .enumerate()
+            .map(|(index, (circuit, instances))| {
+                let mut transcript = transcript.fork(vec![]);
+                create_proof_inner(
+                    self.params,
+                    P::new(self.params),
+                    self.pk,
+                    &[*instances],
+                    |_, phase, column_indices, challenges| {
+                        synthesize_advice(
+                            &self.pk.vk,
+                            &self.config,
+                            circuit,
+                            instances,
+                            phase,
+                            column_indices,
+                            challenges,
+                        )
+                    },
+                    rngs(index),
+                    &mut transcript,
+                    ProverOptions::new().with_vk_hashed(),
+                )?;
+                Ok(transcript.finalize())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,223 @@
+use super::super::{
+    circuit::Expression, ChallengeGamma, ChallengeTheta, ChallengeX, Error, ProvingKey,
+};
+use super::Argument;
+use crate::plonk::evaluation::evaluate;
+use crate::{
+    arithmetic::{batch_invert, eval_polynomial, parallelize, CurveAffine, FieldExt},
+    poly::{
+        commitment::{Blind, Params},
+        Coeff, EvaluationDomain, LagrangeCoeff, Polynomial, ProverQuery, Rotation, Wipe, Zeroizing,
+    },
+    transcript::{EncodedChallenge, TranscriptWrite},
+};
+use group::{ff::Field, Curve};
+use rand_core::RngCore;
+use std::{
+    iter,
+    ops::{Mul, MulAssign},
+};
+
+#[derive(Debug)]
+pub(in crate::plonk) struct Committed<C: CurveAffine> {
+    pub(in crate::plonk) product_poly: Polynomial<C::Scalar, Coeff>,
+    product_blind: Blind<C::Scalar>,
+}
+
+pub(in crate::plonk) struct Evaluated<C: CurveAffine> {
+    constructed: Committed<C>,
+}
+
+impl<F: FieldExt> Argument<F> {
+    /// Given a Shuffle with input expressions [A_0, A_1, ..., A_{m-1}] and shuffle expressions
+    /// [S_0, S_1, ..., S_{m-1}], this method
+    /// - constructs A_compressed = \theta^{m-1} A_0 + theta^{m-2} A_1 + ... + \theta A_{m-2} + A_{m-1}
+    ///   and S_compressed = \theta^{m-1} S_0 + theta^{m-2} S_1 + ... + \theta S_{m-2} + S_{m-1},
+    /// - constructs the grand product polynomial over the shuffle, whose
+    ///   value on each row is the product over the previous rows of
+    ///   (A_compressed + \gamma) / (S_compressed + \gamma).
+    ///
+    /// The product is back at one on the last usable row if and only if the
+    /// rows of S_compressed are a permutation of those of A_compressed (with
+    /// high probability over \gamma); otherwise this method returns
+    /// [`Error::ConstraintSystemFailure`].
+    pub(in crate::plonk) fn commit_product<
+        'a,
+        'params: 'a,
+        C,
+        P: Params<'params, C>,
+        E: EncodedChallenge<C>,
+        R: RngCore,
+        T: TranscriptWrite<C, E>,
+    >(
+        &self,
+        pk: &ProvingKey<C>,
+        params: &P,
+        domain: &EvaluationDomain<C::Scalar>,
+        theta: ChallengeTheta<C>,
+        gamma: ChallengeGamma<C>,
+        advice_values: &'a [Polynomial<C::Scalar, LagrangeCoeff>],
+        fixed_values: &'a [Polynomial<C::Scalar, LagrangeCoeff>],
+        instance_values: &'a [Polynomial<C::Scalar, LagrangeCoeff>],
+        challenges: &'a [C::Scalar],
+        mut rng: R,
+        transcript: &mut T,
+    ) -> Result<Committed<C>, Error>
+    where
+        C: CurveAffine<ScalarExt = F>,
+        C::Curve: Mul<F, Output = C::Curve> + MulAssign<F>,
+    {
+        // Closure to get values of expressions and compress them
+        let compress_expressions = |expressions: &[Expression<C::Scalar>]| {
+            expressions
+                .iter()
+                .map(|expression| {
+                    pk.vk.domain.lagrange_from_vec(evaluate(
+                        expression,
+                        params.n() as usize,
+                        1,
+                        fixed_values,
+                        advice_values,
+                        instance_values,
+                        challenges,
+                    ))
+                })
+                .fold(domain.empty_lagrange(), |mut acc, expression| {
+                    acc *= *theta;
+                    acc += &expression;
+                    acc
+                })
+        };
+
+        // Get values of input expressions involved in the shuffle and compress them
+        let compressed_input_expression =
+            Zeroizing::new(compress_expressions(&self.input_expressions));
+
+        // Get values of shuffle expressions involved in the shuffle and compress them
+        let compressed_shuffle_expression =
+            Zeroizing::new(compress_expressions(&self.shuffle_expressions));
+
+        let blinding_factors = pk.vk.cs.blinding_factors();
+        let usable_rows = params.n() as usize - (blinding_factors + 1);
+
+        // Denominator: (\theta^{m-1} s_0(\omega^i) + ... + s_{m-1}(\omega^i) + \gamma)
+        let mut shuffle_product = vec![C::Scalar::zero(); params.n() as usize];
+        parallelize(&mut shuffle_product, |shuffle_product, start| {
+            for (shuffle_product, shuffle_value) in shuffle_product
+                .iter_mut()
+                .zip(compressed_shuffle_expression[start..].iter())
+            {
+                *shuffle_product = *gamma + shuffle_value;
+            }
+        });
+
+        batch_invert(&mut shuffle_product);
+
+        // Numerator: (\theta^{m-1} a_0(\omega^i) + ... + a_{m-1}(\omega^i) + \gamma)
+        parallelize(&mut shuffle_product, |product, start| {
+            for (i, product) in product.iter_mut().enumerate() {
+                let i = i + start;
+                *product *= &(*gamma + compressed_input_expression[i]);
+            }
+        });
+
+        // Compute the evaluations of the shuffle product polynomial
+        // over our domain, starting with z[0] = 1
+        let z = iter::once(C::Scalar::one())
+            .chain(shuffle_product)
+            .scan(C::Scalar::one(), |state, cur| {
+                *state *= &cur;
+                Some(*state)
+            })
+            // Take all rows including the "last" row which should be one
+            .take(params.n() as usize - blinding_factors)
+            // Chain random blinding factors.
+            .chain((0..blinding_factors).map(|_| C::Scalar::random(&mut rng)))
+            .collect::<Vec<_>>();
+        assert_eq!(z.len(), params.n() as usize);
+        let z = Zeroizing::new(pk.vk.domain.lagrange_from_vec(z));
+
+        // l_last(X) * (z(X)^2 - z(X)) = 0
+        // The product is not back at one when the inputs are not a
+        // permutation of the shuffled values.
+        if z[usable_rows] != C::Scalar::one() {
+            return Err(Error::ConstraintSystemFailure);
+        }
+
+        let product_blind = Blind(C::Scalar::random(rng));
+        let product_commitment = params.commit_lagrange(&z, product_blind).to_affine();
+        let z = Zeroizing::new(pk.vk.domain.lagrange_to_coeff(z.into_inner()));
+
+        // Hash product commitment
+        transcript.write_point(product_commitment)?;
+
+        Ok(Committed {
+            product_poly: z.into_inner(),
+            product_blind,
+        })
+    }
+}
+
+impl<C: CurveAffine> Committed<C> {
+    pub(in crate::plonk) fn evaluate<E: EncodedChallenge<C>, T: TranscriptWrite<C, E>>(
+        self,
+        pk: &ProvingKey<C>,
+        x: ChallengeX<C>,
+        transcript: &mut T,
+    ) -> Result<Evaluated<C>, Error> {
+        let domain = &pk.vk.domain;
+        let x_next = domain.rotate_omega(*x, Rotation::next());
+
+        let product_eval = eval_polynomial(&self.product_poly, *x);
+        let product_next_eval = eval_polynomial(&self.product_poly, x_next);
+
+        // Hash each shuffle product evaluation
+        for eval in iter::empty()
+            .chain(Some(product_eval))
+            .chain(Some(product_next_eval))
+        {
+            transcript.write_scalar(eval)?;
+        }
+
+        Ok(Evaluated { constructed: self })
+    }
+}
+
+impl<C: CurveAffine> Wipe for Committed<C> {
+    #[cfg(feature = "zeroize")]
+    fn wipe(&mut self) {
+        self.product_poly.wipe();
+        self.product_blind.wipe();
+    }
+}
+
+impl<C: CurveAffine> Wipe for Evaluated<C> {
+    #[cfg(feature = "zeroize")]
+    fn wipe(&mut self) {
+        self.constructed.wipe();
+    }
+}
+
+impl<C: CurveAffine> Evaluated<C> {
+    pub(in crate::plonk) fn open<'a>(
+        &'a self,
+        pk: &'a ProvingKey<C>,
+        x: ChallengeX<C>,
+    ) -> impl Iterator<Item = ProverQuery<'a, C>> + Clone {
+        let x_next = pk.vk.domain.rotate_omega(*x, Rotation::next());
+
+        iter::empty()
+            // Open shuffle product commitments at x
+            .chain(Some(ProverQuery {
+                point: *x,
+                poly: &self.constructed.product_poly,
+                blind: self.constructed.product_blind,
+            }))
+            // Open shuffle product commitments at x_next
+            .chain(Some(ProverQuery {
+                point: x_next,
+                poly: &self.constructed.product_poly,
+                blind: self.constructed.product_blind,
+            }))
+    }
+}
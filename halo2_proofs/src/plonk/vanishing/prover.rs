@@ -132,7 +132,11 @@ impl<C: CurveAffine> Constructed<C> {
             .h_pieces
             .iter()
             .rev()
-            .fold(domain.empty_coeff(), |acc, eval| acc * xn + eval);
+            .fold(domain.empty_coeff(), |mut acc, eval| {
+                acc *= xn;
+                acc += eval;
+                acc
+            });
 
         let h_blind = self
             .h_blinds
@@ -3,18 +3,22 @@ use group::Curve;
 use rand_core::RngCore;
 use std::iter;
 
+use super::proof::permutation_products;
 use super::{
     vanishing, ChallengeBeta, ChallengeGamma, ChallengeTheta, ChallengeX, ChallengeY, Error,
-    VerifyingKey,
+    LookupArgument, VerifyingKey,
 };
 use crate::arithmetic::{compute_inner_product, CurveAffine, FieldExt};
 use crate::poly::commitment::{CommitmentScheme, Verifier};
 use crate::poly::VerificationStrategy;
 use crate::poly::{
     commitment::{Blind, Params, MSM},
-    Guard, VerifierQuery,
+    Guard, Rotation, VerifierQuery,
+};
+use crate::transcript::{
+    common_instance_commitment, instance_hash, read_n_points, read_n_scalars, Digest,
+    EncodedChallenge, TranscriptRead,
 };
-use crate::transcript::{read_n_points, read_n_scalars, EncodedChallenge, TranscriptRead};
 
 #[cfg(feature = "batch")]
 mod batch;
@@ -38,34 +42,306 @@ pub fn verify_proof<
     instances: &[&[&[Scheme::Scalar]]],
     transcript: &mut T,
 ) -> Result<Strategy::Output, Error> {
-    // Check that instances matches the expected number of instance columns
-    for instances in instances.iter() {
-        if instances.len() != vk.cs.num_instance_columns {
-            return Err(Error::InvalidInstances);
+    verify_proof_with_verifier(params, V::new(params), vk, strategy, instances, transcript)
+}
+
+/// Same as [`verify_proof`], for proofs made with
+/// [`create_proof_with_domain_tag`](super::create_proof_with_domain_tag).
+/// Fails with [`Error::Transcript`] before anything else is read if the proof
+/// was made for a tag other than `domain_tag`.
+pub fn verify_proof_with_domain_tag<
+    'params,
+    Scheme: CommitmentScheme,
+    V: Verifier<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    T: TranscriptRead<Scheme::Curve, E>,
+    Strategy: VerificationStrategy<'params, Scheme, V>,
+>(
+    params: &'params Scheme::ParamsVerifier,
+    vk: &VerifyingKey<Scheme::Curve>,
+    domain_tag: &[u8],
+    strategy: Strategy,
+    instances: &[&[&[Scheme::Scalar]]],
+    transcript: &mut T,
+) -> Result<Strategy::Output, Error> {
+    transcript.read_domain_tag(domain_tag)?;
+    verify_proof::<Scheme, V, E, T, Strategy>(params, vk, strategy, instances, transcript)
+}
+
+/// Same as [`verify_proof`], but opens the commitments with the given
+/// multiopen `verifier` rather than one created with `V::new(params)`, e.g. a
+/// SHPLONK verifier with a [`VerifierQueryPlan`] shared across proofs.
+///
+/// [`VerifierQueryPlan`]: crate::poly::kzg::multiopen::VerifierQueryPlan
+pub fn verify_proof_with_verifier<
+    'params,
+    Scheme: CommitmentScheme,
+    V: Verifier<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    T: TranscriptRead<Scheme::Curve, E>,
+    Strategy: VerificationStrategy<'params, Scheme, V>,
+>(
+    params: &'params Scheme::ParamsVerifier,
+    verifier: V,
+    vk: &VerifyingKey<Scheme::Curve>,
+    strategy: Strategy,
+    instances: &[&[&[Scheme::Scalar]]],
+    transcript: &mut T,
+) -> Result<Strategy::Output, Error> {
+    verify_proof_inner(
+        params,
+        verifier,
+        vk,
+        strategy,
+        Instances::Values(instances),
+        None,
+        transcript,
+    )
+}
+
+/// Same as [`verify_proof`], for proofs made with
+/// [`ProverOptions::with_instance_hash`](super::ProverOptions::with_instance_hash)
+/// and the same `D`.
+pub fn verify_proof_with_instance_hash<
+    'params,
+    Scheme: CommitmentScheme,
+    V: Verifier<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    T: TranscriptRead<Scheme::Curve, E>,
+    Strategy: VerificationStrategy<'params, Scheme, V>,
+    D: Digest + Clone,
+>(
+    params: &'params Scheme::ParamsVerifier,
+    vk: &VerifyingKey<Scheme::Curve>,
+    strategy: Strategy,
+    instances: &[&[&[Scheme::Scalar]]],
+    transcript: &mut T,
+) -> Result<Strategy::Output, Error> {
+    verify_proof_inner(
+        params,
+        V::new(params),
+        vk,
+        strategy,
+        Instances::Values(instances),
+        Some(instance_hash::<Scheme::Scalar, D>),
+        transcript,
+    )
+}
+
+/// Verifies a proof made with
+/// [`ProverOptions::with_committed_instances`](super::ProverOptions::with_committed_instances),
+/// given the commitments to the instance columns of each proof, as returned
+/// by [`commit_instances`], instead of their values. The verifier never needs
+/// the public inputs themselves: whoever provides the commitments, e.g. a
+/// contract storing them, vouches for them.
+pub fn verify_proof_with_instance_commitments<
+    'params,
+    Scheme: CommitmentScheme,
+    V: Verifier<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    T: TranscriptRead<Scheme::Curve, E>,
+    Strategy: VerificationStrategy<'params, Scheme, V>,
+>(
+    params: &'params Scheme::ParamsVerifier,
+    vk: &VerifyingKey<Scheme::Curve>,
+    strategy: Strategy,
+    instance_commitments: &[&[Scheme::Curve]],
+    transcript: &mut T,
+) -> Result<Strategy::Output, Error> {
+    verify_proof_inner(
+        params,
+        V::new(params),
+        vk,
+        strategy,
+        Instances::Committed(instance_commitments),
+        None,
+        transcript,
+    )
+}
+
+/// Returns the commitments to the instance columns `instance` of one proof,
+/// as the prover computes them for proofs that open the instance columns.
+/// See [`verify_proof_with_instance_commitments`].
+pub fn commit_instances<'params, C: CurveAffine, P: Params<'params, C>>(
+    params: &P,
+    vk: &VerifyingKey<C>,
+    instance: &[&[C::Scalar]],
+) -> Result<Vec<C>, Error> {
+    if instance.len() != vk.cs.num_instance_columns {
+        return Err(Error::InvalidInstances);
+    }
+    if !params.can_commit() {
+        return Err(Error::VerifierOnlyParams);
+    }
+    instance
+        .iter()
+        .map(|instance| {
+            if instance.len() > params.n() as usize - (vk.cs.blinding_factors() + 1) {
+                return Err(Error::InstanceTooLarge);
+            }
+            let mut poly = instance.to_vec();
+            poly.resize(params.n() as usize, C::Scalar::zero());
+            let poly = vk.domain.lagrange_from_vec(poly);
+
+            Ok(params.commit_lagrange(&poly, Blind::default()).to_affine())
+        })
+        .collect()
+}
+
+impl<C: CurveAffine> VerifyingKey<C> {
+    /// Returns the commitment and the rotation of each query that the
+    /// verifier opens for a proof of `num_proofs` circuits, in the order
+    /// [`verify_proof`] makes them, with the commitments numbered so that two
+    /// queries have the same number exactly when they open the same
+    /// commitment. `query_instance` is whether the proof opens the instance
+    /// columns (see [`ProofLayout::query_instance`](super::ProofLayout::query_instance)).
+    ///
+    /// This is the structure a [`VerifierQueryPlan`] is built from.
+    ///
+    /// [`VerifierQueryPlan`]: crate::poly::kzg::multiopen::VerifierQueryPlan
+    pub fn opening_queries(
+        &self,
+        num_proofs: usize,
+        query_instance: bool,
+    ) -> Vec<(usize, Rotation)> {
+        let cs = &self.cs;
+        let num_sets = permutation_products(self);
+        let last = Rotation(-((cs.blinding_factors() + 1) as i32));
+        let num_lookups = cs.lookup_batches().len();
+
+        let mut queries = vec![];
+        let mut num_commitments = 0;
+        let mut commitments = |n: usize| {
+            num_commitments += n;
+            num_commitments - n
+        };
+        for _ in 0..num_proofs {
+            if query_instance {
+                let instance = commitments(cs.num_instance_columns);
+                queries.extend(
+                    cs.instance_queries
+                        .iter()
+                        .map(|(column, at)| (instance + column.index(), *at)),
+                );
+            }
+            let advice = commitments(cs.num_advice_columns);
+            queries.extend(
+                cs.advice_queries
+                    .iter()
+                    .map(|(column, at)| (advice + column.index(), *at)),
+            );
+
+            let products = commitments(num_sets);
+            for set in products..products + num_sets {
+                queries.push((set, Rotation::cur()));
+                queries.push((set, Rotation::next()));
+            }
+            for set in (products..products + num_sets).rev().skip(1) {
+                queries.push((set, last));
+            }
+
+            for _ in 0..num_lookups {
+                let product = commitments(1);
+                queries.push((product, Rotation::cur()));
+                match cs.lookup_argument {
+                    LookupArgument::Permutation => {
+                        let input = commitments(2);
+                        queries.push((input, Rotation::cur()));
+                        queries.push((input + 1, Rotation::cur()));
+                        queries.push((input, Rotation::prev()));
+                    }
+                    LookupArgument::LogDerivative => {
+                        queries.push((commitments(1), Rotation::cur()));
+                    }
+                }
+                queries.push((product, Rotation::next()));
+            }
+
+            for _ in 0..cs.shuffles.len() {
+                let product = commitments(1);
+                queries.push((product, Rotation::cur()));
+                queries.push((product, Rotation::next()));
+            }
         }
+
+        let fixed = commitments(self.fixed_commitments.len());
+        queries.extend(
+            cs.fixed_queries
+                .iter()
+                .map(|(column, at)| (fixed + column.index(), *at)),
+        );
+        let common = commitments(self.permutation.commitments().len());
+        queries.extend(
+            (common..common + self.permutation.commitments().len())
+                .map(|commitment| (commitment, Rotation::cur())),
+        );
+
+        // The quotient pieces are opened together, then the random polynomial
+        let vanishing = commitments(2);
+        queries.push((vanishing, Rotation::cur()));
+        queries.push((vanishing + 1, Rotation::cur()));
+
+        queries
     }
+}
 
-    let instance_commitments = if V::QUERY_INSTANCE {
-        instances
-            .iter()
-            .map(|instance| {
-                instance
+/// The public inputs of the proofs being verified
+enum Instances<'a, C: CurveAffine> {
+    /// The values of the instance columns of each proof
+    Values(&'a [&'a [&'a [C::Scalar]]]),
+    /// The commitments to the instance columns of each proof
+    Committed(&'a [&'a [C]]),
+}
+
+fn verify_proof_inner<
+    'params,
+    Scheme: CommitmentScheme,
+    V: Verifier<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    T: TranscriptRead<Scheme::Curve, E>,
+    Strategy: VerificationStrategy<'params, Scheme, V>,
+>(
+    params: &'params Scheme::ParamsVerifier,
+    verifier: V,
+    vk: &VerifyingKey<Scheme::Curve>,
+    strategy: Strategy,
+    instances: Instances<'_, Scheme::Curve>,
+    instance_hasher: Option<fn(&[&[Scheme::Scalar]]) -> Scheme::Scalar>,
+    transcript: &mut T,
+) -> Result<Strategy::Output, Error> {
+    let query_instance = V::QUERY_INSTANCE || matches!(instances, Instances::Committed(_));
+    let (instances, instance_commitments) = match instances {
+        Instances::Values(instances) => {
+            // Check that instances matches the expected number of instance columns
+            for instances in instances.iter() {
+                if instances.len() != vk.cs.num_instance_columns {
+                    return Err(Error::InvalidInstances);
+                }
+            }
+
+            let instance_commitments = if query_instance {
+                instances
                     .iter()
-                    .map(|instance| {
-                        if instance.len() > params.n() as usize - (vk.cs.blinding_factors() + 1) {
-                            return Err(Error::InstanceTooLarge);
-                        }
-                        let mut poly = instance.to_vec();
-                        poly.resize(params.n() as usize, Scheme::Scalar::zero());
-                        let poly = vk.domain.lagrange_from_vec(poly);
-
-                        Ok(params.commit_lagrange(&poly, Blind::default()).to_affine())
-                    })
-                    .collect::<Result<Vec<_>, _>>()
-            })
-            .collect::<Result<Vec<_>, _>>()?
-    } else {
-        vec![vec![]; instances.len()]
+                    .map(|instance| commit_instances(params, vk, instance))
+                    .collect::<Result<Vec<_>, _>>()?
+            } else {
+                vec![vec![]; instances.len()]
+            };
+            (instances, instance_commitments)
+        }
+        Instances::Committed(instance_commitments) => {
+            for commitments in instance_commitments.iter() {
+                if commitments.len() != vk.cs.num_instance_columns {
+                    return Err(Error::InvalidInstances);
+                }
+            }
+            let instance_commitments = instance_commitments
+                .iter()
+                .map(|commitments| commitments.to_vec())
+                .collect::<Vec<_>>();
+            // The values are not needed when the instance columns are queried
+            (&[][..], instance_commitments)
+        }
     };
 
     let num_proofs = instance_commitments.len();
@@ -73,13 +349,27 @@ pub fn verify_proof<
     // Hash verification key into transcript
     vk.hash_into(transcript)?;
 
-    if V::QUERY_INSTANCE {
+    // The proof starts with the fingerprint of the circuit it was created for
+    if transcript.read_scalar()? != vk.fingerprint {
+        return Err(Error::CircuitMismatch);
+    }
+    if let Some(id) = verifier.scheme_id() {
+        if transcript.read_scalar()? != Scheme::Scalar::from(id as u64) {
+            return Err(Error::MultiopenMismatch);
+        }
+    }
+
+    if query_instance {
         for instance_commitments in instance_commitments.iter() {
             // Hash the instance (external) commitments into the transcript
             for commitment in instance_commitments {
-                transcript.common_point(*commitment)?
+                common_instance_commitment(transcript, *commitment)?
             }
         }
+    } else if let Some(hasher) = instance_hasher {
+        for instance in instances.iter() {
+            transcript.common_scalar(hasher(instance))?;
+        }
     } else {
         for instance in instances.iter() {
             for instance in instance.iter() {
@@ -122,13 +412,13 @@ pub fn verify_proof<
     // Sample theta challenge for keeping lookup columns linearly independent
     let theta: ChallengeTheta<_> = transcript.squeeze_challenge_scalar();
 
+    let lookup_batches = vk.cs.lookup_batches();
     let lookups_permuted = (0..num_proofs)
         .map(|_| -> Result<Vec<_>, _> {
             // Hash each lookup permuted commitment
-            vk.cs
-                .lookups
+            lookup_batches
                 .iter()
-                .map(|argument| argument.read_permuted_commitments(transcript))
+                .map(|batch| batch[0].read_permuted_commitments(vk, transcript))
                 .collect::<Result<Vec<_>, _>>()
         })
         .collect::<Result<Vec<_>, _>>()?;
@@ -157,6 +447,17 @@ pub fn verify_proof<
         })
         .collect::<Result<Vec<_>, _>>()?;
 
+    let shuffles_committed = (0..num_proofs)
+        .map(|_| -> Result<Vec<_>, _> {
+            // Hash each shuffle product commitment
+            vk.cs
+                .shuffles
+                .iter()
+                .map(|argument| argument.read_product_commitment(transcript))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
     let vanishing = vanishing::Argument::read_commitments_before_y(transcript)?;
 
     // Sample y challenge, which keeps the gates linearly independent.
@@ -167,7 +468,7 @@ pub fn verify_proof<
     // Sample x challenge, which is used to ensure the circuit is
     // satisfied with high probability.
     let x: ChallengeX<_> = transcript.squeeze_challenge_scalar();
-    let instance_evals = if V::QUERY_INSTANCE {
+    let instance_evals = if query_instance {
         (0..num_proofs)
             .map(|_| -> Result<Vec<_>, _> {
                 read_n_scalars(transcript, vk.cs.instance_queries.len())
@@ -239,6 +540,16 @@ pub fn verify_proof<
         })
         .collect::<Result<Vec<_>, _>>()?;
 
+    let shuffles_evaluated = shuffles_committed
+        .into_iter()
+        .map(|shuffles| -> Result<Vec<_>, _> {
+            shuffles
+                .into_iter()
+                .map(|shuffle| shuffle.evaluate(transcript))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
     // This check ensures the circuit is satisfied so long as the polynomial
     // commitments open to the correct values.
     let vanishing = {
@@ -262,63 +573,88 @@ pub fn verify_proof<
             .zip(instance_evals.iter())
             .zip(permutations_evaluated.iter())
             .zip(lookups_evaluated.iter())
-            .flat_map(|(((advice_evals, instance_evals), permutation), lookups)| {
-                let challenges = &challenges;
-                let fixed_evals = &fixed_evals;
-                std::iter::empty()
-                    // Evaluate the circuit using the custom gates provided
-                    .chain(vk.cs.gates.iter().flat_map(move |gate| {
-                        gate.polynomials().iter().map(move |poly| {
-                            poly.evaluate(
-                                &|scalar| scalar,
-                                &|_| panic!("virtual selectors are removed during optimization"),
-                                &|query| fixed_evals[query.index],
-                                &|query| advice_evals[query.index],
-                                &|query| instance_evals[query.index],
-                                &|challenge| challenges[challenge.index()],
-                                &|a| -a,
-                                &|a, b| a + &b,
-                                &|a, b| a * &b,
-                                &|a, scalar| a * &scalar,
-                            )
-                        })
-                    }))
-                    .chain(permutation.expressions(
-                        vk,
-                        &vk.cs.permutation,
-                        &permutations_common,
-                        advice_evals,
-                        fixed_evals,
-                        instance_evals,
-                        l_0,
-                        l_last,
-                        l_blind,
-                        beta,
-                        gamma,
-                        x,
-                    ))
-                    .chain(
-                        lookups
-                            .iter()
-                            .zip(vk.cs.lookups.iter())
-                            .flat_map(move |(p, argument)| {
-                                p.expressions(
-                                    l_0,
-                                    l_last,
-                                    l_blind,
-                                    argument,
-                                    theta,
-                                    beta,
-                                    gamma,
-                                    advice_evals,
-                                    fixed_evals,
-                                    instance_evals,
-                                    challenges,
+            .zip(shuffles_evaluated.iter())
+            .flat_map(
+                |((((advice_evals, instance_evals), permutation), lookups), shuffles)| {
+                    let challenges = &challenges;
+                    let fixed_evals = &fixed_evals;
+                    std::iter::empty()
+                        // Evaluate the circuit using the custom gates provided
+                        .chain(vk.cs.gates.iter().flat_map(move |gate| {
+                            gate.polynomials().iter().map(move |poly| {
+                                poly.evaluate(
+                                    &|scalar| scalar,
+                                    &|_| {
+                                        panic!("virtual selectors are removed during optimization")
+                                    },
+                                    &|query| fixed_evals[query.index],
+                                    &|query| advice_evals[query.index],
+                                    &|query| instance_evals[query.index],
+                                    &|challenge| challenges[challenge.index()],
+                                    &|a| -a,
+                                    &|a, b| a + &b,
+                                    &|a, b| a * &b,
+                                    &|a, scalar| a * &scalar,
                                 )
                             })
-                            .into_iter(),
-                    )
-            });
+                        }))
+                        .chain(permutation.expressions(
+                            vk,
+                            &vk.cs.permutation,
+                            &permutations_common,
+                            advice_evals,
+                            fixed_evals,
+                            instance_evals,
+                            l_0,
+                            l_last,
+                            l_blind,
+                            beta,
+                            gamma,
+                            x,
+                        ))
+                        .chain(
+                            lookups
+                                .iter()
+                                .zip(lookup_batches.iter())
+                                .flat_map(move |(p, batch)| {
+                                    p.expressions(
+                                        l_0,
+                                        l_last,
+                                        l_blind,
+                                        batch,
+                                        theta,
+                                        beta,
+                                        gamma,
+                                        advice_evals,
+                                        fixed_evals,
+                                        instance_evals,
+                                        challenges,
+                                    )
+                                })
+                                .into_iter(),
+                        )
+                        .chain(
+                            shuffles
+                                .iter()
+                                .zip(vk.cs.shuffles.iter())
+                                .flat_map(move |(p, argument)| {
+                                    p.expressions(
+                                        l_0,
+                                        l_last,
+                                        l_blind,
+                                        argument,
+                                        theta,
+                                        gamma,
+                                        advice_evals,
+                                        fixed_evals,
+                                        instance_evals,
+                                        challenges,
+                                    )
+                                })
+                                .into_iter(),
+                        )
+                },
+            );
 
         vanishing.verify(params, expressions, y, xn)
     };
@@ -330,17 +666,24 @@ pub fn verify_proof<
         .zip(advice_evals.iter())
         .zip(permutations_evaluated.iter())
         .zip(lookups_evaluated.iter())
+        .zip(shuffles_evaluated.iter())
         .flat_map(
             |(
                 (
-                    (((instance_commitments, instance_evals), advice_commitments), advice_evals),
-                    permutation,
+                    (
+                        (
+                            ((instance_commitments, instance_evals), advice_commitments),
+                            advice_evals,
+                        ),
+                        permutation,
+                    ),
+                    lookups,
                 ),
-                lookups,
+                shuffles,
             )| {
                 iter::empty()
                     .chain(
-                        V::QUERY_INSTANCE
+                        query_instance
                             .then_some(vk.cs.instance_queries.iter().enumerate().map(
                                 move |(query_index, &(column, at))| {
                                     VerifierQuery::new_commitment(
@@ -369,6 +712,12 @@ pub fn verify_proof<
                             .flat_map(move |p| p.queries(vk, x))
                             .into_iter(),
                     )
+                    .chain(
+                        shuffles
+                            .iter()
+                            .flat_map(move |p| p.queries(vk, x))
+                            .into_iter(),
+                    )
             },
         )
         .chain(
@@ -390,7 +739,6 @@ pub fn verify_proof<
     // We are now convinced the circuit is satisfied so long as the
     // polynomial commitments open to the correct values.
 
-    let verifier = V::new(params);
     strategy.process(|msm| {
         verifier
             .verify_proof(transcript, queries, msm)
@@ -18,7 +18,7 @@ use crate::{
             strategy::GuardIPA,
         },
     },
-    transcript::{Blake2bRead, TranscriptReadBuffer},
+    transcript::{Blake2bRead, Challenge255, TranscriptReadBuffer},
 };
 
 /// A proof verification strategy that returns the proof's MSM.
@@ -113,7 +113,7 @@ impl<C: CurveAffine> BatchVerifier<C> {
                 let instances: Vec<_> = instances.iter().map(|i| &i[..]).collect();
 
                 let strategy = BatchStrategy::new(params);
-                let mut transcript = Blake2bRead::init(&item.proof[..]);
+                let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&item.proof[..]);
                 verify_proof(params, vk, strategy, &instances, &mut transcript).map_err(|e| {
                     tracing::debug!("Batch item {} failed verification: {}", i, e);
                     e
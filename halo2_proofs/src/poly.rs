@@ -13,12 +13,17 @@ use halo2curves::FieldExt;
 use std::fmt::Debug;
 use std::io;
 use std::marker::PhantomData;
-use std::ops::{Add, Deref, DerefMut, Index, IndexMut, Mul, RangeFrom, RangeFull, Sub};
+use std::mem;
+use std::ops::{
+    Add, AddAssign, Deref, DerefMut, Index, IndexMut, Mul, MulAssign, RangeFrom, RangeFull, Sub,
+    SubAssign,
+};
 
 /// Generic commitment scheme structures
 pub mod commitment;
 mod domain;
 mod query;
+mod store;
 mod strategy;
 
 /// Inner product argument commitment scheme
@@ -32,6 +37,7 @@ mod multiopen_test;
 
 pub use domain::*;
 pub use query::{ProverQuery, VerifierQuery};
+pub use store::{PolynomialId, PolynomialStore};
 pub use strategy::{Guard, VerificationStrategy};
 
 /// This is an error that could occur during proving or circuit synthesis.
@@ -145,6 +151,150 @@ impl<F, B> Polynomial<F, B> {
     pub fn num_coeffs(&self) -> usize {
         self.values.len()
     }
+
+    /// Moves the values out, leaving the polynomial empty
+    pub(crate) fn take(&mut self) -> Self {
+        Polynomial {
+            values: mem::take(&mut self.values),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Overwrites `values` with `zero` such that the writes are not optimised
+/// away, even though `values` are not read again, so that secrets do not
+/// linger in memory after it is freed.
+#[cfg(feature = "zeroize")]
+#[allow(unsafe_code)]
+pub(crate) fn zeroize<T: Copy>(values: &mut [T], zero: T) {
+    for value in values.iter_mut() {
+        // `value` is valid for writes, and `T: Copy` has no destructor to skip
+        unsafe { std::ptr::write_volatile(value, zero) };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+#[cfg(feature = "zeroize")]
+impl<F: Copy, B> Polynomial<F, B> {
+    /// Overwrites the values with `zero` and frees them
+    pub(crate) fn zeroize_with(mut self, zero: F) {
+        zeroize(&mut self.values, zero);
+    }
+}
+
+/// Values of the witness, or derived from it, that [`Zeroizing`] overwrites
+/// with zeros when they are dropped with the `zeroize` feature
+pub(crate) trait Wipe {
+    /// Overwrites the values with zeros
+    #[cfg(feature = "zeroize")]
+    fn wipe(&mut self);
+}
+
+impl<F: Field, B> Wipe for Polynomial<F, B> {
+    #[cfg(feature = "zeroize")]
+    fn wipe(&mut self) {
+        zeroize(&mut self.values, F::zero());
+    }
+}
+
+impl<F: Field> Wipe for commitment::Blind<F> {
+    #[cfg(feature = "zeroize")]
+    fn wipe(&mut self) {
+        zeroize(std::slice::from_mut(&mut self.0), F::zero());
+    }
+}
+
+impl<T: Wipe> Wipe for Vec<T> {
+    #[cfg(feature = "zeroize")]
+    fn wipe(&mut self) {
+        for value in self.iter_mut() {
+            value.wipe();
+        }
+    }
+}
+
+impl<T: Wipe> Wipe for Option<T> {
+    #[cfg(feature = "zeroize")]
+    fn wipe(&mut self) {
+        if let Some(value) = self {
+            value.wipe();
+        }
+    }
+}
+
+/// Holds a value that is wiped when the guard is dropped with the `zeroize`
+/// feature, which happens on every way out of the prover, including errors
+/// and cancellation. Without the feature, the guard does nothing.
+pub(crate) struct Zeroizing<T: Wipe>(Option<T>);
+
+impl<T: Wipe> Zeroizing<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Zeroizing(Some(value))
+    }
+
+    /// Returns the value without wiping it, leaving it to the caller
+    pub(crate) fn into_inner(mut self) -> T {
+        self.0.take().unwrap()
+    }
+}
+
+impl<T: Wipe> Zeroizing<Vec<T>> {
+    /// Collects `values` such that those collected before an error are wiped
+    /// along with the guard
+    pub(crate) fn try_collect<E>(
+        values: impl IntoIterator<Item = Result<T, E>>,
+    ) -> Result<Self, E> {
+        let mut collected = Zeroizing::new(vec![]);
+        for value in values {
+            collected.push(value?);
+        }
+        Ok(collected)
+    }
+
+    /// Maps the values one at a time, such that each is held by a guard
+    /// except while `f` runs on it
+    pub(crate) fn try_map<U: Wipe, E>(
+        mut self,
+        mut f: impl FnMut(T) -> Result<U, E>,
+    ) -> Result<Zeroizing<Vec<U>>, E> {
+        let mut mapped = Zeroizing::new(Vec::with_capacity(self.len()));
+        self.reverse();
+        while let Some(value) = self.pop() {
+            mapped.push(f(value)?);
+        }
+        Ok(mapped)
+    }
+
+    /// Same as [`Self::try_map`] for an `f` that cannot fail
+    pub(crate) fn map<U: Wipe>(self, mut f: impl FnMut(T) -> U) -> Zeroizing<Vec<U>> {
+        match self.try_map(|value| Ok::<_, std::convert::Infallible>(f(value))) {
+            Ok(mapped) => mapped,
+            Err(never) => match never {},
+        }
+    }
+}
+
+impl<T: Wipe> Deref for Zeroizing<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0.as_ref().unwrap()
+    }
+}
+
+impl<T: Wipe> DerefMut for Zeroizing<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.0.as_mut().unwrap()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: Wipe> Drop for Zeroizing<T> {
+    fn drop(&mut self) {
+        if let Some(value) = &mut self.0 {
+            value.wipe();
+        }
+    }
 }
 
 impl<F: SerdePrimeField, B> Polynomial<F, B> {
@@ -199,13 +349,21 @@ pub(crate) fn batch_invert_assigned<F: FieldExt>(
         })
         .batch_invert();
 
-    assigned
+    let polys = assigned
         .iter()
         .zip(assigned_denominators.into_iter())
         .map(|(poly, inv_denoms)| {
             poly.invert(inv_denoms.into_iter().map(|d| d.unwrap_or_else(F::one)))
         })
-        .collect()
+        .collect();
+
+    // The assigned values of advice columns are the witness
+    #[cfg(feature = "zeroize")]
+    for poly in assigned {
+        poly.zeroize_with(Assigned::Zero);
+    }
+
+    polys
 }
 
 impl<F: Field> Polynomial<Assigned<F>, LagrangeCoeff> {
@@ -226,16 +384,45 @@ impl<F: Field> Polynomial<Assigned<F>, LagrangeCoeff> {
     }
 }
 
-impl<'a, F: Field, B: Basis> Add<&'a Polynomial<F, B>> for Polynomial<F, B> {
-    type Output = Polynomial<F, B>;
-
-    fn add(mut self, rhs: &'a Polynomial<F, B>) -> Polynomial<F, B> {
+impl<'a, F: Field, B: Basis> AddAssign<&'a Polynomial<F, B>> for Polynomial<F, B> {
+    fn add_assign(&mut self, rhs: &'a Polynomial<F, B>) {
         parallelize(&mut self.values, |lhs, start| {
             for (lhs, rhs) in lhs.iter_mut().zip(rhs.values[start..].iter()) {
                 *lhs += *rhs;
             }
         });
+    }
+}
+
+impl<'a, F: Field, B: Basis> SubAssign<&'a Polynomial<F, B>> for Polynomial<F, B> {
+    fn sub_assign(&mut self, rhs: &'a Polynomial<F, B>) {
+        parallelize(&mut self.values, |lhs, start| {
+            for (lhs, rhs) in lhs.iter_mut().zip(rhs.values[start..].iter()) {
+                *lhs -= *rhs;
+            }
+        });
+    }
+}
+
+impl<F: Field, B: Basis> MulAssign<F> for Polynomial<F, B> {
+    fn mul_assign(&mut self, rhs: F) {
+        if rhs == F::one() {
+            return;
+        }
 
+        parallelize(&mut self.values, |lhs, _| {
+            for lhs in lhs.iter_mut() {
+                *lhs *= rhs;
+            }
+        });
+    }
+}
+
+impl<'a, F: Field, B: Basis> Add<&'a Polynomial<F, B>> for Polynomial<F, B> {
+    type Output = Polynomial<F, B>;
+
+    fn add(mut self, rhs: &'a Polynomial<F, B>) -> Polynomial<F, B> {
+        self += rhs;
         self
     }
 }
@@ -244,16 +431,51 @@ impl<'a, F: Field, B: Basis> Sub<&'a Polynomial<F, B>> for Polynomial<F, B> {
     type Output = Polynomial<F, B>;
 
     fn sub(mut self, rhs: &'a Polynomial<F, B>) -> Polynomial<F, B> {
+        self -= rhs;
+        self
+    }
+}
+
+impl<F: Field, B: Basis> Polynomial<F, B> {
+    /// Adds `rhs * scalar` to this polynomial in place, without an
+    /// intermediate copy of `rhs`.
+    pub fn add_scaled(&mut self, rhs: &Polynomial<F, B>, scalar: F) {
         parallelize(&mut self.values, |lhs, start| {
             for (lhs, rhs) in lhs.iter_mut().zip(rhs.values[start..].iter()) {
-                *lhs -= *rhs;
+                *lhs += *rhs * scalar;
             }
         });
-
-        self
     }
 }
 
+/// Pointwise multiplication of polynomials in evaluation form.
+macro_rules! impl_pointwise_mul {
+    ($basis:ty) => {
+        impl<'a, F: Field> MulAssign<&'a Polynomial<F, $basis>> for Polynomial<F, $basis> {
+            fn mul_assign(&mut self, rhs: &'a Polynomial<F, $basis>) {
+                assert_eq!(self.len(), rhs.len());
+                parallelize(&mut self.values, |lhs, start| {
+                    for (lhs, rhs) in lhs.iter_mut().zip(rhs.values[start..].iter()) {
+                        *lhs *= *rhs;
+                    }
+                });
+            }
+        }
+
+        impl<'a, F: Field> Mul<&'a Polynomial<F, $basis>> for Polynomial<F, $basis> {
+            type Output = Polynomial<F, $basis>;
+
+            fn mul(mut self, rhs: &'a Polynomial<F, $basis>) -> Polynomial<F, $basis> {
+                self *= rhs;
+                self
+            }
+        }
+    };
+}
+
+impl_pointwise_mul!(LagrangeCoeff);
+impl_pointwise_mul!(ExtendedLagrangeCoeff);
+
 impl<F: Field> Polynomial<F, LagrangeCoeff> {
     /// Rotates the values in a Lagrange basis polynomial by `Rotation`
     pub fn rotate(&self, rotation: Rotation) -> Polynomial<F, LagrangeCoeff> {
@@ -275,21 +497,10 @@ impl<F: Field, B: Basis> Mul<F> for Polynomial<F, B> {
 
     fn mul(mut self, rhs: F) -> Polynomial<F, B> {
         if rhs == F::zero() {
-            return Polynomial {
-                values: vec![F::zero(); self.len()],
-                _marker: PhantomData,
-            };
-        }
-        if rhs == F::one() {
+            self.values.iter_mut().for_each(|value| *value = F::zero());
             return self;
         }
-
-        parallelize(&mut self.values, |lhs, _| {
-            for lhs in lhs.iter_mut() {
-                *lhs *= rhs;
-            }
-        });
-
+        self *= rhs;
         self
     }
 }
@@ -326,3 +537,38 @@ impl Rotation {
         Rotation(1)
     }
 }
+
+#[cfg(all(test, feature = "zeroize"))]
+mod test {
+    use super::{Wipe, Zeroizing};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct Secret(Rc<Cell<usize>>);
+
+    impl Wipe for Secret {
+        fn wipe(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_wipe_on_error() {
+        let wiped = Rc::new(Cell::new(0));
+        let secrets = Zeroizing::new((0..4).map(|_| Secret(wiped.clone())).collect::<Vec<_>>());
+
+        // The secrets mapped before the error and those not reached yet are
+        // wiped by the guards, and the one in flight by `f`
+        let mut mapped = 0;
+        let result = secrets.try_map(|secret| {
+            if mapped == 2 {
+                drop(Zeroizing::new(secret));
+                return Err(());
+            }
+            mapped += 1;
+            Ok(secret)
+        });
+        assert!(result.is_err());
+        assert_eq!(wiped.get(), 4);
+    }
+}
@@ -67,6 +67,20 @@ pub trait Params<'params, C: CurveAffine>: Sized + Clone {
         r: Blind<C::ScalarExt>,
     ) -> C::CurveExt;
 
+    /// Same as [`Params::commit_lagrange`], but takes the evaluations as
+    /// consecutive chunks, e.g. read from disk, so that the whole column does
+    /// not need to be materialized. The chunks together may have at most
+    /// $2^k$ evaluations; missing trailing evaluations are treated as zero.
+    fn commit_lagrange_chunks<'a, I>(&self, chunks: I, r: Blind<C::ScalarExt>) -> C::CurveExt
+    where
+        I: IntoIterator<Item = &'a [C::ScalarExt]>;
+
+    /// Returns whether the parameters hold the bases to commit with, which
+    /// parameters stripped down to what a verifier needs do not.
+    fn can_commit(&self) -> bool {
+        true
+    }
+
     /// Writes params to a buffer.
     fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()>;
 
@@ -103,6 +117,19 @@ pub trait MSM<C: CurveAffine>: Clone + Debug + Send + Sync {
     /// Add arbitrary term (the scalar and the point)
     fn append_term(&mut self, scalar: C::Scalar, point: C::CurveExt);
 
+    /// Add a batch of terms at once. This function will panic if `scalars`
+    /// and `points` have a different length.
+    fn append_terms(&mut self, scalars: &[C::Scalar], points: &[C::CurveExt]) {
+        assert_eq!(scalars.len(), points.len());
+        self.reserve(scalars.len());
+        for (scalar, point) in scalars.iter().zip(points.iter()) {
+            self.append_term(*scalar, *point);
+        }
+    }
+
+    /// Reserve capacity for at least `additional` more terms
+    fn reserve(&mut self, additional: usize);
+
     /// Add another multiexp into this one
     fn add_msm(&mut self, other: &Self)
     where
@@ -132,6 +159,13 @@ pub trait Prover<'params, Scheme: CommitmentScheme> {
     /// Creates new prover instance
     fn new(params: &'params Scheme::ParamsProver) -> Self;
 
+    /// Identifies the multiopen argument of this prover, for provers that
+    /// choose it at runtime. If set, it is written at the start of each proof
+    /// and checked by a verifier with the same [`Verifier::scheme_id`].
+    fn scheme_id(&self) -> Option<u8> {
+        None
+    }
+
     /// Create a multi-opening proof
     fn create_proof<
         'com,
@@ -165,6 +199,11 @@ pub trait Verifier<'params, Scheme: CommitmentScheme> {
     /// Creates new verifier instance
     fn new(params: &'params Scheme::ParamsVerifier) -> Self;
 
+    /// The [`Prover::scheme_id`] of the proofs this verifier accepts
+    fn scheme_id(&self) -> Option<u8> {
+        None
+    }
+
     /// Process the proof and returns unfinished result named `Guard`
     fn verify_proof<
         'com,
@@ -203,6 +242,12 @@ impl<F: FieldExt> Blind<F> {
     pub fn new<R: RngCore>(rng: &mut R) -> Self {
         Blind(F::random(rng))
     }
+
+    /// Returns a blinding factor of zero, for commitments that need not be
+    /// hiding and should be equal for equal polynomials.
+    pub fn zero() -> Self {
+        Blind(F::zero())
+    }
 }
 
 impl<F: FieldExt> Add for Blind<F> {
@@ -2,19 +2,27 @@
 //! domain that is of a suitable size for the application.
 
 use crate::{
-    arithmetic::{best_fft, parallelize, FieldExt, Group},
-    plonk::Assigned,
+    arithmetic::{batch_invert, best_fft, parallelize, FieldExt, Group},
+    multicore,
+    plonk::{Assigned, Error},
 };
 
 use super::{Coeff, ExtendedLagrangeCoeff, LagrangeCoeff, Polynomial, Rotation};
 
 use group::ff::{BatchInvert, Field, PrimeField};
 
+use once_cell::sync::OnceCell;
+use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
 
 /// This structure contains precomputed constants and other details needed for
 /// performing operations on an evaluation domain of size $2^k$ and an extended
 /// domain of size $2^{k} * j$ with $j \neq 0$.
+///
+/// Both sizes are powers of two, so the extended domain must fit in the
+/// $2^S$ order subgroup of the field. Domains of other sizes are not
+/// supported.
 #[derive(Clone, Debug)]
 pub struct EvaluationDomain<G: Group> {
     n: u64,
@@ -31,25 +39,68 @@ pub struct EvaluationDomain<G: Group> {
     extended_ifft_divisor: G::Scalar,
     t_evaluations: Vec<G::Scalar>,
     barycentric_weight: G::Scalar,
+    barycentric_weights: OnceCell<Vec<G::Scalar>>,
 }
 
 impl<G: Group> EvaluationDomain<G> {
     /// This constructs a new evaluation domain object based on the provided
     /// values $j, k$.
     pub fn new(j: u32, k: u32) -> Self {
-        // quotient_poly_degree * params.n - 1 is the degree of the quotient polynomial
-        let quotient_poly_degree = (j - 1) as u64;
+        Self::from_coset(j, k, G::Scalar::ZETA)
+    }
 
-        // n = 2^k
-        let n = 1u64 << k;
+    /// Returns whether [`Self::new_with_coset`] accepts `g_coset` for `j` and
+    /// `k`, i.e. whether it is nonzero and the vanishing polynomial of the
+    /// $2^k$ order subgroup has no root in the coset it generates.
+    pub fn is_valid_coset(j: u32, k: u32, g_coset: G::Scalar) -> bool {
+        // (g w^i)^n = 1 for some i, with w of order 2^extended_k, if and
+        // only if g^(2^extended_k) = 1
+        let mut power = g_coset;
+        for _ in 0..Self::extended_k(j, k) {
+            power = power.square();
+        }
+        !bool::from(g_coset.is_zero()) && power != G::Scalar::one()
+    }
 
-        // We need to work within an extended domain, not params.k but params.k + i
-        // for some integer i such that 2^(params.k + i) is sufficiently large to
-        // describe the quotient polynomial.
+    /// We need to work within an extended domain, not params.k but params.k + i
+    /// for some integer i such that 2^(params.k + i) is sufficiently large to
+    /// describe the quotient polynomial.
+    fn extended_k(j: u32, k: u32) -> u32 {
+        let quotient_poly_degree = (j - 1) as u64;
+        let n = 1u64 << k;
         let mut extended_k = k;
         while (1 << extended_k) < (n * quotient_poly_degree) {
             extended_k += 1;
         }
+        extended_k
+    }
+
+    /// This constructs a new evaluation domain object based on the provided
+    /// values $j, k$, whose extended domain is the coset of the $2^{k} * j$
+    /// (rounded up to a power of two) order subgroup generated by `g_coset`,
+    /// instead of $\zeta$ as in [`Self::new`].
+    ///
+    /// The coset only affects how the prover computes the quotient polynomial,
+    /// not the polynomial itself, so proofs do not depend on it.
+    ///
+    /// Returns [`Error::InvalidCoset`] if `g_coset` is not
+    /// [valid](Self::is_valid_coset), e.g. zero or in the extended domain
+    /// itself.
+    pub fn new_with_coset(j: u32, k: u32, g_coset: G::Scalar) -> Result<Self, Error> {
+        if !Self::is_valid_coset(j, k, g_coset) {
+            return Err(Error::InvalidCoset);
+        }
+        Ok(Self::from_coset(j, k, g_coset))
+    }
+
+    fn from_coset(j: u32, k: u32, g_coset: G::Scalar) -> Self {
+        // quotient_poly_degree * params.n - 1 is the degree of the quotient polynomial
+        let quotient_poly_degree = (j - 1) as u64;
+
+        // n = 2^k
+        let n = 1u64 << k;
+
+        let extended_k = Self::extended_k(j, k);
 
         let mut extended_omega = G::Scalar::root_of_unity();
 
@@ -74,18 +125,15 @@ impl<G: Group> EvaluationDomain<G> {
         let omega = omega;
         let mut omega_inv = omega; // Inversion computed later
 
-        // We use zeta here because we know it generates a coset, and it's available
-        // already.
         // The coset evaluation domain is:
-        // zeta {1, extended_omega, extended_omega^2, ..., extended_omega^{(2^extended_k) - 1}}
-        let g_coset = G::Scalar::ZETA;
-        let g_coset_inv = g_coset.square();
+        // g_coset {1, extended_omega, extended_omega^2, ..., extended_omega^{(2^extended_k) - 1}}
+        let mut g_coset_inv = g_coset; // Inversion computed later
 
         let mut t_evaluations = Vec::with_capacity(1 << (extended_k - k));
         {
             // Compute the evaluations of t(X) = X^n - 1 in the coset evaluation domain.
             // We don't have to compute all of them, because it will repeat.
-            let orig = G::Scalar::ZETA.pow_vartime(&[n as u64, 0, 0, 0]);
+            let orig = g_coset.pow_vartime(&[n as u64, 0, 0, 0]);
             let step = extended_omega.pow_vartime(&[n as u64, 0, 0, 0]);
             let mut cur = orig;
             loop {
@@ -97,11 +145,10 @@ impl<G: Group> EvaluationDomain<G> {
             }
             assert_eq!(t_evaluations.len(), 1 << (extended_k - k));
 
-            // Subtract 1 from each to give us t_evaluations[i] = t(zeta * extended_omega^i)
+            // Subtract 1 from each to give us t_evaluations[i] = t(g_coset * extended_omega^i)
             for coeff in &mut t_evaluations {
                 *coeff -= &G::Scalar::one();
             }
-
             // Invert, because we're dividing by this polynomial.
             // We invert in a batch, below.
         }
@@ -121,6 +168,7 @@ impl<G: Group> EvaluationDomain<G> {
             .chain(Some(&mut barycentric_weight))
             .chain(Some(&mut extended_omega_inv))
             .chain(Some(&mut omega_inv))
+            .chain(Some(&mut g_coset_inv))
             .batch_invert();
 
         EvaluationDomain {
@@ -138,6 +186,7 @@ impl<G: Group> EvaluationDomain<G> {
             extended_ifft_divisor,
             t_evaluations,
             barycentric_weight,
+            barycentric_weights: OnceCell::new(),
         }
     }
 
@@ -153,6 +202,18 @@ impl<G: Group> EvaluationDomain<G> {
         }
     }
 
+    /// Obtains a polynomial in extended Lagrange form when given a vector of
+    /// evaluations over the extended domain; panics if the provided vector is
+    /// the wrong length.
+    pub fn extended_from_vec(&self, values: Vec<G>) -> Polynomial<G, ExtendedLagrangeCoeff> {
+        assert_eq!(values.len(), self.extended_len());
+
+        Polynomial {
+            values,
+            _marker: PhantomData,
+        }
+    }
+
     /// Obtains a polynomial in coefficient form when given a vector of
     /// coefficients of size `n`; panics if the provided vector is the wrong
     /// length.
@@ -253,6 +314,21 @@ impl<G: Group> EvaluationDomain<G> {
         }
     }
 
+    /// Same as [`Self::coeff_to_extended`] on a copy of `a`, which is made
+    /// with the capacity of the extended domain so that growing it does not
+    /// leave a copy of the coefficients in freed memory.
+    pub(crate) fn coeff_to_extended_copy(
+        &self,
+        a: &Polynomial<G, Coeff>,
+    ) -> Polynomial<G, ExtendedLagrangeCoeff> {
+        let mut values = Vec::with_capacity(self.extended_len());
+        values.extend_from_slice(&a.values);
+        self.coeff_to_extended(Polynomial {
+            values,
+            _marker: PhantomData,
+        })
+    }
+
     /// Rotate the extended domain polynomial over the original domain.
     pub fn rotate_extended(
         &self,
@@ -333,6 +409,15 @@ impl<G: Group> EvaluationDomain<G> {
     /// `into_coset` should be set to `true` when moving into the coset,
     /// and `false` when moving out. This toggles the choice of `zeta`.
     fn distribute_powers_zeta(&self, a: &mut [G], into_coset: bool) {
+        if self.g_coset != G::Scalar::ZETA {
+            let g = if into_coset {
+                self.g_coset
+            } else {
+                self.g_coset_inv
+            };
+            return Self::distribute_powers(a, g);
+        }
+
         let coset_powers = if into_coset {
             [self.g_coset, self.g_coset_inv]
         } else {
@@ -350,6 +435,18 @@ impl<G: Group> EvaluationDomain<G> {
         });
     }
 
+    /// Given a slice of group elements `[a_0, a_1, a_2, ...]`, this returns
+    /// `[a_0, [g]a_1, [g^2]a_2, ...]`.
+    fn distribute_powers(a: &mut [G], g: G::Scalar) {
+        parallelize(a, |a, index| {
+            let mut power = g.pow_vartime(&[index as u64]);
+            for a in a {
+                a.group_scale(&power);
+                power *= &g;
+            }
+        });
+    }
+
     fn ifft(a: &mut [G], omega_inv: G::Scalar, log_n: u32, divisor: G::Scalar) {
         best_fft(a, omega_inv, log_n);
         parallelize(a, |a, _| {
@@ -386,6 +483,11 @@ impl<G: Group> EvaluationDomain<G> {
         self.omega_inv
     }
 
+    /// Get the generator of the coset the extended domain lies in.
+    pub fn get_coset_generator(&self) -> G::Scalar {
+        self.g_coset
+    }
+
     /// Get the generator of the extended domain's multiplicative subgroup.
     pub fn get_extended_omega(&self) -> G::Scalar {
         self.extended_omega
@@ -432,31 +534,143 @@ impl<G: Group> EvaluationDomain<G> {
     ///     $$\frac{1}{\prod_{j=0,j \neq 0}^{n - 1} (\omega^i - \omega^j)}
     ///       = \frac{1}{\prod_{j=0,j \neq i}^{n - 1} (\omega^i - \omega^j)}$$
     /// which is the barycentric weight of $\omega^i$.
+    ///
+    /// Each weight is obtained by rotating $1 / n$, unless the table of all
+    /// weights was computed with [`Self::precompute_barycentric_weights`], in
+    /// which case they are looked up in it.
     pub fn l_i_range<I: IntoIterator<Item = i32> + Clone>(
         &self,
         x: G::Scalar,
         xn: G::Scalar,
         rotations: I,
     ) -> Vec<G::Scalar> {
-        let mut results;
-        {
-            let rotations = rotations.clone().into_iter();
-            results = Vec::with_capacity(rotations.size_hint().1.unwrap_or(0));
-            for rotation in rotations {
-                let rotation = Rotation(rotation);
-                let result = x - self.rotate_omega(G::Scalar::one(), rotation);
-                results.push(result);
-            }
-            results.iter_mut().batch_invert();
+        let n = G::Scalar::from(self.n);
+        let weights: Vec<_> = rotations
+            .into_iter()
+            .map(|rotation| self.barycentric_weight_at(rotation))
+            .collect();
+        let mut results: Vec<_> = weights.iter().map(|weight| x - *weight * n).collect();
+        results.iter_mut().batch_invert();
+
+        let common = xn - G::Scalar::one();
+        for (result, weight) in results.iter_mut().zip(weights.iter()) {
+            // Inversion leaves zero as is, when x = omega^i
+            *result = if bool::from(result.is_zero()) {
+                G::Scalar::one()
+            } else {
+                *result * *weight * common
+            };
         }
+        results
+    }
+
+    /// Computes the barycentric weights of all points of the domain, so that
+    /// later calls to [`Self::l_i`] and [`Self::l_i_range`] look them up
+    /// rather than rotating $1 / n$ for each rotation. This is a one-time
+    /// $O(n)$ setup, worth it when many proofs are checked with the same
+    /// verifying key and many rotations are queried.
+    pub fn precompute_barycentric_weights(&self) {
+        self.barycentric_weights();
+    }
+
+    /// Returns the barycentric weights $\omega^i / n$ of all points
+    /// $\omega^i$ of the domain, computing them on first use.
+    pub fn barycentric_weights(&self) -> &[G::Scalar] {
+        self.barycentric_weights.get_or_init(|| {
+            let omega = self.omega;
+            let mut weights = vec![G::Scalar::zero(); self.n as usize];
+            parallelize(&mut weights, |weights, start| {
+                let mut weight = omega.pow_vartime(&[start as u64]) * self.barycentric_weight;
+                for value in weights.iter_mut() {
+                    *value = weight;
+                    weight *= &omega;
+                }
+            });
+            weights
+        })
+    }
+
+    fn rotation_index(&self, rotation: i32) -> usize {
+        (rotation as i64).rem_euclid(self.n as i64) as usize
+    }
 
-        let common = (xn - G::Scalar::one()) * self.barycentric_weight;
-        for (rotation, result) in rotations.into_iter().zip(results.iter_mut()) {
-            let rotation = Rotation(rotation);
-            *result = self.rotate_omega(*result * common, rotation);
+    fn barycentric_weight_at(&self, rotation: i32) -> G::Scalar {
+        match self.barycentric_weights.get() {
+            Some(weights) => weights[self.rotation_index(rotation)],
+            None => self.rotate_omega(self.barycentric_weight, Rotation(rotation)),
         }
+    }
 
-        results
+    /// Evaluates the Lagrange basis polynomial $l_i(X)$ at `x`, where
+    /// `xn = x^n`, as
+    ///     $$l_i(x) = \frac{\omega^i}{n} \cdot \frac{x^n - 1}{x - \omega^i}.$$
+    /// This takes a single field inversion; [`Self::l_i_range`] shares the
+    /// inversion between several rotations.
+    pub fn l_i(&self, x: G::Scalar, xn: G::Scalar, rotation: i32) -> G::Scalar {
+        let weight = self.barycentric_weight_at(rotation);
+        let omega_i = weight * G::Scalar::from(self.n);
+        match Option::<G::Scalar>::from((x - omega_i).invert()) {
+            Some(inv) => weight * (xn - G::Scalar::one()) * inv,
+            // x = omega^i
+            None => G::Scalar::one(),
+        }
+    }
+
+    /// Evaluates a polynomial given in Lagrange form at `x` with the
+    /// barycentric formula
+    ///     $$p(x) = \frac{x^n - 1}{n} \sum_{i=0}^{n - 1} \frac{p(\omega^i) \omega^i}{x - \omega^i},$$
+    /// without converting it to coefficient form. The work is split across
+    /// threads, with one field inversion per thread.
+    pub fn eval_lagrange(&self, poly: &Polynomial<G, LagrangeCoeff>, x: G::Scalar) -> G {
+        assert_eq!(poly.len(), self.n as usize);
+        let omega = self.omega;
+
+        // x - omega^i, inverted
+        let mut weights = vec![G::Scalar::zero(); poly.len()];
+        parallelize(&mut weights, |weights, start| {
+            let mut omega_i = omega.pow_vartime(&[start as u64]);
+            for weight in weights.iter_mut() {
+                *weight = x - omega_i;
+                omega_i *= &omega;
+            }
+        });
+        if let Some(i) = weights
+            .iter()
+            .position(|weight| bool::from(weight.is_zero()))
+        {
+            return poly[i];
+        }
+        batch_invert(&mut weights);
+
+        let num_threads = multicore::current_num_threads();
+        let chunk_size = (poly.len() + num_threads - 1) / num_threads;
+        let mut parts = vec![G::group_zero(); (poly.len() + chunk_size - 1) / chunk_size];
+        multicore::scope(|scope| {
+            for (chunk_idx, ((part, values), weights)) in parts
+                .iter_mut()
+                .zip(poly.chunks(chunk_size))
+                .zip(weights.chunks(chunk_size))
+                .enumerate()
+            {
+                scope.spawn(move |_| {
+                    let mut omega_i = omega.pow_vartime(&[(chunk_idx * chunk_size) as u64]);
+                    for (value, weight) in values.iter().zip(weights.iter()) {
+                        let mut term = *value;
+                        term.group_scale(&(*weight * omega_i));
+                        part.group_add(&term);
+                        omega_i *= &omega;
+                    }
+                });
+            }
+        });
+
+        let mut result = parts.into_iter().fold(G::group_zero(), |mut acc, part| {
+            acc.group_add(&part);
+            acc
+        });
+        let xn = x.pow_vartime(&[self.n]);
+        result.group_scale(&((xn - G::Scalar::one()) * self.ifft_divisor));
+        result
     }
 
     /// Gets the quotient polynomial's degree (as a multiple of n)
@@ -476,6 +690,50 @@ impl<G: Group> EvaluationDomain<G> {
     }
 }
 
+/// Cache of evaluation domains keyed by `(j, k)`, so that keys of circuits
+/// with the same size and degree share a single [`EvaluationDomain`] instead
+/// of each computing and owning their own.
+#[derive(Debug)]
+pub struct DomainCache<G: Group> {
+    domains: Mutex<HashMap<(u32, u32), Arc<EvaluationDomain<G>>>>,
+}
+
+impl<G: Group> Default for DomainCache<G> {
+    fn default() -> Self {
+        DomainCache {
+            domains: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<G: Group> DomainCache<G> {
+    /// Creates an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the domain for `j` and `k`, constructing it with
+    /// [`EvaluationDomain::new`] if it is not cached yet.
+    pub fn get(&self, j: u32, k: u32) -> Arc<EvaluationDomain<G>> {
+        self.domains
+            .lock()
+            .unwrap()
+            .entry((j, k))
+            .or_insert_with(|| Arc::new(EvaluationDomain::new(j, k)))
+            .clone()
+    }
+
+    /// Returns the number of cached domains
+    pub fn len(&self) -> usize {
+        self.domains.lock().unwrap().len()
+    }
+
+    /// Returns `true` if no domain is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 /// Represents the minimal parameters that determine an `EvaluationDomain`.
 #[allow(dead_code)]
 #[derive(Debug)]
@@ -554,4 +812,110 @@ fn test_l_i() {
         assert_eq!(eval_polynomial(&l[i][..], x), evaluations[7 + i]);
         assert_eq!(eval_polynomial(&l[(8 - i) % 8][..], x), evaluations[7 - i]);
     }
+
+    for i in -7..=7 {
+        assert_eq!(domain.l_i(x, xn, i), evaluations[(7 + i) as usize]);
+    }
+
+    // At a point of the domain, only its own basis polynomial is nonzero
+    let omega_3 = domain.get_omega().pow(&[3, 0, 0, 0]);
+    assert_eq!(domain.l_i(omega_3, Scalar::one(), 3), Scalar::one());
+    assert_eq!(domain.l_i(omega_3, Scalar::one(), 2), Scalar::zero());
+    let expected: Vec<_> = (-7..=7)
+        .map(|i: i32| {
+            if i.rem_euclid(8) == 3 {
+                Scalar::one()
+            } else {
+                Scalar::zero()
+            }
+        })
+        .collect();
+    assert_eq!(domain.l_i_range(omega_3, Scalar::one(), -7..=7), expected);
+
+    // The precomputed weights give the same evaluations
+    domain.precompute_barycentric_weights();
+    assert_eq!(domain.l_i_range(x, xn, -7..=7), evaluations);
+    assert_eq!(domain.l_i_range(omega_3, Scalar::one(), -7..=7), expected);
+    assert_eq!(domain.l_i(omega_3, Scalar::one(), 3), Scalar::one());
+}
+
+#[test]
+fn test_domain_cache() {
+    use halo2curves::pasta::pallas::Scalar;
+
+    let cache = DomainCache::<Scalar>::new();
+    let domain = cache.get(3, 4);
+    assert!(Arc::ptr_eq(&domain, &cache.get(3, 4)));
+    assert!(!Arc::ptr_eq(&domain, &cache.get(4, 4)));
+    assert_eq!(cache.len(), 2);
+
+    let fresh = EvaluationDomain::<Scalar>::new(3, 4);
+    assert_eq!(domain.extended_k(), fresh.extended_k());
+    assert_eq!(domain.get_omega(), fresh.get_omega());
+}
+
+#[test]
+fn test_eval_lagrange() {
+    use rand_core::OsRng;
+
+    use crate::arithmetic::eval_polynomial;
+    use halo2curves::pasta::pallas::Scalar;
+
+    let domain = EvaluationDomain::<Scalar>::new(1, 6);
+
+    let mut poly = domain.empty_lagrange();
+    for value in poly.iter_mut() {
+        *value = Scalar::random(OsRng);
+    }
+    let coeffs = domain.lagrange_to_coeff(poly.clone());
+
+    let x = Scalar::random(OsRng);
+    assert_eq!(domain.eval_lagrange(&poly, x), eval_polynomial(&coeffs, x));
+
+    let omega_5 = domain.get_omega().pow_vartime(&[5]);
+    assert_eq!(domain.eval_lagrange(&poly, omega_5), poly[5]);
+}
+
+#[test]
+fn test_coset() {
+    use rand_core::OsRng;
+
+    use crate::arithmetic::eval_polynomial;
+    use halo2curves::pasta::pallas::Scalar;
+
+    let g_coset = Scalar::multiplicative_generator();
+    let domain = EvaluationDomain::<Scalar>::new_with_coset(3, 4, g_coset).unwrap();
+    assert_eq!(domain.get_coset_generator(), g_coset);
+
+    let mut poly = domain.empty_coeff();
+    for value in poly.iter_mut() {
+        *value = Scalar::random(OsRng);
+    }
+
+    let extended = domain.coeff_to_extended(poly.clone());
+    let omega = domain.get_extended_omega();
+    for i in [0, 1, 7, 31] {
+        let point = g_coset * omega.pow_vartime(&[i as u64]);
+        assert_eq!(extended[i], eval_polynomial(&poly, point));
+    }
+
+    let mut coeffs = domain.extended_to_coeff(extended);
+    coeffs.truncate(poly.len());
+    assert_eq!(coeffs, poly.values);
+
+    assert!(EvaluationDomain::<Scalar>::is_valid_coset(
+        3,
+        4,
+        Scalar::ZETA
+    ));
+    assert!(!EvaluationDomain::<Scalar>::is_valid_coset(
+        3,
+        4,
+        Scalar::zero()
+    ));
+    assert!(!EvaluationDomain::<Scalar>::is_valid_coset(3, 4, omega));
+    assert!(matches!(
+        EvaluationDomain::<Scalar>::new_with_coset(3, 4, omega),
+        Err(Error::InvalidCoset)
+    ));
 }
@@ -627,7 +627,7 @@ impl BasisOps for ExtendedLagrangeCoeff {
         let start = chunk_size * chunk_index;
         (0..cmp::min(chunk_size, poly_len - start))
             .scan(
-                omega.pow_vartime(&[start as u64]) * F::ZETA * scalar,
+                omega.pow_vartime(&[start as u64]) * domain.get_coset_generator() * scalar,
                 |acc, _| {
                     let ret = *acc;
                     *acc *= omega;
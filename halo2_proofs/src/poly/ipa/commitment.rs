@@ -4,7 +4,8 @@
 //! [halo]: https://eprint.iacr.org/2019/1021
 
 use crate::arithmetic::{
-    best_fft, best_multiexp, g_to_lagrange, parallelize, CurveAffine, CurveExt, FieldExt, Group,
+    best_fft, best_multiexp, best_multiexp_chunks, g_to_lagrange, parallelize, CurveAffine,
+    CurveExt, FieldExt, Group,
 };
 use crate::helpers::CurveRead;
 use crate::poly::commitment::{Blind, CommitmentScheme, Params, ParamsProver, ParamsVerifier, MSM};
@@ -24,6 +25,15 @@ pub use verifier::verify_proof;
 
 use std::io;
 
+/// Magic bytes at the start of serialized [`ParamsIPA`]
+const PARAMS_MAGIC: [u8; 4] = *b"H2IP";
+
+/// Version of the [`ParamsIPA`] serialization format
+const PARAMS_VERSION: u8 = 1;
+
+/// Domain-separation prefix of the generators of [`ParamsIPA`]
+const DEFAULT_DOMAIN: &str = "Halo2-Parameters";
+
 /// Public parameters for IPA commitment scheme
 #[derive(Debug, Clone)]
 pub struct ParamsIPA<C: CurveAffine> {
@@ -60,6 +70,107 @@ impl<C: CurveAffine> CommitmentScheme for IPACommitmentScheme<C> {
 /// Verifier parameters
 pub type ParamsVerifierIPA<C> = ParamsIPA<C>;
 
+impl<C: CurveAffine> ParamsIPA<C> {
+    /// Initializes parameters for the curve with generators drawn from
+    /// hash-to-curve under the domain-separation prefix `domain`. Parameters
+    /// for different domains have independent generators; [`ParamsProver::new`]
+    /// uses `"Halo2-Parameters"`.
+    pub fn new_with_domain(k: u32, domain: &str) -> Self {
+        // This is usually a limitation on the curve, but we also want 32-bit
+        // architectures to be supported.
+        assert!(k < 32);
+
+        // In src/arithmetic/fields.rs we ensure that usize is at least 32 bits.
+
+        let n: u64 = 1 << k;
+
+        let g_projective = {
+            let mut g = Vec::with_capacity(n as usize);
+            g.resize(n as usize, C::Curve::identity());
+
+            parallelize(&mut g, move |g, start| {
+                let hasher = C::CurveExt::hash_to_curve(domain);
+
+                for (i, g) in g.iter_mut().enumerate() {
+                    let i = (i + start) as u32;
+
+                    let mut message = [0u8; 5];
+                    message[1..5].copy_from_slice(&i.to_le_bytes());
+
+                    *g = hasher(&message);
+                }
+            });
+
+            g
+        };
+
+        let g = {
+            let mut g = vec![C::identity(); n as usize];
+            parallelize(&mut g, |g, starts| {
+                C::Curve::batch_normalize(&g_projective[starts..(starts + g.len())], g);
+            });
+            g
+        };
+
+        // Let's evaluate all of the Lagrange basis polynomials
+        // using an inverse FFT.
+        let g_lagrange = g_to_lagrange(g_projective, k);
+
+        let hasher = C::CurveExt::hash_to_curve(domain);
+        let w = hasher(&[1]).to_affine();
+        let u = hasher(&[2]).to_affine();
+
+        ParamsIPA {
+            k,
+            n,
+            g,
+            g_lagrange,
+            w,
+            u,
+        }
+    }
+
+    /// Initializes parameters from generators derived elsewhere, e.g. to
+    /// match another implementation: `g` are the `2^k` commitment
+    /// generators, `w` the blinding generator and `u` the generator used for
+    /// inner products in opening proofs.
+    pub fn from_generators(g: Vec<C>, w: C, u: C) -> Self {
+        let n = g.len() as u64;
+        assert!(n.is_power_of_two());
+        let k = n.trailing_zeros();
+        assert!(k < 32);
+
+        let g_projective: Vec<C::Curve> = g.iter().map(|g| g.to_curve()).collect();
+        let g_lagrange = g_to_lagrange(g_projective, k);
+
+        ParamsIPA {
+            k,
+            n,
+            g,
+            g_lagrange,
+            w,
+            u,
+        }
+    }
+
+    /// Commits to a polynomial without a blinding factor. The result equals
+    /// [`ParamsProver::commit`] with [`Blind::zero`], which is what the
+    /// multiopen provers must be given to open it, and is deterministic across
+    /// provers using the same parameters.
+    pub fn commit_unblinded(&self, poly: &Polynomial<C::Scalar, Coeff>) -> C::Curve {
+        best_multiexp(poly, &self.g[..poly.len()])
+    }
+
+    /// Commits to a polynomial in Lagrange form without a blinding factor, see
+    /// [`Self::commit_unblinded`].
+    pub fn commit_lagrange_unblinded(
+        &self,
+        poly: &Polynomial<C::Scalar, LagrangeCoeff>,
+    ) -> C::Curve {
+        best_multiexp(poly, &self.g_lagrange[..poly.len()])
+    }
+}
+
 impl<'params, C: CurveAffine> ParamsVerifier<'params, C> for ParamsIPA<C> {}
 
 impl<'params, C: CurveAffine> Params<'params, C> for ParamsIPA<C> {
@@ -106,8 +217,20 @@ impl<'params, C: CurveAffine> Params<'params, C> for ParamsIPA<C> {
         best_multiexp::<C>(&tmp_scalars, &tmp_bases)
     }
 
-    /// Writes params to a buffer.
+    fn commit_lagrange_chunks<'a, I>(&self, chunks: I, r: Blind<C::Scalar>) -> C::Curve
+    where
+        I: IntoIterator<Item = &'a [C::Scalar]>,
+    {
+        best_multiexp_chunks(chunks, &self.g_lagrange) + self.w * r.0
+    }
+
+    /// Writes params to a buffer, preceded by a header with the format
+    /// version, the curve and `k`.
     fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        let curve_id = C::CurveExt::CURVE_ID.as_bytes();
+        writer.write_all(&PARAMS_MAGIC)?;
+        writer.write_all(&[PARAMS_VERSION, curve_id.len() as u8])?;
+        writer.write_all(curve_id)?;
         writer.write_all(&self.k.to_le_bytes())?;
         for g_element in &self.g {
             writer.write_all(g_element.to_bytes().as_ref())?;
@@ -121,11 +244,43 @@ impl<'params, C: CurveAffine> Params<'params, C> for ParamsIPA<C> {
         Ok(())
     }
 
-    /// Reads params from a buffer.
+    /// Reads params from a buffer. Buffers without a header, as written
+    /// before the format was versioned, are still accepted.
     fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
-        let mut k = [0u8; 4];
-        reader.read_exact(&mut k[..])?;
-        let k = u32::from_le_bytes(k);
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic[..])?;
+        let k = if magic == PARAMS_MAGIC {
+            let mut header = [0u8; 2];
+            reader.read_exact(&mut header[..])?;
+            let [version, curve_id_len] = header;
+            if version != PARAMS_VERSION {
+                return Err(invalid(format!(
+                    "unsupported ParamsIPA format version {}, expected {}",
+                    version, PARAMS_VERSION
+                )));
+            }
+
+            let mut curve_id = vec![0u8; curve_id_len as usize];
+            reader.read_exact(&mut curve_id[..])?;
+            if curve_id != C::CurveExt::CURVE_ID.as_bytes() {
+                return Err(invalid(format!(
+                    "ParamsIPA were generated for curve {}, expected {}",
+                    String::from_utf8_lossy(&curve_id),
+                    C::CurveExt::CURVE_ID
+                )));
+            }
+
+            let mut k = [0u8; 4];
+            reader.read_exact(&mut k[..])?;
+            u32::from_le_bytes(k)
+        } else {
+            u32::from_le_bytes(magic)
+        };
+        if k >= 32 {
+            return Err(invalid(format!("invalid ParamsIPA size k = {}", k)));
+        }
 
         let n: u64 = 1 << k;
 
@@ -156,58 +311,7 @@ impl<'params, C: CurveAffine> ParamsProver<'params, C> for ParamsIPA<C> {
     /// Initializes parameters for the curve, given a random oracle to draw
     /// points from.
     fn new(k: u32) -> Self {
-        // This is usually a limitation on the curve, but we also want 32-bit
-        // architectures to be supported.
-        assert!(k < 32);
-
-        // In src/arithmetic/fields.rs we ensure that usize is at least 32 bits.
-
-        let n: u64 = 1 << k;
-
-        let g_projective = {
-            let mut g = Vec::with_capacity(n as usize);
-            g.resize(n as usize, C::Curve::identity());
-
-            parallelize(&mut g, move |g, start| {
-                let hasher = C::CurveExt::hash_to_curve("Halo2-Parameters");
-
-                for (i, g) in g.iter_mut().enumerate() {
-                    let i = (i + start) as u32;
-
-                    let mut message = [0u8; 5];
-                    message[1..5].copy_from_slice(&i.to_le_bytes());
-
-                    *g = hasher(&message);
-                }
-            });
-
-            g
-        };
-
-        let g = {
-            let mut g = vec![C::identity(); n as usize];
-            parallelize(&mut g, |g, starts| {
-                C::Curve::batch_normalize(&g_projective[starts..(starts + g.len())], g);
-            });
-            g
-        };
-
-        // Let's evaluate all of the Lagrange basis polynomials
-        // using an inverse FFT.
-        let g_lagrange = g_to_lagrange(g_projective, k);
-
-        let hasher = C::CurveExt::hash_to_curve("Halo2-Parameters");
-        let w = hasher(&[1]).to_affine();
-        let u = hasher(&[2]).to_affine();
-
-        ParamsIPA {
-            k,
-            n,
-            g,
-            g_lagrange,
-            w,
-            u,
-        }
+        ParamsIPA::new_with_domain(k, DEFAULT_DOMAIN)
     }
 
     /// This computes a commitment to a polynomial described by the provided
@@ -242,6 +346,7 @@ mod test {
     use crate::poly::commitment::{Blind, CommitmentScheme, Params, MSM};
     use crate::poly::ipa::commitment::{create_proof, verify_proof, ParamsIPA};
     use crate::poly::ipa::msm::MSMIPA;
+    use crate::poly::ipa::strategy::Accumulator;
     use crate::poly::{Coeff, LagrangeCoeff, Polynomial};
 
     use ff::{Field, PrimeField};
@@ -301,6 +406,83 @@ mod test {
         assert_eq!(params.commit(&b, alpha), params.commit_lagrange(&a, alpha));
     }
 
+    #[test]
+    fn test_commit_unblinded() {
+        const K: u32 = 4;
+
+        use crate::halo2curves::pasta::{EpAffine, Fq};
+        use crate::poly::EvaluationDomain;
+        use group::Curve;
+        use rand_core::OsRng;
+
+        let params = ParamsIPA::<EpAffine>::new(K);
+        let domain = EvaluationDomain::new(1, K);
+
+        let mut a = domain.empty_lagrange();
+        for a in a.iter_mut() {
+            *a = Fq::random(OsRng);
+        }
+        let b = domain.lagrange_to_coeff(a.clone());
+
+        let commitment = params.commit_unblinded(&b);
+        assert_eq!(commitment, params.commit(&b, Blind::zero()));
+        assert_eq!(commitment, params.commit_lagrange_unblinded(&a));
+        assert_eq!(
+            commitment.to_affine(),
+            ParamsIPA::<EpAffine>::new(K)
+                .commit_unblinded(&b)
+                .to_affine()
+        );
+    }
+
+    #[test]
+    fn test_params_domain() {
+        use crate::halo2curves::pasta::EpAffine;
+
+        let params = ParamsIPA::<EpAffine>::new(3);
+        assert_eq!(
+            ParamsIPA::<EpAffine>::new_with_domain(3, "Halo2-Parameters").g,
+            params.g
+        );
+
+        let other = ParamsIPA::<EpAffine>::new_with_domain(3, "Other-Application");
+        assert!(other.g.iter().zip(params.g.iter()).all(|(a, b)| a != b));
+        assert_ne!(other.w, params.w);
+
+        let rebuilt = ParamsIPA::from_generators(other.g.clone(), other.w, other.u);
+        assert_eq!(rebuilt.k, 3);
+        assert_eq!(rebuilt.g_lagrange, other.g_lagrange);
+    }
+
+    #[test]
+    fn test_params_header() {
+        use crate::halo2curves::pasta::{EpAffine, EqAffine};
+
+        let params = ParamsIPA::<EpAffine>::new(3);
+        let mut buffer = vec![];
+        params.write(&mut buffer).unwrap();
+
+        let read = ParamsIPA::<EpAffine>::read(&mut &buffer[..]).unwrap();
+        assert_eq!(read.k, params.k);
+        assert_eq!(read.g, params.g);
+
+        // Parameters for a different curve are rejected
+        let err = ParamsIPA::<EqAffine>::read(&mut &buffer[..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // So are unknown versions
+        let mut future = buffer.clone();
+        future[4] += 1;
+        let err = ParamsIPA::<EpAffine>::read(&mut &future[..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // Unversioned buffers start directly with k
+        let header_len = 4 + 2 + future[5] as usize;
+        let legacy = &buffer[header_len..];
+        let read = ParamsIPA::<EpAffine>::read(&mut &legacy[..]).unwrap();
+        assert_eq!(read.g_lagrange, params.g_lagrange);
+    }
+
     #[test]
     fn test_opening_proof() {
         const K: u32 = 6;
@@ -377,8 +559,20 @@ mod test {
 
             // Test use_g()
             let g = guard.compute_g();
-            let (msm_g, _accumulator) = guard.clone().use_g(g);
+            let (msm_g, accumulator) = guard.clone().use_g(g);
             assert!(msm_g.check());
+
+            // Test the deferred check on a serialized accumulator
+            assert_eq!(accumulator.g, guard.to_accumulator().g);
+            let mut bytes = vec![];
+            accumulator.write(&mut bytes).unwrap();
+            let accumulator = Accumulator::<EpAffine>::read(&mut &bytes[..]).unwrap();
+            assert_eq!(accumulator.u_packed, guard.challenges());
+            assert!(accumulator.check(&params));
+
+            let mut tampered = accumulator;
+            tampered.u_packed[0] += Fq::one();
+            assert!(!tampered.check(&params));
         }
     }
 }
@@ -90,6 +90,11 @@ impl<'a, C: CurveAffine> MSM<C> for MSMIPA<'a, C> {
         }
     }
 
+    fn reserve(&mut self, _additional: usize) {
+        // Terms are merged by base as they are appended, so the underlying
+        // map has no capacity to reserve.
+    }
+
     /// Add another multiexp into this one
     fn add_msm(&mut self, other: &Self) {
         for (x, (scalar, y)) in other.other.iter() {
@@ -53,8 +53,9 @@ impl<'params, C: CurveAffine> Prover<'params, IPACommitmentScheme<C>> for Prover
             let mut accumulate = |set_idx: usize,
                                   new_poly: &Polynomial<C::Scalar, Coeff>,
                                   blind: Blind<C::Scalar>| {
-                if let Some(poly) = &q_polys[set_idx] {
-                    q_polys[set_idx] = Some(poly.clone() * *x_1 + new_poly);
+                if let Some(poly) = &mut q_polys[set_idx] {
+                    *poly *= *x_1;
+                    *poly += new_poly;
                 } else {
                     q_polys[set_idx] = Some(new_poly.clone());
                 }
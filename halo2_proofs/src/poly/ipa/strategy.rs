@@ -1,8 +1,10 @@
+use std::io;
 use std::marker::PhantomData;
 
 use super::commitment::{IPACommitmentScheme, ParamsIPA, ParamsVerifierIPA};
 use super::msm::MSMIPA;
 use super::multiopen::VerifierIPA;
+use crate::helpers::CurveRead;
 use crate::poly::commitment::CommitmentScheme;
 use crate::transcript::TranscriptRead;
 use crate::{
@@ -14,7 +16,7 @@ use crate::{
     },
     transcript::EncodedChallenge,
 };
-use ff::Field;
+use ff::{Field, PrimeField};
 use group::Curve;
 use halo2curves::CurveAffine;
 use rand_core::{OsRng, RngCore};
@@ -39,6 +41,52 @@ pub struct Accumulator<C: CurveAffine> {
     pub u_packed: Vec<C::Scalar>,
 }
 
+impl<C: CurveAffine> Accumulator<C> {
+    /// Performs the deferred linear-time check that `g` is the commitment to
+    /// the polynomial defined by the challenges, i.e. `g = ⟨s, params.g⟩`.
+    pub fn check(&self, params: &ParamsIPA<C>) -> bool {
+        if self.u_packed.len() != params.k as usize {
+            return false;
+        }
+        let s = compute_s(&self.u_packed, C::Scalar::one());
+        best_multiexp(&s, &params.g).to_affine() == self.g
+    }
+
+    /// Writes the accumulator to a buffer: the number of challenges, the
+    /// compressed point `g` and the challenges.
+    pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&(self.u_packed.len() as u32).to_le_bytes())?;
+        writer.write_all(self.g.to_bytes().as_ref())?;
+        for u in self.u_packed.iter() {
+            writer.write_all(u.to_repr().as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// Reads an accumulator written by [`Self::write`].
+    pub fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let mut k = [0u8; 4];
+        reader.read_exact(&mut k[..])?;
+        let k = u32::from_le_bytes(k);
+
+        let g = C::read(reader)?;
+        let u_packed = (0..k)
+            .map(|_| {
+                let mut repr = <C::Scalar as PrimeField>::Repr::default();
+                reader.read_exact(repr.as_mut())?;
+                Option::from(C::Scalar::from_repr(repr)).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::Other,
+                        "Invalid scalar encoding in accumulator",
+                    )
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Accumulator { g, u_packed })
+    }
+}
+
 /// Define accumulator type as `MSMIPA`
 impl<'params, C: CurveAffine> Guard<IPACommitmentScheme<C>> for GuardIPA<'params, C> {
     type MSMAccumulator = MSMIPA<'params, C>;
@@ -68,6 +116,21 @@ impl<'params, C: CurveAffine> GuardIPA<'params, C> {
         (self.msm, accumulator)
     }
 
+    /// Returns the challenges u_0, ..., u_{k - 1} of the opening argument.
+    pub fn challenges(&self) -> &[C::Scalar] {
+        &self.u_packed
+    }
+
+    /// Computes the accumulator of this opening, for a prover that passes it
+    /// on to the next proof. This computes `G` in linear time; verifiers that
+    /// defer the check should use [`Self::use_g`] with the claimed `G`.
+    pub fn to_accumulator(&self) -> Accumulator<C> {
+        Accumulator {
+            g: self.compute_g(),
+            u_packed: self.u_packed.clone(),
+        }
+    }
+
     /// Computes G = ⟨s, params.g⟩
     pub fn compute_g(&self) -> C {
         let s = compute_s(&self.u, C::Scalar::one());
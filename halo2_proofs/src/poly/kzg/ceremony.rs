@@ -0,0 +1,850 @@
+//! Importers for the outputs of public powers-of-tau ceremonies.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use blake2b_simd::Params as Blake2bParams;
+use ff::{Field, PrimeField};
+use group::{prime::PrimeCurveAffine, Curve, GroupEncoding};
+use halo2curves::bn256::{Bn256, Fq, Fq2, G1Affine, G2Affine};
+use halo2curves::pairing::{Engine, MillerLoopResult, MultiMillerLoop};
+use halo2curves::serde::SerdeObject;
+use once_cell::sync::OnceCell;
+use rand_core::OsRng;
+
+use super::commitment::{lagrange_cell, ParamsKZG};
+use crate::arithmetic::{best_fft, best_multiexp, g_to_lagrange, CurveAffine, FieldExt};
+use crate::helpers::SerdeCurveAffine;
+use crate::SerdeFormat;
+
+/// Section holding the header of a snarkjs `.ptau` file.
+const PTAU_HEADER: u32 = 1;
+/// Section holding `[tau^i] G1`.
+const PTAU_TAU_G1: u32 = 2;
+/// Section holding `[tau^i] G2`.
+const PTAU_TAU_G2: u32 = 3;
+/// Section holding the Lagrange basis in G1, present in "prepared" files.
+const PTAU_LAGRANGE_G1: u32 = 12;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_u32<R: io::Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: io::Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+impl<E: Engine + Debug> ParamsKZG<E>
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    /// Reads parameters for `2^k` rows from a snarkjs powers-of-tau (`.ptau`)
+    /// file, such as the outputs of the Hermez or perpetual powers of tau
+    /// ceremonies.
+    ///
+    /// Only the `[tau^i] G1` and `[tau^i] G2` sections are used. If the file
+    /// has been prepared for phase 2 and contains a Lagrange basis, it is used
+    /// as long as it was computed over the same evaluation domain as this
+    /// crate uses; otherwise the Lagrange basis is computed with an FFT.
+    ///
+    /// Points are read without their (trusted) ceremony attestations being
+    /// checked; only their encoding and membership of the curve is verified.
+    pub fn read_ptau<R: io::Read + io::Seek>(reader: &mut R, k: u32) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != b"ptau" {
+            return Err(invalid_data("not a ptau file"));
+        }
+        let _version = read_u32(reader)?;
+
+        // Record where each section starts so that they can be read in the
+        // order we need them
+        let num_sections = read_u32(reader)?;
+        let mut sections = HashMap::new();
+        for _ in 0..num_sections {
+            let section = read_u32(reader)?;
+            let size = read_u64(reader)?;
+            let start = reader.stream_position()?;
+            sections.insert(section, (start, size));
+            reader.seek(SeekFrom::Current(size as i64))?;
+        }
+        let seek_section = |reader: &mut R, section: u32| -> io::Result<u64> {
+            let (start, size) = *sections
+                .get(&section)
+                .ok_or_else(|| invalid_data("missing ptau section"))?;
+            reader.seek(SeekFrom::Start(start))?;
+            Ok(size)
+        };
+
+        seek_section(reader, PTAU_HEADER)?;
+        let n8 = read_u32(reader)? as usize;
+        let g1_size = E::G1Affine::default().to_raw_bytes().len();
+        if 2 * n8 != g1_size {
+            return Err(invalid_data("ptau file is for a different curve"));
+        }
+        let mut modulus = vec![0u8; n8];
+        reader.read_exact(&mut modulus)?;
+        let power = read_u32(reader)?;
+        if k > power || k > E::Scalar::S {
+            return Err(invalid_data("ptau file does not contain enough powers"));
+        }
+        let n = 1usize << k;
+
+        let read_g1 = |reader: &mut R, len: usize| -> io::Result<Vec<E::G1Affine>> {
+            (0..len)
+                .map(|_| <E::G1Affine as SerdeCurveAffine>::read(reader, SerdeFormat::RawBytes))
+                .collect()
+        };
+
+        seek_section(reader, PTAU_TAU_G1)?;
+        let g = read_g1(reader, n)?;
+
+        seek_section(reader, PTAU_TAU_G2)?;
+        let g2 = <E::G2Affine as SerdeCurveAffine>::read(reader, SerdeFormat::RawBytes)?;
+        let s_g2 = <E::G2Affine as SerdeCurveAffine>::read(reader, SerdeFormat::RawBytes)?;
+
+        if g[0] != E::G1Affine::generator() || g2 != E::G2Affine::generator() {
+            return Err(invalid_data("ptau file uses unexpected generators"));
+        }
+
+        // The Lagrange section stores the bases for every domain size
+        // `2^0, 2^1, ..., 2^power` one after the other
+        let g_lagrange = match seek_section(reader, PTAU_LAGRANGE_G1) {
+            Ok(_) => {
+                let offset = ((n - 1) * g1_size) as i64;
+                reader.seek(SeekFrom::Current(offset))?;
+                let g_lagrange = read_g1(reader, n)?;
+                if Self::lagrange_matches_domain(&g, &g_lagrange, k) {
+                    Some(g_lagrange)
+                } else {
+                    None
+                }
+            }
+            Err(_) => None,
+        };
+        let g_lagrange = g_lagrange.map(lagrange_cell).unwrap_or_default();
+
+        Ok(Self {
+            k,
+            n: n as u64,
+            g,
+            g_lagrange,
+            g2,
+            s_g2,
+            msm_tables: None,
+        })
+    }
+
+    /// Checks that `g_lagrange` was computed with the same `2^k`-th root of
+    /// unity as [`EvaluationDomain`](crate::poly::EvaluationDomain), by
+    /// recomputing `L_1(tau) = 1/n * sum_j omega^{-j} tau^j` with a single
+    /// multiexp.
+    fn lagrange_matches_domain(g: &[E::G1Affine], g_lagrange: &[E::G1Affine], k: u32) -> bool {
+        if k == 0 {
+            return g[0] == g_lagrange[0];
+        }
+
+        let mut omega_inv = E::Scalar::ROOT_OF_UNITY_INV;
+        for _ in k..E::Scalar::S {
+            omega_inv = omega_inv.square();
+        }
+        let n_inv = E::Scalar::TWO_INV.pow_vartime(&[k as u64, 0, 0, 0]);
+
+        let mut scalars = Vec::with_capacity(g.len());
+        let mut acc = n_inv;
+        for _ in 0..g.len() {
+            scalars.push(acc);
+            acc *= omega_inv;
+        }
+
+        best_multiexp(&scalars, g).to_affine() == g_lagrange[1]
+    }
+}
+
+/// Number of consecutive powers checked per batch by
+/// [`ParamsKZG::verify_srs`], bounding the memory used for random factors.
+const SRS_BATCH_SIZE: usize = 1 << 16;
+
+impl<E: MultiMillerLoop + Debug> ParamsKZG<E> {
+    /// Checks that `g` holds consecutive powers of the secret committed to by
+    /// `s_g2`, that is `e(g[i], [s] G2) == e(g[i + 1], G2)` for all `i`.
+    ///
+    /// The equations are combined with random factors in batches of
+    /// `SRS_BATCH_SIZE`; every batch costs two multiexps, and a single final
+    /// exponentiation is shared by all of them.
+    pub(crate) fn powers_are_consistent(&self) -> bool {
+        if self.g.len() < 2 {
+            return true;
+        }
+
+        let mut lefts = vec![];
+        let mut rights = vec![];
+        for start in (0..self.g.len() - 1).step_by(SRS_BATCH_SIZE) {
+            let end = (start + SRS_BATCH_SIZE).min(self.g.len() - 1);
+            let factors = (start..end)
+                .map(|_| E::Scalar::random(OsRng))
+                .collect::<Vec<_>>();
+            lefts.push(best_multiexp(&factors, &self.g[start..end]).to_affine());
+            rights.push((-best_multiexp(&factors, &self.g[start + 1..end + 1])).to_affine());
+        }
+
+        let s_g2 = E::G2Prepared::from(self.s_g2);
+        let g2 = E::G2Prepared::from(self.g2);
+        let terms = lefts
+            .iter()
+            .map(|left| (left, &s_g2))
+            .chain(rights.iter().map(|right| (right, &g2)))
+            .collect::<Vec<_>>();
+        bool::from(
+            E::multi_miller_loop(&terms[..])
+                .final_exponentiation()
+                .is_identity(),
+        )
+    }
+
+    /// Checks that the Lagrange basis is the inverse FFT of the powers of the
+    /// secret, with a single random linear combination: for random `r`,
+    /// `sum_i r_i L_i(s) == sum_j c_j s^j` where `c = IFFT(r)`.
+    fn lagrange_is_consistent(&self, g_lagrange: &[E::G1Affine]) -> bool {
+        if g_lagrange.len() != self.g.len() {
+            return false;
+        }
+
+        let r = (0..self.g.len())
+            .map(|_| E::Scalar::random(OsRng))
+            .collect::<Vec<_>>();
+
+        let mut omega_inv = E::Scalar::ROOT_OF_UNITY_INV;
+        for _ in self.k..E::Scalar::S {
+            omega_inv = omega_inv.square();
+        }
+        let n_inv = E::Scalar::TWO_INV.pow_vartime(&[self.k as u64, 0, 0, 0]);
+        let mut c = r.clone();
+        best_fft(&mut c, omega_inv, self.k);
+        for c in c.iter_mut() {
+            *c *= n_inv;
+        }
+
+        best_multiexp(&r, g_lagrange) == best_multiexp(&c, &self.g)
+    }
+
+    /// Returns a canonical digest of the parameters, covering `k`, every
+    /// power of the secret in G1 and both G2 elements in their compressed
+    /// encoding. The Lagrange basis is derived data and is not included.
+    pub fn srs_digest(&self) -> [u8; 32] {
+        let mut hasher = Blake2bParams::new()
+            .hash_length(32)
+            .personal(b"Halo2-KZG-SRS-v1")
+            .to_state();
+        hasher.update(&self.k.to_le_bytes());
+        for g in self.g.iter() {
+            hasher.update(g.to_bytes().as_ref());
+        }
+        hasher.update(self.g2.to_bytes().as_ref());
+        hasher.update(self.s_g2.to_bytes().as_ref());
+        hasher.finalize().as_bytes().try_into().unwrap()
+    }
+
+    /// Checks the structural validity of the parameters and returns their
+    /// [canonical digest](Self::srs_digest), which can then be compared
+    /// against the digest published for a ceremony.
+    ///
+    /// This checks that the parameters use the standard generators, that the
+    /// secret is not trivial, that the G1 points are consecutive powers of
+    /// the secret committed to in G2 (with randomized pairing checks), and
+    /// that the Lagrange basis, if it has been loaded or computed, matches.
+    pub fn verify_srs(&self) -> io::Result<[u8; 32]> {
+        if self.k > E::Scalar::S || self.n != 1 << self.k || self.g.len() as u64 != self.n {
+            return Err(invalid_data("SRS has an inconsistent size"));
+        }
+        if self.g[0] != E::G1Affine::generator() || self.g2 != E::G2Affine::generator() {
+            return Err(invalid_data("SRS uses unexpected generators"));
+        }
+        if bool::from(self.s_g2.is_identity()) || self.s_g2 == self.g2 {
+            return Err(invalid_data("SRS secret is trivial"));
+        }
+        if !self.powers_are_consistent() {
+            return Err(invalid_data("SRS points are not consecutive powers"));
+        }
+        if let Some(g_lagrange) = self.g_lagrange.get() {
+            if !self.lagrange_is_consistent(g_lagrange) {
+                return Err(invalid_data("SRS Lagrange basis does not match its powers"));
+            }
+        }
+        Ok(self.srs_digest())
+    }
+}
+
+/// Decodes a big-endian base field element as written by `bellman` and the
+/// perpetual powers of tau tooling.
+fn fq_from_be(bytes: &[u8]) -> io::Result<Fq> {
+    let mut repr = [0u8; 32];
+    repr.copy_from_slice(bytes);
+    repr.reverse();
+    Option::from(Fq::from_repr(repr)).ok_or_else(|| invalid_data("invalid field element encoding"))
+}
+
+/// Decodes a base field element stored by Aztec in Montgomery form, as four
+/// 64-bit big-endian limbs in little-endian limb order.
+fn fq_from_aztec(bytes: &[u8]) -> io::Result<Fq> {
+    let mut raw = [0u8; 32];
+    for (limb, chunk) in raw.chunks_mut(8).zip(bytes.chunks(8)) {
+        limb.copy_from_slice(chunk);
+        limb.reverse();
+    }
+    Fq::from_raw_bytes(&raw).ok_or_else(|| invalid_data("invalid field element encoding"))
+}
+
+/// Lexicographic order `bellman` uses to choose between `y` and `-y`.
+fn fq_is_greater(a: &Fq, b: &Fq) -> bool {
+    let (mut a, mut b) = (a.to_repr(), b.to_repr());
+    a.reverse();
+    b.reverse();
+    a > b
+}
+
+fn fq2_is_greater(a: &Fq2, b: &Fq2) -> bool {
+    if a.c1 == b.c1 {
+        fq_is_greater(&a.c0, &b.c0)
+    } else {
+        fq_is_greater(&a.c1, &b.c1)
+    }
+}
+
+fn point<C: CurveAffine>(x: C::Base, y: C::Base) -> io::Result<C> {
+    Option::from(C::from_xy(x, y)).ok_or_else(|| invalid_data("point is not on the curve"))
+}
+
+/// Recovers `y` from `x` given the `bellman` "greatest" flag.
+fn decompress<C: CurveAffine>(
+    x: C::Base,
+    greatest: bool,
+    is_greater: impl Fn(&C::Base, &C::Base) -> bool,
+) -> io::Result<C> {
+    let y = Option::<C::Base>::from((x.square() * x + C::b()).sqrt())
+        .ok_or_else(|| invalid_data("point is not on the curve"))?;
+    let y = if is_greater(&y, &-y) == greatest {
+        y
+    } else {
+        -y
+    };
+    point(x, y)
+}
+
+/// Reads a G1 point in the `bellman` BN254 encoding.
+fn read_bellman_g1<R: io::Read>(reader: &mut R, compressed: bool) -> io::Result<G1Affine> {
+    let mut bytes = [0u8; 64];
+    let bytes = &mut bytes[..if compressed { 32 } else { 64 }];
+    reader.read_exact(bytes)?;
+
+    let flags = bytes[0];
+    bytes[0] &= 0x3f;
+    if flags & 0x40 != 0 {
+        return Ok(G1Affine::identity());
+    }
+
+    let x = fq_from_be(&bytes[0..32])?;
+    if compressed {
+        decompress(x, flags & 0x80 != 0, fq_is_greater)
+    } else {
+        point(x, fq_from_be(&bytes[32..64])?)
+    }
+}
+
+/// Reads a G2 point in the `bellman` BN254 encoding, in which the `c1`
+/// coefficient of each coordinate comes first.
+fn read_bellman_g2<R: io::Read>(reader: &mut R, compressed: bool) -> io::Result<G2Affine> {
+    let mut bytes = [0u8; 128];
+    let bytes = &mut bytes[..if compressed { 64 } else { 128 }];
+    reader.read_exact(bytes)?;
+
+    let flags = bytes[0];
+    bytes[0] &= 0x3f;
+    if flags & 0x40 != 0 {
+        return Ok(G2Affine::identity());
+    }
+
+    let x = Fq2 {
+        c0: fq_from_be(&bytes[32..64])?,
+        c1: fq_from_be(&bytes[0..32])?,
+    };
+    if compressed {
+        decompress(x, flags & 0x80 != 0, fq2_is_greater)
+    } else {
+        let y = Fq2 {
+            c0: fq_from_be(&bytes[96..128])?,
+            c1: fq_from_be(&bytes[64..96])?,
+        };
+        point(x, y)
+    }
+}
+
+fn read_aztec_g1<R: io::Read>(reader: &mut R) -> io::Result<G1Affine> {
+    let mut bytes = [0u8; 64];
+    reader.read_exact(&mut bytes)?;
+    point(
+        fq_from_aztec(&bytes[0..32])?,
+        fq_from_aztec(&bytes[32..64])?,
+    )
+}
+
+fn read_aztec_g2<R: io::Read>(reader: &mut R) -> io::Result<G2Affine> {
+    let mut bytes = [0u8; 128];
+    reader.read_exact(&mut bytes)?;
+    let x = Fq2 {
+        c0: fq_from_aztec(&bytes[0..32])?,
+        c1: fq_from_aztec(&bytes[32..64])?,
+    };
+    let y = Fq2 {
+        c0: fq_from_aztec(&bytes[64..96])?,
+        c1: fq_from_aztec(&bytes[96..128])?,
+    };
+    point(x, y)
+}
+
+fn skip<R: io::Read>(reader: &mut R, len: u64) -> io::Result<()> {
+    let skipped = io::copy(&mut reader.take(len), &mut io::sink())?;
+    if skipped != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+impl ParamsKZG<Bn256> {
+    /// Reads parameters for `2^k` rows from a perpetual powers of tau
+    /// `challenge` (uncompressed) or `response` (compressed) file produced by
+    /// a ceremony of size `2^power`.
+    ///
+    /// The parameters are checked to be consecutive powers of the same secret
+    /// with a pairing check before being returned.
+    pub fn read_ppot<R: io::Read>(
+        reader: &mut R,
+        power: u32,
+        k: u32,
+        compressed: bool,
+    ) -> io::Result<Self> {
+        if k > power {
+            return Err(invalid_data("ceremony does not contain enough powers"));
+        }
+        let n = 1usize << k;
+        let g1_size = if compressed { 32 } else { 64 };
+
+        // A response file starts with the hash of the challenge it answers
+        skip(reader, 64)?;
+
+        let g = (0..n)
+            .map(|_| read_bellman_g1(reader, compressed))
+            .collect::<io::Result<Vec<_>>>()?;
+        let tau_g1_len = (1u64 << (power + 1)) - 1;
+        skip(reader, (tau_g1_len - n as u64) * g1_size)?;
+
+        let g2 = read_bellman_g2(reader, compressed)?;
+        let s_g2 = read_bellman_g2(reader, compressed)?;
+
+        Self::from_ceremony(k, g, g2, s_g2)
+    }
+
+    /// Reads parameters for `2^k` rows from the Aztec Ignition ceremony
+    /// transcripts (`transcript00.dat`, `transcript01.dat`, ...), given in
+    /// order. Only as many transcripts as are needed for `2^k` points are
+    /// read.
+    ///
+    /// The parameters are checked to be consecutive powers of the same secret
+    /// with a pairing check before being returned.
+    pub fn read_aztec_ignition<R: io::Read>(
+        transcripts: impl IntoIterator<Item = R>,
+        k: u32,
+    ) -> io::Result<Self> {
+        let n = 1usize << k;
+
+        // The transcripts omit the generator and start at `[x] G1`
+        let mut g = Vec::with_capacity(n);
+        g.push(G1Affine::generator());
+        let mut s_g2 = None;
+
+        for mut transcript in transcripts {
+            if g.len() == n && s_g2.is_some() {
+                break;
+            }
+
+            let mut manifest = [0u32; 7];
+            for field in manifest.iter_mut() {
+                let mut bytes = [0u8; 4];
+                transcript.read_exact(&mut bytes)?;
+                *field = u32::from_be_bytes(bytes);
+            }
+            let [_, _, _, _, num_g1_points, num_g2_points, start_from] = manifest;
+            if start_from as usize != g.len() - 1 {
+                return Err(invalid_data("Ignition transcripts are out of order"));
+            }
+
+            let wanted = (n - g.len()).min(num_g1_points as usize);
+            for _ in 0..wanted {
+                g.push(read_aztec_g1(&mut transcript)?);
+            }
+            if num_g2_points > 0 {
+                skip(
+                    &mut transcript,
+                    (num_g1_points as usize - wanted) as u64 * 64,
+                )?;
+                s_g2 = Some(read_aztec_g2(&mut transcript)?);
+            }
+        }
+
+        if g.len() != n {
+            return Err(invalid_data("ceremony does not contain enough powers"));
+        }
+        let s_g2 = s_g2.ok_or_else(|| invalid_data("missing [x] G2 in transcript 0"))?;
+
+        Self::from_ceremony(k, g, G2Affine::generator(), s_g2)
+    }
+
+    fn from_ceremony(k: u32, g: Vec<G1Affine>, g2: G2Affine, s_g2: G2Affine) -> io::Result<Self> {
+        if g[0] != G1Affine::generator() || g2 != G2Affine::generator() {
+            return Err(invalid_data("ceremony uses unexpected generators"));
+        }
+
+        let params = Self {
+            k,
+            n: 1 << k,
+            g,
+            g_lagrange: OnceCell::new(),
+            g2,
+            s_g2,
+            msm_tables: None,
+        };
+        if !params.powers_are_consistent() {
+            return Err(invalid_data("ceremony points are not consecutive powers"));
+        }
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ParamsKZG;
+    use crate::arithmetic::CurveAffine;
+    use crate::helpers::SerdeCurveAffine;
+    use crate::SerdeFormat;
+    use ff::PrimeField;
+    use halo2curves::bn256::{Bn256, Fq, G1Affine, G2Affine};
+    use halo2curves::serde::SerdeObject;
+    use rand_core::OsRng;
+    use std::io::{self, Cursor};
+
+    fn section(out: &mut Vec<u8>, id: u32, data: &[u8]) {
+        out.extend_from_slice(&id.to_le_bytes());
+        out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        out.extend_from_slice(data);
+    }
+
+    fn write_points(points: &[G1Affine]) -> Vec<u8> {
+        let mut out = vec![];
+        for point in points {
+            point.write(&mut out, SerdeFormat::RawBytes).unwrap();
+        }
+        out
+    }
+
+    /// Encodes `params` as a snarkjs ptau file with `power = params.k`
+    fn to_ptau(params: &ParamsKZG<Bn256>, lagrange: bool) -> Vec<u8> {
+        let mut header = vec![];
+        header.extend_from_slice(&32u32.to_le_bytes());
+        header.extend_from_slice(&[0u8; 32]);
+        header.extend_from_slice(&params.k.to_le_bytes());
+        header.extend_from_slice(&params.k.to_le_bytes());
+
+        let mut tau_g2 = vec![];
+        params.g2.write(&mut tau_g2, SerdeFormat::RawBytes).unwrap();
+        params
+            .s_g2
+            .write(&mut tau_g2, SerdeFormat::RawBytes)
+            .unwrap();
+
+        let mut out = b"ptau".to_vec();
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&(if lagrange { 4u32 } else { 3u32 }).to_le_bytes());
+        section(&mut out, super::PTAU_HEADER, &header);
+        section(&mut out, super::PTAU_TAU_G1, &write_points(&params.g));
+        section(&mut out, super::PTAU_TAU_G2, &tau_g2);
+        if lagrange {
+            // Smaller domains are not needed to load `k`; fill them with the
+            // generator
+            let mut points = vec![params.g[0]; params.g.len() - 1];
+            points.extend_from_slice(params.g_lagrange());
+            section(&mut out, super::PTAU_LAGRANGE_G1, &write_points(&points));
+        }
+        out
+    }
+
+    #[test]
+    fn test_read_ptau() {
+        const K: u32 = 4;
+
+        let params = ParamsKZG::<Bn256>::setup(K, OsRng);
+        for lagrange in [false, true] {
+            let ptau = to_ptau(&params, lagrange);
+            let read = ParamsKZG::<Bn256>::read_ptau(&mut Cursor::new(&ptau), K).unwrap();
+            assert_eq!(read.g, params.g);
+            assert_eq!(read.g_lagrange(), params.g_lagrange());
+            assert_eq!(read.g2, params.g2);
+            assert_eq!(read.s_g2, params.s_g2);
+
+            let smaller = ParamsKZG::<Bn256>::read_ptau(&mut Cursor::new(&ptau), K - 1).unwrap();
+            assert_eq!(smaller.g[..], params.g[..1 << (K - 1)]);
+
+            assert!(ParamsKZG::<Bn256>::read_ptau(&mut Cursor::new(&ptau), K + 1).is_err());
+        }
+    }
+
+    fn fq_to_be(value: &Fq) -> [u8; 32] {
+        let mut bytes = value.to_repr();
+        bytes.reverse();
+        bytes
+    }
+
+    fn fq_to_aztec(value: &Fq) -> Vec<u8> {
+        value
+            .to_raw_bytes()
+            .chunks(8)
+            .flat_map(|limb| limb.iter().rev().copied())
+            .collect()
+    }
+
+    /// Encodes a G1 point as `bellman` does
+    fn bellman_g1(point: &G1Affine, compressed: bool) -> Vec<u8> {
+        let coords = point.coordinates().unwrap();
+        let mut out = fq_to_be(coords.x()).to_vec();
+        if compressed {
+            if super::fq_is_greater(coords.y(), &-*coords.y()) {
+                out[0] |= 0x80;
+            }
+        } else {
+            out.extend_from_slice(&fq_to_be(coords.y()));
+        }
+        out
+    }
+
+    /// Encodes a G2 point as `bellman` does, with the `c1` coefficients first
+    fn bellman_g2(point: &G2Affine, compressed: bool) -> Vec<u8> {
+        let coords = point.coordinates().unwrap();
+        let mut out = fq_to_be(&coords.x().c1).to_vec();
+        out.extend_from_slice(&fq_to_be(&coords.x().c0));
+        if compressed {
+            if super::fq2_is_greater(coords.y(), &-*coords.y()) {
+                out[0] |= 0x80;
+            }
+        } else {
+            out.extend_from_slice(&fq_to_be(&coords.y().c1));
+            out.extend_from_slice(&fq_to_be(&coords.y().c0));
+        }
+        out
+    }
+
+    /// Encodes the powers of `params` as a perpetual powers of tau file of a
+    /// ceremony of size `2^power`, up to the `[tau] G2` it holds
+    fn to_ppot(params: &ParamsKZG<Bn256>, power: u32, compressed: bool) -> Vec<u8> {
+        let mut out = vec![0u8; 64];
+        for point in &params.g[..(1 << (power + 1)) - 1] {
+            out.extend(bellman_g1(point, compressed));
+        }
+        out.extend(bellman_g2(&params.g2, compressed));
+        out.extend(bellman_g2(&params.s_g2, compressed));
+        out
+    }
+
+    /// Encodes the powers of `params` as Aztec Ignition transcripts, the
+    /// first of which holds `first` G1 points and `[x] G2`
+    fn to_ignition(params: &ParamsKZG<Bn256>, first: usize) -> Vec<Vec<u8>> {
+        let points = &params.g[1..];
+        [(&points[..first], 0), (&points[first..], first)]
+            .iter()
+            .enumerate()
+            .map(|(index, (points, start_from))| {
+                let num_g2_points = if index == 0 { 1 } else { 0 };
+                let mut out = vec![];
+                for field in [
+                    index as u32,
+                    2,
+                    (params.g.len() - 1) as u32,
+                    1,
+                    points.len() as u32,
+                    num_g2_points,
+                    *start_from as u32,
+                ] {
+                    out.extend_from_slice(&field.to_be_bytes());
+                }
+                for point in points.iter() {
+                    let coords = point.coordinates().unwrap();
+                    out.extend(fq_to_aztec(coords.x()));
+                    out.extend(fq_to_aztec(coords.y()));
+                }
+                if num_g2_points > 0 {
+                    let coords = params.s_g2.coordinates().unwrap();
+                    for value in [coords.x().c0, coords.x().c1, coords.y().c0, coords.y().c1] {
+                        out.extend(fq_to_aztec(&value));
+                    }
+                }
+                out
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_read_ppot() {
+        const POWER: u32 = 3;
+
+        let params = ParamsKZG::<Bn256>::setup(POWER + 1, OsRng);
+        for compressed in [false, true] {
+            let ppot = to_ppot(&params, POWER, compressed);
+            for k in [POWER, POWER - 1] {
+                let read =
+                    ParamsKZG::<Bn256>::read_ppot(&mut Cursor::new(&ppot), POWER, k, compressed)
+                        .unwrap();
+                assert_eq!(read.g[..], params.g[..1 << k]);
+                assert_eq!(read.s_g2, params.s_g2);
+            }
+            assert!(ParamsKZG::<Bn256>::read_ppot(
+                &mut Cursor::new(&ppot),
+                POWER,
+                POWER + 1,
+                compressed
+            )
+            .is_err());
+
+            // Truncated before `[tau] G2`
+            let truncated = &ppot[..ppot.len() - 1];
+            assert_eq!(
+                ParamsKZG::<Bn256>::read_ppot(
+                    &mut Cursor::new(truncated),
+                    POWER,
+                    POWER,
+                    compressed
+                )
+                .unwrap_err()
+                .kind(),
+                io::ErrorKind::UnexpectedEof
+            );
+
+            // A corrupt power, either off the curve or not a power of tau
+            let mut corrupt = ppot.clone();
+            corrupt[64 + 2 * bellman_g1(&params.g[0], compressed).len() + 31] ^= 1;
+            assert!(ParamsKZG::<Bn256>::read_ppot(
+                &mut Cursor::new(&corrupt),
+                POWER,
+                POWER,
+                compressed
+            )
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_read_aztec_ignition() {
+        const K: u32 = 4;
+
+        let params = ParamsKZG::<Bn256>::setup(K, OsRng);
+        let transcripts = to_ignition(&params, 8);
+        let read = |transcripts: &[Vec<u8>], k| {
+            ParamsKZG::<Bn256>::read_aztec_ignition(transcripts.iter().map(Cursor::new), k)
+        };
+
+        let full = read(&transcripts, K).unwrap();
+        assert_eq!(full.g, params.g);
+        assert_eq!(full.s_g2, params.s_g2);
+        // The first transcript is enough for fewer points
+        assert_eq!(read(&transcripts[..1], K - 1).unwrap().g[..], params.g[..8]);
+
+        assert!(read(&transcripts[..1], K).is_err());
+        assert!(read(&[transcripts[1].clone(), transcripts[0].clone()], K).is_err());
+
+        let mut truncated = transcripts.clone();
+        truncated[1].pop();
+        assert_eq!(
+            read(&truncated, K).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        let mut corrupt = transcripts;
+        corrupt[1][28 + 5] ^= 1;
+        assert!(read(&corrupt, K).is_err());
+    }
+
+    #[test]
+    fn test_read_aztec_points() {
+        use ff::Field;
+        use group::prime::PrimeCurveAffine;
+
+        fn from_hex(hex: &str) -> Vec<u8> {
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect()
+        }
+
+        // The generators as barretenberg writes them, in Montgomery form,
+        // computed independently of `fq_to_aztec`. The first points of
+        // `transcript00.dat` are not generators, but are encoded the same way.
+        let g1 = from_hex(concat!(
+            "d35d438dc58f0d9d0a78eb28f5c70b3d666ea36f7879462c0e0a77c19a07df2f",
+            "a6ba871b8b1e1b3a14f1d651eb8e167bccdd46def0f28c581c14ef83340fbe5e",
+        ));
+        let g2 = from_hex(concat!(
+            "8e83b5d102bc2026dceb1935497b0172fbb8264797811adf19573841af96503b",
+            "afb4737da84c61406043dd5a5802d8c409e950fc52a02f8614fef0833aea7b6b",
+            "619dfa9d886be9f6fe7fd297f59e9b78ff9e1a62231b7dfe28fd7eebae9e4206",
+            "64095b56c71856eedc57f922327d3cbb55f935be333510760da4a0e693fd6482",
+        ));
+        assert_eq!(
+            super::read_aztec_g1(&mut &g1[..]).unwrap(),
+            G1Affine::generator()
+        );
+        assert_eq!(
+            super::read_aztec_g2(&mut &g2[..]).unwrap(),
+            G2Affine::generator()
+        );
+        assert_eq!(fq_to_aztec(&Fq::one()), g1[..32]);
+    }
+
+    #[test]
+    fn test_powers_are_consistent() {
+        use group::{prime::PrimeCurveAffine, Curve};
+
+        let mut params = ParamsKZG::<Bn256>::setup(4, OsRng);
+        assert!(params.powers_are_consistent());
+
+        params.g[3] = (params.g[3].to_curve() + params.g[0]).to_affine();
+        assert!(!params.powers_are_consistent());
+    }
+
+    #[test]
+    fn test_verify_srs() {
+        let params = ParamsKZG::<Bn256>::setup(4, OsRng);
+        let digest = params.verify_srs().unwrap();
+
+        let mut lazy = vec![];
+        params
+            .write_custom(&mut lazy, SerdeFormat::RawBytes)
+            .unwrap();
+        let lazy = ParamsKZG::<Bn256>::read_lazy(&mut &lazy[..], SerdeFormat::RawBytes).unwrap();
+        assert_eq!(lazy.verify_srs().unwrap(), digest);
+
+        let mut params = params;
+        params.g_lagrange = super::lagrange_cell({
+            let mut g_lagrange = params.g_lagrange().to_vec();
+            g_lagrange.swap(1, 2);
+            g_lagrange
+        });
+        assert!(params.verify_srs().is_err());
+    }
+}
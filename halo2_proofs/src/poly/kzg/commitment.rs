@@ -1,11 +1,13 @@
 use crate::arithmetic::{
-    best_fft, best_multiexp, g_to_lagrange, parallelize, CurveAffine, CurveExt, FieldExt, Group,
+    best_fft, best_multiexp, best_multiexp_chunks, g_to_lagrange, parallelize, CurveAffine,
+    CurveExt, FieldExt, Group, MultiexpTable,
 };
 use crate::helpers::SerdeCurveAffine;
 use crate::poly::commitment::{Blind, CommitmentScheme, Params, ParamsProver, ParamsVerifier, MSM};
 use crate::poly::{Coeff, LagrangeCoeff, Polynomial};
 use crate::SerdeFormat;
 
+use blake2b_simd::Params as Blake2bParams;
 use ff::{Field, PrimeField};
 use group::{prime::PrimeCurveAffine, Curve, Group as _};
 use halo2curves::pairing::Engine;
@@ -13,9 +15,12 @@ use rand_core::{OsRng, RngCore};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::ops::{Add, AddAssign, Mul, MulAssign};
+use std::sync::Arc;
 
 use std::io;
 
+use once_cell::sync::OnceCell;
+
 use super::msm::MSMKZG;
 
 /// These are the public parameters for the polynomial commitment scheme.
@@ -24,18 +29,32 @@ pub struct ParamsKZG<E: Engine> {
     pub(crate) k: u32,
     pub(crate) n: u64,
     pub(crate) g: Vec<E::G1Affine>,
-    pub(crate) g_lagrange: Vec<E::G1Affine>,
+    pub(crate) g_lagrange: OnceCell<Vec<E::G1Affine>>,
     pub(crate) g2: E::G2Affine,
     pub(crate) s_g2: E::G2Affine,
+    pub(crate) msm_tables: Option<Arc<MsmTables<E::G1Affine>>>,
+}
+
+/// Multiexp tables for the monomial and Lagrange bases, shared between
+/// clones of the parameters.
+#[derive(Debug)]
+pub(crate) struct MsmTables<C: CurveAffine> {
+    g: MultiexpTable<C>,
+    g_lagrange: MultiexpTable<C>,
 }
 
 /// Umbrella commitment scheme construction for all KZG variants
+///
+/// The prover parameters are [`ParamsKZG`] by default, and can be any other
+/// [`ParamsProverKZG`], e.g. `MmapParamsKZG` of the `mmap` feature to create
+/// proofs from memory-mapped bases. All of them share the verifier parameters
+/// [`ParamsVerifierKZG`].
 #[derive(Debug)]
-pub struct KZGCommitmentScheme<E: Engine> {
-    _marker: PhantomData<E>,
+pub struct KZGCommitmentScheme<E: Engine, P = ParamsKZG<E>> {
+    _marker: PhantomData<(E, P)>,
 }
 
-impl<E: Engine + Debug> CommitmentScheme for KZGCommitmentScheme<E>
+impl<E: Engine + Debug, P: ParamsProverKZG<E>> CommitmentScheme for KZGCommitmentScheme<E, P>
 where
     E::G1Affine: SerdeCurveAffine,
     E::G2Affine: SerdeCurveAffine,
@@ -43,22 +62,54 @@ where
     type Scalar = E::Scalar;
     type Curve = E::G1Affine;
 
-    type ParamsProver = ParamsKZG<E>;
+    type ParamsProver = P;
     type ParamsVerifier = ParamsVerifierKZG<E>;
 
     fn new_params(k: u32) -> Self::ParamsProver {
-        ParamsKZG::new(k)
+        P::new(k)
     }
 
     fn read_params<R: io::Read>(reader: &mut R) -> io::Result<Self::ParamsProver> {
-        ParamsKZG::read(reader)
+        P::read(reader)
     }
 }
 
+/// KZG parameters that a prover can commit with.
+///
+/// The KZG multiopen provers take any of them: [`ParamsKZG`], which holds the
+/// G1 bases on the heap, or `MmapParamsKZG` of the `mmap` feature, which
+/// reads them from a memory map.
+pub trait ParamsProverKZG<E: Engine>:
+    for<'params> ParamsProver<'params, E::G1Affine, ParamsVerifier = ParamsVerifierKZG<E>> + Debug
+{
+    /// Returns the powers of the secret in G1.
+    fn g(&self) -> &[E::G1Affine];
+
+    /// Returns the Lagrange basis in G1.
+    fn g_lagrange(&self) -> &[E::G1Affine];
+}
+
 impl<E: Engine + Debug> ParamsKZG<E> {
     /// Initializes parameters for the curve, draws toxic secret from given rng.
     /// MUST NOT be used in production.
     pub fn setup<R: RngCore>(k: u32, rng: R) -> Self {
+        Self::setup_with_secret(k, <E::Scalar>::random(rng))
+    }
+
+    /// Initializes parameters for the curve with a toxic secret derived from
+    /// `seed`, so that the same seed yields byte-identical parameters on every
+    /// run and machine. Intended for tests and reproducible benchmarks.
+    /// Anyone who knows the seed can forge proofs: MUST NOT be used in
+    /// production.
+    pub fn setup_from_seed(k: u32, seed: [u8; 32]) -> Self {
+        let hash = Blake2bParams::new()
+            .hash_length(64)
+            .personal(b"Halo2-KZG-Seeded")
+            .hash(&seed);
+        Self::setup_with_secret(k, E::Scalar::from_bytes_wide(hash.as_array()))
+    }
+
+    fn setup_with_secret(k: u32, s: E::Scalar) -> Self {
         // Largest root of unity exponent of the Engine is `2^E::Scalar::S`, so we can
         // only support FFTs of polynomials below degree `2^E::Scalar::S`.
         assert!(k <= E::Scalar::S);
@@ -66,7 +117,6 @@ impl<E: Engine + Debug> ParamsKZG<E> {
 
         // Calculate g = [G1, [s] G1, [s^2] G1, ..., [s^(n-1)] G1] in parallel.
         let g1 = E::G1Affine::generator();
-        let s = <E::Scalar>::random(rng);
 
         let mut g_projective = vec![E::G1::group_zero(); n as usize];
         parallelize(&mut g_projective, |g, start| {
@@ -122,9 +172,54 @@ impl<E: Engine + Debug> ParamsKZG<E> {
             k,
             n,
             g,
-            g_lagrange,
+            g_lagrange: lagrange_cell(g_lagrange),
             g2,
             s_g2,
+            msm_tables: None,
+        }
+    }
+
+    /// Precomputes [`MultiexpTable`]s with digits of `window` bits for both
+    /// bases, that all subsequent commitments with these parameters (and
+    /// their clones) use instead of a Pippenger multiexp.
+    ///
+    /// This trades `ceil(NUM_BITS / window)` times the memory of the bases for
+    /// skipping the bucket doublings of every commitment, which pays off when
+    /// many columns are committed against the same parameters.
+    pub fn precompute_msm_tables(&mut self, window: usize) {
+        let g = MultiexpTable::new(&self.g, window);
+        let g_lagrange = MultiexpTable::new(self.g_lagrange(), window);
+        self.msm_tables = Some(Arc::new(MsmTables { g, g_lagrange }));
+    }
+
+    /// Returns the Lagrange basis in G1, computing it with an IFFT over the
+    /// powers of the secret on first use if it wasn't loaded with the
+    /// parameters.
+    pub fn g_lagrange(&self) -> &[E::G1Affine] {
+        self.g_lagrange.get_or_init(|| {
+            assert_eq!(
+                self.g.len() as u64,
+                self.n,
+                "the Lagrange basis is not available for verifier-only parameters"
+            );
+            g_to_lagrange(self.g.iter().map(|g| g.to_curve()).collect(), self.k)
+        })
+    }
+
+    /// Returns a copy of the parameters that only holds what KZG verifiers
+    /// need: `k`, the G1 generator and the two G2 elements.
+    ///
+    /// The result can be used everywhere verifier parameters are expected,
+    /// but cannot be used to commit.
+    pub fn verifier_only(&self) -> ParamsVerifierKZG<E> {
+        Self {
+            k: self.k,
+            n: self.n,
+            g: vec![self.g[0]],
+            g_lagrange: OnceCell::new(),
+            g2: self.g2,
+            s_g2: self.s_g2,
+            msm_tables: None,
         }
     }
 
@@ -138,6 +233,35 @@ impl<E: Engine + Debug> ParamsKZG<E> {
         self.s_g2
     }
 
+    /// Returns a digest of the parameters a verifier depends on: `k`, the G1
+    /// generator and the two G2 elements used in the final pairing check.
+    ///
+    /// This is used to bind serialized accumulators to the parameters they
+    /// were created with.
+    pub fn verifier_digest(&self) -> [u8; 32]
+    where
+        E::G1Affine: SerdeCurveAffine,
+        E::G2Affine: SerdeCurveAffine,
+    {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&self.k.to_le_bytes());
+        self.g[0]
+            .write(&mut bytes, SerdeFormat::RawBytes)
+            .expect("Writing to vector should not fail");
+        self.g2
+            .write(&mut bytes, SerdeFormat::RawBytes)
+            .expect("Writing to vector should not fail");
+        self.s_g2
+            .write(&mut bytes, SerdeFormat::RawBytes)
+            .expect("Writing to vector should not fail");
+
+        let hash = Blake2bParams::new()
+            .hash_length(32)
+            .personal(b"Halo2-KZG-Params")
+            .hash(&bytes);
+        hash.as_bytes().try_into().unwrap()
+    }
+
     /// Writes parameters to buffer
     pub fn write_custom<W: io::Write>(&self, writer: &mut W, format: SerdeFormat) -> io::Result<()>
     where
@@ -148,7 +272,7 @@ impl<E: Engine + Debug> ParamsKZG<E> {
         for el in self.g.iter() {
             el.write(writer, format)?;
         }
-        for el in self.g_lagrange.iter() {
+        for el in self.g_lagrange().iter() {
             el.write(writer, format)?;
         }
         self.g2.write(writer, format)?;
@@ -237,16 +361,172 @@ impl<E: Engine + Debug> ParamsKZG<E> {
             k,
             n: n as u64,
             g,
-            g_lagrange,
+            g_lagrange: lagrange_cell(g_lagrange),
+            g2,
+            s_g2,
+            msm_tables: None,
+        })
+    }
+
+    /// Writes the parameters needed by a verifier, see [`Self::verifier_only`].
+    /// The encoding is independent of `k` and only a few hundred bytes long.
+    pub fn write_verifier<W: io::Write>(
+        &self,
+        writer: &mut W,
+        format: SerdeFormat,
+    ) -> io::Result<()>
+    where
+        E::G1Affine: SerdeCurveAffine,
+        E::G2Affine: SerdeCurveAffine,
+    {
+        writer.write_all(&self.k.to_le_bytes())?;
+        self.g[0].write(writer, format)?;
+        self.g2.write(writer, format)?;
+        self.s_g2.write(writer, format)
+    }
+
+    /// Reads verifier parameters written by [`Self::write_verifier`].
+    pub fn read_verifier<R: io::Read>(
+        reader: &mut R,
+        format: SerdeFormat,
+    ) -> io::Result<ParamsVerifierKZG<E>>
+    where
+        E::G1Affine: SerdeCurveAffine,
+        E::G2Affine: SerdeCurveAffine,
+    {
+        let mut k = [0u8; 4];
+        reader.read_exact(&mut k[..])?;
+        let k = u32::from_le_bytes(k);
+        if k > E::Scalar::S {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid k"));
+        }
+
+        let g0 = E::G1Affine::read(reader, format)?;
+        let g2 = E::G2Affine::read(reader, format)?;
+        let s_g2 = E::G2Affine::read(reader, format)?;
+
+        Ok(Self {
+            k,
+            n: 1 << k,
+            g: vec![g0],
+            g_lagrange: OnceCell::new(),
+            g2,
+            s_g2,
+            msm_tables: None,
+        })
+    }
+
+    /// Reads params from a buffer like [`Self::read_custom`], but skips the
+    /// stored Lagrange basis. It is recomputed from the powers of the secret
+    /// the first time it is needed, which makes loading roughly twice as fast
+    /// for users that only commit in coefficient form or only verify.
+    pub fn read_lazy<R: io::Read>(reader: &mut R, format: SerdeFormat) -> io::Result<Self>
+    where
+        E::G1Affine: SerdeCurveAffine,
+        E::G2Affine: SerdeCurveAffine,
+    {
+        let mut k = [0u8; 4];
+        reader.read_exact(&mut k[..])?;
+        let k = u32::from_le_bytes(k);
+        Self::read_tail(reader, k, k, format)
+    }
+
+    /// Reads params for `2^k` rows from a buffer holding params written by
+    /// [`Self::write_custom`] for a larger (or equal) `k`.
+    ///
+    /// Only the first `2^k` powers in G1 are kept in memory; the remaining
+    /// powers and the stored Lagrange basis are skipped while streaming, and
+    /// the Lagrange basis is recomputed for the smaller domain when it is
+    /// first needed. This is the streaming counterpart of [`Params::downsize`].
+    pub fn read_truncated<R: io::Read>(
+        reader: &mut R,
+        k: u32,
+        format: SerdeFormat,
+    ) -> io::Result<Self>
+    where
+        E::G1Affine: SerdeCurveAffine,
+        E::G2Affine: SerdeCurveAffine,
+    {
+        let mut stored_k = [0u8; 4];
+        reader.read_exact(&mut stored_k[..])?;
+        let stored_k = u32::from_le_bytes(stored_k);
+        if k > stored_k {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot read params for a larger k than stored",
+            ));
+        }
+        Self::read_tail(reader, stored_k, k, format)
+    }
+
+    /// Reads the first `2^k` powers of the secret of params stored for
+    /// `stored_k` and the G2 elements, skipping the stored Lagrange basis.
+    fn read_tail<R: io::Read>(
+        reader: &mut R,
+        stored_k: u32,
+        k: u32,
+        format: SerdeFormat,
+    ) -> io::Result<Self>
+    where
+        E::G1Affine: SerdeCurveAffine,
+        E::G2Affine: SerdeCurveAffine,
+    {
+        use group::GroupEncoding;
+        use halo2curves::serde::SerdeObject;
+
+        // Larger params could not be held in memory, and would overflow the
+        // shifts below
+        if stored_k > 32 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid k"));
+        }
+        let stored_n = 1u64 << stored_k;
+        let n = 1u64 << k;
+
+        let g = (0..n)
+            .map(|_| <E::G1Affine as SerdeCurveAffine>::read(reader, format))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let point_size = match format {
+            SerdeFormat::Processed => <<E as Engine>::G1Affine as GroupEncoding>::Repr::default()
+                .as_ref()
+                .len(),
+            _ => E::G1Affine::default().to_raw_bytes().len(),
+        } as u64;
+        let skip = (stored_n - n + stored_n) * point_size;
+        if io::copy(&mut reader.by_ref().take(skip), &mut io::sink())? != skip {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let g2 = E::G2Affine::read(reader, format)?;
+        let s_g2 = E::G2Affine::read(reader, format)?;
+
+        Ok(Self {
+            k,
+            n,
+            g,
+            g_lagrange: OnceCell::new(),
             g2,
             s_g2,
+            msm_tables: None,
         })
     }
 }
 
+/// Wraps an already computed Lagrange basis.
+pub(crate) fn lagrange_cell<C>(g_lagrange: Vec<C>) -> OnceCell<Vec<C>> {
+    let cell = OnceCell::new();
+    let _ = cell.set(g_lagrange);
+    cell
+}
+
 // TODO: see the issue at https://github.com/appliedzkp/halo2/issues/45
 // So we probably need much smaller verifier key. However for new bases in g1 should be in verifier keys.
 /// KZG multi-open verification parameters
+///
+/// KZG verifiers only use the G1 generator and the G2 elements, so compact
+/// parameters obtained with [`ParamsKZG::verifier_only`] or
+/// [`ParamsKZG::read_verifier`] can be passed to `verify_proof` and the KZG
+/// verification strategies.
 pub type ParamsVerifierKZG<C> = ParamsKZG<C>;
 
 impl<'params, E: Engine + Debug> Params<'params, E::G1Affine> for ParamsKZG<E>
@@ -271,7 +551,8 @@ where
         self.n = 1 << k;
 
         self.g.truncate(self.n as usize);
-        self.g_lagrange = g_to_lagrange(self.g.iter().map(|g| g.to_curve()).collect(), k);
+        self.g_lagrange = OnceCell::new();
+        self.msm_tables = None;
     }
 
     fn empty_msm(&'params self) -> MSMKZG<E> {
@@ -283,14 +564,40 @@ where
         poly: &Polynomial<E::Scalar, LagrangeCoeff>,
         _: Blind<E::Scalar>,
     ) -> E::G1 {
+        if let Some(tables) = &self.msm_tables {
+            return tables.g_lagrange.multiexp(poly);
+        }
+
         let mut scalars = Vec::with_capacity(poly.len());
         scalars.extend(poly.iter());
-        let bases = &self.g_lagrange;
+        let bases = self.g_lagrange();
         let size = scalars.len();
         assert!(bases.len() >= size);
         best_multiexp(&scalars, &bases[0..size])
     }
 
+    fn commit_lagrange_chunks<'a, I>(&self, chunks: I, _: Blind<E::Scalar>) -> E::G1
+    where
+        I: IntoIterator<Item = &'a [E::Scalar]>,
+    {
+        match &self.msm_tables {
+            Some(tables) => {
+                let mut offset = 0;
+                let mut acc = E::G1::identity();
+                for chunk in chunks {
+                    acc += tables.g_lagrange.multiexp_from(offset, chunk);
+                    offset += chunk.len();
+                }
+                acc
+            }
+            None => best_multiexp_chunks(chunks, self.g_lagrange()),
+        }
+    }
+
+    fn can_commit(&self) -> bool {
+        self.g.len() as u64 == self.n || self.g_lagrange.get().is_some()
+    }
+
     /// Writes params to a buffer.
     fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
         self.write_custom(writer, SerdeFormat::RawBytes)
@@ -325,6 +632,10 @@ where
     }
 
     fn commit(&self, poly: &Polynomial<E::Scalar, Coeff>, _: Blind<E::Scalar>) -> E::G1 {
+        if let Some(tables) = &self.msm_tables {
+            return tables.g.multiexp(poly);
+        }
+
         let mut scalars = Vec::with_capacity(poly.len());
         scalars.extend(poly.iter());
         let bases = &self.g;
@@ -338,6 +649,20 @@ where
     }
 }
 
+impl<E: Engine + Debug> ParamsProverKZG<E> for ParamsKZG<E>
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    fn g(&self) -> &[E::G1Affine] {
+        &self.g
+    }
+
+    fn g_lagrange(&self) -> &[E::G1Affine] {
+        ParamsKZG::g_lagrange(self)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::arithmetic::{
@@ -383,6 +708,139 @@ mod test {
         assert_eq!(params.commit(&b, alpha), params.commit_lagrange(&a, alpha));
     }
 
+    #[test]
+    fn test_commit_lagrange_chunks() {
+        const K: u32 = 6;
+
+        use rand_core::OsRng;
+
+        use crate::poly::EvaluationDomain;
+        use halo2curves::bn256::{Bn256, Fr};
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let domain = EvaluationDomain::new(1, K);
+
+        let mut a = domain.empty_lagrange();
+        for a in a.iter_mut() {
+            *a = Fr::random(OsRng);
+        }
+
+        let alpha = Blind(Fr::random(OsRng));
+        let expected = params.commit_lagrange(&a, alpha);
+        assert_eq!(params.commit_lagrange_chunks(a.chunks(7), alpha), expected);
+
+        // Trailing zero evaluations may be omitted
+        for a in a[40..].iter_mut() {
+            *a = Fr::zero();
+        }
+        assert_eq!(
+            params.commit_lagrange_chunks(a[..40].chunks(16), alpha),
+            params.commit_lagrange(&a, alpha)
+        );
+    }
+
+    #[test]
+    fn test_msm_tables() {
+        const K: u32 = 5;
+
+        use rand_core::OsRng;
+
+        use crate::poly::EvaluationDomain;
+        use halo2curves::bn256::{Bn256, Fr};
+
+        let params = ParamsKZG::<Bn256>::setup(K, OsRng);
+        let mut precomputed = params.clone();
+        precomputed.precompute_msm_tables(6);
+
+        let domain = EvaluationDomain::new(1, K);
+        let mut a = domain.empty_lagrange();
+        for a in a.iter_mut() {
+            *a = Fr::random(OsRng);
+        }
+        let b = domain.lagrange_to_coeff(a.clone());
+
+        let blind = Blind::default();
+        assert_eq!(precomputed.commit(&b, blind), params.commit(&b, blind));
+        assert_eq!(
+            precomputed.commit_lagrange(&a, blind),
+            params.commit_lagrange(&a, blind)
+        );
+        assert_eq!(
+            precomputed.commit_lagrange_chunks(a.chunks(10), blind),
+            params.commit_lagrange(&a, blind)
+        );
+    }
+
+    #[test]
+    fn test_read_truncated() {
+        use crate::halo2curves::bn256::Bn256;
+        use crate::SerdeFormat;
+        use rand_core::OsRng;
+
+        let params = ParamsKZG::<Bn256>::setup(5, OsRng);
+        for format in [SerdeFormat::Processed, SerdeFormat::RawBytes] {
+            let mut data = vec![];
+            params.write_custom(&mut data, format).unwrap();
+
+            let mut downsized = params.clone();
+            downsized.downsize(3);
+            let truncated = ParamsKZG::<Bn256>::read_truncated(&mut &data[..], 3, format).unwrap();
+
+            assert_eq!(truncated.n, 8);
+            assert_eq!(truncated.g, downsized.g);
+            assert_eq!(truncated.g_lagrange(), downsized.g_lagrange());
+            assert_eq!(truncated.g2, params.g2);
+            assert_eq!(truncated.s_g2, params.s_g2);
+
+            assert!(ParamsKZG::<Bn256>::read_truncated(&mut &data[..], 6, format).is_err());
+
+            // A corrupt k is rejected before anything is read for it
+            data[..4].copy_from_slice(&64u32.to_le_bytes());
+            let err = ParamsKZG::<Bn256>::read_lazy(&mut &data[..], format).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+            let err = ParamsKZG::<Bn256>::read_truncated(&mut &data[..], 3, format).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_verifier_serialisation_roundtrip() {
+        use crate::halo2curves::bn256::Bn256;
+        use crate::SerdeFormat;
+        use rand_core::OsRng;
+
+        let params = ParamsKZG::<Bn256>::setup(5, OsRng);
+        for format in [SerdeFormat::Processed, SerdeFormat::RawBytes] {
+            let mut data = vec![];
+            params.write_verifier(&mut data, format).unwrap();
+            assert!(data.len() < 512);
+
+            let verifier = ParamsKZG::<Bn256>::read_verifier(&mut &data[..], format).unwrap();
+            assert_eq!(verifier.k, params.k);
+            assert_eq!(verifier.n, params.n);
+            assert_eq!(verifier.g, params.g[..1]);
+            assert_eq!(verifier.g2, params.g2);
+            assert_eq!(verifier.s_g2, params.s_g2);
+            assert_eq!(verifier.verifier_digest(), params.verifier_digest());
+        }
+    }
+
+    #[test]
+    fn test_setup_from_seed() {
+        use crate::halo2curves::bn256::Bn256;
+
+        let params0 = ParamsKZG::<Bn256>::setup_from_seed(4, [7; 32]);
+        let params1 = ParamsKZG::<Bn256>::setup_from_seed(4, [7; 32]);
+        let params2 = ParamsKZG::<Bn256>::setup_from_seed(4, [8; 32]);
+
+        let (mut data0, mut data1, mut data2) = (vec![], vec![], vec![]);
+        params0.write(&mut data0).unwrap();
+        params1.write(&mut data1).unwrap();
+        params2.write(&mut data2).unwrap();
+        assert_eq!(data0, data1);
+        assert_ne!(data0, data2);
+    }
+
     #[test]
     fn test_parameter_serialisation_roundtrip() {
         const K: u32 = 4;
@@ -403,10 +861,10 @@ mod test {
         assert_eq!(params0.k, params1.k);
         assert_eq!(params0.n, params1.n);
         assert_eq!(params0.g.len(), params1.g.len());
-        assert_eq!(params0.g_lagrange.len(), params1.g_lagrange.len());
+        assert_eq!(params0.g_lagrange().len(), params1.g_lagrange().len());
 
         assert_eq!(params0.g, params1.g);
-        assert_eq!(params0.g_lagrange, params1.g_lagrange);
+        assert_eq!(params0.g_lagrange(), params1.g_lagrange());
         assert_eq!(params0.g2, params1.g2);
         assert_eq!(params0.s_g2, params1.s_g2);
     }
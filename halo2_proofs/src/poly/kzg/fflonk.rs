@@ -0,0 +1,238 @@
+//! Combined commitments to several polynomials, opened together in the style
+//! of fflonk (Gabizon and Williamson, <https://eprint.iacr.org/2021/1167>).
+//!
+//! The polynomials `f_0, ..., f_{t-1}` are committed to as the single
+//! polynomial `g(X) = sum_i f_i(X^t) X^i`. Opening `g` at the `t` points `z w`
+//! with `w^t = 1` reveals every `f_i(z^t)`, since the remainder of `g` modulo
+//! `X^t - z^t` is `sum_i f_i(z^t) X^i`. The opening is a SHPLONK-style proof
+//! of two group elements, independently of `t`, and needs no powers of `s` in
+//! G2 beyond `[s] G2`.
+
+use std::fmt::Debug;
+use std::io;
+use std::marker::PhantomData;
+
+use ff::Field;
+use group::Curve;
+use halo2curves::pairing::{Engine, MultiMillerLoop};
+
+use super::commitment::{ParamsKZG, ParamsProverKZG, ParamsVerifierKZG};
+use super::msm::DualMSM;
+use crate::arithmetic::{eval_polynomial, kate_division};
+use crate::helpers::SerdeCurveAffine;
+use crate::poly::commitment::{Blind, ParamsProver, MSM};
+use crate::poly::{Coeff, Error, Polynomial};
+use crate::transcript::{ChallengeScalar, EncodedChallenge, TranscriptRead, TranscriptWrite};
+
+#[derive(Clone, Copy, Debug)]
+struct U {}
+type ChallengeU<F> = ChallengeScalar<F, U>;
+
+/// Combines `polys` into `g(X) = sum_i polys[i](X^t) X^i` with
+/// `t = polys.len()`, to be committed with
+/// [`single::commit`](super::single::commit).
+pub fn combine<F: Field>(polys: &[Polynomial<F, Coeff>]) -> Polynomial<F, Coeff> {
+    assert!(!polys.is_empty());
+    let t = polys.len();
+    let len = polys.iter().map(|poly| poly.len()).max().unwrap();
+
+    let mut values = vec![F::zero(); t * len];
+    for (i, poly) in polys.iter().enumerate() {
+        for (j, coeff) in poly.iter().enumerate() {
+            values[j * t + i] = *coeff;
+        }
+    }
+
+    Polynomial {
+        values,
+        _marker: PhantomData,
+    }
+}
+
+/// Divides `poly` by `X^t - c`, returning the quotient and the `t`
+/// coefficients of the remainder.
+fn divide<F: Field>(poly: &[F], t: usize, c: F) -> (Vec<F>, Vec<F>) {
+    let mut remainder = poly.to_vec();
+    if remainder.len() < t {
+        remainder.resize(t, F::zero());
+    }
+    let mut quotient = vec![F::zero(); remainder.len() - t];
+    // X^i = X^(i - t) (X^t - c) + c X^(i - t)
+    for i in (t..remainder.len()).rev() {
+        let coeff = remainder[i];
+        quotient[i - t] = coeff;
+        remainder[i - t] += coeff * c;
+    }
+    remainder.truncate(t);
+    (quotient, remainder)
+}
+
+/// Opens `combined`, the combination of `t` polynomials by [`combine`], at
+/// `z^t`, writing the `t` evaluations followed by the two opening witnesses
+/// to `transcript`.
+///
+/// The commitment to `combined` and the point `z` must be bound to the
+/// transcript by the caller, and `z` must not be known before the commitment.
+pub fn open<E, P, Ch, T>(
+    params: &P,
+    transcript: &mut T,
+    combined: &Polynomial<E::Scalar, Coeff>,
+    t: usize,
+    z: E::Scalar,
+) -> io::Result<()>
+where
+    E: Engine + Debug,
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+    P: ParamsProverKZG<E>,
+    Ch: EncodedChallenge<E::G1Affine>,
+    T: TranscriptWrite<E::G1Affine, Ch>,
+{
+    assert!(t > 0);
+    let z_t = z.pow_vartime(&[t as u64]);
+    let (quotient, evals) = divide(&combined[..], t, z_t);
+    for eval in evals.iter() {
+        transcript.write_scalar(*eval)?;
+    }
+
+    let quotient = Polynomial {
+        values: quotient,
+        _marker: PhantomData,
+    };
+    let w = params.commit(&quotient, Blind::default()).to_affine();
+    transcript.write_point(w)?;
+
+    let u: ChallengeU<_> = transcript.squeeze_challenge_scalar();
+    let u = *u;
+
+    // L(X) = g(X) - r(u) - (u^t - z^t) q(X) vanishes at u
+    let z_u = u.pow_vartime(&[t as u64]) - z_t;
+    let mut linearized = combined.values.clone();
+    if linearized.len() < quotient.len() {
+        linearized.resize(quotient.len(), E::Scalar::zero());
+    }
+    for (value, q) in linearized.iter_mut().zip(quotient.iter()) {
+        *value -= z_u * q;
+    }
+    linearized[0] -= eval_polynomial(&evals, u);
+
+    let witness = Polynomial {
+        values: kate_division(&linearized, u),
+        _marker: PhantomData,
+    };
+    transcript.write_point(params.commit(&witness, Blind::default()).to_affine())
+}
+
+/// Reads an opening written by [`open`] of `commitment`, the combination of
+/// `t` polynomials, at `z^t`. Returns the evaluations of the `t` polynomials
+/// together with the pairing check that makes them valid, which can be
+/// checked directly or combined with other accumulators.
+pub fn accumulate<'params, E, Ch, T>(
+    params: &'params ParamsVerifierKZG<E>,
+    transcript: &mut T,
+    commitment: &E::G1Affine,
+    t: usize,
+    z: E::Scalar,
+) -> Result<(Vec<E::Scalar>, DualMSM<'params, E>), Error>
+where
+    E: MultiMillerLoop + Debug,
+    Ch: EncodedChallenge<E::G1Affine>,
+    T: TranscriptRead<E::G1Affine, Ch>,
+{
+    assert!(t > 0);
+    let evals = (0..t)
+        .map(|_| transcript.read_scalar())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| Error::OpeningError)?;
+    let w: E::G1 = transcript
+        .read_point()
+        .map_err(|_| Error::OpeningError)?
+        .into();
+
+    let u: ChallengeU<_> = transcript.squeeze_challenge_scalar();
+    let u = *u;
+    let witness: E::G1 = transcript
+        .read_point()
+        .map_err(|_| Error::OpeningError)?
+        .into();
+
+    // [L(s)] = C - [r(u)] G1 - (u^t - z^t) W, and L(s) = (s - u) W'(s)
+    let z_u = u.pow_vartime(&[t as u64]) - z.pow_vartime(&[t as u64]);
+    let g0: E::G1 = params.g[0].into();
+
+    let mut accumulator = DualMSM::new(params);
+    accumulator.left.append_term(E::Scalar::one(), witness);
+    accumulator
+        .right
+        .append_term(E::Scalar::one(), (*commitment).into());
+    accumulator
+        .right
+        .append_term(-eval_polynomial(&evals, u), g0);
+    accumulator.right.append_term(-z_u, w);
+    accumulator.right.append_term(u, witness);
+    Ok((evals, accumulator))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{accumulate, combine, open};
+    use crate::arithmetic::eval_polynomial;
+    use crate::poly::kzg::commitment::ParamsKZG;
+    use crate::poly::kzg::single::commit;
+    use crate::poly::EvaluationDomain;
+    use crate::transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    };
+    use ff::Field;
+    use halo2curves::bn256::{Bn256, Fr, G1Affine};
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_combined_opening() {
+        const K: u32 = 5;
+        const T: usize = 3;
+
+        let params = ParamsKZG::<Bn256>::setup(K, OsRng);
+        let domain = EvaluationDomain::<Fr>::new(1, K - 2);
+
+        let polys = (0..T)
+            .map(|_| {
+                let mut poly = domain.empty_coeff();
+                for coeff in poly.iter_mut() {
+                    *coeff = Fr::random(OsRng);
+                }
+                poly
+            })
+            .collect::<Vec<_>>();
+        let combined = combine(&polys);
+        let commitment = commit(&params, &combined);
+
+        let z = Fr::random(OsRng);
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        open(&params, &mut transcript, &combined, T, z).unwrap();
+        let proof = transcript.finalize();
+        assert_eq!(proof.len(), T * 32 + 2 * 32);
+
+        let verifier_params = params.verifier_only();
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+        let (evals, accumulator) =
+            accumulate(&verifier_params, &mut transcript, &commitment, T, z).unwrap();
+        assert!(accumulator.check());
+
+        let x = z.pow_vartime(&[T as u64]);
+        for (eval, poly) in evals.iter().zip(polys.iter()) {
+            assert_eq!(*eval, eval_polynomial(&poly[..], x));
+        }
+
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+        let (_, accumulator) = accumulate(
+            &verifier_params,
+            &mut transcript,
+            &commitment,
+            T,
+            z + Fr::one(),
+        )
+        .unwrap();
+        assert!(!accumulator.check());
+    }
+}
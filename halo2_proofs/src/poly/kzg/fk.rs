@@ -0,0 +1,145 @@
+//! Amortized computation of all `2^k` single-point opening proofs of a
+//! polynomial over the evaluation domain, following Feist and Khovratovich,
+//! "Fast amortized KZG proofs" (<https://eprint.iacr.org/2023/033>).
+
+use std::fmt::Debug;
+
+use ff::{Field, PrimeField};
+use group::{prime::PrimeCurveAffine, Curve};
+use halo2curves::pairing::Engine;
+
+use super::commitment::ParamsKZG;
+use crate::arithmetic::{best_fft, parallelize, Group};
+use crate::poly::{Coeff, Polynomial};
+
+/// Returns a primitive `2^k`-th root of unity, consistent with
+/// [`EvaluationDomain`](crate::poly::EvaluationDomain).
+fn root_of_unity<F: PrimeField>(k: u32) -> F {
+    let mut omega = F::root_of_unity();
+    for _ in k..F::S {
+        omega = omega.square();
+    }
+    omega
+}
+
+/// Precomputation over a KZG SRS that allows computing the opening proofs of
+/// a polynomial at every point `omega^i` of the `2^k` evaluation domain in
+/// `O(n log n)` group operations, instead of `O(n^2)` for `n` separate
+/// openings.
+///
+/// The proof at `omega^i` is the same as the one produced by
+/// [`single::open`](super::single::open) at that point, and is checked with
+/// [`single::verify`](super::single::verify) against the evaluation
+/// `p(omega^i)`, i.e. the `i`-th value of the polynomial in Lagrange form.
+#[derive(Clone, Debug)]
+pub struct OpeningPrecomputation<E: Engine> {
+    k: u32,
+    omega: E::Scalar,
+    omega_double: E::Scalar,
+    /// FFT over the `2n` domain of `[s^0], ..., [s^(n-2)]` padded with zeros
+    bases: Vec<E::G1>,
+}
+
+impl<E: Engine + Debug> OpeningPrecomputation<E> {
+    /// Performs the SRS-dependent part of the computation, which can be
+    /// reused for every polynomial committed with `params`.
+    pub fn new(params: &ParamsKZG<E>) -> Self {
+        let k = params.k;
+        // Openings at all points require a domain of twice the size
+        assert!(k < E::Scalar::S);
+        let n = params.n as usize;
+
+        let omega_double = root_of_unity::<E::Scalar>(k + 1);
+        let mut bases = vec![E::G1::group_zero(); 2 * n];
+        for (base, g) in bases.iter_mut().zip(params.g[..n - 1].iter()) {
+            *base = g.to_curve();
+        }
+        best_fft(&mut bases, omega_double, k + 1);
+
+        OpeningPrecomputation {
+            k,
+            omega: root_of_unity(k),
+            omega_double,
+            bases,
+        }
+    }
+
+    /// Returns the opening proofs of `poly` at `omega^i` for every `i` in the
+    /// evaluation domain.
+    ///
+    /// The commitment to the quotient at `z` is `sum_t z^t h_t`, where
+    /// `h_t = sum_m p_{t + 1 + m} [s^m]` is a Toeplitz matrix-vector product
+    /// computed here as a convolution over the doubled domain. The proofs are
+    /// then the FFT of `h` over the evaluation domain.
+    pub fn open_all(&self, poly: &Polynomial<E::Scalar, Coeff>) -> Vec<E::G1Affine> {
+        let n = 1usize << self.k;
+        assert!(poly.len() <= n);
+        let d = n - 1;
+
+        // The convolution uses the coefficients in reverse order
+        let mut coeffs = vec![E::Scalar::zero(); 2 * n];
+        for (i, coeff) in poly.iter().enumerate() {
+            coeffs[d - i] = *coeff;
+        }
+        best_fft(&mut coeffs, self.omega_double, self.k + 1);
+
+        let mut conv = self.bases.clone();
+        parallelize(&mut conv, |conv, start| {
+            for (point, coeff) in conv.iter_mut().zip(coeffs[start..].iter()) {
+                *point *= *coeff;
+            }
+        });
+        best_fft(&mut conv, self.omega_double.invert().unwrap(), self.k + 1);
+
+        let size_inv = E::Scalar::from(2 * n as u64).invert().unwrap();
+        let mut h = vec![E::G1::group_zero(); n];
+        parallelize(&mut h[..d], |h, start| {
+            for (j, h) in h.iter_mut().enumerate() {
+                *h = conv[d - 1 - (start + j)] * size_inv;
+            }
+        });
+        best_fft(&mut h, self.omega, self.k);
+
+        let mut proofs = vec![E::G1Affine::identity(); n];
+        parallelize(&mut proofs, |proofs, start| {
+            E::G1::batch_normalize(&h[start..start + proofs.len()], proofs);
+        });
+        proofs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::OpeningPrecomputation;
+    use crate::poly::kzg::commitment::ParamsKZG;
+    use crate::poly::kzg::single;
+    use crate::poly::EvaluationDomain;
+    use ff::Field;
+    use halo2curves::bn256::{Bn256, Fr};
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_open_all() {
+        const K: u32 = 3;
+
+        let params = ParamsKZG::<Bn256>::setup(K, OsRng);
+        let domain = EvaluationDomain::new(1, K);
+
+        let mut poly = domain.empty_coeff();
+        for coeff in poly.iter_mut() {
+            *coeff = Fr::random(OsRng);
+        }
+        let commitment = single::commit(&params, &poly);
+
+        let proofs = OpeningPrecomputation::new(&params).open_all(&poly);
+        assert_eq!(proofs.len(), 1 << K);
+
+        let mut point = Fr::one();
+        for proof in proofs {
+            let opening = single::open(&params, &poly, point);
+            assert_eq!(opening.witness, proof);
+            assert!(single::verify(&params, &commitment, point, &opening));
+            point *= domain.get_omega();
+        }
+    }
+}
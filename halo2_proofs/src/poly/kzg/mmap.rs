@@ -0,0 +1,458 @@
+//! Memory-mapped KZG parameters.
+//!
+//! Parameters written with [`ParamsKZG::write_mmapable`] store the G1 bases in
+//! their in-memory representation at page-aligned offsets, so that
+//! [`MmapParamsKZG`] can commit directly from the mapped file without reading
+//! or copying it, also when creating proofs.
+
+#![allow(unsafe_code)]
+
+use std::fmt::Debug;
+use std::fs::File;
+use std::io;
+use std::mem;
+use std::sync::Arc;
+
+use group::{prime::PrimeCurveAffine, Curve};
+use halo2curves::pairing::Engine;
+use halo2curves::serde::SerdeObject;
+use memmap2::{Mmap, MmapMut};
+use once_cell::sync::OnceCell;
+
+use super::commitment::{lagrange_cell, ParamsKZG, ParamsProverKZG, ParamsVerifierKZG};
+use super::msm::MSMKZG;
+use crate::arithmetic::{best_multiexp, best_multiexp_chunks};
+use crate::helpers::SerdeCurveAffine;
+use crate::poly::commitment::{Blind, Params, ParamsProver};
+use crate::poly::{Coeff, LagrangeCoeff, Polynomial};
+use crate::SerdeFormat;
+
+const MAGIC: &[u8; 8] = b"halo2mmp";
+const VERSION: u32 = 1;
+const PAGE_SIZE: usize = 4096;
+/// The largest `k` of a params file; larger ones cannot hold their bases.
+const MAX_K: u32 = 32;
+
+fn align_to_page(offset: usize) -> Option<usize> {
+    Some(offset.checked_add(PAGE_SIZE - 1)? / PAGE_SIZE * PAGE_SIZE)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Returns the byte offsets of `g` and `g_lagrange` for `n` points of type
+/// `C`, and the length of the file, or an error if they overflow.
+fn layout<C>(n: usize) -> io::Result<(usize, usize, usize)> {
+    let layout = || {
+        let g_offset = PAGE_SIZE;
+        let points_len = n.checked_mul(mem::size_of::<C>())?;
+        let g_lagrange_offset = align_to_page(g_offset.checked_add(points_len)?)?;
+        let end = align_to_page(g_lagrange_offset.checked_add(points_len)?)?;
+        Some((g_offset, g_lagrange_offset, end))
+    };
+    layout().ok_or_else(|| invalid_data("params are too large to be memory-mapped"))
+}
+
+/// Checks that points of type `C` have no padding, so that their in-memory
+/// representation can be written out and mapped back in.
+fn check_representation<C: SerdeCurveAffine>() -> io::Result<()> {
+    if mem::size_of::<C>() != C::default().to_raw_bytes().len()
+        || PAGE_SIZE % mem::align_of::<C>() != 0
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "curve points cannot be memory-mapped",
+        ));
+    }
+    Ok(())
+}
+
+fn as_bytes<C>(points: &[C]) -> &[u8] {
+    // Safety: `check_representation` ensures that `C` has no padding bytes, so
+    // every byte of the slice is initialized.
+    unsafe { std::slice::from_raw_parts(points.as_ptr() as *const u8, mem::size_of_val(points)) }
+}
+
+impl<E: Engine + Debug> ParamsKZG<E>
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    /// Writes parameters in the layout expected by [`MmapParamsKZG::map`].
+    ///
+    /// The G1 bases are written in their in-memory representation, so the
+    /// resulting file can only be mapped by a build for the same target and
+    /// version of the curve implementation.
+    pub fn write_mmapable<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        check_representation::<E::G1Affine>()?;
+
+        let n = self.n as usize;
+        let (g_offset, g_lagrange_offset, end) = layout::<E::G1Affine>(n)?;
+
+        let mut header = Vec::with_capacity(PAGE_SIZE);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&self.k.to_le_bytes());
+        header.extend_from_slice(&(mem::size_of::<E::G1Affine>() as u32).to_le_bytes());
+        self.g2.write(&mut header, SerdeFormat::RawBytes)?;
+        self.s_g2.write(&mut header, SerdeFormat::RawBytes)?;
+        assert!(header.len() <= g_offset);
+        header.resize(g_offset, 0);
+        writer.write_all(&header)?;
+
+        let g = as_bytes(&self.g);
+        writer.write_all(g)?;
+        writer.write_all(&vec![0; g_lagrange_offset - g_offset - g.len()])?;
+
+        let g_lagrange = as_bytes(self.g_lagrange());
+        writer.write_all(g_lagrange)?;
+        writer.write_all(&vec![0; end - g_lagrange_offset - g_lagrange.len()])?;
+
+        Ok(())
+    }
+}
+
+/// KZG parameters whose G1 bases are read directly from a memory map.
+///
+/// Commitments are computed from the mapped bases without copying them to
+/// the heap, so loading parameters is constant-time and the operating system
+/// can share and evict their pages as needed.
+///
+/// They implement [`ParamsProverKZG`], so proofs are created from them with
+/// the scheme `KZGCommitmentScheme<E, MmapParamsKZG<E>>`
+/// ([`KZGCommitmentScheme`](super::commitment::KZGCommitmentScheme)) and any
+/// KZG multiopen prover. Verifying only needs
+/// [`ParamsProver::verifier_params`], which are not mapped, with the default
+/// `KZGCommitmentScheme<E>`.
+///
+/// [`ParamsProver::new`] and [`Params::downsize`] cannot fail, so if no
+/// anonymous memory can be mapped for their bases, they keep them on the
+/// heap instead.
+#[derive(Clone, Debug)]
+pub struct MmapParamsKZG<E: Engine> {
+    bases: Bases<E::G1Affine>,
+    /// Everything a verifier needs: `k`, `n`, the G1 generator and the G2
+    /// elements.
+    verifier: ParamsKZG<E>,
+}
+
+/// The G1 bases of [`MmapParamsKZG`]
+#[derive(Clone, Debug)]
+enum Bases<C> {
+    /// `g` at `PAGE_SIZE` and `g_lagrange` at `g_lagrange_offset` of the map
+    Mapped {
+        map: Arc<Mmap>,
+        g_lagrange_offset: usize,
+    },
+    /// `g` and `g_lagrange` on the heap, when no memory could be mapped
+    Heap(Arc<(Vec<C>, Vec<C>)>),
+}
+
+impl<E: Engine + Debug> MmapParamsKZG<E>
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    /// Maps parameters written by [`ParamsKZG::write_mmapable`].
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified while it is mapped, and must have been
+    /// written by a build for the same target and curve implementation. The
+    /// bases are not checked to lie on the curve.
+    pub unsafe fn map(file: &File) -> io::Result<Self> {
+        Self::from_map(Mmap::map(file)?)
+    }
+
+    /// Copies `params` into an anonymous memory map.
+    pub fn from_params(params: &ParamsKZG<E>) -> io::Result<Self> {
+        let (_, _, len) = layout::<E::G1Affine>(params.n as usize)?;
+        let mut map = MmapMut::map_anon(len)?;
+        params.write_mmapable(&mut &mut map[..])?;
+        Self::from_map(map.make_read_only()?)
+    }
+
+    /// Copies `params` into an anonymous memory map, or keeps them on the
+    /// heap if that fails.
+    fn from_params_or_heap(params: ParamsKZG<E>) -> Self {
+        Self::from_params(&params).unwrap_or_else(|_| Self::from_heap(params))
+    }
+
+    fn from_heap(params: ParamsKZG<E>) -> Self {
+        let g_lagrange = params.g_lagrange().to_vec();
+        Self {
+            verifier: params.verifier_only(),
+            bases: Bases::Heap(Arc::new((params.g, g_lagrange))),
+        }
+    }
+
+    fn from_map(map: Mmap) -> io::Result<Self> {
+        check_representation::<E::G1Affine>()?;
+
+        let mut header = &map[..];
+        if map.len() < PAGE_SIZE || &header[..MAGIC.len()] != MAGIC {
+            return Err(invalid_data("not a memory-mappable params file"));
+        }
+        header = &header[MAGIC.len()..];
+
+        let mut read_u32 = || -> u32 {
+            let value = u32::from_le_bytes(header[..4].try_into().unwrap());
+            header = &header[4..];
+            value
+        };
+        let version = read_u32();
+        let k = read_u32();
+        let point_size = read_u32();
+        if version != VERSION || point_size as usize != mem::size_of::<E::G1Affine>() {
+            return Err(invalid_data(
+                "params file was written by an incompatible build",
+            ));
+        }
+        if k > MAX_K || k >= usize::BITS {
+            return Err(invalid_data("invalid k"));
+        }
+        let g2 = E::G2Affine::read(&mut header, SerdeFormat::RawBytes)?;
+        let s_g2 = E::G2Affine::read(&mut header, SerdeFormat::RawBytes)?;
+
+        let n = 1usize << k;
+        let (g_offset, g_lagrange_offset, end) = layout::<E::G1Affine>(n)?;
+        if map.len() < end {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let mut params = Self {
+            bases: Bases::Mapped {
+                map: Arc::new(map),
+                g_lagrange_offset,
+            },
+            verifier: ParamsKZG {
+                k,
+                n: n as u64,
+                g: vec![],
+                g_lagrange: OnceCell::new(),
+                g2,
+                s_g2,
+                msm_tables: None,
+            },
+        };
+        debug_assert_eq!(g_offset, PAGE_SIZE);
+        let g0 = params.g()[0];
+        params.verifier.g.push(g0);
+        Ok(params)
+    }
+
+    fn points<'a>(&self, map: &'a Mmap, offset: usize) -> &'a [E::G1Affine] {
+        // Safety: `from_map` checked that the map holds `n` points at
+        // `offset`, which is page-aligned and hence suitably aligned for
+        // `E::G1Affine`, and that points have no padding.
+        unsafe {
+            std::slice::from_raw_parts(
+                map[offset..].as_ptr() as *const E::G1Affine,
+                self.verifier.n as usize,
+            )
+        }
+    }
+
+    /// Returns the powers of the secret in G1.
+    pub fn g(&self) -> &[E::G1Affine] {
+        match &self.bases {
+            Bases::Mapped { map, .. } => self.points(map, PAGE_SIZE),
+            Bases::Heap(bases) => &bases.0,
+        }
+    }
+
+    /// Returns the Lagrange basis in G1.
+    pub fn g_lagrange(&self) -> &[E::G1Affine] {
+        match &self.bases {
+            Bases::Mapped {
+                map,
+                g_lagrange_offset,
+            } => self.points(map, *g_lagrange_offset),
+            Bases::Heap(bases) => &bases.1,
+        }
+    }
+
+    /// Copies the parameters to the heap.
+    pub fn to_params(&self) -> ParamsKZG<E> {
+        ParamsKZG {
+            g: self.g().to_vec(),
+            g_lagrange: lagrange_cell(self.g_lagrange().to_vec()),
+            ..self.verifier.clone()
+        }
+    }
+}
+
+impl<'params, E: Engine + Debug> Params<'params, E::G1Affine> for MmapParamsKZG<E>
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    type MSM = MSMKZG<E>;
+
+    fn k(&self) -> u32 {
+        self.verifier.k
+    }
+
+    fn n(&self) -> u64 {
+        self.verifier.n
+    }
+
+    /// Downsizing copies the parameters into a new anonymous map.
+    fn downsize(&mut self, k: u32) {
+        let mut params = self.to_params();
+        params.downsize(k);
+        *self = Self::from_params_or_heap(params);
+    }
+
+    fn empty_msm(&'params self) -> MSMKZG<E> {
+        MSMKZG::new()
+    }
+
+    fn commit_lagrange(
+        &self,
+        poly: &Polynomial<E::Scalar, LagrangeCoeff>,
+        _: Blind<E::Scalar>,
+    ) -> E::G1 {
+        let bases = self.g_lagrange();
+        let size = poly.len();
+        assert!(bases.len() >= size);
+        best_multiexp(&poly[..], &bases[0..size])
+    }
+
+    fn commit_lagrange_chunks<'a, I>(&self, chunks: I, _: Blind<E::Scalar>) -> E::G1
+    where
+        I: IntoIterator<Item = &'a [E::Scalar]>,
+    {
+        best_multiexp_chunks(chunks, self.g_lagrange())
+    }
+
+    /// Writes params in the same format as [`ParamsKZG`].
+    fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.verifier.k.to_le_bytes())?;
+        for el in self.g().iter().chain(self.g_lagrange().iter()) {
+            el.write(writer, SerdeFormat::RawBytes)?;
+        }
+        self.verifier.g2.write(writer, SerdeFormat::RawBytes)?;
+        self.verifier.s_g2.write(writer, SerdeFormat::RawBytes)
+    }
+
+    /// Reads params in the format of [`ParamsKZG`] into an anonymous map.
+    fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        Self::from_params(&<ParamsKZG<E> as Params<_>>::read(reader)?)
+    }
+}
+
+impl<'params, E: Engine + Debug> ParamsProver<'params, E::G1Affine> for MmapParamsKZG<E>
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    type ParamsVerifier = ParamsVerifierKZG<E>;
+
+    fn verifier_params(&'params self) -> &'params Self::ParamsVerifier {
+        &self.verifier
+    }
+
+    fn new(k: u32) -> Self {
+        Self::from_params_or_heap(<ParamsKZG<E> as ParamsProver<_>>::new(k))
+    }
+
+    fn commit(&self, poly: &Polynomial<E::Scalar, Coeff>, _: Blind<E::Scalar>) -> E::G1 {
+        let bases = self.g();
+        let size = poly.len();
+        assert!(bases.len() >= size);
+        best_multiexp(&poly[..], &bases[0..size])
+    }
+
+    fn get_g(&self) -> &[E::G1Affine] {
+        self.g()
+    }
+}
+
+impl<E: Engine + Debug> ParamsProverKZG<E> for MmapParamsKZG<E>
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    fn g(&self) -> &[E::G1Affine] {
+        MmapParamsKZG::g(self)
+    }
+
+    fn g_lagrange(&self) -> &[E::G1Affine] {
+        MmapParamsKZG::g_lagrange(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MmapParamsKZG, MAGIC};
+    use crate::poly::commitment::{Blind, Params, ParamsProver};
+    use crate::poly::kzg::commitment::ParamsKZG;
+    use crate::poly::EvaluationDomain;
+    use ff::Field;
+    use halo2curves::bn256::{Bn256, Fr};
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_mmap_roundtrip() {
+        const K: u32 = 4;
+
+        let params = ParamsKZG::<Bn256>::setup(K, OsRng);
+        let mapped = MmapParamsKZG::from_params(&params).unwrap();
+        assert_eq!(mapped.g(), &params.g[..]);
+        assert_eq!(mapped.g_lagrange(), params.g_lagrange());
+        assert_eq!(mapped.verifier_params().s_g2, params.s_g2);
+
+        let domain = EvaluationDomain::new(1, K);
+        let mut a = domain.empty_lagrange();
+        for a in a.iter_mut() {
+            *a = Fr::random(OsRng);
+        }
+        let b = domain.lagrange_to_coeff(a.clone());
+        let blind = Blind(Fr::random(OsRng));
+        assert_eq!(
+            mapped.commit_lagrange(&a, blind),
+            params.commit_lagrange(&a, blind)
+        );
+        assert_eq!(mapped.commit(&b, blind), params.commit(&b, blind));
+
+        let mut written = vec![];
+        mapped.write(&mut written).unwrap();
+        let mut expected = vec![];
+        params.write(&mut expected).unwrap();
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn test_mmap_invalid_k() {
+        let params = ParamsKZG::<Bn256>::setup(1, OsRng);
+        let mut bytes = vec![];
+        params.write_mmapable(&mut bytes).unwrap();
+        // The header holds the magic, the version and then `k`
+        let k_offset = MAGIC.len() + 4;
+        for k in [33u32, 63] {
+            bytes[k_offset..k_offset + 4].copy_from_slice(&k.to_le_bytes());
+            let mut map = memmap2::MmapMut::map_anon(bytes.len()).unwrap();
+            map.copy_from_slice(&bytes);
+            let err = MmapParamsKZG::<Bn256>::from_map(map.make_read_only().unwrap()).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_heap_bases() {
+        let params = ParamsKZG::<Bn256>::setup(2, OsRng);
+        let mapped = MmapParamsKZG::from_params(&params).unwrap();
+        let heap = MmapParamsKZG::from_heap(params);
+        assert_eq!(heap.g(), mapped.g());
+        assert_eq!(heap.g_lagrange(), mapped.g_lagrange());
+        assert_eq!(heap.verifier_params().g, mapped.verifier_params().g);
+
+        let mut written = vec![];
+        heap.write(&mut written).unwrap();
+        let mut expected = vec![];
+        mapped.write(&mut expected).unwrap();
+        assert_eq!(written, expected);
+    }
+}
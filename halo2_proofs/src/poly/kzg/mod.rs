@@ -1,8 +1,19 @@
+mod ceremony;
 /// KZG commitment scheme
 pub mod commitment;
+/// Combined commitments to several polynomials, opened together
+pub mod fflonk;
+/// Amortized openings at every point of the evaluation domain
+pub mod fk;
+/// Memory-mapped KZG parameters
+#[cfg(feature = "mmap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+pub mod mmap;
 /// Multiscalar multiplication engines
 pub mod msm;
 /// KZG multi-open scheme
 pub mod multiopen;
+/// Single-point KZG openings
+pub mod single;
 /// Strategies used with KZG scheme
 pub mod strategy;
@@ -3,16 +3,66 @@ use std::fmt::Debug;
 use super::commitment::{KZGCommitmentScheme, ParamsKZG};
 use crate::{
     arithmetic::{best_multiexp, parallelize, CurveAffine},
+    helpers::{SerdeCurveAffine, SerdePrimeField},
     poly::commitment::MSM,
+    SerdeFormat,
 };
-use group::{Curve, Group};
+use group::{Curve, Group, GroupEncoding};
 use halo2curves::pairing::{Engine, MillerLoopResult, MultiMillerLoop};
+use std::collections::{hash_map::Entry, HashMap};
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Number of terms evaluated between two progress reports to an
+/// [`MSMObserver`]
+const PROGRESS_CHUNK: usize = 1 << 16;
+
+/// Progress of a multiexp evaluation, reported to an [`MSMObserver`]
+#[derive(Clone, Debug)]
+pub struct MSMMetrics {
+    /// Which MSM is being evaluated: `"msm"` for a standalone [`MSMKZG`], or
+    /// `"left"`/`"right"` for the channels of a [`DualMSM`]
+    pub label: &'static str,
+    /// Number of terms evaluated so far
+    pub processed: usize,
+    /// Total number of terms, after consolidation of repeated bases
+    pub total: usize,
+    /// Time spent in this evaluation so far
+    pub elapsed: Duration,
+    /// The multiexp implementation used
+    pub engine: &'static str,
+}
+
+/// Callback that receives [`MSMMetrics`] while an MSM is evaluated.
+///
+/// The MSM is evaluated in chunks of `2^16` terms when an observer is set, and
+/// the observer is called after each chunk, so the last report of an
+/// evaluation has `processed == total`.
+#[derive(Clone)]
+pub struct MSMObserver(Arc<dyn Fn(&MSMMetrics) + Send + Sync>);
+
+impl MSMObserver {
+    /// Wraps `f` into an observer
+    pub fn new<F: Fn(&MSMMetrics) + Send + Sync + 'static>(f: F) -> Self {
+        MSMObserver(Arc::new(f))
+    }
+}
+
+impl Debug for MSMObserver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MSMObserver")
+    }
+}
 
 /// A multiscalar multiplication in the polynomial commitment scheme
 #[derive(Clone, Default, Debug)]
 pub struct MSMKZG<E: Engine> {
     pub(crate) scalars: Vec<E::Scalar>,
     pub(crate) bases: Vec<E::G1>,
+    /// Affine form of `bases[..affine.len()]`, filled in by `normalize`
+    pub(crate) affine: Vec<E::G1Affine>,
+    pub(crate) observer: Option<MSMObserver>,
 }
 
 impl<E: Engine> MSMKZG<E> {
@@ -21,9 +71,66 @@ impl<E: Engine> MSMKZG<E> {
         MSMKZG {
             scalars: vec![],
             bases: vec![],
+            affine: vec![],
+            observer: None,
         }
     }
 
+    /// Create an empty MSM instance with room for `capacity` terms
+    pub fn with_capacity(capacity: usize) -> Self {
+        MSMKZG {
+            scalars: Vec::with_capacity(capacity),
+            bases: Vec::with_capacity(capacity),
+            affine: vec![],
+            observer: None,
+        }
+    }
+
+    /// Reports the progress of every evaluation of this MSM to `observer`
+    pub fn set_observer(&mut self, observer: MSMObserver) {
+        self.observer = Some(observer);
+    }
+
+    /// Evaluates `sum_i scalars[i] * bases[i]`, reporting progress to the
+    /// observer if one is set.
+    pub(crate) fn multiexp(
+        &self,
+        scalars: &[E::Scalar],
+        bases: &[E::G1Affine],
+        label: &'static str,
+    ) -> E::G1 {
+        let observer = match &self.observer {
+            Some(observer) => observer,
+            None => return best_multiexp(scalars, bases),
+        };
+
+        let start = Instant::now();
+        let report = |processed| {
+            (observer.0)(&MSMMetrics {
+                label,
+                processed,
+                total: scalars.len(),
+                elapsed: start.elapsed(),
+                engine: "best_multiexp",
+            })
+        };
+
+        let mut acc = E::G1::identity();
+        let mut processed = 0;
+        for (scalars, bases) in scalars
+            .chunks(PROGRESS_CHUNK)
+            .zip(bases.chunks(PROGRESS_CHUNK))
+        {
+            acc += best_multiexp(scalars, bases);
+            processed += scalars.len();
+            report(processed);
+        }
+        if scalars.is_empty() {
+            report(0);
+        }
+        acc
+    }
+
     /// Prepares all scalars in the MSM to linear combination
     pub fn combine_with_base(&mut self, base: E::Scalar) {
         use ff::Field;
@@ -35,6 +142,112 @@ impl<E: Engine> MSMKZG<E> {
             }
         }
     }
+
+    /// Converts all bases to affine form with a single batched inversion per
+    /// thread. Bases already covered by the affine cache are not converted
+    /// again.
+    pub(crate) fn normalized_bases(&self) -> Vec<E::G1Affine> {
+        use group::prime::PrimeCurveAffine;
+
+        let cached = self.affine.len();
+        let bases = &self.bases[cached..];
+        let mut affine = vec![E::G1Affine::identity(); bases.len()];
+        parallelize(&mut affine, |affine, start| {
+            E::G1::batch_normalize(&bases[start..start + affine.len()], affine);
+        });
+
+        if cached == 0 {
+            affine
+        } else {
+            let mut all = Vec::with_capacity(self.bases.len());
+            all.extend_from_slice(&self.affine);
+            all.extend(affine);
+            all
+        }
+    }
+
+    /// Fills the affine cache so that subsequent evaluations of this MSM, and
+    /// of MSMs it is merged into, don't normalize the same bases again.
+    pub fn normalize(&mut self) {
+        if self.affine.len() != self.bases.len() {
+            self.affine = self.normalized_bases();
+        }
+    }
+
+    /// Merges the scalars of repeated bases so that every distinct point
+    /// appears exactly once in the MSM.
+    ///
+    /// Batch verification accumulates the same commitments (fixed columns,
+    /// permutation commitments, the generator) once per proof; consolidating
+    /// them before the final multiexp keeps its size proportional to the
+    /// number of distinct bases rather than the number of proofs.
+    pub fn consolidate(&mut self) {
+        use group::prime::PrimeCurveAffine;
+
+        let (scalars, bases) = self.consolidated();
+        self.scalars = scalars;
+        self.bases = bases.iter().map(|base| base.to_curve()).collect();
+        self.affine = bases;
+    }
+
+    /// Returns the consolidated scalars together with their affine bases.
+    pub(crate) fn consolidated(&self) -> (Vec<E::Scalar>, Vec<E::G1Affine>) {
+        let affine = self.normalized_bases();
+
+        let mut positions = HashMap::<Vec<u8>, usize>::with_capacity(affine.len());
+        let mut scalars = Vec::with_capacity(self.scalars.len());
+        let mut bases = Vec::with_capacity(affine.len());
+        for (scalar, point) in self.scalars.iter().zip(affine.into_iter()) {
+            match positions.entry(point.to_bytes().as_ref().to_vec()) {
+                Entry::Occupied(entry) => scalars[*entry.get()] += scalar,
+                Entry::Vacant(entry) => {
+                    entry.insert(scalars.len());
+                    scalars.push(*scalar);
+                    bases.push(point);
+                }
+            }
+        }
+
+        (scalars, bases)
+    }
+
+    /// Writes the consolidated terms of the MSM to a buffer.
+    pub(crate) fn write<W: io::Write>(&self, writer: &mut W, format: SerdeFormat) -> io::Result<()>
+    where
+        E::Scalar: SerdePrimeField,
+        E::G1Affine: SerdeCurveAffine,
+    {
+        let (scalars, bases) = self.consolidated();
+        writer.write_all(&(scalars.len() as u32).to_be_bytes())?;
+        for (scalar, base) in scalars.iter().zip(bases.iter()) {
+            scalar.write(writer, format)?;
+            base.write(writer, format)?;
+        }
+        Ok(())
+    }
+
+    /// Reads an MSM written by [`Self::write`] from a buffer.
+    pub(crate) fn read<R: io::Read>(reader: &mut R, format: SerdeFormat) -> io::Result<Self>
+    where
+        E::Scalar: SerdePrimeField,
+        E::G1Affine: SerdeCurveAffine,
+    {
+        use group::prime::PrimeCurveAffine;
+
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        let len = u32::from_be_bytes(len);
+
+        let mut msm = MSMKZG::new();
+        for _ in 0..len {
+            let scalar = E::Scalar::read(reader, format)?;
+            let base = <E::G1Affine as SerdeCurveAffine>::read(reader, format)?;
+            msm.scalars.push(scalar);
+            msm.bases.push(base.to_curve());
+            msm.affine.push(base);
+        }
+        Ok(msm)
+    }
 }
 
 impl<E: Engine + Debug> MSM<E::G1Affine> for MSMKZG<E> {
@@ -43,9 +256,25 @@ impl<E: Engine + Debug> MSM<E::G1Affine> for MSMKZG<E> {
         self.bases.push(point);
     }
 
+    fn append_terms(&mut self, scalars: &[E::Scalar], points: &[E::G1]) {
+        assert_eq!(scalars.len(), points.len());
+        self.scalars.extend_from_slice(scalars);
+        self.bases.extend_from_slice(points);
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.scalars.reserve(additional);
+        self.bases.reserve(additional);
+    }
+
     fn add_msm(&mut self, other: &Self) {
-        self.scalars.extend(other.scalars().iter());
-        self.bases.extend(other.bases().iter());
+        // The cache only stays useful if it keeps covering a prefix of the
+        // bases, so it can only be extended while it is complete.
+        if self.affine.len() == self.bases.len() {
+            self.affine.extend_from_slice(&other.affine);
+        }
+        self.scalars.extend_from_slice(&other.scalars);
+        self.bases.extend_from_slice(&other.bases);
     }
 
     fn scale(&mut self, factor: E::Scalar) {
@@ -63,10 +292,7 @@ impl<E: Engine + Debug> MSM<E::G1Affine> for MSMKZG<E> {
     }
 
     fn eval(&self) -> E::G1 {
-        use group::prime::PrimeCurveAffine;
-        let mut bases = vec![E::G1Affine::identity(); self.scalars.len()];
-        E::G1::batch_normalize(&self.bases, &mut bases);
-        best_multiexp(&self.scalars, &bases)
+        self.multiexp(&self.scalars, &self.normalized_bases(), "msm")
     }
 
     fn bases(&self) -> Vec<E::G1> {
@@ -91,19 +317,25 @@ impl<E: Engine + Debug> PreMSM<E> {
         }
     }
 
+    /// Flattens the collected MSMs into a single one. Bases are left in
+    /// projective form; they are normalized once, in parallel, when the
+    /// resulting MSM is evaluated.
     pub(crate) fn normalize(self) -> MSMKZG<E> {
-        use group::prime::PrimeCurveAffine;
-
-        let (scalars, bases) = self
+        let size = self
             .projectives_msms
-            .into_iter()
-            .map(|msm| (msm.scalars, msm.bases))
-            .unzip::<_, _, Vec<_>, Vec<_>>();
+            .iter()
+            .map(|msm| msm.scalars.len())
+            .sum();
 
-        MSMKZG {
-            scalars: scalars.into_iter().flatten().collect(),
-            bases: bases.into_iter().flatten().collect(),
+        let mut msm = MSMKZG::with_capacity(size);
+        for other in self.projectives_msms {
+            if msm.affine.len() == msm.bases.len() {
+                msm.affine.extend(other.affine);
+            }
+            msm.scalars.extend(other.scalars);
+            msm.bases.extend(other.bases);
         }
+        msm
     }
 
     pub(crate) fn add_msm(&mut self, other: MSMKZG<E>) {
@@ -117,6 +349,31 @@ impl<'params, E: MultiMillerLoop + Debug> From<&'params ParamsKZG<E>> for DualMS
     }
 }
 
+/// Outcome of [`DualMSM::check_detailed`]
+#[derive(Debug, Clone)]
+pub struct DualMSMReport<E: Engine> {
+    /// Evaluation of the left channel, paired with `[s] G2`
+    pub left: E::G1Affine,
+    /// Evaluation of the right channel, paired with `-G2`
+    pub right: E::G1Affine,
+    /// Whether the final pairing check passed
+    pub valid: bool,
+}
+
+impl<E: Engine> DualMSMReport<E> {
+    /// Returns `true` if the left channel evaluated to the identity
+    pub fn left_is_identity(&self) -> bool {
+        use group::prime::PrimeCurveAffine;
+        bool::from(self.left.is_identity())
+    }
+
+    /// Returns `true` if the right channel evaluated to the identity
+    pub fn right_is_identity(&self) -> bool {
+        use group::prime::PrimeCurveAffine;
+        bool::from(self.right.is_identity())
+    }
+}
+
 /// Two channel MSM accumulator
 #[derive(Debug, Clone)]
 pub struct DualMSM<'a, E: Engine> {
@@ -135,6 +392,12 @@ impl<'a, E: MultiMillerLoop + Debug> DualMSM<'a, E> {
         }
     }
 
+    /// Reports the progress of the evaluation of both channels to `observer`
+    pub fn set_observer(&mut self, observer: MSMObserver) {
+        self.left.set_observer(observer.clone());
+        self.right.set_observer(observer);
+    }
+
     /// Scale all scalars in the MSM by some scaling factor
     pub fn scale(&mut self, e: E::Scalar) {
         self.left.scale(e);
@@ -147,18 +410,107 @@ impl<'a, E: MultiMillerLoop + Debug> DualMSM<'a, E> {
         self.right.add_msm(&other.right);
     }
 
+    /// Evaluates both channels and returns the two G1/G2 pairs whose pairing
+    /// product must be the identity, i.e. the accumulator is valid iff
+    /// `e(left, [s] G2) * e(right, -G2) == 1`.
+    ///
+    /// This allows the final pairing to be deferred, for example to an EVM
+    /// pairing precompile or an outer recursive circuit.
+    pub fn into_pairing_terms(self) -> [(E::G1Affine, E::G2Affine); 2] {
+        let eval = |msm: &MSMKZG<E>, label| {
+            let (scalars, bases) = msm.consolidated();
+            msm.multiexp(&scalars, &bases, label).to_affine()
+        };
+        let left = eval(&self.left, "left");
+        let right = eval(&self.right, "right");
+
+        [(left, self.params.s_g2), (right, -self.params.g2)]
+    }
+
+    /// Writes the accumulator to a buffer, so that the final pairing check
+    /// can be deferred to another process or machine.
+    ///
+    /// The encoding starts with [`ParamsKZG::verifier_digest`] so that the
+    /// accumulator can only be read back against the same parameters. Scalars
+    /// and points are encoded according to `format`, as for verifying keys.
+    pub fn write<W: io::Write>(&self, writer: &mut W, format: SerdeFormat) -> io::Result<()>
+    where
+        E::Scalar: SerdePrimeField,
+        E::G1Affine: SerdeCurveAffine,
+        E::G2Affine: SerdeCurveAffine,
+    {
+        writer.write_all(&self.params.verifier_digest())?;
+        self.left.write(writer, format)?;
+        self.right.write(writer, format)
+    }
+
+    /// Reads an accumulator written by [`Self::write`].
+    ///
+    /// Returns an error if the accumulator was created with parameters other
+    /// than `params`.
+    pub fn read<R: io::Read>(
+        reader: &mut R,
+        params: &'a ParamsKZG<E>,
+        format: SerdeFormat,
+    ) -> io::Result<Self>
+    where
+        E::Scalar: SerdePrimeField,
+        E::G1Affine: SerdeCurveAffine,
+        E::G2Affine: SerdeCurveAffine,
+    {
+        let mut digest = [0u8; 32];
+        reader.read_exact(&mut digest)?;
+        if digest != params.verifier_digest() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "accumulator was created with different parameters",
+            ));
+        }
+
+        let left = MSMKZG::read(reader, format)?;
+        let right = MSMKZG::read(reader, format)?;
+        Ok(Self {
+            params,
+            left,
+            right,
+        })
+    }
+
+    /// Writes the accumulator to a vector of bytes using [`Self::write`].
+    pub fn to_bytes(&self, format: SerdeFormat) -> Vec<u8>
+    where
+        E::Scalar: SerdePrimeField,
+        E::G1Affine: SerdeCurveAffine,
+        E::G2Affine: SerdeCurveAffine,
+    {
+        let mut bytes = vec![];
+        self.write(&mut bytes, format)
+            .expect("Writing to vector should not fail");
+        bytes
+    }
+
+    /// Reads an accumulator from a slice of bytes using [`Self::read`].
+    pub fn from_bytes(
+        mut bytes: &[u8],
+        params: &'a ParamsKZG<E>,
+        format: SerdeFormat,
+    ) -> io::Result<Self>
+    where
+        E::Scalar: SerdePrimeField,
+        E::G1Affine: SerdeCurveAffine,
+        E::G2Affine: SerdeCurveAffine,
+    {
+        Self::read(&mut bytes, params, format)
+    }
+
     /// Performs final pairing check with given verifier params and two channel linear combination
     pub fn check(self) -> bool {
-        let s_g2_prepared = E::G2Prepared::from(self.params.s_g2);
-        let n_g2_prepared = E::G2Prepared::from(-self.params.g2);
+        let [(left, s_g2), (right, n_g2)] = self.into_pairing_terms();
 
-        let left = self.left.eval();
-        let right = self.right.eval();
+        let s_g2_prepared = E::G2Prepared::from(s_g2);
+        let n_g2_prepared = E::G2Prepared::from(n_g2);
 
-        let (term_1, term_2) = (
-            (&left.into(), &s_g2_prepared),
-            (&right.into(), &n_g2_prepared),
-        );
+        let (term_1, term_2) = ((&left, &s_g2_prepared), (&right, &n_g2_prepared));
         let terms = &[term_1, term_2];
 
         bool::from(
@@ -167,4 +519,208 @@ impl<'a, E: MultiMillerLoop + Debug> DualMSM<'a, E> {
                 .is_identity(),
         )
     }
+
+    /// Performs the same check as [`Self::check`], but also returns the
+    /// evaluation of both channels so that a failure can be inspected.
+    pub fn check_detailed(self) -> DualMSMReport<E> {
+        let [(left, s_g2), (right, n_g2)] = self.into_pairing_terms();
+
+        let s_g2_prepared = E::G2Prepared::from(s_g2);
+        let n_g2_prepared = E::G2Prepared::from(n_g2);
+        let terms = &[(&left, &s_g2_prepared), (&right, &n_g2_prepared)];
+
+        let valid = bool::from(
+            E::multi_miller_loop(&terms[..])
+                .final_exponentiation()
+                .is_identity(),
+        );
+
+        DualMSMReport { left, right, valid }
+    }
+
+    /// Checks many independent accumulators with a single multi-Miller loop
+    /// and final exponentiation.
+    ///
+    /// Each accumulator is scaled by a random factor before being merged, so
+    /// the result is `true` only if every accumulator would pass [`Self::check`]
+    /// (except with negligible probability). Accumulators created with the
+    /// same parameters are folded into a single MSM per channel, so repeated
+    /// bases across them are only paid for once.
+    pub fn check_batch(accumulators: &[Self]) -> bool {
+        use ff::Field;
+        use rand_core::OsRng;
+
+        let mut groups: Vec<DualMSM<'a, E>> = vec![];
+        for accumulator in accumulators {
+            let mut accumulator = accumulator.clone();
+            accumulator.scale(E::Scalar::random(OsRng));
+            match groups
+                .iter_mut()
+                .find(|group| std::ptr::eq(group.params, accumulator.params))
+            {
+                Some(group) => group.add_msm(accumulator),
+                None => groups.push(accumulator),
+            }
+        }
+
+        let terms = groups
+            .into_iter()
+            .flat_map(|group| group.into_pairing_terms())
+            .collect::<Vec<_>>();
+        let prepared = terms
+            .iter()
+            .map(|(g1, g2)| (g1, E::G2Prepared::from(*g2)))
+            .collect::<Vec<_>>();
+        let terms = prepared
+            .iter()
+            .map(|(g1, g2)| (*g1, g2))
+            .collect::<Vec<_>>();
+
+        bool::from(
+            E::multi_miller_loop(&terms[..])
+                .final_exponentiation()
+                .is_identity(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DualMSM, MSMKZG};
+    use crate::poly::{commitment::MSM, kzg::commitment::ParamsKZG};
+    use crate::SerdeFormat;
+    use ff::Field;
+    use group::Group;
+    use halo2curves::bn256::{Bn256, Fr, G1};
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_consolidate() {
+        let p = G1::random(OsRng);
+        let q = G1::random(OsRng);
+
+        let mut msm = MSMKZG::<Bn256>::new();
+        msm.append_term(Fr::random(OsRng), p);
+        msm.append_term(Fr::random(OsRng), q);
+        // Same point as `p`, but with a different projective representation
+        msm.append_term(Fr::random(OsRng), p.double() - p);
+        msm.append_term(Fr::random(OsRng), q);
+
+        let expected = msm.eval();
+        msm.consolidate();
+        assert_eq!(msm.scalars.len(), 2);
+        assert_eq!(msm.bases.len(), 2);
+        assert_eq!(msm.eval(), expected);
+    }
+
+    #[test]
+    fn test_affine_cache() {
+        let mut a = MSMKZG::<Bn256>::new();
+        let mut b = MSMKZG::<Bn256>::new();
+        for _ in 0..4 {
+            a.append_term(Fr::random(OsRng), G1::random(OsRng));
+            b.append_term(Fr::random(OsRng), G1::random(OsRng));
+        }
+        let mut expected = a.clone();
+        expected.add_msm(&b);
+        let expected = expected.eval();
+
+        a.normalize();
+        b.normalize();
+        a.add_msm(&b);
+        assert_eq!(a.affine.len(), a.bases.len());
+        assert_eq!(a.eval(), expected);
+
+        // New terms are normalized on demand
+        a.append_term(Fr::random(OsRng), G1::random(OsRng));
+        assert_eq!(a.affine.len() + 1, a.bases.len());
+        assert_eq!(a.normalized_bases().len(), a.bases.len());
+    }
+
+    #[test]
+    fn test_dual_msm_serialization() {
+        let params = ParamsKZG::<Bn256>::setup(3, OsRng);
+        let other = ParamsKZG::<Bn256>::setup(3, OsRng);
+
+        let mut accumulator = DualMSM::new(&params);
+        for _ in 0..4 {
+            accumulator
+                .left
+                .append_term(Fr::random(OsRng), G1::random(OsRng));
+            accumulator
+                .right
+                .append_term(Fr::random(OsRng), G1::random(OsRng));
+        }
+
+        for format in [SerdeFormat::Processed, SerdeFormat::RawBytes] {
+            let bytes = accumulator.to_bytes(format);
+            let decoded = DualMSM::from_bytes(&bytes, &params, format).unwrap();
+            assert_eq!(decoded.left.eval(), accumulator.left.eval());
+            assert_eq!(decoded.right.eval(), accumulator.right.eval());
+
+            assert!(DualMSM::from_bytes(&bytes, &other, format).is_err());
+        }
+    }
+
+    #[test]
+    fn test_check_batch() {
+        use group::prime::PrimeCurveAffine;
+
+        // `e(a G1, [s] G2) == e(a [s] G1, G2)` holds for any `a`
+        fn valid(params: &ParamsKZG<Bn256>) -> DualMSM<'_, Bn256> {
+            let a = Fr::random(OsRng);
+            let mut accumulator = DualMSM::new(params);
+            accumulator.left.append_term(a, params.g[0].to_curve());
+            accumulator.right.append_term(a, params.g[1].to_curve());
+            accumulator
+        }
+
+        let params = ParamsKZG::<Bn256>::setup(3, OsRng);
+
+        let mut accumulators = (0..4).map(|_| valid(&params)).collect::<Vec<_>>();
+        assert!(accumulators
+            .iter()
+            .all(|accumulator| accumulator.clone().check()));
+        assert!(DualMSM::check_batch(&accumulators));
+
+        accumulators[2]
+            .right
+            .append_term(Fr::one(), params.g[0].to_curve());
+        assert!(!DualMSM::check_batch(&accumulators));
+    }
+
+    #[test]
+    fn test_observer() {
+        use super::MSMObserver;
+        use std::sync::{Arc, Mutex};
+
+        let reports = Arc::new(Mutex::new(vec![]));
+        let observer = {
+            let reports = reports.clone();
+            MSMObserver::new(move |metrics| {
+                reports
+                    .lock()
+                    .unwrap()
+                    .push((metrics.label, metrics.processed, metrics.total))
+            })
+        };
+
+        let params = ParamsKZG::<Bn256>::setup(3, OsRng);
+        let mut accumulator = DualMSM::new(&params);
+        for _ in 0..3 {
+            accumulator
+                .left
+                .append_term(Fr::random(OsRng), G1::random(OsRng));
+        }
+        let expected = accumulator.left.eval();
+
+        accumulator.set_observer(observer);
+        assert_eq!(accumulator.left.eval(), expected);
+        accumulator.check();
+
+        assert_eq!(
+            *reports.lock().unwrap(),
+            vec![("msm", 3, 3), ("left", 3, 3), ("right", 0, 0)]
+        );
+    }
 }
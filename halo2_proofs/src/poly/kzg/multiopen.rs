@@ -1,5 +1,231 @@
+mod fflonk;
 mod gwc;
 mod shplonk;
 
+pub use fflonk::*;
 pub use gwc::*;
 pub use shplonk::*;
+
+use std::fmt::Debug;
+use std::io;
+
+use ff::PrimeField;
+use halo2curves::pairing::{Engine, MultiMillerLoop};
+use rand_core::RngCore;
+
+use super::commitment::{KZGCommitmentScheme, ParamsKZG, ParamsProverKZG};
+use super::msm::{DualMSM, MSMKZG};
+use super::strategy::GuardKZG;
+use crate::helpers::SerdeCurveAffine;
+use crate::poly::commitment::{Prover, Verifier};
+use crate::poly::query::{ProverQuery, VerifierQuery};
+use crate::poly::Error;
+use crate::transcript::{EncodedChallenge, TranscriptRead, TranscriptWrite};
+
+/// KZG multiopen scheme selected at runtime, for provers and verifiers that
+/// handle proofs of either kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultiopenScheme {
+    /// [`ProverGWC`] and [`VerifierGWC`]
+    Gwc,
+    /// [`ProverSHPLONK`] and [`VerifierSHPLONK`]
+    Shplonk,
+}
+
+impl MultiopenScheme {
+    /// Returns the identifier that [`ProverMultiopen`] writes at the start of
+    /// the proofs of this scheme, and [`VerifierMultiopen`] checks.
+    pub fn id(&self) -> u8 {
+        match self {
+            MultiopenScheme::Gwc => 0,
+            MultiopenScheme::Shplonk => 1,
+        }
+    }
+
+    /// Reads the scheme that a [`ProverMultiopen`] recorded in `proof`, right
+    /// after the fingerprint of the circuit. The proof must not start with a
+    /// domain-separation tag, as written by
+    /// [`create_proof_with_domain_tag`](crate::plonk::create_proof_with_domain_tag).
+    pub fn read<F: PrimeField>(proof: &[u8]) -> io::Result<Self> {
+        let mut reader = proof;
+        let mut repr = F::Repr::default();
+        // Skip the fingerprint
+        io::Read::read_exact(&mut reader, repr.as_mut())?;
+        io::Read::read_exact(&mut reader, repr.as_mut())?;
+        let id = Option::<F>::from(F::from_repr(repr))
+            .ok_or_else(|| invalid_data("invalid field element encoding in proof"))?;
+        [MultiopenScheme::Gwc, MultiopenScheme::Shplonk]
+            .into_iter()
+            .find(|scheme| F::from(scheme.id() as u64) == id)
+            .ok_or_else(|| invalid_data("proof does not record a known multiopen scheme"))
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// KZG multiopen prover dispatching to GWC or SHPLONK at runtime.
+///
+/// [`Prover::new`] selects SHPLONK; use [`Self::with_scheme`] together with
+/// [`create_proof_with_options`](crate::plonk::create_proof_with_options) to
+/// choose the scheme. The proofs start with the [id](MultiopenScheme::id) of
+/// the scheme, so that a [`VerifierMultiopen`] for another scheme rejects
+/// them with [`Error::MultiopenMismatch`](crate::plonk::Error::MultiopenMismatch).
+#[derive(Debug)]
+pub enum ProverMultiopen<'params, E: Engine, P = ParamsKZG<E>> {
+    /// GWC prover
+    Gwc(ProverGWC<'params, E, P>),
+    /// SHPLONK prover
+    Shplonk(ProverSHPLONK<'params, E, P>),
+}
+
+impl<'params, E: Engine + Debug, P: ParamsProverKZG<E>> ProverMultiopen<'params, E, P>
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    /// Creates a prover for `scheme`
+    pub fn with_scheme(scheme: MultiopenScheme, params: &'params P) -> Self {
+        match scheme {
+            MultiopenScheme::Gwc => ProverMultiopen::Gwc(ProverGWC::new(params)),
+            MultiopenScheme::Shplonk => ProverMultiopen::Shplonk(ProverSHPLONK::new(params)),
+        }
+    }
+
+    /// Returns the scheme of this prover
+    pub fn scheme(&self) -> MultiopenScheme {
+        match self {
+            ProverMultiopen::Gwc(_) => MultiopenScheme::Gwc,
+            ProverMultiopen::Shplonk(_) => MultiopenScheme::Shplonk,
+        }
+    }
+}
+
+impl<'params, E: Engine + Debug, P: ParamsProverKZG<E>> Prover<'params, KZGCommitmentScheme<E, P>>
+    for ProverMultiopen<'params, E, P>
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    const QUERY_INSTANCE: bool = false;
+
+    fn new(params: &'params P) -> Self {
+        Self::with_scheme(MultiopenScheme::Shplonk, params)
+    }
+
+    fn scheme_id(&self) -> Option<u8> {
+        Some(self.scheme().id())
+    }
+
+    fn create_proof<
+        'com,
+        Ch: EncodedChallenge<E::G1Affine>,
+        T: TranscriptWrite<E::G1Affine, Ch>,
+        R,
+        I,
+    >(
+        &self,
+        rng: R,
+        transcript: &mut T,
+        queries: I,
+    ) -> io::Result<()>
+    where
+        I: IntoIterator<Item = ProverQuery<'com, E::G1Affine>> + Clone,
+        R: RngCore,
+    {
+        match self {
+            ProverMultiopen::Gwc(prover) => prover.create_proof(rng, transcript, queries),
+            ProverMultiopen::Shplonk(prover) => prover.create_proof(rng, transcript, queries),
+        }
+    }
+}
+
+/// KZG multiopen verifier dispatching to GWC or SHPLONK at runtime.
+///
+/// [`Verifier::new`] selects SHPLONK; use [`Self::with_scheme`], or
+/// [`Self::for_proof`] to follow the scheme the proof records, together with
+/// [`verify_proof_with_verifier`](crate::plonk::verify_proof_with_verifier)
+/// to choose the scheme. Proofs from a [`ProverMultiopen`] for another
+/// scheme are rejected before their opening is checked.
+#[derive(Debug)]
+pub enum VerifierMultiopen<'params, E: Engine> {
+    /// GWC verifier
+    Gwc(VerifierGWC<'params, E>),
+    /// SHPLONK verifier
+    Shplonk(VerifierSHPLONK<'params, E>),
+}
+
+impl<'params, E: MultiMillerLoop + Debug> VerifierMultiopen<'params, E>
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    /// Creates a verifier for `scheme`
+    pub fn with_scheme(scheme: MultiopenScheme, params: &'params ParamsKZG<E>) -> Self {
+        match scheme {
+            MultiopenScheme::Gwc => VerifierMultiopen::Gwc(VerifierGWC::new(params)),
+            MultiopenScheme::Shplonk => VerifierMultiopen::Shplonk(VerifierSHPLONK::new(params)),
+        }
+    }
+
+    /// Creates a verifier for the scheme recorded in `proof`, see
+    /// [`MultiopenScheme::read`], so that one verifier accepts the proofs of
+    /// either scheme
+    pub fn for_proof(params: &'params ParamsKZG<E>, proof: &[u8]) -> io::Result<Self> {
+        MultiopenScheme::read::<E::Scalar>(proof).map(|scheme| Self::with_scheme(scheme, params))
+    }
+
+    /// Returns the scheme of this verifier
+    pub fn scheme(&self) -> MultiopenScheme {
+        match self {
+            VerifierMultiopen::Gwc(_) => MultiopenScheme::Gwc,
+            VerifierMultiopen::Shplonk(_) => MultiopenScheme::Shplonk,
+        }
+    }
+}
+
+impl<'params, E> Verifier<'params, KZGCommitmentScheme<E>> for VerifierMultiopen<'params, E>
+where
+    E: MultiMillerLoop + Debug,
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    type Guard = GuardKZG<'params, E>;
+    type MSMAccumulator = DualMSM<'params, E>;
+
+    const QUERY_INSTANCE: bool = false;
+
+    fn new(params: &'params ParamsKZG<E>) -> Self {
+        Self::with_scheme(MultiopenScheme::Shplonk, params)
+    }
+
+    fn scheme_id(&self) -> Option<u8> {
+        Some(self.scheme().id())
+    }
+
+    fn verify_proof<
+        'com,
+        Ch: EncodedChallenge<E::G1Affine>,
+        T: TranscriptRead<E::G1Affine, Ch>,
+        I,
+    >(
+        &self,
+        transcript: &mut T,
+        queries: I,
+        msm_accumulator: DualMSM<'params, E>,
+    ) -> Result<Self::Guard, Error>
+    where
+        'params: 'com,
+        I: IntoIterator<Item = VerifierQuery<'com, E::G1Affine, MSMKZG<E>>> + Clone,
+    {
+        match self {
+            VerifierMultiopen::Gwc(verifier) => {
+                verifier.verify_proof(transcript, queries, msm_accumulator)
+            }
+            VerifierMultiopen::Shplonk(verifier) => {
+                verifier.verify_proof(transcript, queries, msm_accumulator)
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Multiopen argument opening the quotients at all points with a single
+//! fflonk-style combined commitment (see [`crate::poly::kzg::fflonk`]).
+//!
+//! The queries are batched at each of their `m` distinct points `p_j` into
+//! `F_j(X)` with a challenge `v`, as in GWC. The prover then commits to the
+//! combination `H(X) = sum_j h_j(X^m) X^j` of the quotients
+//! `h_j(X) = (F_j(X) - F_j(p_j)) / (X - p_j)`, opens it at a random `r = z^m`
+//! to reveal every `h_j(r)`, and opens the `F_j` at `r` together. The
+//! verifier checks `h_j(r) (r - p_j) = F_j(r) - F_j(p_j)` for each point and
+//! a single pairing for both openings.
+//!
+//! The proof has four group elements and `2 m` scalars, whatever the number
+//! of points. Committing to `H` takes an SRS of `m n` powers for polynomials
+//! of `n` coefficients, so the prover needs parameters for a larger `k` than
+//! the circuit, while the verifier only uses the G1 generator and the G2
+//! elements that the parameters of the circuit share with them.
+//!
+//! Only the opening argument is combined: the columns of the circuit are
+//! still committed to one by one, as with the other multiopen arguments, so
+//! this is not the fflonk proof system with a single commitment per phase.
+//! Compared to GWC, it saves one group element per point beyond the first
+//! three, at the cost of the larger SRS.
+
+mod prover;
+mod verifier;
+
+pub use prover::ProverFFLONK;
+pub use verifier::VerifierFFLONK;
+
+use crate::transcript::ChallengeScalar;
+
+#[derive(Clone, Copy, Debug)]
+struct V {}
+type ChallengeV<F> = ChallengeScalar<F, V>;
+
+#[derive(Clone, Copy, Debug)]
+struct Z {}
+type ChallengeZ<F> = ChallengeScalar<F, Z>;
+
+#[derive(Clone, Copy, Debug)]
+struct W {}
+type ChallengeW<F> = ChallengeScalar<F, W>;
+
+#[derive(Clone, Copy, Debug)]
+struct Xi {}
+type ChallengeXi<F> = ChallengeScalar<F, Xi>;
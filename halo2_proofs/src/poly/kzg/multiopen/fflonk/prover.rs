@@ -0,0 +1,149 @@
+use super::{ChallengeV, ChallengeW, ChallengeXi, ChallengeZ};
+use crate::arithmetic::{eval_polynomial, kate_division, parallelize, powers};
+use crate::helpers::SerdeCurveAffine;
+use crate::poly::commitment::{Blind, Params, ParamsProver, Prover};
+use crate::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG, ParamsProverKZG};
+use crate::poly::kzg::fflonk::{combine, open};
+use crate::poly::kzg::multiopen::gwc::construct_intermediate_sets;
+use crate::poly::query::{ProverQuery, Query};
+use crate::poly::{Coeff, Polynomial};
+use crate::transcript::{EncodedChallenge, TranscriptWrite};
+
+use ff::Field;
+use group::Curve;
+use halo2curves::pairing::Engine;
+use rand_core::RngCore;
+use rayon::prelude::*;
+use std::fmt::Debug;
+use std::io;
+use std::marker::PhantomData;
+
+/// Concrete KZG prover with the fflonk variant
+///
+/// The parameters must hold at least `m n` powers of the secret for `m`
+/// distinct query points and polynomials of `n` coefficients, e.g. those the
+/// parameters of the circuit were [downsized](Params::downsize) from.
+/// Otherwise [`Prover::create_proof`] fails with
+/// [`io::ErrorKind::InvalidInput`], which
+/// [`create_proof`](crate::plonk::create_proof) returns as
+/// [`Error::Transcript`](crate::plonk::Error::Transcript).
+#[derive(Debug)]
+pub struct ProverFFLONK<'params, E: Engine, P = ParamsKZG<E>> {
+    params: &'params P,
+    _marker: PhantomData<E>,
+}
+
+/// Create a multi-opening proof
+impl<'params, E: Engine + Debug, P: ParamsProverKZG<E>> Prover<'params, KZGCommitmentScheme<E, P>>
+    for ProverFFLONK<'params, E, P>
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    const QUERY_INSTANCE: bool = false;
+
+    fn new(params: &'params P) -> Self {
+        Self {
+            params,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Create a multi-opening proof
+    fn create_proof<
+        'com,
+        Ch: EncodedChallenge<E::G1Affine>,
+        T: TranscriptWrite<E::G1Affine, Ch>,
+        R,
+        I,
+    >(
+        &self,
+        _: R,
+        transcript: &mut T,
+        queries: I,
+    ) -> io::Result<()>
+    where
+        I: IntoIterator<Item = ProverQuery<'com, E::G1Affine>> + Clone,
+        R: RngCore,
+    {
+        let v: ChallengeV<_> = transcript.squeeze_challenge_scalar();
+        let v = *v;
+        let commitment_data = construct_intermediate_sets(queries);
+        let m = commitment_data.len();
+        if m == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "there are no queries to open",
+            ));
+        }
+
+        // The polynomials batched at each point, and their quotients
+        let (batches, quotients): (Vec<Polynomial<E::Scalar, Coeff>>, Vec<_>) = commitment_data
+            .par_iter()
+            .map(|commitment_at_a_point| {
+                let z = commitment_at_a_point.point;
+                let n = commitment_at_a_point.queries[0].get_commitment().poly.len();
+
+                let mut poly_batch = vec![E::Scalar::zero(); n];
+                for (query, power_of_v) in commitment_at_a_point.queries.iter().zip(powers(v)) {
+                    assert_eq!(query.get_point(), z);
+
+                    let poly = query.get_commitment().poly;
+                    parallelize(&mut poly_batch, |acc, start| {
+                        for (acc, coeff) in acc.iter_mut().zip(poly[start..].iter()) {
+                            *acc += *coeff * power_of_v;
+                        }
+                    });
+                }
+
+                let quotient = Polynomial {
+                    values: kate_division(&poly_batch, z),
+                    _marker: PhantomData,
+                };
+                let batch = Polynomial {
+                    values: poly_batch,
+                    _marker: PhantomData,
+                };
+                (batch, quotient)
+            })
+            .unzip();
+
+        let combined = combine(&quotients);
+        if combined.len() > self.params.n() as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "params are too small to commit to the combined quotients",
+            ));
+        }
+        transcript.write_point(self.params.commit(&combined, Blind::default()).to_affine())?;
+
+        // Open the quotients at r = z^m
+        let z: ChallengeZ<_> = transcript.squeeze_challenge_scalar();
+        open(self.params, transcript, &combined, m, *z)?;
+        let r = z.pow_vartime(&[m as u64]);
+
+        // Open the batched polynomials at r together
+        for batch in batches.iter() {
+            transcript.write_scalar(eval_polynomial(batch, r))?;
+        }
+        let w: ChallengeW<_> = transcript.squeeze_challenge_scalar();
+        let mut opened = vec![E::Scalar::zero(); batches[0].len()];
+        for (batch, power_of_w) in batches.iter().zip(powers(*w)) {
+            parallelize(&mut opened, |acc, start| {
+                for (acc, coeff) in acc.iter_mut().zip(batch[start..].iter()) {
+                    *acc += *coeff * power_of_w;
+                }
+            });
+        }
+        let witness = Polynomial {
+            values: kate_division(&opened, r),
+            _marker: PhantomData,
+        };
+        transcript.write_point(self.params.commit(&witness, Blind::default()).to_affine())?;
+
+        // The verifier combines the two pairing checks with this challenge
+        let _: ChallengeXi<_> = transcript.squeeze_challenge_scalar();
+
+        Ok(())
+    }
+}
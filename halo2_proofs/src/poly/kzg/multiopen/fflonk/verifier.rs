@@ -0,0 +1,144 @@
+use std::fmt::Debug;
+
+use super::{ChallengeV, ChallengeW, ChallengeXi, ChallengeZ};
+use crate::arithmetic::powers;
+use crate::helpers::SerdeCurveAffine;
+use crate::poly::commitment::Verifier;
+use crate::poly::commitment::MSM;
+use crate::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use crate::poly::kzg::fflonk::accumulate;
+use crate::poly::kzg::msm::{DualMSM, MSMKZG};
+use crate::poly::kzg::multiopen::gwc::construct_intermediate_sets;
+use crate::poly::kzg::strategy::GuardKZG;
+use crate::poly::query::{CommitmentReference, Query, VerifierQuery};
+use crate::poly::Error;
+use crate::transcript::{EncodedChallenge, TranscriptRead};
+
+use ff::Field;
+use halo2curves::pairing::{Engine, MultiMillerLoop};
+
+#[derive(Debug)]
+/// Concrete KZG verifier with the fflonk variant
+///
+/// Unlike [`ProverFFLONK`](super::ProverFFLONK), it works with the
+/// parameters of the circuit.
+pub struct VerifierFFLONK<'params, E: Engine> {
+    params: &'params ParamsKZG<E>,
+}
+
+impl<'params, E> Verifier<'params, KZGCommitmentScheme<E>> for VerifierFFLONK<'params, E>
+where
+    E: MultiMillerLoop + Debug,
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    type Guard = GuardKZG<'params, E>;
+    type MSMAccumulator = DualMSM<'params, E>;
+
+    const QUERY_INSTANCE: bool = false;
+
+    fn new(params: &'params ParamsKZG<E>) -> Self {
+        Self { params }
+    }
+
+    fn verify_proof<
+        'com,
+        Ch: EncodedChallenge<E::G1Affine>,
+        T: TranscriptRead<E::G1Affine, Ch>,
+        I,
+    >(
+        &self,
+        transcript: &mut T,
+        queries: I,
+        mut msm_accumulator: DualMSM<'params, E>,
+    ) -> Result<Self::Guard, Error>
+    where
+        I: IntoIterator<Item = VerifierQuery<'com, E::G1Affine, MSMKZG<E>>> + Clone,
+    {
+        let v: ChallengeV<_> = transcript.squeeze_challenge_scalar();
+
+        let commitment_data = construct_intermediate_sets(queries);
+        let m = commitment_data.len();
+        if m == 0 {
+            return Err(Error::OpeningError);
+        }
+
+        let combined = transcript.read_point().map_err(|_| Error::OpeningError)?;
+        let z: ChallengeZ<_> = transcript.squeeze_challenge_scalar();
+        let (quotient_evals, mut quotients_opening) =
+            accumulate(self.params, transcript, &combined, m, *z)?;
+        let r = z.pow_vartime(&[m as u64]);
+
+        let batch_evals = (0..m)
+            .map(|_| transcript.read_scalar())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Error::OpeningError)?;
+        let w: ChallengeW<_> = transcript.squeeze_challenge_scalar();
+        let witness: E::G1 = transcript
+            .read_point()
+            .map_err(|_| Error::OpeningError)?
+            .into();
+
+        let mut commitment_multi = MSMKZG::<E>::new();
+        let mut eval_multi = E::Scalar::zero();
+        for (((commitment_at_a_point, quotient_eval), batch_eval), power_of_w) in commitment_data
+            .iter()
+            .zip(quotient_evals.iter())
+            .zip(batch_evals.iter())
+            .zip(powers(*w))
+        {
+            let z = commitment_at_a_point.point;
+
+            let (mut commitment_batch, eval_batch) = commitment_at_a_point
+                .queries
+                .iter()
+                .zip(powers(*v))
+                .map(|(query, power_of_v)| {
+                    assert_eq!(query.get_point(), z);
+
+                    let commitment = match query.get_commitment() {
+                        CommitmentReference::Commitment(c) => {
+                            let mut msm = MSMKZG::<E>::new();
+                            msm.append_term(power_of_v, (*c).into());
+                            msm
+                        }
+                        CommitmentReference::MSM(msm) => {
+                            let mut msm = msm.clone();
+                            msm.scale(power_of_v);
+                            msm
+                        }
+                    };
+                    let eval = power_of_v * query.get_eval();
+
+                    (commitment, eval)
+                })
+                .reduce(|(mut commitment_acc, eval_acc), (commitment, eval)| {
+                    commitment_acc.add_msm(&commitment);
+                    (commitment_acc, eval_acc + eval)
+                })
+                .unwrap();
+
+            // h_j(r) (r - p_j) = F_j(r) - F_j(p_j)
+            if *quotient_eval * (r - z) != *batch_eval - eval_batch {
+                return Err(Error::OpeningError);
+            }
+
+            commitment_batch.scale(power_of_w);
+            commitment_multi.add_msm(&commitment_batch);
+            eval_multi += power_of_w * batch_eval;
+        }
+
+        // Both openings are checked with one pairing
+        let xi: ChallengeXi<_> = transcript.squeeze_challenge_scalar();
+        quotients_opening.scale(*xi);
+        msm_accumulator.add_msm(quotients_opening);
+
+        msm_accumulator.left.append_term(E::Scalar::one(), witness);
+        msm_accumulator.right.add_msm(&commitment_multi);
+        msm_accumulator.right.append_term(r, witness);
+        let g0: E::G1 = self.params.g[0].into();
+        msm_accumulator.right.append_term(eval_multi, -g0);
+
+        Ok(Self::Guard::new(msm_accumulator))
+    }
+}
@@ -27,13 +27,15 @@ type ChallengeU<F> = ChallengeScalar<F, U>;
 struct V {}
 type ChallengeV<F> = ChallengeScalar<F, V>;
 
-struct CommitmentData<F: FieldExt, Q: Query<F>> {
-    queries: Vec<Q>,
-    point: F,
+pub(super) struct CommitmentData<F: FieldExt, Q: Query<F>> {
+    pub(super) queries: Vec<Q>,
+    pub(super) point: F,
     _marker: PhantomData<F>,
 }
 
-fn construct_intermediate_sets<F: FieldExt, I, Q: Query<F>>(queries: I) -> Vec<CommitmentData<F, Q>>
+pub(super) fn construct_intermediate_sets<F: FieldExt, I, Q: Query<F>>(
+    queries: I,
+) -> Vec<CommitmentData<F, Q>>
 where
     I: IntoIterator<Item = Q> + Clone,
 {
@@ -1,9 +1,11 @@
 use super::{construct_intermediate_sets, ChallengeV, Query};
-use crate::arithmetic::{eval_polynomial, kate_division, powers, CurveAffine, FieldExt};
+use crate::arithmetic::{
+    eval_polynomial, kate_division, parallelize, powers, CurveAffine, FieldExt,
+};
 use crate::helpers::SerdeCurveAffine;
 use crate::poly::commitment::ParamsProver;
 use crate::poly::commitment::Prover;
-use crate::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use crate::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG, ParamsProverKZG};
 use crate::poly::query::ProverQuery;
 use crate::poly::Rotation;
 use crate::poly::{
@@ -16,26 +18,32 @@ use ff::Field;
 use group::Curve;
 use halo2curves::pairing::Engine;
 use rand_core::RngCore;
+use rayon::prelude::*;
 use std::fmt::Debug;
 use std::io::{self, Write};
 use std::marker::PhantomData;
 
 /// Concrete KZG prover with GWC variant
 #[derive(Debug)]
-pub struct ProverGWC<'params, E: Engine> {
-    params: &'params ParamsKZG<E>,
+pub struct ProverGWC<'params, E: Engine, P = ParamsKZG<E>> {
+    params: &'params P,
+    _marker: PhantomData<E>,
 }
 
 /// Create a multi-opening proof
-impl<'params, E: Engine + Debug> Prover<'params, KZGCommitmentScheme<E>> for ProverGWC<'params, E>
+impl<'params, E: Engine + Debug, P: ParamsProverKZG<E>> Prover<'params, KZGCommitmentScheme<E, P>>
+    for ProverGWC<'params, E, P>
 where
     E::G1Affine: SerdeCurveAffine,
     E::G2Affine: SerdeCurveAffine,
 {
     const QUERY_INSTANCE: bool = false;
 
-    fn new(params: &'params ParamsKZG<E>) -> Self {
-        Self { params }
+    fn new(params: &'params P) -> Self {
+        Self {
+            params,
+            _marker: PhantomData,
+        }
     }
 
     /// Create a multi-opening proof
@@ -56,33 +64,44 @@ where
         R: RngCore,
     {
         let v: ChallengeV<_> = transcript.squeeze_challenge_scalar();
+        let v = *v;
         let commitment_data = construct_intermediate_sets(queries);
 
-        for commitment_at_a_point in commitment_data.iter() {
-            let z = commitment_at_a_point.point;
-            let (poly_batch, eval_batch) = commitment_at_a_point
-                .queries
-                .iter()
-                .zip(powers(*v))
-                .map(|(query, power_of_v)| {
+        // Witness polynomials at distinct points are independent of each
+        // other, so they are constructed in parallel and only the commitments
+        // are written in order.
+        let witness_polys: Vec<Polynomial<E::Scalar, Coeff>> = commitment_data
+            .par_iter()
+            .map(|commitment_at_a_point| {
+                let z = commitment_at_a_point.point;
+                let n = commitment_at_a_point.queries[0].get_commitment().poly.len();
+
+                let mut poly_batch = vec![E::Scalar::zero(); n];
+                let mut eval_batch = E::Scalar::zero();
+                for (query, power_of_v) in commitment_at_a_point.queries.iter().zip(powers(v)) {
                     assert_eq!(query.get_point(), z);
 
                     let poly = query.get_commitment().poly;
-                    let eval = query.get_eval();
+                    parallelize(&mut poly_batch, |acc, start| {
+                        for (acc, coeff) in acc.iter_mut().zip(poly[start..].iter()) {
+                            *acc += *coeff * power_of_v;
+                        }
+                    });
+                    eval_batch += query.get_eval() * power_of_v;
+                }
+                poly_batch[0] -= eval_batch;
 
-                    (poly.clone() * power_of_v, eval * power_of_v)
-                })
-                .reduce(|(poly_acc, eval_acc), (poly, eval)| (poly_acc + &poly, eval_acc + eval))
-                .unwrap();
+                Polynomial {
+                    values: kate_division(&poly_batch, z),
+                    _marker: PhantomData,
+                }
+            })
+            .collect();
 
-            let poly_batch = &poly_batch - eval_batch;
-            let witness_poly = Polynomial {
-                values: kate_division(&poly_batch.values, z),
-                _marker: PhantomData,
-            };
+        for witness_poly in witness_polys.iter() {
             let w = self
                 .params
-                .commit(&witness_poly, Blind::default())
+                .commit(witness_poly, Blind::default())
                 .to_affine();
 
             transcript.write_point(w)?;
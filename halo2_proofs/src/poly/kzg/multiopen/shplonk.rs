@@ -2,7 +2,7 @@ mod prover;
 mod verifier;
 
 pub use prover::ProverSHPLONK;
-pub use verifier::VerifierSHPLONK;
+pub use verifier::{VerifierQueryPlan, VerifierSHPLONK};
 
 use crate::{
     arithmetic::{eval_polynomial, lagrange_interpolate, CurveAffine, FieldExt},
@@ -12,6 +12,7 @@ use crate::{
 use rayon::prelude::*;
 use std::{
     collections::{btree_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet},
+    hash::Hash,
     marker::PhantomData,
     sync::Arc,
 };
@@ -146,6 +147,194 @@ where
     }
 }
 
+/// Structure of the rotation sets of a sequence of queries, in terms of
+/// indices into the queries and into their distinct points.
+///
+/// The structure only depends on which queries share a commitment and which
+/// share a point, so it stays the same for every proof of a circuit even
+/// though the points and evaluations change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct QueryPlan {
+    num_queries: usize,
+    /// Index of the distinct point of each query
+    query_points: Vec<usize>,
+    num_points: usize,
+    /// Index of the distinct commitment of each query
+    query_commitments: Vec<usize>,
+    num_commitments: usize,
+    sets: Vec<QueryPlanSet>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct QueryPlanSet {
+    /// Distinct points of the set
+    points: Vec<usize>,
+    /// Distinct points that are not in the set
+    diffs: Vec<usize>,
+    /// For each commitment of the set, the index of its query at every point
+    commitments: Vec<Vec<usize>>,
+}
+
+impl QueryPlan {
+    /// Groups queries, given by the index of their distinct point and of
+    /// their distinct commitment, in the same order as
+    /// [`construct_intermediate_sets`]
+    fn from_indices(query_points: Vec<usize>, query_commitments: Vec<usize>) -> Self {
+        assert_eq!(query_points.len(), query_commitments.len());
+
+        // Number the points and commitments in order of first appearance
+        let renumber = |indices: Vec<usize>| {
+            let mut numbers = HashMap::new();
+            let indices = indices
+                .into_iter()
+                .map(|index| {
+                    let next = numbers.len();
+                    *numbers.entry(index).or_insert(next)
+                })
+                .collect::<Vec<_>>();
+            (indices, numbers.len())
+        };
+        let (query_points, num_points) = renumber(query_points);
+        let (query_commitments, num_commitments) = renumber(query_commitments);
+
+        // The query at each of the points of every commitment
+        let mut commitments = vec![BTreeMap::new(); num_commitments];
+        for (index, (point, commitment)) in query_points
+            .iter()
+            .zip(query_commitments.iter())
+            .enumerate()
+        {
+            commitments[*commitment].entry(*point).or_insert(index);
+        }
+
+        let mut sets: Vec<QueryPlanSet> = vec![];
+        for points in commitments {
+            let (set_points, indices): (Vec<_>, Vec<_>) = points.into_iter().unzip();
+            match sets.iter_mut().find(|set| set.points == set_points) {
+                Some(set) => set.commitments.push(indices),
+                None => {
+                    let diffs = (0..num_points)
+                        .filter(|point| !set_points.contains(point))
+                        .collect();
+                    sets.push(QueryPlanSet {
+                        points: set_points,
+                        diffs,
+                        commitments: vec![indices],
+                    })
+                }
+            }
+        }
+
+        QueryPlan {
+            num_queries: query_points.len(),
+            query_points,
+            num_points,
+            query_commitments,
+            num_commitments,
+            sets,
+        }
+    }
+
+    /// Groups `queries` by comparing their points and commitments
+    #[cfg(test)]
+    fn new<F: FieldExt, Q: Query<F, Eval = F>>(queries: &[Q]) -> Self {
+        let mut points = BTreeMap::new();
+        let query_points = queries
+            .iter()
+            .map(|query| {
+                let next = points.len();
+                *points.entry(query.get_point()).or_insert(next)
+            })
+            .collect();
+        let mut commitments: Vec<Q::Commitment> = vec![];
+        let query_commitments = queries
+            .iter()
+            .map(|query| {
+                let commitment = query.get_commitment();
+                commitments
+                    .iter()
+                    .position(|other| *other == commitment)
+                    .unwrap_or_else(|| {
+                        commitments.push(commitment);
+                        commitments.len() - 1
+                    })
+            })
+            .collect();
+        Self::from_indices(query_points, query_commitments)
+    }
+
+    /// Returns whether `queries` have the structure of the plan: the same
+    /// number of queries, of which exactly the same ones share a point and
+    /// exactly the same ones share a commitment.
+    fn matches<F: FieldExt, Q: Query<F, Eval = F>>(&self, queries: &[Q]) -> bool
+    where
+        Q::Commitment: Hash + Eq,
+    {
+        if queries.len() != self.num_queries {
+            return false;
+        }
+
+        // The first query at each distinct point and commitment
+        let mut points: Vec<Option<F>> = vec![None; self.num_points];
+        let mut commitments: Vec<Option<Q::Commitment>> = vec![None; self.num_commitments];
+        for ((query, point), commitment) in queries
+            .iter()
+            .zip(self.query_points.iter())
+            .zip(self.query_commitments.iter())
+        {
+            let point = points[*point].get_or_insert_with(|| query.get_point());
+            let commitment = commitments[*commitment].get_or_insert_with(|| query.get_commitment());
+            if *point != query.get_point() || *commitment != query.get_commitment() {
+                return false;
+            }
+        }
+
+        // Distinct in the plan, distinct in the queries
+        let distinct_points = points.iter().flatten().collect::<BTreeSet<_>>();
+        let distinct_commitments = commitments.iter().flatten().collect::<HashSet<_>>();
+        distinct_points.len() == self.num_points
+            && distinct_commitments.len() == self.num_commitments
+    }
+
+    /// Returns the rotation sets of `queries`, together with the points of
+    /// every set's complement in the super point set.
+    #[allow(clippy::type_complexity)]
+    fn rotation_sets<F: FieldExt, Q: Query<F, Eval = F>>(
+        &self,
+        queries: &[Q],
+    ) -> (Vec<RotationSet<F, Q::Commitment>>, Vec<Vec<F>>) {
+        assert_eq!(queries.len(), self.num_queries);
+
+        let mut points = vec![F::zero(); self.num_points];
+        for (query, point) in queries.iter().zip(self.query_points.iter()) {
+            points[*point] = query.get_point();
+        }
+
+        self.sets
+            .iter()
+            .map(|set| {
+                let commitments = set
+                    .commitments
+                    .iter()
+                    .map(|indices| {
+                        let evals = indices
+                            .iter()
+                            .map(|index| queries[*index].get_eval())
+                            .collect();
+                        Commitment((queries[indices[0]].get_commitment(), evals))
+                    })
+                    .collect();
+                let rotation_set = RotationSet {
+                    commitments,
+                    points: set.points.iter().map(|point| points[*point]).collect(),
+                };
+                let diffs = set.diffs.iter().map(|point| points[*point]).collect();
+                (rotation_set, diffs)
+            })
+            .unzip()
+    }
+}
+
 #[cfg(test)]
 mod proptests {
     use proptest::{
@@ -155,11 +344,14 @@ mod proptests {
         strategy::Strategy,
     };
 
-    use super::{construct_intermediate_sets, Commitment, IntermediateSets};
+    use super::{
+        construct_intermediate_sets, Commitment, IntermediateSets, QueryPlan, RotationSet,
+    };
     use crate::poly::Rotation;
+    use ff::Field;
     use halo2curves::{pasta::Fp, FieldExt};
 
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, BTreeSet};
     use std::convert::TryFrom;
 
     #[derive(Debug, Clone)]
@@ -258,5 +450,54 @@ mod proptests {
 
             assert_eq!(commitment_sets, new_commitment_sets);
         }
+
+        #[test]
+        fn test_query_plan(
+            (queries_1, queries_2) in compare_queries(8, 8, 16)
+        ) {
+            // A plan learned from one set of queries reproduces the rotation
+            // sets of another set with the same structure
+            let plan = QueryPlan::new(&queries_1);
+            prop_assert!(plan.matches(&queries_2));
+            let (rotation_sets, diffs) = plan.rotation_sets(&queries_2);
+            let IntermediateSets { rotation_sets: expected, super_point_set } =
+                construct_intermediate_sets(queries_2);
+
+            assert_eq!(rotation_sets.len(), expected.len());
+            for ((set, diffs), expected) in rotation_sets.iter().zip(diffs).zip(expected) {
+                let evals = |set: &RotationSet<Fp, usize>| {
+                    set.commitments.iter().map(|commitment| {
+                        (commitment.get(), set.points.iter().copied().zip(commitment.evals()).collect::<BTreeMap<_, _>>())
+                    }).collect::<Vec<_>>()
+                };
+                assert_eq!(evals(set), evals(&expected));
+
+                let expected_diffs = super_point_set
+                    .iter()
+                    .filter(|point| !expected.points.contains(point))
+                    .copied()
+                    .collect::<BTreeSet<_>>();
+                assert_eq!(diffs.into_iter().collect::<BTreeSet<_>>(), expected_diffs);
+            }
+        }
+    }
+
+    #[test]
+    fn test_query_plan_mismatch() {
+        let query = |commitment, point| MyQuery {
+            point: Fp::from(point),
+            eval: Fp::zero(),
+            commitment,
+        };
+        let plan = QueryPlan::new(&[query(0, 1), query(1, 1), query(1, 2)]);
+        assert!(plan.matches(&[query(2, 3), query(0, 3), query(0, 4)]));
+
+        // Another commitment, a merged commitment, another point, a merged
+        // point and another number of queries
+        assert!(!plan.matches(&[query(0, 1), query(1, 1), query(2, 2)]));
+        assert!(!plan.matches(&[query(1, 1), query(1, 1), query(1, 2)]));
+        assert!(!plan.matches(&[query(0, 1), query(1, 2), query(1, 2)]));
+        assert!(!plan.matches(&[query(0, 1), query(1, 1), query(1, 1)]));
+        assert!(!plan.matches(&[query(0, 1), query(1, 1)]));
     }
 }
@@ -7,7 +7,7 @@ use crate::arithmetic::{
 };
 use crate::helpers::SerdeCurveAffine;
 use crate::poly::commitment::{Blind, ParamsProver, Prover};
-use crate::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use crate::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG, ParamsProverKZG};
 use crate::poly::query::{PolynomialPointer, ProverQuery};
 use crate::poly::Rotation;
 use crate::poly::{commitment::Params, Coeff, Polynomial};
@@ -90,28 +90,35 @@ impl<'a, C: CurveAffine> RotationSet<C::Scalar, PolynomialPointer<'a, C>> {
 
 /// Concrete KZG prover with SHPLONK variant
 #[derive(Debug)]
-pub struct ProverSHPLONK<'a, E: Engine> {
-    params: &'a ParamsKZG<E>,
+pub struct ProverSHPLONK<'a, E: Engine, P = ParamsKZG<E>> {
+    params: &'a P,
+    _marker: PhantomData<E>,
 }
 
-impl<'a, E: Engine> ProverSHPLONK<'a, E> {
+impl<'a, E: Engine, P: ParamsProverKZG<E>> ProverSHPLONK<'a, E, P> {
     /// Given parameters creates new prover instance
-    pub fn new(params: &'a ParamsKZG<E>) -> Self {
-        Self { params }
+    pub fn new(params: &'a P) -> Self {
+        Self {
+            params,
+            _marker: PhantomData,
+        }
     }
 }
 
 /// Create a multi-opening proof
-impl<'params, E: Engine + Debug> Prover<'params, KZGCommitmentScheme<E>>
-    for ProverSHPLONK<'params, E>
+impl<'params, E: Engine + Debug, P: ParamsProverKZG<E>> Prover<'params, KZGCommitmentScheme<E, P>>
+    for ProverSHPLONK<'params, E, P>
 where
     E::G1Affine: SerdeCurveAffine,
     E::G2Affine: SerdeCurveAffine,
 {
     const QUERY_INSTANCE: bool = false;
 
-    fn new(params: &'params ParamsKZG<E>) -> Self {
-        Self { params }
+    fn new(params: &'params P) -> Self {
+        Self {
+            params,
+            _marker: PhantomData,
+        }
     }
 
     /// Create a multi-opening proof
@@ -162,7 +169,7 @@ where
                 // Q_i(X) = N_i(X) / Z_i(X) where
                 // Z_i(X) = (x - r_i_0) * (x - r_i_1) * ...
                 let mut poly = div_by_vanishing(n_x, points);
-                poly.resize(self.params.n as usize, E::Scalar::zero());
+                poly.resize(self.params.n() as usize, E::Scalar::zero());
 
                 Polynomial {
                     values: poly,
@@ -2,7 +2,7 @@ use std::fmt::Debug;
 use std::io::Read;
 
 use super::ChallengeY;
-use super::{construct_intermediate_sets, ChallengeU, ChallengeV};
+use super::{construct_intermediate_sets, ChallengeU, ChallengeV, QueryPlan, RotationSet};
 use crate::arithmetic::{
     eval_polynomial, evaluate_vanishing_polynomial, lagrange_interpolate, powers, CurveAffine,
     FieldExt,
@@ -19,7 +19,7 @@ use crate::poly::query::{CommitmentReference, VerifierQuery};
 use crate::poly::strategy::VerificationStrategy;
 use crate::poly::{
     commitment::{Params, ParamsVerifier},
-    Error,
+    Error, Rotation,
 };
 use crate::transcript::{EncodedChallenge, TranscriptRead};
 use ff::Field;
@@ -28,10 +28,74 @@ use halo2curves::pairing::{Engine, MillerLoopResult, MultiMillerLoop};
 use rand_core::OsRng;
 use std::ops::MulAssign;
 
+/// Grouping of the queries into rotation sets, built once from the
+/// structure of the queries of a verifying key and reused for each of its
+/// proofs.
+///
+/// The grouping only depends on which queries share a commitment and which
+/// share a point, which is the same for all proofs of a circuit with a given
+/// number of instances. Each proof's queries are checked against the plan,
+/// and queries of another structure, e.g. those of another verifying key, are
+/// rejected.
+#[derive(Debug)]
+pub struct VerifierQueryPlan {
+    plan: QueryPlan,
+}
+
+impl VerifierQueryPlan {
+    /// Creates the plan of queries given, in order, by the index of their
+    /// commitment and by their rotation, such as those returned by
+    /// [`VerifyingKey::opening_queries`](crate::plonk::VerifyingKey::opening_queries).
+    /// Two queries share a commitment exactly when they have the same index.
+    pub fn new(queries: &[(usize, Rotation)]) -> Self {
+        // Each distinct rotation stands for a distinct point
+        let (commitments, points) = queries
+            .iter()
+            .map(|(commitment, rotation)| (*commitment, rotation.0 as u32 as usize))
+            .unzip();
+        Self {
+            plan: QueryPlan::from_indices(points, commitments),
+        }
+    }
+}
+
+/// Groups `queries` into rotation sets from scratch, together with the points
+/// of every set's complement in the super point set.
+#[allow(clippy::type_complexity)]
+fn rotation_sets_with_diffs<F: FieldExt, Q: Query<F, Eval = F>>(
+    queries: Vec<Q>,
+) -> (Vec<RotationSet<F, Q::Commitment>>, Vec<Vec<F>>) {
+    let intermediate_sets = construct_intermediate_sets(queries);
+    let super_point_set = intermediate_sets.super_point_set;
+    let diffs = intermediate_sets
+        .rotation_sets
+        .iter()
+        .map(|rotation_set| {
+            super_point_set
+                .iter()
+                .filter(|point| !rotation_set.points.contains(point))
+                .copied()
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    (intermediate_sets.rotation_sets, diffs)
+}
+
 /// Concrete KZG multiopen verifier with SHPLONK variant
 #[derive(Debug)]
 pub struct VerifierSHPLONK<'params, E: Engine> {
     params: &'params ParamsKZG<E>,
+    plan: Option<&'params VerifierQueryPlan>,
+}
+
+impl<'params, E: Engine> VerifierSHPLONK<'params, E> {
+    /// Creates a verifier that groups the queries with `plan`
+    pub fn with_plan(params: &'params ParamsKZG<E>, plan: &'params VerifierQueryPlan) -> Self {
+        Self {
+            params,
+            plan: Some(plan),
+        }
+    }
 }
 
 impl<'params, E> Verifier<'params, KZGCommitmentScheme<E>> for VerifierSHPLONK<'params, E>
@@ -46,7 +110,7 @@ where
     const QUERY_INSTANCE: bool = false;
 
     fn new(params: &'params ParamsKZG<E>) -> Self {
-        Self { params }
+        Self { params, plan: None }
     }
 
     /// Verify a multi-opening proof
@@ -64,11 +128,16 @@ where
     where
         I: IntoIterator<Item = VerifierQuery<'com, E::G1Affine, MSMKZG<E>>> + Clone,
     {
-        let intermediate_sets = construct_intermediate_sets(queries);
-        let (rotation_sets, super_point_set) = (
-            intermediate_sets.rotation_sets,
-            intermediate_sets.super_point_set,
-        );
+        let queries = queries.into_iter().collect::<Vec<_>>();
+        let (rotation_sets, diffs) = match self.plan {
+            Some(VerifierQueryPlan { plan }) => {
+                if !plan.matches(&queries) {
+                    return Err(Error::OpeningError);
+                }
+                plan.rotation_sets(&queries)
+            }
+            None => rotation_sets_with_diffs(queries),
+        };
 
         let y: ChallengeY<_> = transcript.squeeze_challenge_scalar();
         let v: ChallengeV<_> = transcript.squeeze_challenge_scalar();
@@ -79,12 +148,12 @@ where
 
         let (mut z_0_diff_inverse, mut z_0) = (E::Scalar::zero(), E::Scalar::zero());
         let (mut outer_msm, mut r_outer_acc) = (PreMSM::<E>::new(), E::Scalar::zero());
-        for (i, (rotation_set, power_of_v)) in rotation_sets.iter().zip(powers(*v)).enumerate() {
-            let diffs: Vec<E::Scalar> = super_point_set
-                .iter()
-                .filter(|point| !rotation_set.points.contains(point))
-                .copied()
-                .collect();
+        for (i, ((rotation_set, diffs), power_of_v)) in rotation_sets
+            .iter()
+            .zip(diffs.iter())
+            .zip(powers(*v))
+            .enumerate()
+        {
             let mut z_diff_i = evaluate_vanishing_polynomial(&diffs[..], *u);
 
             // normalize coefficients by the coefficient of the first commitment
@@ -0,0 +1,134 @@
+//! Commitments to a single polynomial and openings of it at a single point,
+//! independent of the PLONK proof system.
+
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use ff::Field;
+use group::Curve;
+use halo2curves::pairing::{Engine, MultiMillerLoop};
+
+use super::commitment::{ParamsKZG, ParamsVerifierKZG};
+use super::msm::DualMSM;
+use crate::arithmetic::{eval_polynomial, kate_division};
+use crate::helpers::SerdeCurveAffine;
+use crate::poly::commitment::{Blind, ParamsProver, MSM};
+use crate::poly::{Coeff, Polynomial};
+
+/// An opening of a committed polynomial at a single point
+#[derive(Clone, Debug)]
+pub struct Opening<E: Engine> {
+    /// Evaluation of the polynomial at the opened point
+    pub eval: E::Scalar,
+    /// Commitment to the quotient `(p(X) - eval) / (X - point)`
+    pub witness: E::G1Affine,
+}
+
+/// Commits to `poly`, which must have at most `params.n()` coefficients.
+pub fn commit<E: Engine + Debug>(
+    params: &ParamsKZG<E>,
+    poly: &Polynomial<E::Scalar, Coeff>,
+) -> E::G1Affine
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    params.commit(poly, Blind::default()).to_affine()
+}
+
+/// Opens `poly` at `point`.
+pub fn open<E: Engine + Debug>(
+    params: &ParamsKZG<E>,
+    poly: &Polynomial<E::Scalar, Coeff>,
+    point: E::Scalar,
+) -> Opening<E>
+where
+    E::G1Affine: SerdeCurveAffine,
+    E::G2Affine: SerdeCurveAffine,
+{
+    let eval = eval_polynomial(&poly[..], point);
+
+    let mut values = poly.values.clone();
+    values[0] -= eval;
+    let quotient = Polynomial {
+        values: kate_division(&values, point),
+        _marker: PhantomData,
+    };
+
+    Opening {
+        eval,
+        witness: params.commit(&quotient, Blind::default()).to_affine(),
+    }
+}
+
+/// Returns the pairing check for `opening` of `commitment` at `point`, which
+/// can be checked directly or combined with other accumulators.
+///
+/// The opening is valid iff `e(witness, [s - point] G2) == e(commitment -
+/// [eval] G1, G2)`, i.e. `e(witness, [s] G2) * e(commitment - [eval] G1 +
+/// [point] witness, -G2) == 1`.
+pub fn accumulate<'params, E: MultiMillerLoop + Debug>(
+    params: &'params ParamsVerifierKZG<E>,
+    commitment: &E::G1Affine,
+    point: E::Scalar,
+    opening: &Opening<E>,
+) -> DualMSM<'params, E> {
+    let g0: E::G1 = params.g[0].into();
+    let witness: E::G1 = opening.witness.into();
+
+    let mut accumulator = DualMSM::new(params);
+    accumulator.left.append_term(E::Scalar::one(), witness);
+    accumulator
+        .right
+        .append_term(E::Scalar::one(), (*commitment).into());
+    accumulator.right.append_term(-opening.eval, g0);
+    accumulator.right.append_term(point, witness);
+    accumulator
+}
+
+/// Verifies `opening` of `commitment` at `point`.
+pub fn verify<E: MultiMillerLoop + Debug>(
+    params: &ParamsVerifierKZG<E>,
+    commitment: &E::G1Affine,
+    point: E::Scalar,
+    opening: &Opening<E>,
+) -> bool {
+    accumulate(params, commitment, point, opening).check()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{commit, open, verify};
+    use crate::poly::kzg::commitment::ParamsKZG;
+    use crate::poly::EvaluationDomain;
+    use ff::Field;
+    use halo2curves::bn256::{Bn256, Fr};
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_single_opening() {
+        const K: u32 = 4;
+
+        let params = ParamsKZG::<Bn256>::setup(K, OsRng);
+        let domain = EvaluationDomain::new(1, K);
+
+        let mut poly = domain.empty_coeff();
+        for coeff in poly.iter_mut() {
+            *coeff = Fr::random(OsRng);
+        }
+
+        let commitment = commit(&params, &poly);
+        let point = Fr::random(OsRng);
+        let mut opening = open(&params, &poly, point);
+        assert!(verify(
+            &params.verifier_only(),
+            &commitment,
+            point,
+            &opening
+        ));
+
+        assert!(!verify(&params, &commitment, point + Fr::one(), &opening));
+        opening.eval += Fr::one();
+        assert!(!verify(&params, &commitment, point, &opening));
+    }
+}
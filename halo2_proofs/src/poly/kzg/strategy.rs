@@ -2,11 +2,11 @@ use std::{fmt::Debug, marker::PhantomData};
 
 use super::{
     commitment::{KZGCommitmentScheme, ParamsKZG},
-    msm::{DualMSM, MSMKZG},
+    msm::{DualMSM, DualMSMReport, MSMKZG},
     multiopen::VerifierGWC,
 };
 use crate::{
-    helpers::SerdeCurveAffine,
+    helpers::{SerdeCurveAffine, SerdePrimeField},
     plonk::Error,
     poly::{
         commitment::{Verifier, MSM},
@@ -14,14 +14,16 @@ use crate::{
         strategy::{Guard, VerificationStrategy},
     },
     transcript::{EncodedChallenge, TranscriptRead},
+    SerdeFormat,
 };
 use ff::Field;
 use group::Group;
 use halo2curves::{
     pairing::{Engine, MillerLoopResult, MultiMillerLoop},
-    CurveAffine,
+    CurveAffine, FieldExt,
 };
-use rand_core::OsRng;
+use rand_core::{OsRng, RngCore};
+use std::io;
 
 /// Wrapper for linear verification accumulator
 #[derive(Debug, Clone)]
@@ -44,12 +46,38 @@ impl<'params, E: MultiMillerLoop + Debug> GuardKZG<'params, E> {
     pub(crate) fn new(msm_accumulator: DualMSM<'params, E>) -> Self {
         Self { msm_accumulator }
     }
+
+    /// Serializes the guarded accumulator, see [`DualMSM::to_bytes`].
+    pub fn to_bytes(&self, format: SerdeFormat) -> Vec<u8>
+    where
+        E::Scalar: SerdePrimeField,
+        E::G1Affine: SerdeCurveAffine,
+        E::G2Affine: SerdeCurveAffine,
+    {
+        self.msm_accumulator.to_bytes(format)
+    }
+
+    /// Deserializes a guarded accumulator, see [`DualMSM::from_bytes`].
+    pub fn from_bytes(
+        bytes: &[u8],
+        params: &'params ParamsKZG<E>,
+        format: SerdeFormat,
+    ) -> io::Result<Self>
+    where
+        E::Scalar: SerdePrimeField,
+        E::G1Affine: SerdeCurveAffine,
+        E::G2Affine: SerdeCurveAffine,
+    {
+        DualMSM::from_bytes(bytes, params, format).map(Self::new)
+    }
 }
 
 /// A verifier that checks multiple proofs in a batch
 #[derive(Clone, Debug)]
 pub struct AccumulatorStrategy<'params, E: Engine> {
     pub(crate) msm_accumulator: DualMSM<'params, E>,
+    pub(crate) short_scalars: bool,
+    pub(crate) proofs: Option<Vec<DualMSM<'params, E>>>,
 }
 
 impl<'params, E: MultiMillerLoop + Debug> AccumulatorStrategy<'params, E> {
@@ -57,12 +85,73 @@ impl<'params, E: MultiMillerLoop + Debug> AccumulatorStrategy<'params, E> {
     pub fn new(params: &'params ParamsKZG<E>) -> Self {
         AccumulatorStrategy {
             msm_accumulator: DualMSM::new(params),
+            short_scalars: false,
+            proofs: None,
         }
     }
 
     /// Constructs and initialized new batch verifier
     pub fn with(msm_accumulator: DualMSM<'params, E>) -> Self {
-        AccumulatorStrategy { msm_accumulator }
+        AccumulatorStrategy {
+            msm_accumulator,
+            short_scalars: false,
+            proofs: None,
+        }
+    }
+
+    /// Samples the random factors used to combine proofs from 128 bits
+    /// instead of the full scalar field.
+    ///
+    /// Each proof's terms are scaled by its own factor, so that terms the
+    /// verifier adds with a small scalar, e.g. the `h2` commitment of
+    /// [`VerifierSHPLONK`](super::multiopen::VerifierSHPLONK) with scalar
+    /// one, keep 128-bit scalars in the final MSM. Terms with full-width
+    /// scalars stay full width.
+    ///
+    /// An invalid proof is only accepted if the random factor it is combined
+    /// with happens to cancel its error, which occurs with probability at most
+    /// `2^-128` for a factor drawn uniformly from `[0, 2^128)`. This matches
+    /// the security level of the curves supported by this crate.
+    pub fn with_short_scalars(mut self) -> Self {
+        self.short_scalars = true;
+        self
+    }
+
+    /// Keeps a copy of every processed proof's own accumulator, so that
+    /// [`Self::failing_proofs`] can tell which proofs of a rejected batch are
+    /// invalid. This roughly doubles the memory used by the strategy.
+    pub fn with_proof_tracking(mut self) -> Self {
+        self.proofs = Some(vec![]);
+        self
+    }
+
+    /// Returns the indices, in processing order, of the proofs whose own
+    /// accumulator fails the pairing check, or `None` if proof tracking was
+    /// not enabled with [`Self::with_proof_tracking`].
+    pub fn failing_proofs(&self) -> Option<Vec<usize>> {
+        self.proofs.as_ref().map(|proofs| {
+            proofs
+                .iter()
+                .enumerate()
+                .filter(|(_, proof)| !proof.clone().check())
+                .map(|(index, _)| index)
+                .collect()
+        })
+    }
+
+    /// Performs the final check of the batch, see [`DualMSM::check_detailed`].
+    pub fn check_detailed(&self) -> DualMSMReport<E> {
+        self.msm_accumulator.clone().check_detailed()
+    }
+
+    fn random_scalar(&self) -> E::Scalar {
+        if self.short_scalars {
+            let hi = OsRng.next_u64() as u128;
+            let lo = OsRng.next_u64() as u128;
+            E::Scalar::from_u128((hi << 64) | lo)
+        } else {
+            E::Scalar::random(OsRng)
+        }
     }
 }
 
@@ -105,13 +194,33 @@ where
         mut self,
         f: impl FnOnce(V::MSMAccumulator) -> Result<V::Guard, Error>,
     ) -> Result<Self::Output, Error> {
-        self.msm_accumulator.scale(E::Scalar::random(OsRng));
+        let factor = self.random_scalar();
 
-        // Guard is updated with new msm contributions
-        let guard = f(self.msm_accumulator)?;
-        Ok(Self {
-            msm_accumulator: guard.msm_accumulator,
-        })
+        if !self.short_scalars && self.proofs.is_none() {
+            self.msm_accumulator.scale(factor);
+
+            // Guard is updated with new msm contributions
+            let guard = f(self.msm_accumulator)?;
+            return Ok(Self {
+                msm_accumulator: guard.msm_accumulator,
+                short_scalars: self.short_scalars,
+                proofs: None,
+            });
+        }
+
+        // Verify the proof against an empty accumulator so that its
+        // contribution can be kept on its own, and only scale its own terms:
+        // scaling the accumulator instead would multiply the factors of all
+        // proofs together into full-width scalars.
+        let guard = f(DualMSM::new(self.msm_accumulator.params))?;
+        let mut proof = guard.msm_accumulator;
+        if let Some(proofs) = self.proofs.as_mut() {
+            proofs.push(proof.clone());
+        }
+        proof.scale(factor);
+        self.msm_accumulator.add_msm(proof);
+
+        Ok(self)
     }
 
     fn finalize(self) -> bool {
@@ -157,3 +266,46 @@ where
         unreachable!();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{AccumulatorStrategy, GuardKZG};
+    use crate::poly::commitment::{ParamsProver, MSM};
+    use crate::poly::kzg::commitment::ParamsKZG;
+    use crate::poly::kzg::msm::DualMSM;
+    use crate::poly::kzg::multiopen::VerifierSHPLONK;
+    use crate::poly::VerificationStrategy;
+    use ff::{Field, PrimeField};
+    use group::Group;
+    use halo2curves::bn256::{Bn256, Fr, G1};
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_short_scalars() {
+        let params = ParamsKZG::<Bn256>::new(1);
+
+        fn process(strategy: AccumulatorStrategy<'_, Bn256>) -> AccumulatorStrategy<'_, Bn256> {
+            VerificationStrategy::<'_, _, VerifierSHPLONK<'_, Bn256>>::process(
+                strategy,
+                |mut msm: DualMSM<'_, Bn256>| {
+                    // A term with a small scalar, as SHPLONK adds to the left
+                    msm.left.append_term(Fr::one(), G1::random(OsRng));
+                    msm.right.append_term(Fr::random(OsRng), G1::random(OsRng));
+                    Ok(GuardKZG::new(msm))
+                },
+            )
+            .unwrap()
+        }
+
+        let is_short = |scalar: &Fr| scalar.to_repr().as_ref()[16..].iter().all(|b| *b == 0);
+        let mut short = AccumulatorStrategy::new(&params).with_short_scalars();
+        let mut full = AccumulatorStrategy::new(&params);
+        for _ in 0..4 {
+            short = process(short);
+            full = process(full);
+        }
+        assert_eq!(short.msm_accumulator.left.scalars.len(), 4);
+        assert!(short.msm_accumulator.left.scalars.iter().all(is_short));
+        assert!(!full.msm_accumulator.left.scalars.iter().all(is_short));
+    }
+}
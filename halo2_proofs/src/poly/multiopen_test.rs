@@ -167,6 +167,59 @@ mod test {
         >(verifier_params, &proof[..], true);
     }
 
+    #[test]
+    fn test_prover_query_at_arbitrary_point() {
+        use crate::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use crate::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use crate::poly::kzg::strategy::AccumulatorStrategy;
+        use crate::poly::query::Query;
+        use halo2curves::bn256::{Bn256, Fr};
+
+        const K: u32 = 4;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let domain = EvaluationDomain::new(1, K);
+        let mut poly = domain.empty_coeff();
+        for coeff in poly.iter_mut() {
+            *coeff = Fr::random(OsRng);
+        }
+        let blind = Blind::new(&mut OsRng);
+        let commitment = params.commit(&poly, blind).to_affine();
+
+        // A point of another protocol, not a rotation of a PLONK challenge
+        let point = Fr::random(OsRng);
+        let query = ProverQuery::new(point, &poly, blind);
+        assert_eq!(query.get_point(), point);
+        let eval = query.get_eval();
+        assert_eq!(eval, eval_polynomial(&poly, point));
+
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        ProverSHPLONK::new(&params)
+            .create_proof(OsRng, &mut transcript, std::iter::once(query))
+            .unwrap();
+        let proof = transcript.finalize();
+
+        let verifier_params = params.verifier_params();
+        let verifier = VerifierSHPLONK::new(verifier_params);
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+        let strategy = VerificationStrategy::<_, VerifierSHPLONK<_>>::process(
+            AccumulatorStrategy::new(verifier_params),
+            |msm_accumulator| {
+                verifier
+                    .verify_proof(
+                        &mut transcript,
+                        std::iter::once(VerifierQuery::new_commitment(&commitment, point, eval)),
+                        msm_accumulator,
+                    )
+                    .map_err(|_| Error::Opening)
+            },
+        )
+        .unwrap();
+        assert!(VerificationStrategy::<_, VerifierSHPLONK<_>>::finalize(
+            strategy
+        ));
+    }
+
     fn verify<
         'a,
         'params,
@@ -1,4 +1,8 @@
-use std::{fmt::Debug, ops::Deref};
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
 
 use super::commitment::{Blind, CommitmentScheme, Params, MSM};
 use crate::{
@@ -28,6 +32,21 @@ pub struct ProverQuery<'com, C: CurveAffine> {
     pub(crate) blind: Blind<C::Scalar>,
 }
 
+impl<'com, C: CurveAffine> ProverQuery<'com, C> {
+    /// Create a new prover query opening `poly` at `point`.
+    ///
+    /// `point` may be any field element, not only a rotation of the PLONK
+    /// challenge `x`, so protocols built on top of the multiopen arguments can
+    /// batch openings at their own points with those of a proof.
+    pub fn new(
+        point: C::Scalar,
+        poly: &'com Polynomial<C::Scalar, Coeff>,
+        blind: Blind<C::Scalar>,
+    ) -> Self {
+        ProverQuery { point, poly, blind }
+    }
+}
+
 #[doc(hidden)]
 #[derive(Copy, Clone)]
 pub struct PolynomialPointer<'com, C: CurveAffine> {
@@ -121,6 +140,18 @@ impl<'r, C: CurveAffine, M: MSM<C>> PartialEq for CommitmentReference<'r, C, M>
     }
 }
 
+impl<'r, C: CurveAffine, M: MSM<C>> Eq for CommitmentReference<'r, C, M> {}
+
+/// Hashes the address of the commitment, consistently with [`PartialEq`]
+impl<'r, C: CurveAffine, M: MSM<C>> Hash for CommitmentReference<'r, C, M> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            CommitmentReference::Commitment(a) => std::ptr::hash(*a, state),
+            CommitmentReference::MSM(a) => std::ptr::hash(*a, state),
+        }
+    }
+}
+
 impl<'com, C: CurveAffine, M: MSM<C>> Query<C::Scalar> for VerifierQuery<'com, C, M> {
     type Eval = C::Scalar;
     type Commitment = CommitmentReference<'com, C, M>;
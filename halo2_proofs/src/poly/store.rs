@@ -0,0 +1,283 @@
+//! Storage for polynomials that keeps them in memory up to a budget and
+//! spills the rest to files, for proving large circuits on machines with
+//! limited memory.
+
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::marker::PhantomData;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{Basis, Polynomial, Wipe, Zeroizing};
+use crate::helpers::SerdePrimeField;
+use crate::SerdeFormat;
+
+/// Distinguishes the files of stores within a process
+static NEXT_STORE: AtomicUsize = AtomicUsize::new(0);
+
+/// Handle to a polynomial in the basis `B` in a [`PolynomialStore`]
+#[derive(Clone, Copy, Debug)]
+pub struct PolynomialId<B> {
+    index: usize,
+    _marker: PhantomData<B>,
+}
+
+#[derive(Debug)]
+enum Slot<F> {
+    Memory(Vec<F>),
+    Disk(PathBuf),
+}
+
+/// A collection of polynomials, in any basis, of which at most `budget`
+/// bytes are held in memory. Polynomials inserted beyond the budget are
+/// written to a file in the store's directory and read back when accessed.
+/// Files are removed when the polynomial is taken out of the store or the
+/// store is dropped; with the `zeroize` feature, they are overwritten with
+/// zeros first.
+#[derive(Debug)]
+pub struct PolynomialStore<F> {
+    budget: usize,
+    in_memory: usize,
+    dir: PathBuf,
+    prefix: String,
+    slots: Vec<Option<Slot<F>>>,
+}
+
+impl<F: SerdePrimeField> PolynomialStore<F> {
+    /// Creates a store that keeps up to `budget` bytes of polynomials in
+    /// memory and spills the rest to the system's temporary directory.
+    pub fn new(budget: usize) -> Self {
+        Self::with_dir(budget, std::env::temp_dir())
+    }
+
+    /// Creates a store that keeps up to `budget` bytes of polynomials in
+    /// memory and spills the rest to files in `dir`.
+    pub fn with_dir<P: AsRef<Path>>(budget: usize, dir: P) -> Self {
+        PolynomialStore {
+            budget,
+            in_memory: 0,
+            dir: dir.as_ref().to_path_buf(),
+            prefix: format!(
+                "halo2-polys-{}-{}",
+                std::process::id(),
+                NEXT_STORE.fetch_add(1, Ordering::Relaxed)
+            ),
+            slots: vec![],
+        }
+    }
+
+    fn size_of(values: &[F]) -> usize {
+        values.len() * mem::size_of::<F>()
+    }
+
+    /// Adds `poly` to the store, writing it to disk if keeping it in memory
+    /// would exceed the budget.
+    pub fn insert<B: Basis>(&mut self, poly: Polynomial<F, B>) -> io::Result<PolynomialId<B>> {
+        let id = self.slots.len();
+        let size = Self::size_of(&poly);
+
+        let slot = if size <= self.budget - self.in_memory {
+            self.in_memory += size;
+            Slot::Memory(poly.values)
+        } else {
+            let path = self.dir.join(format!("{}-{}.bin", self.prefix, id));
+            let written = Self::write(&path, &poly);
+            drop(Zeroizing::new(poly));
+            if let Err(error) = written {
+                let _ = remove(&path);
+                return Err(error);
+            }
+            Slot::Disk(path)
+        };
+        self.slots.push(Some(slot));
+
+        Ok(PolynomialId {
+            index: id,
+            _marker: PhantomData,
+        })
+    }
+
+    fn slot<B>(&self, id: PolynomialId<B>) -> &Slot<F> {
+        self.slots[id.index]
+            .as_ref()
+            .expect("polynomial was taken out of the store")
+    }
+
+    fn write<B: Basis>(path: &Path, poly: &Polynomial<F, B>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        poly.write(&mut writer, SerdeFormat::RawBytesUnchecked)?;
+        writer.flush()
+    }
+
+    fn read<B: Basis>(path: &Path) -> io::Result<Polynomial<F, B>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Polynomial::read(&mut reader, SerdeFormat::RawBytesUnchecked)
+    }
+
+    /// Returns the values of the polynomial `id`, borrowed if it is held in
+    /// memory and read from disk otherwise.
+    ///
+    /// Panics if the polynomial was taken out of the store.
+    pub fn get<B: Basis>(&self, id: PolynomialId<B>) -> io::Result<Cow<'_, [F]>> {
+        match self.slot(id) {
+            Slot::Memory(values) => Ok(Cow::Borrowed(values)),
+            Slot::Disk(path) => Self::read::<B>(path).map(|poly| Cow::Owned(poly.values)),
+        }
+    }
+
+    /// Removes the polynomial `id` from the store and returns it, freeing
+    /// its share of the budget or deleting its file. If the file cannot be
+    /// read or removed, the polynomial stays in the store, which tries to
+    /// remove the file again when it is dropped.
+    ///
+    /// Panics if the polynomial was already taken out of the store.
+    pub fn take<B: Basis>(&mut self, id: PolynomialId<B>) -> io::Result<Polynomial<F, B>> {
+        if let Slot::Disk(path) = self.slot(id) {
+            let poly = Zeroizing::new(Self::read(path)?);
+            remove(path)?;
+            self.slots[id.index] = None;
+            return Ok(poly.into_inner());
+        }
+
+        match self.slots[id.index].take() {
+            Some(Slot::Memory(values)) => {
+                self.in_memory -= Self::size_of(&values);
+                Ok(Polynomial {
+                    values,
+                    _marker: PhantomData,
+                })
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns `true` if the polynomial `id` is stored on disk.
+    pub fn is_spilled<B>(&self, id: PolynomialId<B>) -> bool {
+        matches!(self.slot(id), Slot::Disk(_))
+    }
+
+    /// Returns the number of bytes of polynomials held in memory
+    pub fn in_memory_bytes(&self) -> usize {
+        self.in_memory
+    }
+}
+
+impl<F: SerdePrimeField> Wipe for PolynomialStore<F> {
+    /// Wipes the polynomials held in memory; files are wiped as they are
+    /// removed
+    #[cfg(feature = "zeroize")]
+    fn wipe(&mut self) {
+        for slot in self.slots.iter_mut() {
+            if let Some(Slot::Memory(values)) = slot {
+                super::zeroize(values, <F as ff::Field>::zero());
+            }
+        }
+    }
+}
+
+impl<F> Drop for PolynomialStore<F> {
+    fn drop(&mut self) {
+        for slot in self.slots.iter() {
+            if let Some(Slot::Disk(path)) = slot {
+                let _ = remove(path);
+            }
+        }
+    }
+}
+
+/// Removes the file at `path`, overwriting it with zeros first with the
+/// `zeroize` feature
+fn remove(path: &Path) -> io::Result<()> {
+    #[cfg(feature = "zeroize")]
+    {
+        let len = fs::metadata(path)?.len();
+        let mut file = fs::OpenOptions::new().write(true).open(path)?;
+        io::copy(&mut io::Read::take(io::repeat(0), len), &mut file)?;
+        file.sync_all()?;
+    }
+    fs::remove_file(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::PolynomialStore;
+    use crate::poly::{Coeff, EvaluationDomain, LagrangeCoeff, Polynomial};
+    use ff::Field;
+    use halo2curves::pasta::Fp;
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_spill() {
+        let domain = EvaluationDomain::<Fp>::new(1, 4);
+        let polys: Vec<Polynomial<Fp, LagrangeCoeff>> = (0..3)
+            .map(|_| {
+                let mut poly = domain.empty_lagrange();
+                for value in poly.iter_mut() {
+                    *value = Fp::random(OsRng);
+                }
+                poly
+            })
+            .collect();
+
+        // Room for a single polynomial
+        let budget = polys[0].len() * std::mem::size_of::<Fp>();
+        let mut store = PolynomialStore::new(budget);
+        let ids: Vec<_> = polys
+            .iter()
+            .map(|poly| store.insert(poly.clone()).unwrap())
+            .collect();
+
+        assert!(!store.is_spilled(ids[0]));
+        assert!(store.is_spilled(ids[1]));
+        assert!(store.is_spilled(ids[2]));
+        assert_eq!(store.in_memory_bytes(), budget);
+
+        for (id, poly) in ids.iter().zip(polys.iter()) {
+            assert_eq!(*store.get(*id).unwrap(), poly.values);
+        }
+
+        let path = match store.slot(ids[1]) {
+            super::Slot::Disk(path) => path.clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!(store.take(ids[1]).unwrap().values, polys[1].values);
+        assert!(!path.exists());
+
+        assert_eq!(store.take(ids[0]).unwrap().values, polys[0].values);
+        assert_eq!(store.in_memory_bytes(), 0);
+
+        // Polynomials of other bases share the budget
+        let coeff: Polynomial<Fp, Coeff> = domain.lagrange_to_coeff(polys[0].clone());
+        let coeff_id = store.insert(coeff.clone()).unwrap();
+        assert!(!store.is_spilled(coeff_id));
+        assert_eq!(store.take(coeff_id).unwrap().values, coeff.values);
+
+        let path = match store.slot(ids[2]) {
+            super::Slot::Disk(path) => path.clone(),
+            _ => unreachable!(),
+        };
+        drop(store);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_take_keeps_the_slot_on_error() {
+        let domain = EvaluationDomain::<Fp>::new(1, 4);
+        let mut store = PolynomialStore::new(0);
+        let id = store
+            .insert::<LagrangeCoeff>(domain.empty_lagrange())
+            .unwrap();
+
+        let path = match store.slot(id) {
+            super::Slot::Disk(path) => path.clone(),
+            _ => unreachable!(),
+        };
+        std::fs::remove_file(&path).unwrap();
+
+        // The polynomial is not lost from the store when taking it fails
+        assert!(store.take(id).is_err());
+        assert!(store.is_spilled(id));
+    }
+}
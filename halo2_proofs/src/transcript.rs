@@ -2,8 +2,10 @@
 //! transcripts.
 
 use blake2b_simd::{Params as Blake2bParams, State as Blake2bState};
-use group::ff::PrimeField;
-use sha3::{Digest, Keccak256};
+use group::ff::{Field, PrimeField};
+use group::prime::PrimeCurveAffine;
+pub use sha3::Digest;
+use sha3::Keccak256;
 use std::convert::TryInto;
 
 use halo2curves::{Coordinates, CurveAffine, FieldExt};
@@ -11,6 +13,13 @@ use halo2curves::{Coordinates, CurveAffine, FieldExt};
 use std::io::{self, Read, Write};
 use std::marker::PhantomData;
 
+mod framed;
+mod recorder;
+pub use framed::{FramedRead, FramedWrite};
+pub use recorder::{
+    TranscriptEvent, TranscriptLabeler, TranscriptOp, TranscriptRecorder, TranscriptValue,
+};
+
 /// Prefix to a prover's message soliciting a challenge
 const BLAKE2B_PREFIX_CHALLENGE: u8 = 0;
 
@@ -20,30 +29,238 @@ const BLAKE2B_PREFIX_POINT: u8 = 1;
 /// Prefix to a prover's message containing a scalar
 const BLAKE2B_PREFIX_SCALAR: u8 = 2;
 
-/// Prefix to a prover's message soliciting a challenge
-const KECCAK256_PREFIX_CHALLENGE: u8 = 0;
+/// Prefix to a prover's message containing a point of a second group, such
+/// as the G2 of a pairing
+const BLAKE2B_PREFIX_POINT_G2: u8 = 3;
+
+/// Domain-separation constants of [`Blake2bRead`] and [`Blake2bWrite`].
+///
+/// The default is the transcript used throughout this crate. Applications
+/// choosing their own constants make sure that their proofs are not accepted
+/// by verifiers of other applications, and vice versa.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Blake2bConfig {
+    /// The BLAKE2b personalization, `"Halo2-Transcript"` by default. As in
+    /// BLAKE2b, shorter personalizations are padded with zeros.
+    pub personalization: [u8; 16],
+    /// Prefix to a prover's message soliciting a challenge, `0` by default
+    pub challenge_prefix: u8,
+    /// Prefix to a prover's message containing a curve point, `1` by default
+    pub point_prefix: u8,
+    /// Prefix to a prover's message containing a scalar, `2` by default
+    pub scalar_prefix: u8,
+    /// Prefix to a prover's message containing a point of a second group,
+    /// `3` by default
+    pub point_g2_prefix: u8,
+}
+
+impl Default for Blake2bConfig {
+    fn default() -> Self {
+        Blake2bConfig {
+            personalization: *b"Halo2-Transcript",
+            challenge_prefix: BLAKE2B_PREFIX_CHALLENGE,
+            point_prefix: BLAKE2B_PREFIX_POINT,
+            scalar_prefix: BLAKE2B_PREFIX_SCALAR,
+            point_g2_prefix: BLAKE2B_PREFIX_POINT_G2,
+        }
+    }
+}
 
-/// First prefix to a prover's message soliciting a challenge
-/// Not included in the growing state!
-const KECCAK256_PREFIX_CHALLENGE_LO: u8 = 10;
+impl Blake2bConfig {
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if two of the
+    /// prefixes are equal, as messages of different kinds would then be
+    /// hashed alike.
+    pub fn validate(&self) -> io::Result<()> {
+        let prefixes = [
+            self.challenge_prefix,
+            self.point_prefix,
+            self.scalar_prefix,
+            self.point_g2_prefix,
+        ];
+        for (i, prefix) in prefixes.iter().enumerate() {
+            if prefixes[..i].contains(prefix) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "transcript prefixes must be distinct",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn initial_state(&self) -> io::Result<Blake2bState> {
+        self.validate()?;
+        Ok(Blake2bParams::new()
+            .hash_length(64)
+            .personal(&self.personalization)
+            .to_state())
+    }
+}
+
+/// Prefix to a prover's message soliciting a challenge
+const DIGEST_PREFIX_CHALLENGE: u8 = 0;
 
-/// Second prefix to a prover's message soliciting a challenge
-/// Not included in the growing state!
-const KECCAK256_PREFIX_CHALLENGE_HI: u8 = 11;
+/// First of the consecutive prefixes of the hashes a challenge is derived
+/// from. Not included in the growing state!
+const DIGEST_PREFIX_CHALLENGE_WIDE: u8 = 10;
 
 /// Prefix to a prover's message containing a curve point
-const KECCAK256_PREFIX_POINT: u8 = 1;
+const DIGEST_PREFIX_POINT: u8 = 1;
 
 /// Prefix to a prover's message containing a scalar
-const KECCAK256_PREFIX_SCALAR: u8 = 2;
+const DIGEST_PREFIX_SCALAR: u8 = 2;
+
+/// Prefix to a prover's message containing a point of a second group, such
+/// as the G2 of a pairing
+const DIGEST_PREFIX_POINT_G2: u8 = 3;
+
+/// Encoding of curve points in proofs.
+///
+/// The encoding only affects the bytes of the proof; points are absorbed into
+/// the transcript by their coordinates, so challenges do not depend on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointEncoding {
+    /// Points are written in compressed form, as produced by
+    /// `GroupEncoding::to_bytes`.
+    Compressed,
+    /// Points are written as their affine coordinates `x || y`, which is
+    /// twice the size but avoids the square root needed to decompress them.
+    Uncompressed,
+}
+
+impl Default for PointEncoding {
+    fn default() -> Self {
+        PointEncoding::Compressed
+    }
+}
+
+pub(crate) fn read_point_encoded<R: Read, C: CurveAffine>(
+    reader: &mut R,
+    encoding: PointEncoding,
+) -> io::Result<C> {
+    let point = match encoding {
+        PointEncoding::Compressed => {
+            let mut compressed = C::Repr::default();
+            reader.read_exact(compressed.as_mut())?;
+            Option::from(C::from_bytes(&compressed))
+        }
+        PointEncoding::Uncompressed => {
+            let mut x = <C::Base as PrimeField>::Repr::default();
+            let mut y = <C::Base as PrimeField>::Repr::default();
+            reader.read_exact(x.as_mut())?;
+            reader.read_exact(y.as_mut())?;
+            let x: Option<C::Base> = Option::from(C::Base::from_repr(x));
+            let y: Option<C::Base> = Option::from(C::Base::from_repr(y));
+            x.zip(y).and_then(|(x, y)| Option::from(C::from_xy(x, y)))
+        }
+    };
+    point.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof"))
+}
+
+pub(crate) fn write_point_encoded<W: Write, C: CurveAffine>(
+    writer: &mut W,
+    point: C,
+    encoding: PointEncoding,
+) -> io::Result<usize> {
+    match encoding {
+        PointEncoding::Compressed => {
+            let bytes = point.to_bytes();
+            writer.write_all(bytes.as_ref())?;
+            Ok(bytes.as_ref().len())
+        }
+        PointEncoding::Uncompressed => {
+            let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    "cannot write points at infinity to the transcript",
+                )
+            })?;
+            let (x, y) = (coords.x().to_repr(), coords.y().to_repr());
+            writer.write_all(x.as_ref())?;
+            writer.write_all(y.as_ref())?;
+            Ok(x.as_ref().len() + y.as_ref().len())
+        }
+    }
+}
+
+/// Counts of the messages a prover wrote to a proof, per round of the
+/// protocol, as kept by [`Blake2bWrite::stats`] and [`DigestWrite::stats`].
+/// A round ends when a challenge is squeezed, so the first round holds what
+/// was written before the first challenge and each challenge starts a new
+/// round, which may stay empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptStats {
+    rounds: Vec<RoundStats>,
+}
+
+/// Counts of the messages written in a round; see [`TranscriptStats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RoundStats {
+    /// Number of points written, including points of a second group
+    pub points: usize,
+    /// Number of scalars written
+    pub scalars: usize,
+    /// Number of bytes written
+    pub bytes: usize,
+}
+
+impl Default for TranscriptStats {
+    fn default() -> Self {
+        TranscriptStats {
+            rounds: vec![RoundStats::default()],
+        }
+    }
+}
+
+impl TranscriptStats {
+    /// Returns the counts of each round, in order
+    pub fn rounds(&self) -> &[RoundStats] {
+        &self.rounds
+    }
+
+    /// Returns the counts of the whole proof
+    pub fn total(&self) -> RoundStats {
+        self.rounds
+            .iter()
+            .fold(RoundStats::default(), |total, round| RoundStats {
+                points: total.points + round.points,
+                scalars: total.scalars + round.scalars,
+                bytes: total.bytes + round.bytes,
+            })
+    }
+
+    fn point(&mut self, bytes: usize) {
+        let round = self.rounds.last_mut().unwrap();
+        round.points += 1;
+        round.bytes += bytes;
+    }
+
+    fn scalar(&mut self, bytes: usize) {
+        let round = self.rounds.last_mut().unwrap();
+        round.scalars += 1;
+        round.bytes += bytes;
+    }
+
+    fn challenge(&mut self) {
+        self.rounds.push(RoundStats::default());
+    }
+}
 
 /// Generic transcript view (from either the prover or verifier's perspective)
+///
+/// This trait, [`TranscriptRead`] and [`TranscriptWrite`] are object safe,
+/// and are implemented for boxed transcripts. A verifier can therefore pick
+/// the transcript at runtime and pass a `Box<dyn TranscriptRead<C, E>>`
+/// wherever a transcript is expected.
 pub trait Transcript<C: CurveAffine, E: EncodedChallenge<C>> {
     /// Squeeze an encoded verifier challenge from the transcript.
     fn squeeze_challenge(&mut self) -> E;
 
     /// Squeeze a typed challenge (in the scalar field) from the transcript.
-    fn squeeze_challenge_scalar<T>(&mut self) -> ChallengeScalar<C, T> {
+    fn squeeze_challenge_scalar<T>(&mut self) -> ChallengeScalar<C, T>
+    where
+        Self: Sized,
+    {
         ChallengeScalar {
             inner: self.squeeze_challenge().get_scalar(),
             _marker: PhantomData,
@@ -67,6 +284,21 @@ pub trait TranscriptRead<C: CurveAffine, E: EncodedChallenge<C>>: Transcript<C,
 
     /// Read a curve scalar from the prover.
     fn read_scalar(&mut self) -> io::Result<C::Scalar>;
+
+    /// Read the domain-separation tag written with
+    /// [`TranscriptWrite::write_domain_tag`], failing with
+    /// [`io::ErrorKind::InvalidData`] if the proof was made for a different
+    /// tag.
+    fn read_domain_tag(&mut self, tag: &[u8]) -> io::Result<()> {
+        if self.read_scalar()? != domain_tag_scalar::<C>(tag) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "proof was made for a different domain-separation tag",
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 /// Transcript view from the perspective of a prover that has access to an
@@ -77,6 +309,103 @@ pub trait TranscriptWrite<C: CurveAffine, E: EncodedChallenge<C>>: Transcript<C,
 
     /// Write a scalar to the proof and the transcript.
     fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()>;
+
+    /// Write a domain-separation tag, e.g. the name and version of the
+    /// protocol the proof is made for, to the proof and the transcript.
+    /// Verifiers expecting another tag reject the proof as soon as they read
+    /// it with [`TranscriptRead::read_domain_tag`].
+    fn write_domain_tag(&mut self, tag: &[u8]) -> io::Result<()> {
+        self.write_scalar(domain_tag_scalar::<C>(tag))
+    }
+}
+
+/// Maps a domain-separation tag to the scalar that stands for it in proofs
+fn domain_tag_scalar<C: CurveAffine>(tag: &[u8]) -> C::Scalar {
+    let hash = Blake2bParams::new()
+        .hash_length(64)
+        .personal(b"Halo2-Domain-Tag")
+        .hash(tag);
+    C::Scalar::from_bytes_wide(hash.as_array())
+}
+
+impl<C: CurveAffine, E: EncodedChallenge<C>, T: Transcript<C, E> + ?Sized> Transcript<C, E>
+    for Box<T>
+{
+    fn squeeze_challenge(&mut self) -> E {
+        (**self).squeeze_challenge()
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        (**self).common_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        (**self).common_scalar(scalar)
+    }
+}
+
+impl<C: CurveAffine, E: EncodedChallenge<C>, T: TranscriptRead<C, E> + ?Sized> TranscriptRead<C, E>
+    for Box<T>
+{
+    fn read_point(&mut self) -> io::Result<C> {
+        (**self).read_point()
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        (**self).read_scalar()
+    }
+}
+
+impl<C: CurveAffine, E: EncodedChallenge<C>, T: TranscriptWrite<C, E> + ?Sized>
+    TranscriptWrite<C, E> for Box<T>
+{
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        (**self).write_point(point)
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        (**self).write_scalar(scalar)
+    }
+}
+
+impl<'a, C: CurveAffine, E: EncodedChallenge<C>, T: Transcript<C, E> + ?Sized> Transcript<C, E>
+    for &'a mut T
+{
+    fn squeeze_challenge(&mut self) -> E {
+        (**self).squeeze_challenge()
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        (**self).common_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        (**self).common_scalar(scalar)
+    }
+}
+
+impl<'a, C: CurveAffine, E: EncodedChallenge<C>, T: TranscriptRead<C, E> + ?Sized>
+    TranscriptRead<C, E> for &'a mut T
+{
+    fn read_point(&mut self) -> io::Result<C> {
+        (**self).read_point()
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        (**self).read_scalar()
+    }
+}
+
+impl<'a, C: CurveAffine, E: EncodedChallenge<C>, T: TranscriptWrite<C, E> + ?Sized>
+    TranscriptWrite<C, E> for &'a mut T
+{
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        (**self).write_point(point)
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        (**self).write_scalar(scalar)
+    }
 }
 
 /// Initializes transcript at verifier side.
@@ -85,6 +414,66 @@ pub trait TranscriptReadBuffer<R: Read, C: CurveAffine, E: EncodedChallenge<C>>:
 {
     /// Initialize a transcript given an input buffer.
     fn init(reader: R) -> Self;
+
+    /// Initialize a transcript given an input buffer whose proof starts with
+    /// the domain-separation tag `tag`; see
+    /// [`TranscriptRead::read_domain_tag`].
+    fn init_with_domain_tag(reader: R, tag: &[u8]) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut transcript = Self::init(reader);
+        transcript.read_domain_tag(tag)?;
+        Ok(transcript)
+    }
+
+    /// Returns a transcript reading from `reader` in the state this one is
+    /// in, e.g. to absorb the common inputs of a batch of proofs once and
+    /// verify each proof from a fork.
+    fn fork(&self, reader: R) -> Self;
+}
+
+/// Transcript view of a verifier reading points of a second group, e.g. the
+/// `G2Affine` of a pairing-friendly curve whose G1 is `C`, for protocol
+/// extensions carrying such points.
+pub trait TranscriptReadG2<C: CurveAffine, E: EncodedChallenge<C>>: TranscriptRead<C, E> {
+    /// Read a point of a second group from the prover. Points are encoded
+    /// with `GroupEncoding::to_bytes`, and absorbed into the transcript as
+    /// that encoding.
+    fn read_point_g2<P: PrimeCurveAffine>(&mut self) -> io::Result<P>;
+}
+
+/// Transcript view of a prover writing points of a second group; see
+/// [`TranscriptReadG2`].
+pub trait TranscriptWriteG2<C: CurveAffine, E: EncodedChallenge<C>>: TranscriptWrite<C, E> {
+    /// Write a point of a second group to the proof and the transcript.
+    fn write_point_g2<P: PrimeCurveAffine>(&mut self, point: P) -> io::Result<()>;
+}
+
+fn read_point_g2_encoded<P: PrimeCurveAffine, R: Read>(reader: &mut R) -> io::Result<P> {
+    let mut encoding = P::Repr::default();
+    reader.read_exact(encoding.as_mut())?;
+    let point: P = Option::from(P::from_bytes(&encoding))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof"))?;
+    if bool::from(point.is_identity()) {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "point at infinity in proof",
+        ));
+    }
+
+    Ok(point)
+}
+
+fn point_g2_encoding<P: PrimeCurveAffine>(point: P) -> io::Result<P::Repr> {
+    if bool::from(point.is_identity()) {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "cannot write points at infinity to the transcript",
+        ));
+    }
+
+    Ok(point.to_bytes())
 }
 
 /// Manages begining and finising of transcript pipeline.
@@ -94,6 +483,24 @@ pub trait TranscriptWriterBuffer<W: Write, C: CurveAffine, E: EncodedChallenge<C
     /// Initialize a transcript given an output buffer.
     fn init(writer: W) -> Self;
 
+    /// Initialize a transcript given an output buffer and start the proof
+    /// with the domain-separation tag `tag`; see
+    /// [`TranscriptWrite::write_domain_tag`].
+    fn init_with_domain_tag(writer: W, tag: &[u8]) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut transcript = Self::init(writer);
+        transcript.write_domain_tag(tag)?;
+        Ok(transcript)
+    }
+
+    /// Returns a transcript writing to `writer` in the state this one is in,
+    /// e.g. to absorb the common inputs of a batch of proofs once and write
+    /// each proof from a fork. What this transcript has written so far is
+    /// not copied to `writer`.
+    fn fork(&self, writer: W) -> Self;
+
     /// Conclude the interaction and return the output buffer (writer).
     fn finalize(self) -> W;
 }
@@ -103,57 +510,125 @@ pub trait TranscriptWriterBuffer<W: Write, C: CurveAffine, E: EncodedChallenge<C
 pub struct Blake2bRead<R: Read, C: CurveAffine, E: EncodedChallenge<C>> {
     state: Blake2bState,
     reader: R,
+    encoding: PointEncoding,
+    config: Blake2bConfig,
     _marker: PhantomData<(C, E)>,
 }
 
-/// Keccak256 hash function reader for EVM compatibility
+/// Transcript reader backed by any hash function implementing [`Digest`],
+/// such as SHA-256 or BLAKE3.
+///
+/// The state of the transcript is the hash of the concatenation of
+/// - `"Halo2-Transcript"`, at initialization;
+/// - for each point, the byte `1` followed by the little-endian
+///   representations (`to_repr`) of its affine coordinates `x` and `y`;
+/// - for each scalar, the byte `2` followed by its little-endian
+///   representation;
+/// - for each challenge, the byte `0`.
+///
+/// With `state` the bytes absorbed up to and including the `0` of a
+/// challenge, the challenge is the 512-bit little-endian integer
+/// `hash(state || 10) || hash(state || 11) || ...`, truncated to 64 bytes and
+/// reduced modulo the order of the scalar field. For a 256-bit hash, that
+/// is `hash(state || 10) || hash(state || 11)`.
 #[derive(Debug, Clone)]
-pub struct Keccak256Read<R: Read, C: CurveAffine, E: EncodedChallenge<C>> {
-    state: Keccak256,
+pub struct DigestRead<R: Read, C: CurveAffine, E: EncodedChallenge<C>, D: Digest + Clone> {
+    state: D,
     reader: R,
+    encoding: PointEncoding,
     _marker: PhantomData<(C, E)>,
 }
 
-impl<R: Read, C: CurveAffine> TranscriptReadBuffer<R, C, Challenge255<C>>
-    for Blake2bRead<R, C, Challenge255<C>>
+/// Keccak256 hash function reader for EVM compatibility
+///
+/// An on-chain verifier reproduces the challenges of the transcript, as
+/// described in [`DigestRead`], with Solidity's
+/// `keccak256(abi.encodePacked(...))` over the same bytes, keeping in mind
+/// that field elements are absorbed little-endian.
+pub type Keccak256Read<R, C, E> = DigestRead<R, C, E, Keccak256>;
+
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C>> Blake2bRead<R, C, E> {
+    /// Initialize a transcript given an input buffer and the
+    /// domain-separation constants the proof was written with.
+    ///
+    /// Fails if `config` is not [valid](Blake2bConfig::validate).
+    pub fn with_config(reader: R, config: Blake2bConfig) -> io::Result<Self> {
+        Ok(Blake2bRead {
+            state: config.initial_state()?,
+            reader,
+            encoding: PointEncoding::default(),
+            config,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Sets the encoding of the points in the proof, which must match the
+    /// one the proof was written with.
+    pub fn with_point_encoding(mut self, encoding: PointEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+}
+
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C>, D: Digest + Clone> DigestRead<R, C, E, D> {
+    /// Sets the encoding of the points in the proof, which must match the
+    /// one the proof was written with.
+    pub fn with_point_encoding(mut self, encoding: PointEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+}
+
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>>
+    TranscriptReadBuffer<R, C, E> for Blake2bRead<R, C, E>
 {
     /// Initialize a transcript given an input buffer.
     fn init(reader: R) -> Self {
+        Self::with_config(reader, Blake2bConfig::default())
+            .expect("default transcript prefixes are distinct")
+    }
+
+    fn fork(&self, reader: R) -> Self {
         Blake2bRead {
-            state: Blake2bParams::new()
-                .hash_length(64)
-                .personal(b"Halo2-Transcript")
-                .to_state(),
+            state: self.state.clone(),
             reader,
+            encoding: self.encoding,
+            config: self.config,
             _marker: PhantomData,
         }
     }
 }
 
-impl<R: Read, C: CurveAffine> TranscriptReadBuffer<R, C, Challenge255<C>>
-    for Keccak256Read<R, C, Challenge255<C>>
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, D: Digest + Clone>
+    TranscriptReadBuffer<R, C, E> for DigestRead<R, C, E, D>
 {
     /// Initialize a transcript given an input buffer.
     fn init(reader: R) -> Self {
-        let mut state = Keccak256::new();
+        let mut state = D::new();
         state.update(b"Halo2-Transcript");
-        Keccak256Read {
+        DigestRead {
             state,
             reader,
+            encoding: PointEncoding::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn fork(&self, reader: R) -> Self {
+        DigestRead {
+            state: self.state.clone(),
+            reader,
+            encoding: self.encoding,
             _marker: PhantomData,
         }
     }
 }
 
-impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>>
-    for Blake2bRead<R, C, Challenge255<C>>
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>> TranscriptRead<C, E>
+    for Blake2bRead<R, C, E>
 {
     fn read_point(&mut self) -> io::Result<C> {
-        let mut compressed = C::Repr::default();
-        self.reader.read_exact(compressed.as_mut())?;
-        let point: C = Option::from(C::from_bytes(&compressed)).ok_or_else(|| {
-            io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof")
-        })?;
+        let point: C = read_point_encoded(&mut self.reader, self.encoding)?;
         self.common_point(point)?;
 
         Ok(point)
@@ -174,15 +649,11 @@ impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>>
     }
 }
 
-impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>>
-    for Keccak256Read<R, C, Challenge255<C>>
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, D: Digest + Clone>
+    TranscriptRead<C, E> for DigestRead<R, C, E, D>
 {
     fn read_point(&mut self) -> io::Result<C> {
-        let mut compressed = C::Repr::default();
-        self.reader.read_exact(compressed.as_mut())?;
-        let point: C = Option::from(C::from_bytes(&compressed)).ok_or_else(|| {
-            io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof")
-        })?;
+        let point: C = read_point_encoded(&mut self.reader, self.encoding)?;
         self.common_point(point)?;
 
         Ok(point)
@@ -203,18 +674,18 @@ impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>>
     }
 }
 
-impl<R: Read, C: CurveAffine> Transcript<C, Challenge255<C>>
-    for Blake2bRead<R, C, Challenge255<C>>
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>> Transcript<C, E>
+    for Blake2bRead<R, C, E>
 {
-    fn squeeze_challenge(&mut self) -> Challenge255<C> {
-        self.state.update(&[BLAKE2B_PREFIX_CHALLENGE]);
+    fn squeeze_challenge(&mut self) -> E {
+        self.state.update(&[self.config.challenge_prefix]);
         let hasher = self.state.clone();
         let result: [u8; 64] = hasher.finalize().as_bytes().try_into().unwrap();
-        Challenge255::<C>::new(&result)
+        E::new(&result)
     }
 
     fn common_point(&mut self, point: C) -> io::Result<()> {
-        self.state.update(&[BLAKE2B_PREFIX_POINT]);
+        self.state.update(&[self.config.point_prefix]);
         let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
             io::Error::new(
                 io::ErrorKind::Other,
@@ -228,35 +699,23 @@ impl<R: Read, C: CurveAffine> Transcript<C, Challenge255<C>>
     }
 
     fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
-        self.state.update(&[BLAKE2B_PREFIX_SCALAR]);
+        self.state.update(&[self.config.scalar_prefix]);
         self.state.update(scalar.to_repr().as_ref());
 
         Ok(())
     }
 }
 
-impl<R: Read, C: CurveAffine> Transcript<C, Challenge255<C>>
-    for Keccak256Read<R, C, Challenge255<C>>
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, D: Digest + Clone>
+    Transcript<C, E> for DigestRead<R, C, E, D>
 {
-    fn squeeze_challenge(&mut self) -> Challenge255<C> {
-        self.state.update(&[KECCAK256_PREFIX_CHALLENGE]);
-
-        let mut state_lo = self.state.clone();
-        let mut state_hi = self.state.clone();
-        state_lo.update(&[KECCAK256_PREFIX_CHALLENGE_LO]);
-        state_hi.update(&[KECCAK256_PREFIX_CHALLENGE_HI]);
-        let result_lo: [u8; 32] = state_lo.finalize().as_slice().try_into().unwrap();
-        let result_hi: [u8; 32] = state_hi.finalize().as_slice().try_into().unwrap();
-
-        let mut t = result_lo.to_vec();
-        t.extend_from_slice(&result_hi[..]);
-        let result: [u8; 64] = t.as_slice().try_into().unwrap();
-
-        Challenge255::<C>::new(&result)
+    fn squeeze_challenge(&mut self) -> E {
+        self.state.update(&[DIGEST_PREFIX_CHALLENGE]);
+        E::new(&digest_challenge_input(&self.state))
     }
 
     fn common_point(&mut self, point: C) -> io::Result<()> {
-        self.state.update(&[KECCAK256_PREFIX_POINT]);
+        self.state.update(&[DIGEST_PREFIX_POINT]);
         let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
             io::Error::new(
                 io::ErrorKind::Other,
@@ -270,7 +729,7 @@ impl<R: Read, C: CurveAffine> Transcript<C, Challenge255<C>>
     }
 
     fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
-        self.state.update(&[KECCAK256_PREFIX_SCALAR]);
+        self.state.update(&[DIGEST_PREFIX_SCALAR]);
         self.state.update(scalar.to_repr().as_ref());
 
         Ok(())
@@ -282,28 +741,84 @@ impl<R: Read, C: CurveAffine> Transcript<C, Challenge255<C>>
 pub struct Blake2bWrite<W: Write, C: CurveAffine, E: EncodedChallenge<C>> {
     state: Blake2bState,
     writer: W,
+    encoding: PointEncoding,
+    config: Blake2bConfig,
+    stats: TranscriptStats,
     _marker: PhantomData<(C, E)>,
 }
 
-/// Keccak256 hash function writer for EVM compatibility
+/// Transcript writer backed by any hash function implementing [`Digest`],
+/// with the same transcript as [`DigestRead`].
 #[derive(Debug, Clone)]
-pub struct Keccak256Write<W: Write, C: CurveAffine, E: EncodedChallenge<C>> {
-    state: Keccak256,
+pub struct DigestWrite<W: Write, C: CurveAffine, E: EncodedChallenge<C>, D: Digest + Clone> {
+    state: D,
     writer: W,
+    encoding: PointEncoding,
+    stats: TranscriptStats,
     _marker: PhantomData<(C, E)>,
 }
 
-impl<W: Write, C: CurveAffine> TranscriptWriterBuffer<W, C, Challenge255<C>>
-    for Blake2bWrite<W, C, Challenge255<C>>
+/// Keccak256 hash function writer for EVM compatibility, with the same
+/// transcript as [`Keccak256Read`].
+pub type Keccak256Write<W, C, E> = DigestWrite<W, C, E, Keccak256>;
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C>> Blake2bWrite<W, C, E> {
+    /// Returns the counts of what was written to the proof so far
+    pub fn stats(&self) -> &TranscriptStats {
+        &self.stats
+    }
+
+    /// Initialize a transcript given an output buffer and
+    /// domain-separation constants.
+    ///
+    /// Fails if `config` is not [valid](Blake2bConfig::validate).
+    pub fn with_config(writer: W, config: Blake2bConfig) -> io::Result<Self> {
+        Ok(Blake2bWrite {
+            state: config.initial_state()?,
+            writer,
+            encoding: PointEncoding::default(),
+            config,
+            stats: TranscriptStats::default(),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Sets the encoding of the points written to the proof.
+    pub fn with_point_encoding(mut self, encoding: PointEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+}
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C>, D: Digest + Clone> DigestWrite<W, C, E, D> {
+    /// Returns the counts of what was written to the proof so far
+    pub fn stats(&self) -> &TranscriptStats {
+        &self.stats
+    }
+
+    /// Sets the encoding of the points written to the proof.
+    pub fn with_point_encoding(mut self, encoding: PointEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+}
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>>
+    TranscriptWriterBuffer<W, C, E> for Blake2bWrite<W, C, E>
 {
     /// Initialize a transcript given an output buffer.
     fn init(writer: W) -> Self {
+        Self::with_config(writer, Blake2bConfig::default())
+            .expect("default transcript prefixes are distinct")
+    }
+
+    fn fork(&self, writer: W) -> Self {
         Blake2bWrite {
-            state: Blake2bParams::new()
-                .hash_length(64)
-                .personal(b"Halo2-Transcript")
-                .to_state(),
+            state: self.state.clone(),
             writer,
+            encoding: self.encoding,
+            config: self.config,
+            stats: TranscriptStats::default(),
             _marker: PhantomData,
         }
     }
@@ -314,16 +829,28 @@ impl<W: Write, C: CurveAffine> TranscriptWriterBuffer<W, C, Challenge255<C>>
     }
 }
 
-impl<W: Write, C: CurveAffine> TranscriptWriterBuffer<W, C, Challenge255<C>>
-    for Keccak256Write<W, C, Challenge255<C>>
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, D: Digest + Clone>
+    TranscriptWriterBuffer<W, C, E> for DigestWrite<W, C, E, D>
 {
     /// Initialize a transcript given an output buffer.
     fn init(writer: W) -> Self {
-        let mut state = Keccak256::new();
+        let mut state = D::new();
         state.update(b"Halo2-Transcript");
-        Keccak256Write {
+        DigestWrite {
             state,
             writer,
+            encoding: PointEncoding::default(),
+            stats: TranscriptStats::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn fork(&self, writer: W) -> Self {
+        DigestWrite {
+            state: self.state.clone(),
+            writer,
+            encoding: self.encoding,
+            stats: TranscriptStats::default(),
             _marker: PhantomData,
         }
     }
@@ -335,48 +862,55 @@ impl<W: Write, C: CurveAffine> TranscriptWriterBuffer<W, C, Challenge255<C>>
     }
 }
 
-impl<W: Write, C: CurveAffine> TranscriptWrite<C, Challenge255<C>>
-    for Blake2bWrite<W, C, Challenge255<C>>
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>> TranscriptWrite<C, E>
+    for Blake2bWrite<W, C, E>
 {
     fn write_point(&mut self, point: C) -> io::Result<()> {
         self.common_point(point)?;
-        let compressed = point.to_bytes();
-        self.writer.write_all(compressed.as_ref())
+        let bytes = write_point_encoded(&mut self.writer, point, self.encoding)?;
+        self.stats.point(bytes);
+        Ok(())
     }
     fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
         self.common_scalar(scalar)?;
         let data = scalar.to_repr();
-        self.writer.write_all(data.as_ref())
+        self.writer.write_all(data.as_ref())?;
+        self.stats.scalar(data.as_ref().len());
+        Ok(())
     }
 }
 
-impl<W: Write, C: CurveAffine> TranscriptWrite<C, Challenge255<C>>
-    for Keccak256Write<W, C, Challenge255<C>>
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, D: Digest + Clone>
+    TranscriptWrite<C, E> for DigestWrite<W, C, E, D>
 {
     fn write_point(&mut self, point: C) -> io::Result<()> {
         self.common_point(point)?;
-        let compressed = point.to_bytes();
-        self.writer.write_all(compressed.as_ref())
+        let bytes = write_point_encoded(&mut self.writer, point, self.encoding)?;
+        self.stats.point(bytes);
+        Ok(())
     }
     fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
         self.common_scalar(scalar)?;
         let data = scalar.to_repr();
-        self.writer.write_all(data.as_ref())
+        self.writer.write_all(data.as_ref())?;
+        self.stats.scalar(data.as_ref().len());
+        Ok(())
     }
 }
 
-impl<W: Write, C: CurveAffine> Transcript<C, Challenge255<C>>
-    for Blake2bWrite<W, C, Challenge255<C>>
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>> Transcript<C, E>
+    for Blake2bWrite<W, C, E>
 {
-    fn squeeze_challenge(&mut self) -> Challenge255<C> {
-        self.state.update(&[BLAKE2B_PREFIX_CHALLENGE]);
+    fn squeeze_challenge(&mut self) -> E {
+        self.stats.challenge();
+        self.state.update(&[self.config.challenge_prefix]);
         let hasher = self.state.clone();
         let result: [u8; 64] = hasher.finalize().as_bytes().try_into().unwrap();
-        Challenge255::<C>::new(&result)
+        E::new(&result)
     }
 
     fn common_point(&mut self, point: C) -> io::Result<()> {
-        self.state.update(&[BLAKE2B_PREFIX_POINT]);
+        self.state.update(&[self.config.point_prefix]);
         let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
             io::Error::new(
                 io::ErrorKind::Other,
@@ -390,35 +924,24 @@ impl<W: Write, C: CurveAffine> Transcript<C, Challenge255<C>>
     }
 
     fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
-        self.state.update(&[BLAKE2B_PREFIX_SCALAR]);
+        self.state.update(&[self.config.scalar_prefix]);
         self.state.update(scalar.to_repr().as_ref());
 
         Ok(())
     }
 }
 
-impl<W: Write, C: CurveAffine> Transcript<C, Challenge255<C>>
-    for Keccak256Write<W, C, Challenge255<C>>
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, D: Digest + Clone>
+    Transcript<C, E> for DigestWrite<W, C, E, D>
 {
-    fn squeeze_challenge(&mut self) -> Challenge255<C> {
-        self.state.update(&[KECCAK256_PREFIX_CHALLENGE]);
-
-        let mut state_lo = self.state.clone();
-        let mut state_hi = self.state.clone();
-        state_lo.update(&[KECCAK256_PREFIX_CHALLENGE_LO]);
-        state_hi.update(&[KECCAK256_PREFIX_CHALLENGE_HI]);
-        let result_lo: [u8; 32] = state_lo.finalize().as_slice().try_into().unwrap();
-        let result_hi: [u8; 32] = state_hi.finalize().as_slice().try_into().unwrap();
-
-        let mut t = result_lo.to_vec();
-        t.extend_from_slice(&result_hi[..]);
-        let result: [u8; 64] = t.as_slice().try_into().unwrap();
-
-        Challenge255::<C>::new(&result)
+    fn squeeze_challenge(&mut self) -> E {
+        self.stats.challenge();
+        self.state.update(&[DIGEST_PREFIX_CHALLENGE]);
+        E::new(&digest_challenge_input(&self.state))
     }
 
     fn common_point(&mut self, point: C) -> io::Result<()> {
-        self.state.update(&[KECCAK256_PREFIX_POINT]);
+        self.state.update(&[DIGEST_PREFIX_POINT]);
         let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
             io::Error::new(
                 io::ErrorKind::Other,
@@ -432,13 +955,116 @@ impl<W: Write, C: CurveAffine> Transcript<C, Challenge255<C>>
     }
 
     fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
-        self.state.update(&[KECCAK256_PREFIX_SCALAR]);
+        self.state.update(&[DIGEST_PREFIX_SCALAR]);
         self.state.update(scalar.to_repr().as_ref());
 
         Ok(())
     }
 }
 
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>> TranscriptReadG2<C, E>
+    for Blake2bRead<R, C, E>
+{
+    fn read_point_g2<P: PrimeCurveAffine>(&mut self) -> io::Result<P> {
+        let point: P = read_point_g2_encoded(&mut self.reader)?;
+        self.state.update(&[self.config.point_g2_prefix]);
+        self.state.update(point.to_bytes().as_ref());
+
+        Ok(point)
+    }
+}
+
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, D: Digest + Clone>
+    TranscriptReadG2<C, E> for DigestRead<R, C, E, D>
+{
+    fn read_point_g2<P: PrimeCurveAffine>(&mut self) -> io::Result<P> {
+        let point: P = read_point_g2_encoded(&mut self.reader)?;
+        self.state.update(&[DIGEST_PREFIX_POINT_G2]);
+        self.state.update(point.to_bytes().as_ref());
+
+        Ok(point)
+    }
+}
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>> TranscriptWriteG2<C, E>
+    for Blake2bWrite<W, C, E>
+{
+    fn write_point_g2<P: PrimeCurveAffine>(&mut self, point: P) -> io::Result<()> {
+        let encoding = point_g2_encoding(point)?;
+        self.state.update(&[self.config.point_g2_prefix]);
+        self.state.update(encoding.as_ref());
+        self.writer.write_all(encoding.as_ref())?;
+        self.stats.point(encoding.as_ref().len());
+        Ok(())
+    }
+}
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, D: Digest + Clone>
+    TranscriptWriteG2<C, E> for DigestWrite<W, C, E, D>
+{
+    fn write_point_g2<P: PrimeCurveAffine>(&mut self, point: P) -> io::Result<()> {
+        let encoding = point_g2_encoding(point)?;
+        self.state.update(&[DIGEST_PREFIX_POINT_G2]);
+        self.state.update(encoding.as_ref());
+        self.writer.write_all(encoding.as_ref())?;
+        self.stats.point(encoding.as_ref().len());
+        Ok(())
+    }
+}
+
+/// Derives the 64 bytes a challenge is reduced from by hashing `state` with
+/// consecutive prefixes, starting at [`DIGEST_PREFIX_CHALLENGE_WIDE`].
+fn digest_challenge_input<D: Digest + Clone>(state: &D) -> [u8; 64] {
+    let mut result = Vec::with_capacity(64);
+    let mut prefix = DIGEST_PREFIX_CHALLENGE_WIDE;
+    while result.len() < 64 {
+        let mut hasher = state.clone();
+        hasher.update(&[prefix]);
+        result.extend_from_slice(hasher.finalize().as_slice());
+        prefix += 1;
+    }
+    result[..64].try_into().unwrap()
+}
+
+/// Hashes the instance columns of a proof with `D` into a scalar, which
+/// [`ProverOptions::with_instance_hash`](crate::plonk::ProverOptions::with_instance_hash)
+/// makes the prover absorb in place of the instance values.
+///
+/// The scalar is reduced as in [`DigestRead`] from the state given by
+/// `"Halo2-Instances"` followed, for each column, by its number of values as
+/// a little-endian `u64` and the little-endian representations of the
+/// values.
+pub fn instance_hash<F: FieldExt, D: Digest + Clone>(columns: &[&[F]]) -> F {
+    let mut state = D::new();
+    state.update(b"Halo2-Instances");
+    for column in columns {
+        state.update(&(column.len() as u64).to_le_bytes());
+        for value in column.iter() {
+            state.update(value.to_repr().as_ref());
+        }
+    }
+    F::from_bytes_wide(&digest_challenge_input(&state))
+}
+
+/// Absorbs the commitment to an instance column into `transcript`. Under KZG
+/// an all-zero column commits to the identity, which has no coordinates, so
+/// the identity is absorbed as the scalar zero instead; transcripts keep
+/// scalars and points apart with distinct prefixes.
+pub(crate) fn common_instance_commitment<
+    C: CurveAffine,
+    E: EncodedChallenge<C>,
+    T: Transcript<C, E> + ?Sized,
+>(
+    transcript: &mut T,
+    commitment: C,
+) -> io::Result<()> {
+    if bool::from(commitment.is_identity()) {
+        transcript.common_scalar(C::Scalar::zero())
+    } else {
+        transcript.common_point(commitment)
+    }
+}
+
 /// The scalar representation of a verifier challenge.
 ///
 /// The `Type` type can be used to scope the challenge to a specific context, or
@@ -513,6 +1139,36 @@ impl<C: CurveAffine> EncodedChallenge<C> for Challenge255<C> {
     }
 }
 
+/// A 128-bit challenge, for verifiers to which full-width challenges are
+/// expensive, such as verifier circuits and EVM contracts. The challenge is
+/// the first 16 bytes of the 64-byte hash output read as a little-endian
+/// integer, so that it needs no reduction, at the cost of a soundness error
+/// of up to $2^{-128}$ per challenge instead of $2^{-255}$.
+///
+/// The prover and the verifier must use the same challenge encoding, e.g.
+/// [`Blake2bWrite`] and [`Blake2bRead`] both with `Challenge128`.
+#[derive(Copy, Clone, Debug)]
+pub struct Challenge128<C: CurveAffine>([u8; 16], PhantomData<C>);
+
+impl<C: CurveAffine> std::ops::Deref for Challenge128<C> {
+    type Target = [u8; 16];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<C: CurveAffine> EncodedChallenge<C> for Challenge128<C> {
+    type Input = [u8; 64];
+
+    fn new(challenge_input: &[u8; 64]) -> Self {
+        Challenge128(challenge_input[..16].try_into().unwrap(), PhantomData)
+    }
+    fn get_scalar(&self) -> C::Scalar {
+        C::Scalar::from_u128(u128::from_le_bytes(self.0))
+    }
+}
+
 pub(crate) fn read_n_points<C: CurveAffine, E: EncodedChallenge<C>, T: TranscriptRead<C, E>>(
     transcript: &mut T,
     n: usize,
@@ -526,3 +1182,359 @@ pub(crate) fn read_n_scalars<C: CurveAffine, E: EncodedChallenge<C>, T: Transcri
 ) -> io::Result<Vec<C::Scalar>> {
     (0..n).map(|_| transcript.read_scalar()).collect()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ff::Field;
+    use group::{Curve, Group};
+    use halo2curves::pasta::{Eq, EqAffine, Fp};
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_point_encoding() {
+        let points: Vec<EqAffine> = (0..4).map(|_| Eq::random(OsRng).to_affine()).collect();
+        let scalar = Fp::random(OsRng);
+
+        let mut proofs = vec![];
+        let mut challenges = vec![];
+        for encoding in [PointEncoding::Compressed, PointEncoding::Uncompressed] {
+            let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![])
+                .with_point_encoding(encoding);
+            for point in points.iter() {
+                transcript.write_point(*point).unwrap();
+            }
+            transcript.write_scalar(scalar).unwrap();
+            challenges.push(*transcript.squeeze_challenge_scalar::<()>());
+            let proof = transcript.finalize();
+
+            let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(&proof[..])
+                .with_point_encoding(encoding);
+            for point in points.iter() {
+                assert_eq!(transcript.read_point().unwrap(), *point);
+            }
+            assert_eq!(transcript.read_scalar().unwrap(), scalar);
+            assert_eq!(
+                *transcript.squeeze_challenge_scalar::<()>(),
+                challenges[challenges.len() - 1]
+            );
+            proofs.push(proof);
+        }
+
+        // Challenges do not depend on the encoding, only the proof size does
+        assert_eq!(challenges[0], challenges[1]);
+        assert_eq!(proofs[1].len() - proofs[0].len(), 4 * 32);
+    }
+
+    #[test]
+    fn test_keccak256_layout() {
+        use halo2curves::bn256::{Fr, G1Affine, G1};
+
+        let point = G1::random(OsRng).to_affine();
+        let scalar = Fr::random(OsRng);
+
+        let mut transcript = Keccak256Write::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        transcript.write_point(point).unwrap();
+        transcript.write_scalar(scalar).unwrap();
+        let challenge = *transcript.squeeze_challenge_scalar::<()>();
+
+        // The same bytes hashed in one go, as an on-chain verifier would
+        let coords = point.coordinates().unwrap();
+        let mut state = b"Halo2-Transcript".to_vec();
+        state.push(DIGEST_PREFIX_POINT);
+        state.extend_from_slice(coords.x().to_repr().as_ref());
+        state.extend_from_slice(coords.y().to_repr().as_ref());
+        state.push(DIGEST_PREFIX_SCALAR);
+        state.extend_from_slice(scalar.to_repr().as_ref());
+        state.push(DIGEST_PREFIX_CHALLENGE);
+
+        let mut wide = [0u8; 64];
+        for (half, prefix) in wide.chunks_mut(32).zip([
+            DIGEST_PREFIX_CHALLENGE_WIDE,
+            DIGEST_PREFIX_CHALLENGE_WIDE + 1,
+        ]) {
+            let mut preimage = state.clone();
+            preimage.push(prefix);
+            half.copy_from_slice(Keccak256::digest(&preimage).as_slice());
+        }
+        assert_eq!(challenge, Fr::from_bytes_wide(&wide));
+
+        // Known answer for the generator `(1, 2)` and the scalar 5, computed
+        // independently from Keccak-256 over the layout above
+        let mut transcript = Keccak256Write::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        transcript.common_point(G1Affine::generator()).unwrap();
+        transcript.common_scalar(Fr::from(5)).unwrap();
+        let challenge = *transcript.squeeze_challenge_scalar::<()>();
+        let expected: [u8; 32] = [
+            0x39, 0xa8, 0x77, 0x54, 0x97, 0x49, 0xc8, 0x78, 0x95, 0x8e, 0xfb, 0x51, 0xc7, 0x16,
+            0x12, 0x34, 0xb2, 0x1d, 0xbc, 0xc2, 0xd6, 0x0c, 0xd1, 0xd2, 0x96, 0xc8, 0xb3, 0x8f,
+            0x77, 0xdf, 0x82, 0x2c,
+        ];
+        assert_eq!(challenge.to_repr(), expected);
+    }
+
+    #[test]
+    fn test_digest_transcripts() {
+        use sha3::{Keccak512, Sha3_256};
+
+        fn challenge<D: Digest + Clone>(points: &[EqAffine], scalar: Fp) -> Fp {
+            let mut transcript = DigestWrite::<_, EqAffine, Challenge255<_>, D>::init(vec![]);
+            for point in points.iter() {
+                transcript.write_point(*point).unwrap();
+            }
+            transcript.write_scalar(scalar).unwrap();
+            let challenge = *transcript.squeeze_challenge_scalar::<()>();
+            let proof = transcript.finalize();
+
+            let mut transcript = DigestRead::<_, EqAffine, Challenge255<_>, D>::init(&proof[..]);
+            for point in points.iter() {
+                assert_eq!(transcript.read_point().unwrap(), *point);
+            }
+            assert_eq!(transcript.read_scalar().unwrap(), scalar);
+            assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), challenge);
+            challenge
+        }
+
+        let points: Vec<EqAffine> = (0..2).map(|_| Eq::random(OsRng).to_affine()).collect();
+        let scalar = Fp::random(OsRng);
+
+        // 256-bit and 512-bit digests give independent challenges
+        let sha3 = challenge::<Sha3_256>(&points, scalar);
+        let keccak256 = challenge::<Keccak256>(&points, scalar);
+        let keccak512 = challenge::<Keccak512>(&points, scalar);
+        assert_ne!(sha3, keccak256);
+        assert_ne!(keccak256, keccak512);
+    }
+
+    #[test]
+    fn test_dyn_transcript() {
+        fn write<T: TranscriptWriterBuffer<Vec<u8>, EqAffine, Challenge255<EqAffine>>>(
+            point: EqAffine,
+            scalar: Fp,
+        ) -> (Vec<u8>, Fp) {
+            let mut transcript = T::init(vec![]);
+            transcript.write_point(point).unwrap();
+            transcript.write_scalar(scalar).unwrap();
+            let challenge = *transcript.squeeze_challenge_scalar::<()>();
+            (transcript.finalize(), challenge)
+        }
+
+        let point = Eq::random(OsRng).to_affine();
+        let scalar = Fp::random(OsRng);
+        let (proof, blake2b) = write::<Blake2bWrite<_, _, _>>(point, scalar);
+        let (_, keccak256) = write::<Keccak256Write<_, _, _>>(point, scalar);
+        assert_ne!(blake2b, keccak256);
+
+        // A prover and verifier choosing the transcript at runtime
+        for (keccak, expected) in [(false, blake2b), (true, keccak256)] {
+            let mut transcript: Box<dyn TranscriptWrite<EqAffine, Challenge255<_>>> = if keccak {
+                Box::new(Keccak256Write::<_, EqAffine, Challenge255<_>>::init(vec![]))
+            } else {
+                Box::new(Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]))
+            };
+            transcript.write_point(point).unwrap();
+            transcript.write_scalar(scalar).unwrap();
+            assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), expected);
+
+            let mut transcript: Box<dyn TranscriptRead<EqAffine, Challenge255<_>> + '_> = if keccak
+            {
+                Box::new(Keccak256Read::<_, EqAffine, Challenge255<_>>::init(
+                    &proof[..],
+                ))
+            } else {
+                Box::new(Blake2bRead::<_, EqAffine, Challenge255<_>>::init(
+                    &proof[..],
+                ))
+            };
+            assert_eq!(transcript.read_point().unwrap(), point);
+            assert_eq!(transcript.read_scalar().unwrap(), scalar);
+            assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), expected);
+        }
+    }
+
+    #[test]
+    fn test_challenge128() {
+        let point = Eq::random(OsRng).to_affine();
+        let scalar = Fp::random(OsRng);
+
+        let mut transcript = Blake2bWrite::<_, EqAffine, Challenge128<_>>::init(vec![]);
+        transcript.write_point(point).unwrap();
+        transcript.write_scalar(scalar).unwrap();
+        let challenge = *transcript.squeeze_challenge_scalar::<()>();
+        let proof = transcript.finalize();
+
+        // The challenge fits in 128 bits
+        assert!(challenge.to_repr()[16..].iter().all(|byte| *byte == 0));
+
+        let mut transcript = Blake2bRead::<_, EqAffine, Challenge128<_>>::init(&proof[..]);
+        assert_eq!(transcript.read_point().unwrap(), point);
+        assert_eq!(transcript.read_scalar().unwrap(), scalar);
+        assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), challenge);
+
+        // A verifier squeezing full-width challenges disagrees with the prover
+        let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(&proof[..]);
+        transcript.read_point().unwrap();
+        transcript.read_scalar().unwrap();
+        assert_ne!(*transcript.squeeze_challenge_scalar::<()>(), challenge);
+    }
+
+    #[test]
+    fn test_fork() {
+        let points: Vec<EqAffine> = (0..2).map(|_| Eq::random(OsRng).to_affine()).collect();
+        let common = Fp::random(OsRng);
+
+        let mut prefix = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+        prefix.common_scalar(common).unwrap();
+
+        for point in points.iter() {
+            let mut forked = prefix.fork(vec![]);
+            forked.write_point(*point).unwrap();
+            let challenge = *forked.squeeze_challenge_scalar::<()>();
+            let proof = forked.finalize();
+
+            // The fork behaves as a transcript that absorbed the prefix itself
+            let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+            transcript.common_scalar(common).unwrap();
+            transcript.write_point(*point).unwrap();
+            assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), challenge);
+            assert_eq!(transcript.finalize(), proof);
+
+            let mut prefix = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(&[][..]);
+            prefix.common_scalar(common).unwrap();
+            let mut transcript = prefix.fork(&proof[..]);
+            assert_eq!(transcript.read_point().unwrap(), *point);
+            assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), challenge);
+        }
+
+        // Forking does not advance the prefix
+        assert!(prefix.finalize().is_empty());
+    }
+
+    #[test]
+    fn test_blake2b_config() {
+        let point = Eq::random(OsRng).to_affine();
+
+        let challenge = |config: Blake2bConfig| {
+            let mut transcript =
+                Blake2bWrite::<_, EqAffine, Challenge255<_>>::with_config(vec![], config).unwrap();
+            transcript.write_point(point).unwrap();
+            let challenge = *transcript.squeeze_challenge_scalar::<()>();
+
+            let proof = transcript.finalize();
+            let mut transcript =
+                Blake2bRead::<_, EqAffine, Challenge255<_>>::with_config(&proof[..], config)
+                    .unwrap();
+            transcript.read_point().unwrap();
+            assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), challenge);
+            challenge
+        };
+
+        // The default is the usual transcript
+        let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+        transcript.write_point(point).unwrap();
+        let default = *transcript.squeeze_challenge_scalar::<()>();
+        assert_eq!(challenge(Blake2bConfig::default()), default);
+
+        let mut personalization = [0u8; 16];
+        personalization[..6].copy_from_slice(b"my-app");
+        let config = Blake2bConfig {
+            personalization,
+            ..Default::default()
+        };
+        assert_ne!(challenge(config), default);
+        let config = Blake2bConfig {
+            challenge_prefix: 4,
+            ..Default::default()
+        };
+        assert_ne!(challenge(config), default);
+
+        // Prefixes must tell the kinds of messages apart
+        let config = Blake2bConfig {
+            challenge_prefix: BLAKE2B_PREFIX_POINT_G2,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate().unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert!(Blake2bWrite::<_, EqAffine, Challenge255<_>>::with_config(vec![], config).is_err());
+        assert!(
+            Blake2bRead::<_, EqAffine, Challenge255<_>>::with_config(&b""[..], config).is_err()
+        );
+    }
+
+    #[test]
+    fn test_point_g2() {
+        use group::GroupEncoding;
+        use halo2curves::bn256::{G1Affine, G2Affine, G2};
+
+        fn round_trip<T, U>(
+            write: impl FnOnce(Vec<u8>) -> T,
+            read: impl Fn(io::Cursor<Vec<u8>>) -> U,
+        ) where
+            T: TranscriptWriterBuffer<Vec<u8>, G1Affine, Challenge255<G1Affine>>
+                + TranscriptWriteG2<G1Affine, Challenge255<G1Affine>>,
+            U: TranscriptReadG2<G1Affine, Challenge255<G1Affine>>,
+        {
+            let point = G2::random(OsRng).to_affine();
+
+            let mut transcript = write(vec![]);
+            transcript.write_point_g2(point).unwrap();
+            let challenge = *transcript.squeeze_challenge_scalar::<()>();
+            assert!(transcript.write_point_g2(G2Affine::identity()).is_err());
+            let proof = transcript.finalize();
+            assert_eq!(proof.len(), 64);
+
+            let mut transcript = read(io::Cursor::new(proof));
+            assert_eq!(transcript.read_point_g2::<G2Affine>().unwrap(), point);
+            assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), challenge);
+
+            let identity = G2Affine::identity().to_bytes().as_ref().to_vec();
+            let mut transcript = read(io::Cursor::new(identity));
+            assert_eq!(
+                transcript
+                    .read_point_g2::<G2Affine>()
+                    .unwrap_err()
+                    .to_string(),
+                "point at infinity in proof"
+            );
+        }
+
+        round_trip(
+            Blake2bWrite::<_, _, Challenge255<_>>::init,
+            Blake2bRead::<_, _, Challenge255<_>>::init,
+        );
+        round_trip(
+            Keccak256Write::<_, _, Challenge255<_>>::init,
+            Keccak256Read::<_, _, Challenge255<_>>::init,
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let point = Eq::random(OsRng).to_affine();
+        let scalar = Fp::random(OsRng);
+
+        let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![])
+            .with_point_encoding(PointEncoding::Uncompressed);
+        transcript.common_scalar(scalar).unwrap();
+        transcript.write_point(point).unwrap();
+        transcript.write_scalar(scalar).unwrap();
+        transcript.squeeze_challenge();
+        transcript.squeeze_challenge();
+        transcript.write_scalar(scalar).unwrap();
+
+        let stats = transcript.stats().clone();
+        let round = |points, scalars, bytes| RoundStats {
+            points,
+            scalars,
+            bytes,
+        };
+        assert_eq!(
+            stats.rounds(),
+            &[round(1, 1, 96), round(0, 0, 0), round(0, 1, 32)]
+        );
+        assert_eq!(stats.total(), round(1, 2, 128));
+        assert_eq!(transcript.finalize().len(), stats.total().bytes);
+    }
+}
@@ -0,0 +1,345 @@
+//! Proofs framed into length-prefixed sections, one per round of the
+//! protocol, for verifying proofs while they are received.
+//!
+//! A framed proof is a sequence of sections, each a 4-byte little-endian
+//! length followed by that many bytes, and ends with a section of length
+//! zero. A section holds the compressed points and the scalars the prover
+//! sent between two challenges; rounds in which the prover sent nothing have
+//! no section. A verifier reading from a network stream therefore knows how
+//! much of the proof to wait for before it can continue, and notices a
+//! truncated proof as soon as a section is cut short.
+
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use group::ff::PrimeField;
+use group::GroupEncoding;
+use halo2curves::CurveAffine;
+
+use super::{EncodedChallenge, Transcript, TranscriptRead, TranscriptWrite};
+
+/// Writes a framed proof to `W`, hashing with the transcript `T`, e.g. a
+/// [`Blake2bWrite`](super::Blake2bWrite) writing to [`io::sink`]. The
+/// challenges are those of `T` writing the unframed proof with compressed
+/// points.
+#[derive(Debug)]
+pub struct FramedWrite<W: Write, C: CurveAffine, E: EncodedChallenge<C>, T: Transcript<C, E>> {
+    transcript: T,
+    writer: W,
+    section: Vec<u8>,
+    error: Option<io::Error>,
+    _marker: PhantomData<(C, E)>,
+}
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C>, T: Transcript<C, E>>
+    FramedWrite<W, C, E, T>
+{
+    /// Writes the proof to `writer`, hashing with `transcript`
+    pub fn new(transcript: T, writer: W) -> Self {
+        FramedWrite {
+            transcript,
+            writer,
+            section: vec![],
+            error: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the error, if any, of writing a section when a challenge was
+    /// squeezed.
+    fn check(&mut self) -> io::Result<()> {
+        self.error.take().map_or(Ok(()), Err)
+    }
+
+    fn end_section(&mut self) -> io::Result<()> {
+        if self.section.is_empty() {
+            return Ok(());
+        }
+        let len: u32 = self.section.len().try_into().map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "proof section is too long")
+        })?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(&self.section)?;
+        self.section.clear();
+        self.writer.flush()
+    }
+
+    /// Writes the last section and the end of the proof, and returns the
+    /// writer.
+    pub fn finalize(mut self) -> io::Result<W> {
+        self.check()?;
+        self.end_section()?;
+        self.writer.write_all(&0u32.to_le_bytes())?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C>, T: Transcript<C, E>> Transcript<C, E>
+    for FramedWrite<W, C, E, T>
+{
+    fn squeeze_challenge(&mut self) -> E {
+        // The verifier needs everything sent so far to compute the challenge,
+        // so this is where a section ends. An error writing the section is
+        // reported by the next write or by `finalize`.
+        if let Err(error) = self.end_section() {
+            self.error.get_or_insert(error);
+        }
+        self.transcript.squeeze_challenge()
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        self.transcript.common_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.transcript.common_scalar(scalar)
+    }
+}
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C>, T: Transcript<C, E>> TranscriptWrite<C, E>
+    for FramedWrite<W, C, E, T>
+{
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        self.check()?;
+        self.transcript.common_point(point)?;
+        self.section.extend_from_slice(point.to_bytes().as_ref());
+        Ok(())
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.check()?;
+        self.transcript.common_scalar(scalar)?;
+        self.section.extend_from_slice(scalar.to_repr().as_ref());
+        Ok(())
+    }
+}
+
+/// Reads a framed proof from `R` as it arrives, hashing with the transcript
+/// `T`, e.g. a [`Blake2bRead`](super::Blake2bRead) reading from
+/// [`io::empty`].
+#[derive(Debug)]
+pub struct FramedRead<R: Read, C: CurveAffine, E: EncodedChallenge<C>, T: Transcript<C, E>> {
+    transcript: T,
+    reader: R,
+    section: Vec<u8>,
+    position: usize,
+    finished: bool,
+    _marker: PhantomData<(C, E)>,
+}
+
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C>, T: Transcript<C, E>> FramedRead<R, C, E, T> {
+    /// Reads the proof from `reader`, hashing with `transcript`
+    pub fn new(transcript: T, reader: R) -> Self {
+        FramedRead {
+            transcript,
+            reader,
+            section: vec![],
+            position: 0,
+            finished: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Reads the header of the next section and the section itself, or
+    /// notes the end of the proof.
+    fn next_section(&mut self) -> io::Result<()> {
+        let mut len = [0u8; 4];
+        self.reader.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+
+        self.section.clear();
+        self.position = 0;
+        if len == 0 {
+            self.finished = true;
+            return Ok(());
+        }
+        // Grow the buffer with the bytes actually received rather than
+        // trusting the length up front.
+        (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut self.section)?;
+        if self.section.len() != len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "proof section was cut short",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the next `len` bytes of the proof, which must not cross the
+    /// end of a section.
+    fn next_bytes(&mut self, len: usize) -> io::Result<&[u8]> {
+        if self.position == self.section.len() && !self.finished {
+            self.next_section()?;
+        }
+        if self.finished {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "proof ended early",
+            ));
+        }
+        let start = self.position;
+        if self.section.len() - start < len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "proof element crosses the end of a section",
+            ));
+        }
+        self.position += len;
+        Ok(&self.section[start..self.position])
+    }
+
+    /// Checks that the proof was read to its end and returns the reader,
+    /// which is left just after the proof.
+    pub fn finish(mut self) -> io::Result<R> {
+        if self.position == self.section.len() && !self.finished {
+            self.next_section()?;
+        }
+        if !self.finished {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "proof has trailing data",
+            ));
+        }
+        Ok(self.reader)
+    }
+}
+
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C>, T: Transcript<C, E>> Transcript<C, E>
+    for FramedRead<R, C, E, T>
+{
+    fn squeeze_challenge(&mut self) -> E {
+        self.transcript.squeeze_challenge()
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        self.transcript.common_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.transcript.common_scalar(scalar)
+    }
+}
+
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C>, T: Transcript<C, E>> TranscriptRead<C, E>
+    for FramedRead<R, C, E, T>
+{
+    fn read_point(&mut self) -> io::Result<C> {
+        let mut compressed = C::Repr::default();
+        let len = compressed.as_ref().len();
+        compressed.as_mut().copy_from_slice(self.next_bytes(len)?);
+        let point: C = Option::from(C::from_bytes(&compressed)).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof")
+        })?;
+        self.transcript.common_point(point)?;
+
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        let mut data = <C::Scalar as PrimeField>::Repr::default();
+        let len = data.as_ref().len();
+        data.as_mut().copy_from_slice(self.next_bytes(len)?);
+        let scalar: C::Scalar = Option::from(C::Scalar::from_repr(data)).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                "invalid field element encoding in proof",
+            )
+        })?;
+        self.transcript.common_scalar(scalar)?;
+
+        Ok(scalar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FramedRead, FramedWrite};
+    use crate::transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, Transcript, TranscriptRead, TranscriptReadBuffer,
+        TranscriptWrite, TranscriptWriterBuffer,
+    };
+    use ff::Field;
+    use group::{Curve, Group};
+    use halo2curves::pasta::{Eq, EqAffine, Fp};
+    use rand_core::OsRng;
+    use std::io::{self, ErrorKind};
+
+    #[test]
+    fn test_framed() {
+        let point = Eq::random(OsRng).to_affine();
+        let scalars = [Fp::random(OsRng), Fp::random(OsRng)];
+
+        let mut plain = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+        let mut framed = FramedWrite::new(
+            Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(io::sink()),
+            vec![],
+        );
+        plain.write_point(point).unwrap();
+        framed.write_point(point).unwrap();
+        plain.write_scalar(scalars[0]).unwrap();
+        framed.write_scalar(scalars[0]).unwrap();
+        let mut challenges = vec![];
+        for _ in 0..2 {
+            challenges.push(*plain.squeeze_challenge_scalar::<()>());
+            assert_eq!(
+                *framed.squeeze_challenge_scalar::<()>(),
+                challenges[challenges.len() - 1]
+            );
+        }
+        plain.write_scalar(scalars[1]).unwrap();
+        framed.write_scalar(scalars[1]).unwrap();
+        let plain = plain.finalize();
+        let proof = framed.finalize().unwrap();
+
+        // Two sections and the end of the proof around the unframed proof
+        let mut expected = vec![];
+        expected.extend_from_slice(&64u32.to_le_bytes());
+        expected.extend_from_slice(&plain[..64]);
+        expected.extend_from_slice(&32u32.to_le_bytes());
+        expected.extend_from_slice(&plain[64..]);
+        expected.extend_from_slice(&0u32.to_le_bytes());
+        assert_eq!(proof, expected);
+
+        let read = |proof| {
+            FramedRead::new(
+                Blake2bRead::<_, EqAffine, Challenge255<_>>::init(io::empty()),
+                proof,
+            )
+        };
+        let mut transcript = read(&proof[..]);
+        assert_eq!(transcript.read_point().unwrap(), point);
+        assert_eq!(transcript.read_scalar().unwrap(), scalars[0]);
+        assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), challenges[0]);
+        assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), challenges[1]);
+        assert_eq!(transcript.read_scalar().unwrap(), scalars[1]);
+        assert!(transcript.finish().unwrap().is_empty());
+
+        // Reading past the end of the proof
+        let mut transcript = read(&proof[..]);
+        transcript.read_point().unwrap();
+        for _ in 0..2 {
+            transcript.read_scalar().unwrap();
+        }
+        assert_eq!(
+            transcript.read_scalar().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+
+        // A proof cut short in a section, and one not read to the end
+        let mut transcript = read(&proof[..40]);
+        assert_eq!(
+            transcript.read_point().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+        let mut transcript = read(&proof[..]);
+        transcript.read_point().unwrap();
+        assert_eq!(
+            transcript.finish().unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+}
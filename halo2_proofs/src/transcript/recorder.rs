@@ -0,0 +1,325 @@
+//! A transcript wrapper recording everything absorbed and squeezed, for
+//! comparing Fiat-Shamir transcripts across implementations.
+
+use std::fmt::Write as _;
+use std::io;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+use group::ff::PrimeField;
+use halo2curves::{Coordinates, CurveAffine};
+
+use super::{EncodedChallenge, Transcript, TranscriptRead, TranscriptWrite};
+
+/// An operation on a transcript
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TranscriptOp {
+    /// [`Transcript::common_point`]
+    CommonPoint,
+    /// [`Transcript::common_scalar`]
+    CommonScalar,
+    /// [`TranscriptRead::read_point`]
+    ReadPoint,
+    /// [`TranscriptRead::read_scalar`]
+    ReadScalar,
+    /// [`TranscriptWrite::write_point`]
+    WritePoint,
+    /// [`TranscriptWrite::write_scalar`]
+    WriteScalar,
+    /// [`Transcript::squeeze_challenge`]
+    SqueezeChallenge,
+}
+
+impl TranscriptOp {
+    fn name(&self) -> &'static str {
+        match self {
+            TranscriptOp::CommonPoint => "common_point",
+            TranscriptOp::CommonScalar => "common_scalar",
+            TranscriptOp::ReadPoint => "read_point",
+            TranscriptOp::ReadScalar => "read_scalar",
+            TranscriptOp::WritePoint => "write_point",
+            TranscriptOp::WriteScalar => "write_scalar",
+            TranscriptOp::SqueezeChallenge => "squeeze_challenge",
+        }
+    }
+}
+
+/// A value absorbed into or squeezed from a transcript
+#[derive(Clone, Debug)]
+pub enum TranscriptValue<C: CurveAffine> {
+    /// A curve point
+    Point(C),
+    /// A scalar, or the scalar of a challenge
+    Scalar(C::Scalar),
+}
+
+/// An operation recorded by a [`TranscriptRecorder`]
+#[derive(Clone, Debug)]
+pub struct TranscriptEvent<C: CurveAffine> {
+    /// The label set with [`TranscriptRecorder::set_label`], or through its
+    /// [`TranscriptLabeler`], when the operation happened
+    pub label: Option<String>,
+    /// The operation
+    pub op: TranscriptOp,
+    /// The value absorbed or squeezed
+    pub value: TranscriptValue<C>,
+}
+
+/// Wraps a transcript and records every point and scalar absorbed into it and
+/// every challenge squeezed from it, in order. Proving or verifying with the
+/// recorder in place of the transcript and dumping the record with
+/// [`Self::to_json`] shows where two implementations of the same transcript
+/// diverge.
+///
+/// The prover labels the operations with the stage it is in when it is given
+/// [`StageLabels`](crate::plonk::StageLabels) over the [`Self::labeler`] of
+/// the recorder as its observer. The verifier
+/// reports no stages, so its operations are only labelled by
+/// [`Self::set_label`], e.g. from a custom verifier between its reads.
+#[derive(Debug)]
+pub struct TranscriptRecorder<C: CurveAffine, T> {
+    inner: T,
+    label: Arc<Mutex<Option<String>>>,
+    events: Vec<TranscriptEvent<C>>,
+    _marker: PhantomData<C>,
+}
+
+impl<C: CurveAffine, T> TranscriptRecorder<C, T> {
+    /// Wraps `inner`
+    pub fn new(inner: T) -> Self {
+        TranscriptRecorder {
+            inner,
+            label: Arc::new(Mutex::new(None)),
+            events: vec![],
+            _marker: PhantomData,
+        }
+    }
+
+    /// Labels the operations from here on, e.g. with the phase of the
+    /// protocol they belong to.
+    pub fn set_label<S: Into<String>>(&mut self, label: S) {
+        *self.label.lock().unwrap() = Some(label.into());
+    }
+
+    /// Returns a handle setting the label of the recorder while it is
+    /// borrowed elsewhere, e.g. by the prover.
+    pub fn labeler(&self) -> TranscriptLabeler {
+        TranscriptLabeler {
+            label: self.label.clone(),
+        }
+    }
+
+    /// Returns the operations recorded so far
+    pub fn events(&self) -> &[TranscriptEvent<C>] {
+        &self.events
+    }
+
+    /// Returns the wrapped transcript, e.g. to finalize it
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn record(&mut self, op: TranscriptOp, value: TranscriptValue<C>) {
+        self.events.push(TranscriptEvent {
+            label: self.label.lock().unwrap().clone(),
+            op,
+            value,
+        });
+    }
+
+    /// Returns the recorded operations as a JSON array of objects with the
+    /// fields `index`, `op`, `label` (or `null`) and `value`. Scalars and
+    /// coordinates are hex strings of their `to_repr` bytes, which is how the
+    /// transcripts in this crate absorb them; points are objects with the
+    /// fields `x` and `y`, or `null` for the identity.
+    pub fn to_json(&self) -> String {
+        fn hex(bytes: &[u8]) -> String {
+            bytes.iter().fold(String::from("\"0x"), |mut acc, byte| {
+                write!(acc, "{:02x}", byte).unwrap();
+                acc
+            }) + "\""
+        }
+
+        fn string(value: &str) -> String {
+            let mut out = String::from("\"");
+            for c in value.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+            out
+        }
+
+        let mut json = String::from("[");
+        for (index, event) in self.events.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            let value = match &event.value {
+                TranscriptValue::Point(point) => Option::from(point.coordinates()).map_or_else(
+                    || "null".to_string(),
+                    |coords: Coordinates<C>| {
+                        format!(
+                            "{{\"x\":{},\"y\":{}}}",
+                            hex(coords.x().to_repr().as_ref()),
+                            hex(coords.y().to_repr().as_ref())
+                        )
+                    },
+                ),
+                TranscriptValue::Scalar(scalar) => hex(scalar.to_repr().as_ref()),
+            };
+            write!(
+                json,
+                "\n  {{\"index\":{},\"op\":\"{}\",\"label\":{},\"value\":{}}}",
+                index,
+                event.op.name(),
+                event
+                    .label
+                    .as_deref()
+                    .map_or_else(|| "null".to_string(), string),
+                value
+            )
+            .unwrap();
+        }
+        json.push_str("\n]\n");
+        json
+    }
+
+    /// Writes [`Self::to_json`] to `writer`
+    pub fn write_json<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.to_json().as_bytes())
+    }
+}
+
+/// Sets the label of a [`TranscriptRecorder`], from
+/// [`TranscriptRecorder::labeler`]
+#[derive(Clone, Debug)]
+pub struct TranscriptLabeler {
+    label: Arc<Mutex<Option<String>>>,
+}
+
+impl TranscriptLabeler {
+    /// Labels the operations of the recorder from here on, like
+    /// [`TranscriptRecorder::set_label`]
+    pub fn set_label<S: Into<String>>(&self, label: S) {
+        *self.label.lock().unwrap() = Some(label.into());
+    }
+}
+
+impl<C: CurveAffine, E: EncodedChallenge<C>, T: Transcript<C, E>> Transcript<C, E>
+    for TranscriptRecorder<C, T>
+{
+    fn squeeze_challenge(&mut self) -> E {
+        let challenge = self.inner.squeeze_challenge();
+        self.record(
+            TranscriptOp::SqueezeChallenge,
+            TranscriptValue::Scalar(challenge.get_scalar()),
+        );
+        challenge
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        self.inner.common_point(point)?;
+        self.record(TranscriptOp::CommonPoint, TranscriptValue::Point(point));
+        Ok(())
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.inner.common_scalar(scalar)?;
+        self.record(TranscriptOp::CommonScalar, TranscriptValue::Scalar(scalar));
+        Ok(())
+    }
+}
+
+impl<C: CurveAffine, E: EncodedChallenge<C>, T: TranscriptRead<C, E>> TranscriptRead<C, E>
+    for TranscriptRecorder<C, T>
+{
+    fn read_point(&mut self) -> io::Result<C> {
+        let point = self.inner.read_point()?;
+        self.record(TranscriptOp::ReadPoint, TranscriptValue::Point(point));
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        let scalar = self.inner.read_scalar()?;
+        self.record(TranscriptOp::ReadScalar, TranscriptValue::Scalar(scalar));
+        Ok(scalar)
+    }
+}
+
+impl<C: CurveAffine, E: EncodedChallenge<C>, T: TranscriptWrite<C, E>> TranscriptWrite<C, E>
+    for TranscriptRecorder<C, T>
+{
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        self.inner.write_point(point)?;
+        self.record(TranscriptOp::WritePoint, TranscriptValue::Point(point));
+        Ok(())
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.inner.write_scalar(scalar)?;
+        self.record(TranscriptOp::WriteScalar, TranscriptValue::Scalar(scalar));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TranscriptOp, TranscriptRecorder};
+    use crate::transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, Transcript, TranscriptRead, TranscriptReadBuffer,
+        TranscriptWrite, TranscriptWriterBuffer,
+    };
+    use ff::Field;
+    use group::{Curve, Group};
+    use halo2curves::pasta::{Eq, EqAffine, Fp};
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_recorder() {
+        let point = Eq::random(OsRng).to_affine();
+        let scalar = Fp::random(OsRng);
+
+        let mut transcript =
+            TranscriptRecorder::new(Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]));
+        transcript.common_scalar(scalar).unwrap();
+        transcript.set_label("round \"1\"");
+        transcript.write_point(point).unwrap();
+        transcript.write_scalar(scalar).unwrap();
+        let challenge = *transcript.squeeze_challenge_scalar::<()>();
+        let prover = transcript.to_json();
+        let proof = transcript.into_inner().finalize();
+
+        let mut transcript = TranscriptRecorder::new(
+            Blake2bRead::<_, EqAffine, Challenge255<_>>::init(&proof[..]),
+        );
+        transcript.common_scalar(scalar).unwrap();
+        transcript.set_label("round \"1\"");
+        assert_eq!(transcript.read_point().unwrap(), point);
+        assert_eq!(transcript.read_scalar().unwrap(), scalar);
+        assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), challenge);
+
+        let ops: Vec<_> = transcript.events().iter().map(|event| event.op).collect();
+        assert_eq!(
+            ops,
+            vec![
+                TranscriptOp::CommonScalar,
+                TranscriptOp::ReadPoint,
+                TranscriptOp::ReadScalar,
+                TranscriptOp::SqueezeChallenge
+            ]
+        );
+        assert_eq!(transcript.events()[0].label, None);
+        assert_eq!(transcript.events()[3].label.as_deref(), Some("round \"1\""));
+
+        // Both sides agree on everything but the direction of the messages
+        let verifier = transcript.to_json();
+        assert_eq!(prover.replace("write_", "read_"), verifier);
+        assert!(verifier.contains("\"label\":\"round \\\"1\\\"\""));
+        assert_eq!(verifier.matches("\"index\"").count(), 4);
+    }
+}
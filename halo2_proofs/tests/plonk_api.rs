@@ -561,9 +561,66 @@ fn plonk_api() {
         >(verifier_params, pk.get_vk(), &proof[..]);
     }
 
+    fn test_plonk_api_fflonk() {
+        use halo2_proofs::plonk::{create_proof_with_options, ProverOptions};
+        use halo2_proofs::poly::commitment::Params;
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverFFLONK, VerifierFFLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2curves::bn256::Bn256;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        // The prover commits to the quotients at all points combined, which
+        // takes more powers than the circuit
+        let opening_params = ParamsKZG::<Bn256>::new(K + 3);
+        let mut params = opening_params.clone();
+        params.downsize(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        let prove = |prover| {
+            let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+            create_proof_with_options(
+                &params,
+                prover,
+                &pk,
+                &[circuit.clone(), circuit.clone()],
+                &[&[&[instance]], &[&[instance]]],
+                OsRng,
+                &mut transcript,
+                ProverOptions::new(),
+            )
+            .map(|_| transcript.finalize())
+        };
+
+        let proof =
+            prove(ProverFFLONK::new(&opening_params)).expect("proof generation should not fail");
+        verify_proof::<
+            _,
+            VerifierFFLONK<_>,
+            _,
+            Blake2bRead<_, _, Challenge255<_>>,
+            AccumulatorStrategy<_>,
+        >(params.verifier_params(), pk.get_vk(), &proof[..]);
+
+        // The parameters of the circuit are too small for the prover
+        assert_matches!(
+            prove(ProverFFLONK::new(&params)),
+            Err(Error::Transcript(e)) if e.kind() == std::io::ErrorKind::InvalidInput
+        );
+    }
+
     fn test_plonk_api_shplonk() {
+        use halo2_proofs::plonk::verify_proof_with_verifier;
         use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
-        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::multiopen::{
+            ProverSHPLONK, VerifierQueryPlan, VerifierSHPLONK,
+        };
         use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
         use halo2curves::bn256::Bn256;
 
@@ -588,6 +645,1716 @@ fn plonk_api() {
             Blake2bRead<_, _, Challenge255<_>>,
             AccumulatorStrategy<_>,
         >(verifier_params, pk.get_vk(), &proof[..]);
+
+        // Verifiers don't need the full parameters
+        let verifier_params = params.verifier_only();
+
+        verify_proof::<
+            _,
+            VerifierSHPLONK<_>,
+            _,
+            Blake2bRead<_, _, Challenge255<_>>,
+            AccumulatorStrategy<_>,
+        >(&verifier_params, pk.get_vk(), &proof[..]);
+
+        // A query plan built from the verifying key is reused by each
+        // verification
+        let (_, instance, _) = common!(Scheme);
+        let pubinputs = vec![instance];
+        let plan = VerifierQueryPlan::new(&pk.get_vk().opening_queries(2, false));
+        for _ in 0..2 {
+            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+            let strategy = verify_proof_with_verifier(
+                &verifier_params,
+                VerifierSHPLONK::with_plan(&verifier_params, &plan),
+                pk.get_vk(),
+                AccumulatorStrategy::new(&verifier_params),
+                &[&[&pubinputs[..]], &[&pubinputs[..]]],
+                &mut transcript,
+            )
+            .unwrap();
+            assert!(strategy.finalize());
+        }
+
+        // The queries of two proofs do not have the structure of one
+        let plan = VerifierQueryPlan::new(&pk.get_vk().opening_queries(1, false));
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+        assert_matches!(
+            verify_proof_with_verifier(
+                &verifier_params,
+                VerifierSHPLONK::with_plan(&verifier_params, &plan),
+                pk.get_vk(),
+                AccumulatorStrategy::new(&verifier_params),
+                &[&[&pubinputs[..]], &[&pubinputs[..]]],
+                &mut transcript,
+            ),
+            Err(Error::Opening)
+        );
+    }
+
+    fn test_plonk_api_runtime_multiopen() {
+        use halo2_proofs::plonk::{
+            create_typed_proof_with_options, verify_proof_with_verifier, ProverOptions,
+        };
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{
+            MultiopenScheme, ProverMultiopen, VerifierMultiopen,
+        };
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::transcript::PointEncoding;
+        use halo2curves::bn256::{Bn256, Fr};
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        let verify_any = |proof: &[u8]| {
+            let verifier = VerifierMultiopen::for_proof(&params, proof).unwrap();
+            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+            verify_proof_with_verifier(
+                &params,
+                verifier,
+                pk.get_vk(),
+                AccumulatorStrategy::new(&params),
+                &[&[&[instance]], &[&[instance]]],
+                &mut transcript,
+            )
+            .unwrap()
+            .finalize()
+        };
+
+        for scheme in [MultiopenScheme::Gwc, MultiopenScheme::Shplonk] {
+            let proof = create_typed_proof_with_options::<
+                _,
+                _,
+                _,
+                _,
+                Blake2bWrite<_, _, Challenge255<_>>,
+                _,
+            >(
+                &params,
+                ProverMultiopen::with_scheme(scheme, &params),
+                &pk,
+                &[circuit.clone(), circuit.clone()],
+                &[&[&[instance]], &[&[instance]]],
+                OsRng,
+                ProverOptions::new(),
+            )
+            .expect("proof generation should not fail");
+            assert_eq!(proof.scheme_id, Some(Fr::from(scheme.id() as u64)));
+            let proof = proof.to_bytes(PointEncoding::Compressed).unwrap();
+
+            let verify = |scheme| {
+                let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+                verify_proof_with_verifier(
+                    &params,
+                    VerifierMultiopen::with_scheme(scheme, &params),
+                    pk.get_vk(),
+                    AccumulatorStrategy::new(&params),
+                    &[&[&[instance]], &[&[instance]]],
+                    &mut transcript,
+                )
+                .map(|strategy| strategy.finalize())
+            };
+            assert_matches!(verify(scheme), Ok(true));
+
+            // The proof records its scheme, so the verifier of the other one
+            // rejects it up front
+            let other = match scheme {
+                MultiopenScheme::Gwc => MultiopenScheme::Shplonk,
+                MultiopenScheme::Shplonk => MultiopenScheme::Gwc,
+            };
+            assert_matches!(verify(other), Err(Error::MultiopenMismatch));
+
+            // A single entry point verifies the proofs of either scheme
+            assert_eq!(MultiopenScheme::read::<Fr>(&proof).unwrap(), scheme);
+            assert!(verify_any(&proof));
+        }
+    }
+
+    fn test_plonk_api_domain_tag() {
+        use halo2_proofs::plonk::{
+            create_proof_with_domain_tag, verify_proof_with_domain_tag, Proof, ProofLayout,
+        };
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverGWC, VerifierGWC};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::transcript::PointEncoding;
+        use halo2curves::bn256::Bn256;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof_with_domain_tag::<_, ProverGWC<_>, _, _, _, _>(
+            &params,
+            &pk,
+            b"plonk_api v1",
+            &[circuit.clone(), circuit],
+            &[&[&[instance]], &[&[instance]]],
+            OsRng,
+            &mut transcript,
+        )
+        .expect("proof generation should not fail");
+        let proof = transcript.finalize();
+
+        let verify = |domain_tag: &[u8]| {
+            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+            verify_proof_with_domain_tag::<_, VerifierGWC<_>, _, _, _>(
+                &params,
+                pk.get_vk(),
+                domain_tag,
+                AccumulatorStrategy::new(&params),
+                &[&[&[instance]], &[&[instance]]],
+                &mut transcript,
+            )
+        };
+        assert!(verify(b"plonk_api v1").unwrap().finalize());
+        assert_matches!(verify(b"plonk_api v2"), Err(Error::Transcript(_)));
+
+        // The proof is split past its tag only when the layout expects one
+        let layout = ProofLayout {
+            domain_tag: true,
+            ..ProofLayout::new(2)
+        };
+        let parsed = Proof::from_bytes(pk.get_vk(), &layout, &proof).unwrap();
+        assert!(parsed.domain_tag.is_some());
+        assert_eq!(parsed.to_bytes(PointEncoding::Compressed).unwrap(), proof);
+        assert!(Proof::from_bytes(pk.get_vk(), &ProofLayout::new(2), &proof).is_err());
+    }
+
+    fn test_plonk_api_instance_hash() {
+        use halo2_proofs::plonk::{
+            create_typed_proof_with_options, verify_proof_with_instance_hash, ProverOptions,
+        };
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverGWC, VerifierGWC};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::transcript::PointEncoding;
+        use halo2curves::bn256::{Bn256, Fr};
+        use sha3::Keccak256;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        // Hashing the instances does not change the layout of the proof
+        let proof =
+            create_typed_proof_with_options::<_, _, _, _, Blake2bWrite<_, _, Challenge255<_>>, _>(
+                &params,
+                ProverGWC::new(&params),
+                &pk,
+                &[circuit.clone(), circuit],
+                &[&[&[instance]], &[&[instance]]],
+                OsRng,
+                ProverOptions::new().with_instance_hash::<Keccak256>(),
+            )
+            .expect("proof generation should not fail");
+        assert!(proof.instance_evals.iter().all(|evals| evals.is_empty()));
+        let proof = proof.to_bytes(PointEncoding::Compressed).unwrap();
+
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+        let strategy = verify_proof_with_instance_hash::<_, VerifierGWC<_>, _, _, _, Keccak256>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&[&[instance]], &[&[instance]]],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+
+        // The proof does not verify for other instances, nor when the
+        // verifier absorbs the instance values themselves
+        for (hashed, other) in [(true, instance + Fr::one()), (false, instance)] {
+            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+            let instances: &[&[&[Fr]]] = &[&[&[other]], &[&[other]]];
+            let result = if hashed {
+                verify_proof_with_instance_hash::<_, VerifierGWC<_>, _, _, _, Keccak256>(
+                    &params,
+                    pk.get_vk(),
+                    AccumulatorStrategy::new(&params),
+                    instances,
+                    &mut transcript,
+                )
+            } else {
+                verify_plonk_proof::<_, VerifierGWC<_>, _, _, _>(
+                    &params,
+                    pk.get_vk(),
+                    AccumulatorStrategy::new(&params),
+                    instances,
+                    &mut transcript,
+                )
+            };
+            assert!(!result.map_or(false, |strategy| strategy.finalize()));
+        }
+    }
+
+    fn test_plonk_api_witness() {
+        use halo2_proofs::plonk::{create_proof_from_witness, generate_witness, Witness};
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::SerdeFormat;
+        use halo2curves::bn256::{Bn256, Fr};
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        let witness = generate_witness(pk.get_vk(), &circuit, &[&[instance]])
+            .expect("witness generation should not fail");
+        let mut bytes = vec![];
+        witness.write(&mut bytes, SerdeFormat::RawBytes).unwrap();
+        let read = Witness::<Fr>::read(&mut &bytes[..], SerdeFormat::RawBytes).unwrap();
+        assert_eq!(read.advice().len(), witness.advice().len());
+
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof_from_witness::<_, ProverSHPLONK<_>, _, _, _>(
+            &params,
+            &pk,
+            vec![witness, read],
+            &[&[&[instance]], &[&[instance]]],
+            OsRng,
+            &mut transcript,
+        )
+        .expect("proof generation should not fail");
+        let proof = transcript.finalize();
+
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+        let strategy = verify_plonk_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&[&[instance]], &[&[instance]]],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+
+        // A witness is needed for each proof
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        let witness = generate_witness(pk.get_vk(), &circuit, &[&[instance]]).unwrap();
+        let result = create_proof_from_witness::<_, ProverSHPLONK<_>, _, _, _>(
+            &params,
+            &pk,
+            vec![witness],
+            &[&[&[instance]], &[&[instance]]],
+            OsRng,
+            &mut transcript,
+        );
+        assert_matches!(result, Err(Error::InvalidInstances));
+    }
+
+    fn test_plonk_api_advice() {
+        use halo2_proofs::plonk::{create_proof_with_advice, generate_witness};
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2curves::bn256::Bn256;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        // Advice values produced elsewhere, here by synthesizing the circuit
+        let witness = generate_witness(pk.get_vk(), &circuit, &[&[instance]]).unwrap();
+        let usable_rows = (1 << K) - (pk.get_vk().cs().blinding_factors() + 1);
+        let columns = witness
+            .advice()
+            .iter()
+            .map(|column| &column[..usable_rows])
+            .collect::<Vec<_>>();
+        let full_columns = witness
+            .advice()
+            .iter()
+            .map(|column| &column[..])
+            .collect::<Vec<_>>();
+
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof_with_advice::<_, ProverSHPLONK<_>, _, _, _>(
+            &params,
+            &pk,
+            &[&columns],
+            &[&[&[instance]]],
+            OsRng,
+            &mut transcript,
+        )
+        .expect("proof generation should not fail");
+        let proof = transcript.finalize();
+
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+        let strategy = verify_plonk_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&[&[instance]]],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+
+        // Columns must fit in the usable rows, and all columns are needed
+        for (columns, expected) in [
+            (
+                &full_columns[..],
+                Error::NotEnoughRowsAvailable { current_k: K },
+            ),
+            (&columns[1..], Error::InvalidWitness),
+        ] {
+            let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+            let result = create_proof_with_advice::<_, ProverSHPLONK<_>, _, _, _>(
+                &params,
+                &pk,
+                &[columns],
+                &[&[&[instance]]],
+                OsRng,
+                &mut transcript,
+            );
+            assert_eq!(format!("{}", result.unwrap_err()), format!("{}", expected));
+        }
+    }
+
+    fn test_plonk_api_without_blinding() {
+        use halo2_proofs::plonk::create_proof_without_blinding;
+        use halo2_proofs::poly::ipa::commitment::{IPACommitmentScheme, ParamsIPA};
+        use halo2_proofs::poly::ipa::multiopen::{ProverIPA, VerifierIPA};
+        use halo2_proofs::poly::ipa::strategy::AccumulatorStrategy;
+        use halo2curves::pasta::EqAffine;
+
+        type Scheme = IPACommitmentScheme<EqAffine>;
+
+        let params = ParamsIPA::<EqAffine>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        let prove = || {
+            let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+            create_proof_without_blinding::<_, ProverIPA<_>, _, _, _>(
+                &params,
+                &pk,
+                &[circuit.clone()],
+                &[&[&[instance]]],
+                &mut transcript,
+            )
+            .expect("proof generation should not fail");
+            transcript.finalize()
+        };
+
+        // Proving is deterministic
+        let proof = prove();
+        assert_eq!(proof, prove());
+
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+        let strategy = verify_plonk_proof::<_, VerifierIPA<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&[&[instance]]],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+    }
+
+    fn test_plonk_api_typed_proof() {
+        use halo2_proofs::plonk::{create_typed_proof, Proof, ProofLayout};
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverGWC, VerifierGWC};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::transcript::PointEncoding;
+        use halo2curves::bn256::Bn256;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        let proof =
+            create_typed_proof::<_, ProverGWC<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>, _>(
+                &params,
+                &pk,
+                &[circuit.clone(), circuit],
+                &[&[&[instance]], &[&[instance]]],
+                OsRng,
+            )
+            .expect("proof generation should not fail");
+        assert_eq!(proof.advice_commitments.len(), 1);
+        assert_eq!(proof.advice_commitments[0].len(), 2);
+        assert_eq!(proof.lookup_evals[1].len(), 1);
+        assert!(proof.instance_evals.iter().all(|evals| evals.is_empty()));
+
+        let bytes = proof.to_bytes(PointEncoding::Compressed).unwrap();
+        let parsed = Proof::from_bytes(pk.get_vk(), &ProofLayout::new(2), &bytes).unwrap();
+        assert_eq!(parsed, proof);
+
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&bytes[..]);
+        let strategy = verify_plonk_proof::<_, VerifierGWC<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&[&[instance]], &[&[instance]]],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+    }
+
+    fn test_plonk_api_observer() {
+        use halo2_proofs::plonk::{
+            create_proof_with_options, ProverObserver, ProverOptions, ProverStage, StageStats,
+        };
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::ProverSHPLONK;
+        use halo2curves::bn256::Bn256;
+
+        #[derive(Default)]
+        struct Recorder {
+            started: Vec<ProverStage>,
+            finished: Vec<(ProverStage, StageStats)>,
+        }
+
+        impl ProverObserver for Recorder {
+            fn stage_started(&mut self, stage: ProverStage) {
+                self.started.push(stage);
+            }
+
+            fn stage_finished(&mut self, stage: ProverStage, stats: &StageStats) {
+                self.finished.push((stage, *stats));
+            }
+        }
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        let mut observer = Recorder::default();
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof_with_options(
+            &params,
+            ProverSHPLONK::new(&params),
+            &pk,
+            &[circuit],
+            &[&[&[instance]]],
+            OsRng,
+            &mut transcript,
+            ProverOptions::new().with_observer(&mut observer),
+        )
+        .expect("proof generation should not fail");
+
+        assert_eq!(
+            observer.started,
+            vec![
+                ProverStage::Instance,
+                ProverStage::Advice,
+                ProverStage::LookupPermuted,
+                ProverStage::Products,
+                ProverStage::Vanishing,
+                ProverStage::Evaluations,
+                ProverStage::Opening,
+            ]
+        );
+        let stages = observer
+            .finished
+            .iter()
+            .map(|(stage, _)| *stage)
+            .collect::<Vec<_>>();
+        assert_eq!(stages, observer.started);
+
+        // Each advice column is committed to with one multi-exponentiation
+        let num_advice_columns = pk.get_vk().cs().num_advice_columns();
+        let (_, advice) = observer.finished[1];
+        assert!(advice.msms >= num_advice_columns);
+        assert!(advice.committed_bytes >= num_advice_columns * (1 << K) * 32);
+        let (_, vanishing) = observer.finished[4];
+        assert!(vanishing.ffts > 0);
+    }
+
+    fn test_plonk_api_options() {
+        use halo2_proofs::plonk::{create_proof_with_options, ProverOptions};
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2curves::bn256::Bn256;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        // Spill every advice column
+        let options = ProverOptions::new().with_memory_limit(0, std::env::temp_dir());
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof_with_options(
+            &params,
+            ProverSHPLONK::new(&params),
+            &pk,
+            &[circuit.clone(), circuit],
+            &[&[&[instance]], &[&[instance]]],
+            OsRng,
+            &mut transcript,
+            options,
+        )
+        .expect("proof generation should not fail");
+        let proof = transcript.finalize();
+
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+        let strategy = verify_plonk_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&[&[instance]], &[&[instance]]],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+    }
+
+    fn test_plonk_api_parallel_prover() {
+        use halo2_proofs::plonk::ParallelProver;
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2curves::bn256::Bn256;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        let prover = ParallelProver::<Scheme, MyCircuit<_>>::new(&params, &pk);
+        let proofs = prover
+            .create_proofs::<ProverSHPLONK<_>, _, _, _, _>(
+                &[circuit.clone(), circuit.clone(), circuit],
+                &[&[&[instance]], &[&[instance]], &[&[instance]]],
+                |_| OsRng,
+                Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]),
+            )
+            .expect("proof generation should not fail");
+        assert_eq!(proofs.len(), 3);
+
+        for proof in proofs {
+            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+            let strategy = verify_plonk_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+                &params,
+                pk.get_vk(),
+                AccumulatorStrategy::new(&params),
+                &[&[&[instance]]],
+                &mut transcript,
+            )
+            .unwrap();
+            assert!(strategy.finalize());
+        }
+    }
+
+    fn test_plonk_api_cancellation() {
+        use halo2_proofs::plonk::{create_proof_with_options, CancellationToken, ProverOptions};
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2curves::bn256::Bn256;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        // Cancellation combines with the other options
+        let prove = |cancellation: &CancellationToken| {
+            let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+            create_proof_with_options(
+                &params,
+                ProverSHPLONK::new(&params),
+                &pk,
+                &[circuit.clone()],
+                &[&[&[instance]]],
+                OsRng,
+                &mut transcript,
+                ProverOptions::new()
+                    .with_cancellation(cancellation)
+                    .with_memory_limit(0, std::env::temp_dir()),
+            )
+            .map(|_| transcript.finalize())
+        };
+
+        let cancellation = CancellationToken::new();
+        let proof = prove(&cancellation).expect("proof generation should not fail");
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+        let strategy = verify_plonk_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&[&[instance]]],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+
+        // Clones share the state of the token
+        cancellation.clone().cancel();
+        assert!(cancellation.is_cancelled());
+        assert_matches!(prove(&cancellation), Err(Error::Cancelled));
+    }
+
+    fn test_plonk_api_challenges() {
+        use halo2_proofs::plonk::create_proof_with_challenges;
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::transcript::{TranscriptOp, TranscriptRecorder, TranscriptValue};
+        use halo2curves::bn256::Bn256;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        let challenges = create_proof_with_challenges::<_, ProverSHPLONK<_>, _, _, _, _>(
+            &params,
+            &pk,
+            &[circuit],
+            &[&[&[instance]]],
+            OsRng,
+            &mut transcript,
+        )
+        .expect("proof generation should not fail");
+        let proof = transcript.finalize();
+        assert!(challenges.challenges.is_empty());
+        assert_eq!(challenges.multiopen.len(), 3);
+
+        // The verifier squeezes the same challenges
+        let mut transcript =
+            TranscriptRecorder::new(Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]));
+        let strategy = verify_plonk_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&[&[instance]]],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+        let squeezed: Vec<_> = transcript
+            .events()
+            .iter()
+            .filter_map(|event| match (event.op, &event.value) {
+                (TranscriptOp::SqueezeChallenge, TranscriptValue::Scalar(challenge)) => {
+                    Some(*challenge)
+                }
+                _ => None,
+            })
+            .collect();
+        let mut expected = vec![
+            challenges.theta,
+            challenges.beta,
+            challenges.gamma,
+            challenges.y,
+            challenges.x,
+        ];
+        expected.extend(challenges.multiopen);
+        assert_eq!(squeezed, expected);
+    }
+
+    #[cfg(feature = "timings")]
+    fn test_plonk_api_timings() {
+        use halo2_proofs::plonk::{
+            create_proof_with_options, ProverOptions, ProverStage, ProverTimings,
+        };
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::ProverSHPLONK;
+        use halo2curves::bn256::Bn256;
+        use std::time::Duration;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        let mut timings = ProverTimings::new();
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof_with_options(
+            &params,
+            ProverSHPLONK::new(&params),
+            &pk,
+            &[circuit],
+            &[&[&[instance]]],
+            OsRng,
+            &mut transcript,
+            ProverOptions::new().with_observer(&mut timings),
+        )
+        .expect("proof generation should not fail");
+
+        assert_eq!(timings.stages.len(), 7);
+        assert!(timings.stage(ProverStage::Vanishing) > Duration::ZERO);
+        assert!(timings.msm_time() > Duration::ZERO);
+        assert!(timings.fft_time() > Duration::ZERO);
+        assert!(timings.total() >= timings.stage(ProverStage::Opening));
+        assert_eq!(timings.to_string().lines().count(), 9);
+    }
+
+    #[cfg(feature = "mmap")]
+    fn test_plonk_api_mmap_params() {
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::mmap::MmapParamsKZG;
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2curves::bn256::Bn256;
+
+        type Scheme = KZGCommitmentScheme<Bn256, MmapParamsKZG<Bn256>>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let mapped = MmapParamsKZG::from_params(&params).unwrap();
+
+        // Keys and proofs are computed from the mapped bases directly
+        let pk = keygen::<Scheme>(&mapped);
+        let proof =
+            create_proof::<Scheme, ProverSHPLONK<_, _>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+                OsRng, &mapped, &pk,
+            );
+
+        // Verifiers use the default scheme, with either parameters
+        verify_proof::<
+            KZGCommitmentScheme<_>,
+            VerifierSHPLONK<_>,
+            _,
+            Blake2bRead<_, _, Challenge255<_>>,
+            AccumulatorStrategy<_>,
+        >(mapped.verifier_params(), pk.get_vk(), &proof[..]);
+        verify_proof::<
+            KZGCommitmentScheme<_>,
+            VerifierSHPLONK<_>,
+            _,
+            Blake2bRead<_, _, Challenge255<_>>,
+            AccumulatorStrategy<_>,
+        >(params.verifier_params(), pk.get_vk(), &proof[..]);
+    }
+
+    fn test_plonk_api_committed_instances() {
+        use halo2_proofs::plonk::{
+            commit_instances, create_proof_with_options, create_typed_proof_with_options,
+            verify_proof_with_instance_commitments, Instance, ProverOptions, Selector,
+        };
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::transcript::PointEncoding;
+        use halo2curves::bn256::{Bn256, Fr, G1Affine};
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let pk = keygen::<Scheme>(&params);
+        let (a, instance, lookup_table) = common!(Scheme);
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            lookup_table,
+        };
+
+        // The proof opens the instance column, although SHPLONK does not
+        // query it
+        let proof =
+            create_typed_proof_with_options::<_, _, _, _, Blake2bWrite<_, _, Challenge255<_>>, _>(
+                &params,
+                ProverSHPLONK::new(&params),
+                &pk,
+                &[circuit],
+                &[&[&[instance]]],
+                OsRng,
+                ProverOptions::new().with_committed_instances(),
+            )
+            .expect("proof generation should not fail");
+        assert_eq!(proof.instance_evals.len(), 1);
+        assert!(!proof.instance_evals[0].is_empty());
+        let proof = proof.to_bytes(PointEncoding::Compressed).unwrap();
+
+        let verify = |commitments: &[_]| {
+            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+            verify_proof_with_instance_commitments::<_, VerifierSHPLONK<_>, _, _, _>(
+                &params,
+                pk.get_vk(),
+                AccumulatorStrategy::new(&params),
+                &[commitments],
+                &mut transcript,
+            )
+            .map(|strategy| strategy.finalize())
+        };
+        let commitments = commit_instances(&params, pk.get_vk(), &[&[instance]]).unwrap();
+        assert_matches!(verify(&commitments), Ok(true));
+
+        // The proof does not verify against the commitments to other instances
+        let other = commit_instances(&params, pk.get_vk(), &[&[instance + Fr::one()]]).unwrap();
+        assert!(!matches!(verify(&other), Ok(true)));
+        assert_matches!(verify(&[]), Err(Error::InvalidInstances));
+
+        // Verifier-only parameters cannot commit to the instances
+        assert_matches!(
+            commit_instances(&params.verifier_only(), pk.get_vk(), &[&[instance]]),
+            Err(Error::VerifierOnlyParams)
+        );
+
+        // An all-zero instance column commits to the identity under KZG
+        #[derive(Clone, Default)]
+        struct CopyCircuit {
+            value: Fr,
+        }
+
+        impl Circuit<Fr> for CopyCircuit {
+            type Config = (Selector, Column<Advice>, Column<Instance>);
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                Self::default()
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+                let s = meta.selector();
+                let a = meta.advice_column();
+                let i = meta.instance_column();
+                meta.create_gate("copy", |meta| {
+                    let s = meta.query_selector(s);
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let i = meta.query_instance(i, Rotation::cur());
+                    vec![s * (a - i)]
+                });
+                (s, a, i)
+            }
+
+            fn synthesize(
+                &self,
+                (s, a, _): Self::Config,
+                mut layouter: impl Layouter<Fr>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "copy",
+                    |mut region| {
+                        s.enable(&mut region, 0)?;
+                        region.assign_advice(|| "a", a, 0, || Value::known(self.value))?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let circuit = CopyCircuit { value: Fr::zero() };
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let pk = keygen_pk(&params, vk, &circuit).unwrap();
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof_with_options(
+            &params,
+            ProverSHPLONK::new(&params),
+            &pk,
+            &[circuit],
+            &[&[&[Fr::zero()]]],
+            OsRng,
+            &mut transcript,
+            ProverOptions::new().with_committed_instances(),
+        )
+        .expect("proof generation should not fail");
+        let proof = transcript.finalize();
+
+        let commitments = commit_instances(&params, pk.get_vk(), &[&[Fr::zero()]]).unwrap();
+        assert_eq!(commitments, vec![G1Affine::default()]);
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+        let strategy = verify_proof_with_instance_commitments::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&commitments],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+    }
+
+    fn test_plonk_api_log_derivative_lookups() {
+        use halo2_proofs::plonk::{create_typed_proof, LookupArgument, Proof, ProofLayout};
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::transcript::PointEncoding;
+        use halo2curves::bn256::{Bn256, Fr, G1Affine};
+
+        #[derive(Clone)]
+        struct LookupCircuit<const LOG_DERIVATIVE: bool> {
+            values: Vec<(u64, u64)>,
+        }
+
+        impl<const LOG_DERIVATIVE: bool> Circuit<Fr> for LookupCircuit<LOG_DERIVATIVE> {
+            type Config = (Column<Advice>, Column<Advice>, TableColumn, TableColumn);
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                Self { values: vec![] }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+                if LOG_DERIVATIVE {
+                    meta.set_lookup_argument(LookupArgument::LogDerivative);
+                }
+                let (a, b) = (meta.advice_column(), meta.advice_column());
+                let (x, square) = (meta.lookup_table_column(), meta.lookup_table_column());
+                meta.lookup("square", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    vec![(a, x), (b, square)]
+                });
+                meta.lookup("range", |meta| {
+                    vec![(meta.query_advice(a, Rotation::cur()), x)]
+                });
+                (a, b, x, square)
+            }
+
+            fn synthesize(
+                &self,
+                (a, b, x, square): Self::Config,
+                mut layouter: impl Layouter<Fr>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "squares",
+                    |mut table| {
+                        for i in 0..8 {
+                            table.assign_cell(|| "x", x, i, || Value::known(Fr::from(i as u64)))?;
+                            table.assign_cell(
+                                || "square",
+                                square,
+                                i,
+                                || Value::known(Fr::from((i * i) as u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+                layouter.assign_region(
+                    || "values",
+                    |mut region| {
+                        for (row, (x, y)) in self.values.iter().enumerate() {
+                            region.assign_advice(|| "a", a, row, || Value::known(Fr::from(*x)))?;
+                            region.assign_advice(|| "b", b, row, || Value::known(Fr::from(*y)))?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        fn prove<const LOG_DERIVATIVE: bool>(
+            params: &ParamsKZG<Bn256>,
+            values: &[(u64, u64)],
+        ) -> (ProvingKey<G1Affine>, Result<Proof<G1Affine>, Error>) {
+            let circuit = LookupCircuit::<LOG_DERIVATIVE> {
+                values: values.to_vec(),
+            };
+            let vk = keygen_vk(params, &circuit.without_witnesses()).unwrap();
+            let pk = keygen_pk(params, vk, &circuit.without_witnesses()).unwrap();
+            let proof = create_typed_proof::<
+                KZGCommitmentScheme<Bn256>,
+                ProverSHPLONK<_>,
+                _,
+                _,
+                Blake2bWrite<_, _, Challenge255<_>>,
+                _,
+            >(params, &pk, &[circuit], &[&[]], OsRng);
+            (pk, proof)
+        }
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let values = [(3, 9), (5, 25), (3, 9), (7, 49)];
+        let (pk, proof) = prove::<true>(&params, &values);
+        let proof = proof.expect("proof generation should not fail");
+        assert!(proof.lookup_permuted_commitments[0].is_empty());
+        assert_eq!(proof.lookup_multiplicities_commitments[0].len(), 2);
+        assert!(proof.lookup_evals[0].iter().all(|evals| evals.len() == 3));
+
+        let bytes = proof.to_bytes(PointEncoding::Compressed).unwrap();
+        let parsed = Proof::from_bytes(pk.get_vk(), &ProofLayout::new(1), &bytes).unwrap();
+        assert_eq!(parsed, proof);
+
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&bytes[..]);
+        let strategy = verify_plonk_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&[]],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+
+        // Each lookup commits to one column and sends two evaluations fewer
+        // than with the permutation argument
+        let (_, permutation_proof) = prove::<false>(&params, &values);
+        let permutation_bytes = permutation_proof
+            .unwrap()
+            .to_bytes(PointEncoding::Compressed)
+            .unwrap();
+        assert_eq!(permutation_bytes.len() - bytes.len(), 2 * 3 * 32);
+
+        // An input missing from the table
+        let (_, proof) = prove::<true>(&params, &[(3, 10)]);
+        assert_matches!(proof, Err(Error::ConstraintSystemFailure));
+    }
+
+    fn test_plonk_api_batched_lookups() {
+        use halo2_proofs::plonk::{create_typed_proof, LookupArgument, Proof, ProofLayout};
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::transcript::PointEncoding;
+        use halo2curves::bn256::{Bn256, Fr, G1Affine};
+
+        #[derive(Clone)]
+        struct RangeCircuit<const BATCH: bool> {
+            values: Vec<[u64; 3]>,
+        }
+
+        impl<const BATCH: bool> Circuit<Fr> for RangeCircuit<BATCH> {
+            type Config = ([Column<Advice>; 3], TableColumn);
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                Self { values: vec![] }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+                meta.set_lookup_argument(LookupArgument::LogDerivative);
+                meta.set_lookup_batching(BATCH);
+                // Room for two of the lookups in a batch
+                meta.set_minimum_degree(5);
+                let advice = [(); 3].map(|_| meta.advice_column());
+                let table = meta.lookup_table_column();
+                for column in advice {
+                    meta.lookup("range", |meta| {
+                        vec![(meta.query_advice(column, Rotation::cur()), table)]
+                    });
+                }
+                (advice, table)
+            }
+
+            fn synthesize(
+                &self,
+                (advice, table): Self::Config,
+                mut layouter: impl Layouter<Fr>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "range",
+                    |mut t| {
+                        for i in 0..8 {
+                            t.assign_cell(|| "x", table, i, || Value::known(Fr::from(i as u64)))?;
+                        }
+                        Ok(())
+                    },
+                )?;
+                layouter.assign_region(
+                    || "values",
+                    |mut region| {
+                        for (row, values) in self.values.iter().enumerate() {
+                            for (column, value) in advice.iter().zip(values.iter()) {
+                                region.assign_advice(
+                                    || "value",
+                                    *column,
+                                    row,
+                                    || Value::known(Fr::from(*value)),
+                                )?;
+                            }
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        fn prove<const BATCH: bool>(
+            params: &ParamsKZG<Bn256>,
+            values: &[[u64; 3]],
+        ) -> (ProvingKey<G1Affine>, Result<Proof<G1Affine>, Error>) {
+            let circuit = RangeCircuit::<BATCH> {
+                values: values.to_vec(),
+            };
+            let vk = keygen_vk(params, &circuit.without_witnesses()).unwrap();
+            let pk = keygen_pk(params, vk, &circuit.without_witnesses()).unwrap();
+            let proof = create_typed_proof::<
+                KZGCommitmentScheme<Bn256>,
+                ProverSHPLONK<_>,
+                _,
+                _,
+                Blake2bWrite<_, _, Challenge255<_>>,
+                _,
+            >(params, &pk, &[circuit], &[&[]], OsRng);
+            (pk, proof)
+        }
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let values = [[1, 2, 3], [7, 7, 0], [4, 1, 6]];
+        let (pk, proof) = prove::<true>(&params, &values);
+        let proof = proof.expect("proof generation should not fail");
+        // The first two lookups are batched, the third does not fit
+        assert_eq!(proof.lookup_multiplicities_commitments[0].len(), 2);
+
+        let bytes = proof.to_bytes(PointEncoding::Compressed).unwrap();
+        let parsed = Proof::from_bytes(pk.get_vk(), &ProofLayout::new(1), &bytes).unwrap();
+        assert_eq!(parsed, proof);
+
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&bytes[..]);
+        let strategy = verify_plonk_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&[]],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+
+        // The batched lookup saves two commitments and three evaluations
+        let (_, unbatched_proof) = prove::<false>(&params, &values);
+        let unbatched_bytes = unbatched_proof
+            .unwrap()
+            .to_bytes(PointEncoding::Compressed)
+            .unwrap();
+        assert_eq!(unbatched_bytes.len() - bytes.len(), (2 + 3) * 32);
+
+        // An input of a batched lookup missing from the table
+        let (_, proof) = prove::<true>(&params, &[[1, 9, 3]]);
+        assert_matches!(proof, Err(Error::ConstraintSystemFailure));
+    }
+
+    fn test_plonk_api_shuffles() {
+        use halo2_proofs::plonk::{create_typed_proof, Proof, ProofLayout, Selector};
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::transcript::PointEncoding;
+        use halo2curves::bn256::{Bn256, Fr, G1Affine};
+
+        #[derive(Clone)]
+        struct ShuffleCircuit {
+            original: Vec<(u64, u64)>,
+            shuffled: Vec<(u64, u64)>,
+        }
+
+        impl Circuit<Fr> for ShuffleCircuit {
+            type Config = (Selector, [Column<Advice>; 4]);
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    original: vec![],
+                    shuffled: vec![],
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+                let q = meta.complex_selector();
+                let columns = [(); 4].map(|_| meta.advice_column());
+                meta.shuffle("pairs", |meta| {
+                    let q = meta.query_selector(q);
+                    let [a, b, c, d] = columns
+                        .map(|column| q.clone() * meta.query_advice(column, Rotation::cur()));
+                    vec![(a, c), (b, d)]
+                });
+                (q, columns)
+            }
+
+            fn synthesize(
+                &self,
+                (q, [a, b, c, d]): Self::Config,
+                mut layouter: impl Layouter<Fr>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "pairs",
+                    |mut region| {
+                        for (row, ((x, y), (z, w))) in
+                            self.original.iter().zip(self.shuffled.iter()).enumerate()
+                        {
+                            q.enable(&mut region, row)?;
+                            for (column, value) in [(a, x), (b, y), (c, z), (d, w)] {
+                                region.assign_advice(
+                                    || "value",
+                                    column,
+                                    row,
+                                    || Value::known(Fr::from(*value)),
+                                )?;
+                            }
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        fn prove(
+            params: &ParamsKZG<Bn256>,
+            shuffled: &[(u64, u64)],
+        ) -> (ProvingKey<G1Affine>, Result<Proof<G1Affine>, Error>) {
+            let circuit = ShuffleCircuit {
+                original: vec![(1, 2), (3, 4), (5, 6), (1, 2)],
+                shuffled: shuffled.to_vec(),
+            };
+            let vk = keygen_vk(params, &circuit.without_witnesses()).unwrap();
+            let pk = keygen_pk(params, vk, &circuit.without_witnesses()).unwrap();
+            let proof = create_typed_proof::<
+                KZGCommitmentScheme<Bn256>,
+                ProverSHPLONK<_>,
+                _,
+                _,
+                Blake2bWrite<_, _, Challenge255<_>>,
+                _,
+            >(params, &pk, &[circuit], &[&[]], OsRng);
+            (pk, proof)
+        }
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let (pk, proof) = prove(&params, &[(5, 6), (1, 2), (1, 2), (3, 4)]);
+        let proof = proof.expect("proof generation should not fail");
+        assert_eq!(proof.shuffle_product_commitments[0].len(), 1);
+        assert_eq!(proof.shuffle_evals[0].len(), 1);
+        assert_eq!(proof.shuffle_evals[0][0].len(), 2);
+
+        let bytes = proof.to_bytes(PointEncoding::Compressed).unwrap();
+        let parsed = Proof::from_bytes(pk.get_vk(), &ProofLayout::new(1), &bytes).unwrap();
+        assert_eq!(parsed, proof);
+
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&bytes[..]);
+        let strategy = verify_plonk_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&[]],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+
+        // The pairs are permuted, but each pair must stay together
+        let (_, proof) = prove(&params, &[(5, 6), (1, 4), (1, 2), (3, 2)]);
+        assert_matches!(proof, Err(Error::ConstraintSystemFailure));
+    }
+
+    fn test_plonk_api_uncompressed_selectors() {
+        use halo2_proofs::plonk::{
+            create_typed_proof, keygen_vk_with_options, Expression, KeygenOptions, Selector,
+        };
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::transcript::PointEncoding;
+        use halo2_proofs::SerdeFormat;
+        use halo2curves::bn256::{Bn256, Fr};
+        use halo2curves::serde::SerdeObject;
+
+        #[derive(Clone, Default)]
+        struct SelectorCircuit {
+            values: Vec<u64>,
+        }
+
+        impl Circuit<Fr> for SelectorCircuit {
+            type Config = ([Selector; 3], Column<Advice>);
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                Self::default()
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+                // Leave room to compress the selectors into a single column
+                meta.set_minimum_degree(4);
+                let selectors = [(); 3].map(|_| meta.selector());
+                let a = meta.advice_column();
+                // The value equals 1, 2 or 3 on the rows of each selector
+                for (value, selector) in selectors.iter().enumerate() {
+                    meta.create_gate("value", |meta| {
+                        let s = meta.query_selector(*selector);
+                        let a = meta.query_advice(a, Rotation::cur());
+                        vec![s * (a - Expression::Constant(Fr::from(value as u64 + 1)))]
+                    });
+                }
+                (selectors, a)
+            }
+
+            fn synthesize(
+                &self,
+                (selectors, a): Self::Config,
+                mut layouter: impl Layouter<Fr>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "values",
+                    |mut region| {
+                        for (row, value) in self.values.iter().enumerate() {
+                            selectors[*value as usize - 1].enable(&mut region, row)?;
+                            region.assign_advice(
+                                || "a",
+                                a,
+                                row,
+                                || Value::known(Fr::from(*value)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let circuit = SelectorCircuit {
+            values: vec![1, 3, 2, 3],
+        };
+        let compressed_vk = keygen_vk(&params, &circuit).unwrap();
+        assert!(compressed_vk.compress_selectors());
+        let vk = keygen_vk_with_options(
+            &params,
+            &circuit,
+            KeygenOptions::new().without_selector_compression(),
+        )
+        .unwrap();
+        assert!(!vk.compress_selectors());
+        // A fixed column for each selector, in place of the compressed ones
+        assert_eq!(vk.fixed_commitments().len(), 3);
+        assert!(compressed_vk.fixed_commitments().len() < 3);
+        assert_ne!(
+            format!("{:?}", vk.pinned()),
+            format!("{:?}", compressed_vk.pinned())
+        );
+
+        // The choice survives serialization
+        let vk_bytes = vk.to_bytes(SerdeFormat::RawBytes);
+        let vk =
+            VerifyingKey::from_bytes::<SelectorCircuit>(&vk_bytes, SerdeFormat::RawBytes).unwrap();
+        assert!(!vk.compress_selectors());
+        assert_eq!(vk.to_bytes(SerdeFormat::RawBytes), vk_bytes);
+
+        // Keys with compressed selectors keep the layout they had before
+        // the option existed: `k`, the fixed commitments, the permutation
+        // commitments and the selectors
+        let compressed_bytes = compressed_vk.to_bytes(SerdeFormat::RawBytes);
+        let mut baseline = vec![];
+        baseline.extend_from_slice(&K.to_be_bytes());
+        let fixed_commitments = compressed_vk.fixed_commitments();
+        baseline.extend_from_slice(&(fixed_commitments.len() as u32).to_be_bytes());
+        for commitment in fixed_commitments
+            .iter()
+            .chain(compressed_vk.permutation().commitments())
+        {
+            commitment.write_raw(&mut baseline).unwrap();
+        }
+        let selectors = &compressed_bytes[baseline.len()..];
+        assert_eq!(selectors.len(), 3 * ((1 << K) / 8));
+        baseline.extend_from_slice(selectors);
+        assert_eq!(compressed_bytes, baseline);
+        let read =
+            VerifyingKey::from_bytes::<SelectorCircuit>(&baseline, SerdeFormat::RawBytes).unwrap();
+        assert!(read.compress_selectors());
+        assert_eq!(
+            format!("{:?}", read.pinned()),
+            format!("{:?}", compressed_vk.pinned())
+        );
+
+        let pk = keygen_pk(&params, vk, &circuit).unwrap();
+        let proof = create_typed_proof::<
+            KZGCommitmentScheme<Bn256>,
+            ProverSHPLONK<_>,
+            _,
+            _,
+            Blake2bWrite<_, _, Challenge255<_>>,
+            _,
+        >(&params, &pk, &[circuit], &[&[]], OsRng)
+        .expect("proof generation should not fail");
+        let bytes = proof.to_bytes(PointEncoding::Compressed).unwrap();
+
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&bytes[..]);
+        let strategy = verify_plonk_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            AccumulatorStrategy::new(&params),
+            &[&[]],
+            &mut transcript,
+        )
+        .unwrap();
+        assert!(strategy.finalize());
+    }
+
+    fn test_plonk_api_combined_keygen() {
+        use halo2_proofs::plonk::{keygen as keygen_both, KeygenOptions};
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::SerdeFormat;
+        use halo2curves::bn256::Bn256;
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let (_, _, lookup_table) = common!(Scheme);
+        let empty_circuit = MyCircuit {
+            a: Value::unknown(),
+            lookup_table,
+        };
+
+        // The keys are the same as those generated separately
+        let (vk, pk) = keygen_both(&params, &empty_circuit, KeygenOptions::new())
+            .expect("keygen should not fail");
+        let separate_pk = keygen::<Scheme>(&params);
+        assert_eq!(
+            vk.to_bytes(SerdeFormat::RawBytes),
+            separate_pk.get_vk().to_bytes(SerdeFormat::RawBytes)
+        );
+        assert_eq!(
+            format!("{:?}", vk.pinned()),
+            format!("{:?}", separate_pk.get_vk().pinned())
+        );
+        assert_eq!(
+            pk.to_bytes(SerdeFormat::RawBytes),
+            separate_pk.to_bytes(SerdeFormat::RawBytes)
+        );
+    }
+
+    fn test_plonk_api_custom_coset() {
+        use halo2_proofs::plonk::{keygen as keygen_both, keygen_vk_with_options, KeygenOptions};
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::SerdeFormat;
+        use halo2curves::bn256::{Bn256, Fr};
+
+        type Scheme = KZGCommitmentScheme<Bn256>;
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let (_, _, lookup_table) = common!(Scheme);
+        let empty_circuit = MyCircuit {
+            a: Value::unknown(),
+            lookup_table,
+        };
+
+        // A generator of the multiplicative group, rather than zeta
+        let g_coset = Fr::from(7);
+        let options = KeygenOptions::new().with_coset(g_coset);
+        let vk = keygen_vk_with_options(&params, &empty_circuit, options)
+            .expect("keygen_vk should not fail");
+        assert_eq!(vk.get_domain().get_coset_generator(), g_coset);
+
+        // The combined keygen takes the same options
+        let (combined_vk, _) =
+            keygen_both(&params, &empty_circuit, options).expect("keygen should not fail");
+        assert_eq!(
+            combined_vk.to_bytes(SerdeFormat::RawBytes),
+            vk.to_bytes(SerdeFormat::RawBytes)
+        );
+
+        let pk = keygen_pk(&params, vk, &empty_circuit).expect("keygen_pk should not fail");
+
+        // A coset that intersects the evaluation domain is rejected
+        for g_coset in [Fr::zero(), Fr::one()] {
+            assert_matches!(
+                keygen_vk_with_options(
+                    &params,
+                    &empty_circuit,
+                    KeygenOptions::new().with_coset(g_coset)
+                ),
+                Err(Error::InvalidCoset)
+            );
+        }
+
+        // The coset survives serialization, along with the cosets the proving
+        // key holds
+        let pk = ProvingKey::<_>::from_bytes::<MyCircuit<Fr>>(
+            &pk.to_bytes(SerdeFormat::RawBytes),
+            SerdeFormat::RawBytes,
+        )
+        .unwrap();
+        assert_eq!(pk.get_vk().get_domain().get_coset_generator(), g_coset);
+
+        let proof = create_proof::<_, ProverSHPLONK<_>, _, _, Blake2bWrite<_, _, Challenge255<_>>>(
+            OsRng, &params, &pk,
+        );
+
+        // Proofs don't depend on the coset, so the usual key accepts them too
+        let default_pk = keygen::<Scheme>(&params);
+        assert_ne!(
+            pk.get_vk().to_bytes(SerdeFormat::RawBytes),
+            default_pk.get_vk().to_bytes(SerdeFormat::RawBytes)
+        );
+        for vk in [pk.get_vk(), default_pk.get_vk()] {
+            verify_proof::<
+                _,
+                VerifierSHPLONK<_>,
+                _,
+                Blake2bRead<_, _, Challenge255<_>>,
+                AccumulatorStrategy<_>,
+            >(params.verifier_params(), vk, &proof[..]);
+        }
+    }
+
+    fn test_plonk_api_circuit_fingerprint() {
+        use halo2_proofs::plonk::{create_typed_proof, Expression, Proof, ProofLayout, Selector};
+        use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+        use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+        use halo2_proofs::poly::kzg::strategy::AccumulatorStrategy;
+        use halo2_proofs::transcript::PointEncoding;
+        use halo2_proofs::SerdeFormat;
+        use halo2curves::bn256::{Bn256, Fr, G1Affine};
+
+        /// Constrains an advice cell to equal `VALUE`
+        #[derive(Clone, Default)]
+        struct ValueCircuit<const VALUE: u64>;
+
+        impl<const VALUE: u64> Circuit<Fr> for ValueCircuit<VALUE> {
+            type Config = (Selector, Column<Advice>);
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+                let s = meta.selector();
+                let a = meta.advice_column();
+                meta.create_gate("value", |meta| {
+                    let s = meta.query_selector(s);
+                    let a = meta.query_advice(a, Rotation::cur());
+                    vec![s * (a - Expression::Constant(Fr::from(VALUE)))]
+                });
+                (s, a)
+            }
+
+            fn synthesize(
+                &self,
+                (s, a): Self::Config,
+                mut layouter: impl Layouter<Fr>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "value",
+                    |mut region| {
+                        s.enable(&mut region, 0)?;
+                        region.assign_advice(|| "a", a, 0, || Value::known(Fr::from(VALUE)))?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let params = ParamsKZG::<Bn256>::new(K);
+        let vk = keygen_vk(&params, &ValueCircuit::<1>).unwrap();
+        let other_vk = keygen_vk(&params, &ValueCircuit::<2>).unwrap();
+        assert_ne!(vk.fingerprint(), other_vk.fingerprint());
+        // The fingerprint does not depend on the params, and survives
+        // serialization
+        for other_params in [ParamsKZG::<Bn256>::new(K), ParamsKZG::<Bn256>::new(K - 1)] {
+            assert_eq!(
+                keygen_vk(&other_params, &ValueCircuit::<1>)
+                    .unwrap()
+                    .fingerprint(),
+                vk.fingerprint()
+            );
+        }
+        assert_eq!(
+            VerifyingKey::<G1Affine>::from_bytes::<ValueCircuit<1>>(
+                &vk.to_bytes(SerdeFormat::RawBytes),
+                SerdeFormat::RawBytes
+            )
+            .unwrap()
+            .fingerprint(),
+            vk.fingerprint()
+        );
+
+        let pk = keygen_pk(&params, vk, &ValueCircuit::<1>).unwrap();
+        let proof = create_typed_proof::<
+            KZGCommitmentScheme<Bn256>,
+            ProverSHPLONK<_>,
+            _,
+            _,
+            Blake2bWrite<_, _, Challenge255<_>>,
+            _,
+        >(&params, &pk, &[ValueCircuit::<1>], &[&[]], OsRng)
+        .expect("proof generation should not fail");
+        assert_eq!(proof.fingerprint, pk.get_vk().fingerprint());
+        let bytes = proof.to_bytes(PointEncoding::Compressed).unwrap();
+
+        let verify = |vk: &VerifyingKey<_>| {
+            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&bytes[..]);
+            verify_plonk_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+                &params,
+                vk,
+                AccumulatorStrategy::new(&params),
+                &[&[]],
+                &mut transcript,
+            )
+            .map(|strategy| strategy.finalize())
+        };
+        assert_matches!(verify(pk.get_vk()), Ok(true));
+
+        // The proof is rejected by the key of another circuit before any of
+        // its constraints are checked
+        assert_matches!(verify(&other_vk), Err(Error::CircuitMismatch));
+        assert!(Proof::from_bytes(&other_vk, &ProofLayout::new(1), &bytes).is_err());
     }
 
     fn test_plonk_api_ipa() {
@@ -1023,5 +2790,30 @@ fn plonk_api() {
 
     test_plonk_api_ipa();
     test_plonk_api_gwc();
+    test_plonk_api_fflonk();
     test_plonk_api_shplonk();
+    test_plonk_api_runtime_multiopen();
+    test_plonk_api_domain_tag();
+    test_plonk_api_instance_hash();
+    test_plonk_api_witness();
+    test_plonk_api_advice();
+    test_plonk_api_without_blinding();
+    test_plonk_api_typed_proof();
+    test_plonk_api_observer();
+    test_plonk_api_options();
+    test_plonk_api_parallel_prover();
+    test_plonk_api_cancellation();
+    test_plonk_api_challenges();
+    test_plonk_api_committed_instances();
+    test_plonk_api_log_derivative_lookups();
+    test_plonk_api_batched_lookups();
+    test_plonk_api_shuffles();
+    test_plonk_api_uncompressed_selectors();
+    test_plonk_api_combined_keygen();
+    test_plonk_api_custom_coset();
+    test_plonk_api_circuit_fingerprint();
+    #[cfg(feature = "timings")]
+    test_plonk_api_timings();
+    #[cfg(feature = "mmap")]
+    test_plonk_api_mmap_params();
 }
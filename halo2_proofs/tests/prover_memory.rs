@@ -0,0 +1,235 @@
+//! Measures the memory the prover allocates while it synthesizes and commits
+//! to the advice columns, and while it creates a whole proof. This runs in
+//! its own test binary, as it replaces the global allocator and runs rayon
+//! on a single thread.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::mem::size_of;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    create_proof_with_options, keygen_pk, keygen_vk, Advice, Assigned, Circuit, Column,
+    ConstraintSystem, Error, ProverObserver, ProverOptions, ProverStage, ProvingKey, Selector,
+    StageStats,
+};
+use halo2_proofs::poly::commitment::{ParamsProver, Prover};
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use halo2_proofs::poly::kzg::multiopen::ProverSHPLONK;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+const K: u32 = 10;
+const NUM_ADVICE: usize = 32;
+
+/// Bytes currently allocated, and the most allocated at once since the
+/// observer last reset it
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// The counters are shared by all tests, so they run one at a time
+static SERIAL: Mutex<()> = Mutex::new(());
+
+fn serial() -> MutexGuard<'static, ()> {
+    let guard = SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+    // One thread, so that the buckets of parallel multi-exponentiations
+    // don't dominate the measurement
+    let _ = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build_global();
+    guard
+}
+
+/// Records how much memory the advice stage allocates beyond what was
+/// allocated when it started
+#[derive(Default)]
+struct AdviceMemory {
+    start: usize,
+    peak: usize,
+}
+
+impl ProverObserver for AdviceMemory {
+    fn stage_started(&mut self, stage: ProverStage) {
+        if stage == ProverStage::Advice {
+            self.start = ALLOCATED.load(Ordering::SeqCst);
+            PEAK.store(self.start, Ordering::SeqCst);
+        }
+    }
+
+    fn stage_finished(&mut self, stage: ProverStage, _: &StageStats) {
+        if stage == ProverStage::Advice {
+            self.peak = PEAK.load(Ordering::SeqCst) - self.start;
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct WideConfig {
+    advice: Vec<Column<Advice>>,
+    s: Selector,
+}
+
+/// Fills every usable row of many advice columns with the same values, some
+/// of them assigned as fractions
+#[derive(Clone, Default)]
+struct WideCircuit;
+
+impl Circuit<Fr> for WideCircuit {
+    type Config = WideConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> WideConfig {
+        let advice = (0..NUM_ADVICE)
+            .map(|_| meta.advice_column())
+            .collect::<Vec<_>>();
+        let s = meta.selector();
+
+        let columns = advice.clone();
+        meta.create_gate("equal", |meta| {
+            let s = meta.query_selector(s);
+            let first = meta.query_advice(columns[0], Rotation::cur());
+            columns[1..]
+                .iter()
+                .map(|column| {
+                    s.clone() * (meta.query_advice(*column, Rotation::cur()) - first.clone())
+                })
+                .collect::<Vec<_>>()
+        });
+
+        WideConfig { advice, s }
+    }
+
+    fn synthesize(&self, config: WideConfig, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "rows",
+            |mut region| {
+                for row in 0..(1 << K) - 16 {
+                    config.s.enable(&mut region, row)?;
+                    for (index, column) in config.advice.iter().enumerate() {
+                        let value = if index == 0 && row % 16 == 0 {
+                            Assigned::from((Fr::from(2 * row as u64), Fr::from(2)))
+                        } else {
+                            Assigned::from(Fr::from(row as u64))
+                        };
+                        region.assign_advice(|| "value", *column, row, || Value::known(value))?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn advice_stage_holds_the_advice_matrix_once() {
+    let _serial = serial();
+
+    let params = ParamsKZG::<Bn256>::new(K);
+    let vk = keygen_vk(&params, &WideCircuit).unwrap();
+    let pk = keygen_pk(&params, vk, &WideCircuit).unwrap();
+
+    let mut observer = AdviceMemory::default();
+    let instances: &[&[&[Fr]]] = &[&[]];
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof_with_options(
+        &params,
+        ProverSHPLONK::new(&params),
+        &pk,
+        &[WideCircuit],
+        instances,
+        OsRng,
+        &mut transcript,
+        ProverOptions::new().with_observer(&mut observer),
+    )
+    .expect("proof generation should not fail");
+
+    // Holding the columns as `Assigned` values while synthesizing, as well
+    // as converted, would take more than twice the advice matrix
+    let matrix = NUM_ADVICE * (1 << K) * size_of::<Fr>();
+    assert!(
+        observer.peak < matrix * 3 / 2,
+        "the advice stage allocated {} bytes for an advice matrix of {} bytes",
+        observer.peak,
+        matrix
+    );
+}
+
+/// Returns the most memory allocated at once while proving `circuits`,
+/// beyond what was allocated before
+fn proof_peak(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuits: &[WideCircuit],
+    options: ProverOptions<'_, Fr>,
+) -> usize {
+    let instances = vec![&[][..]; circuits.len()];
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+
+    let start = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(start, Ordering::SeqCst);
+    create_proof_with_options(
+        params,
+        ProverSHPLONK::new(params),
+        pk,
+        circuits,
+        &instances,
+        OsRng,
+        &mut transcript,
+        options,
+    )
+    .expect("proof generation should not fail");
+    PEAK.load(Ordering::SeqCst) - start
+}
+
+#[test]
+fn spilling_lowers_the_peak_of_a_proof() {
+    let _serial = serial();
+
+    let params = ParamsKZG::<Bn256>::new(K);
+    let vk = keygen_vk(&params, &WideCircuit).unwrap();
+    let pk = keygen_pk(&params, vk, &WideCircuit).unwrap();
+    let circuits = vec![WideCircuit; 4];
+
+    let baseline = proof_peak(&params, &pk, &circuits, ProverOptions::new());
+    let spilled = proof_peak(
+        &params,
+        &pk,
+        &circuits,
+        ProverOptions::new().with_memory_limit(0, std::env::temp_dir()),
+    );
+
+    // Without spilling, the h(X) polynomial is evaluated with the advice
+    // polynomials of all proofs in memory, as well as the cosets of those
+    // of the proof being evaluated
+    assert!(
+        spilled < baseline,
+        "proving with spilling allocated {} bytes, and without {} bytes",
+        spilled,
+        baseline
+    );
+}